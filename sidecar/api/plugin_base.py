@@ -7,7 +7,7 @@ Provides standardized lifecycle hooks and command registration.
 
 from abc import ABC, abstractmethod
 from pathlib import Path
-from typing import Dict, Any, TYPE_CHECKING, cast, Callable, Awaitable
+from typing import Dict, Any, List, Optional, TYPE_CHECKING, cast, Callable, Awaitable
 
 # Handle imports for both package context (tests) and standalone context (plugins)
 from sidecar import constants
@@ -112,6 +112,25 @@ class PluginBase(ABC):
         """
         pass
 
+    async def unsaved_changes(self) -> List[str]:
+        """
+        Called before the vault closes. Return a description of each piece
+        of work that closing now would lose; an empty list lets it close.
+        """
+        return []
+
+    async def on_pause(self) -> None:
+        """
+        Called when the host asks the sidecar to hold background work, e.g.
+        to save battery. Stop timers and polling; state is kept. Ticks stop
+        until on_resume().
+        """
+        pass
+
+    async def on_resume(self) -> None:
+        """Called when background work may carry on after on_pause()."""
+        pass
+
     async def on_unload(self) -> None:
         """Called when plugin is being unloaded."""
         self._loaded = False
@@ -138,6 +157,7 @@ class PluginBase(ABC):
         event_type: str,
         data: Dict[str, Any],
         scope: str = constants.EventScope.WINDOW,
+        coalesce_key: Optional[str] = None,
     ) -> None:
         """
         Emit a generic event to the frontend.
@@ -146,8 +166,10 @@ class PluginBase(ABC):
             event_type: Type/Name of event (e.g. "llm.response")
             data: Payload dictionary
             scope: Event scope (window/global)
+            coalesce_key: If set, a newer event of the same type and key
+                replaces this one while it waits to be delivered
         """
-        self.brain.emit_to_frontend(event_type, data, scope)
+        self.brain.emit_to_frontend(event_type, data, scope, coalesce_key)
 
     def get_config_path(self, filename: str = constants.PLUGIN_SETTINGS_FILE) -> Path:
         """Get path to a config file."""
@@ -32,6 +32,17 @@ JSONRPC_INVALID_PARAMS: Final[int] = -32602
 JSONRPC_INTERNAL_ERROR: Final[int] = -32603
 """Internal JSON-RPC error."""
 
+STREAM_CHUNK_METHOD: Final[str] = "stream.chunk"
+"""Notification carrying one partial result of a streaming request."""
+
+BINARY_CHUNK_TAG: Final[int] = 0
+"""First byte of a binary frame carrying raw bytes of a streaming request,
+followed by the request id's length (2 bytes, big-endian), the id and the
+data. Compressed responses start with a zlib header instead."""
+
+HOST_METHOD_PREFIX: Final[str] = "host."
+"""Prefix of JSON-RPC methods the sidecar calls on the host."""
+
 
 # ============================================================================
 # Timing Constants
@@ -43,8 +54,19 @@ DEFAULT_TICK_INTERVAL: Final[float] = 5.0
 WEBSOCKET_TIMEOUT: Final[float] = 30.0
 """WebSocket connection timeout in seconds."""
 
-WEBSOCKET_PING_INTERVAL: Final[float] = 20.0
-"""WebSocket ping interval in seconds."""
+HOST_REQUEST_TIMEOUT: Final[float] = 300.0
+"""Seconds to wait for the host to answer a request (dialogs wait on the user)."""
+
+WEBSOCKET_PING_INTERVAL: Final[float] = 30.0
+"""WebSocket keepalive ping interval in seconds (a missing pong within one
+interval closes the connection)."""
+
+WEBSOCKET_CLOSE_NORMAL: Final[int] = 1000
+"""Close code for shutting down on purpose; the host does not reconnect."""
+
+WEBSOCKET_CLOSE_INTERNAL_ERROR: Final[int] = 1011
+"""Close code for dropping the connection after an error; the host treats it
+like a crash."""
 
 
 # ============================================================================
@@ -82,6 +104,9 @@ class EventType(str, Enum):
     CHAT_STREAM_END = "CHAT_STREAM_END"
     """Chat stream completed event - sent when streaming finishes."""
 
+    FILE_CHANGED = "file-changed"
+    """Vault files were added, removed or modified; invalidates cached listings."""
+
 
 class EventScope(str, Enum):
     """Event routing scopes."""
@@ -191,6 +216,9 @@ PLUGINS_DIR: Final[str] = "plugins"
 LIB_DIR: Final[str] = "lib"
 """Library directory name within vault."""
 
+VENV_DIR: Final[str] = ".venv"
+"""Virtual environment within vault; replaces the library directory once migrated."""
+
 PLUGIN_MAIN_FILE: Final[str] = "main.py"
 """Plugin entry point file name."""
 
@@ -232,6 +260,9 @@ LOG_DATE_FORMAT: Final[str] = "%Y-%m-%d %H:%M:%S"
 DEFAULT_LOG_LEVEL: Final[str] = "INFO"
 """Default logging level."""
 
+LOG_LEVELS: Final[tuple] = ("DEBUG", "INFO", "WARNING", "ERROR", "CRITICAL")
+"""Levels accepted by --log-level and system.set_log_level."""
+
 
 # ============================================================================
 # Vault Configuration Defaults
@@ -200,6 +200,17 @@ class MethodNotFoundError(JSONRPCError):
         super().__init__(f"Method not found: {method}", code=-32601)
 
 
+class HostRequestError(WebSocketError):
+    """Raised when the host rejects or fails a request made with call_host."""
+
+    def __init__(self, method: str, message: str, code: int = -32000):
+        super().__init__(
+            f"Host request '{method}' failed: {message}",
+            {"method": method, "code": code},
+        )
+        self.code = code
+
+
 # ============================================================================
 # Command Registry Exceptions
 # ============================================================================
@@ -18,6 +18,7 @@ from .websocket_server import WebSocketServer
 from .vault_brain import VaultBrain
 from . import utils
 from . import exceptions
+from . import constants
 
 from loguru import logger
 
@@ -63,9 +64,26 @@ def parse_arguments() -> argparse.Namespace:
     )
 
     # Optional arguments
+    parser.add_argument(
+        "--ws-host",
+        default=constants.DEFAULT_WEBSOCKET_HOST,
+        help="Interface the WebSocket server listens on",
+    )
+    parser.add_argument(
+        "--ws-uds",
+        type=Path,
+        help="Unix domain socket to also accept host connections on; the "
+        "port stays open for the frontend",
+    )
+    parser.add_argument(
+        "--ping-interval",
+        type=float,
+        default=constants.WEBSOCKET_PING_INTERVAL,
+        help="Seconds between WebSocket keepalive pings (0 disables)",
+    )
     parser.add_argument(
         "--log-level",
-        choices=["DEBUG", "INFO", "WARNING", "ERROR", "CRITICAL"],
+        choices=constants.LOG_LEVELS,
         help="Logging level (default: from environment or INFO)",
     )
     parser.add_argument(
@@ -130,7 +148,9 @@ def main() -> None:
     logger.info("Tailor Python Sidecar starting...")
     logger.info("=" * 60)
     logger.info(f"Vault path: {vault_path}")
-    logger.info(f"WebSocket port: {args.ws_port}")
+    logger.info(f"WebSocket address: {args.ws_host}:{args.ws_port}")
+    if args.ws_uds:
+        logger.info(f"WebSocket socket: {args.ws_uds}")
 
     # Add sidecar to Python path (so plugins can import sidecar.* modules)
     sidecar_dir = Path(__file__).parent.parent
@@ -138,8 +158,9 @@ def main() -> None:
         sys.path.insert(0, str(sidecar_dir))
         logger.info(f"Added sidecar root to PYTHONPATH: {sidecar_dir}")
 
-    # Add vault's lib directory to Python path for isolated dependencies
-    lib_path = vault_path / "lib"
+    # Add vault's isolated dependencies to Python path: its virtual
+    # environment once migrated, else its lib directory
+    lib_path = utils.get_venv_site_packages(vault_path) or vault_path / constants.LIB_DIR
     if lib_path.exists():
         sys.path.insert(0, str(lib_path))
         logger.info(f"Added to PYTHONPATH: {lib_path}")
@@ -150,7 +171,12 @@ def main() -> None:
     try:
         # Initialize WebSocket server
         logger.info("Initializing WebSocket server...")
-        ws_server = WebSocketServer(port=args.ws_port)
+        ws_server = WebSocketServer(
+            host=args.ws_host,
+            port=args.ws_port,
+            ping_interval=args.ping_interval or None,
+            uds_path=args.ws_uds,
+        )
 
         # Initialize vault brain (creates emitter internally)
         logger.info("Initializing VaultBrain...")
@@ -22,6 +22,9 @@ from enum import Enum
 import httpx
 from loguru import logger
 
+from . import constants
+from . import utils
+
 
 class InstallStatus(Enum):
     """Plugin installation status."""
@@ -556,17 +559,20 @@ class PluginInstaller:
     async def _install_dependencies(self, requirements_file: Path) -> bool:
         """Install Python dependencies from requirements.txt."""
         try:
-            lib_dir = self.vault_path / "lib"
-            lib_dir.mkdir(exist_ok=True)
+            # Migrated vaults install into their virtual environment
+            venv_python = utils.get_venv_python(self.vault_path)
+            if venv_python is not None:
+                pip = [str(venv_python), "-m", "pip", "install"]
+            else:
+                lib_dir = self.vault_path / constants.LIB_DIR
+                lib_dir.mkdir(exist_ok=True)
+                pip = ["pip", "install", "--target", str(lib_dir)]
 
             # Use async subprocess for pip install
             process = await asyncio.create_subprocess_exec(
-                "pip",
-                "install",
+                *pip,
                 "-r",
                 str(requirements_file),
-                "--target",
-                str(lib_dir),
                 "--quiet",
                 stdout=asyncio.subprocess.PIPE,
                 stderr=asyncio.subprocess.PIPE,
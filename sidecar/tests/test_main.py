@@ -9,6 +9,7 @@ def test_parse_arguments():
         args = main.parse_arguments()
         assert str(args.vault) == "/tmp/vault"
         assert args.ws_port == 8080
+        assert args.ws_host == "127.0.0.1"
         assert args.verbose is True
 
 @pytest.mark.asyncio
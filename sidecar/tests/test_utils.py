@@ -75,3 +75,26 @@ def test_generate_id():
 
     id3 = utils.generate_id("prefix_")
     assert id3.startswith("prefix_")
+
+
+def test_set_log_level():
+    assert utils.set_log_level("debug") == "DEBUG"
+    with pytest.raises(ValueError, match="Unknown log level"):
+        utils.set_log_level("loud")
+    utils.set_log_level("INFO")
+
+
+def test_rotate_log_file(tmp_path):
+    utils.configure_logging(level="INFO")
+    with pytest.raises(RuntimeError, match="File logging is disabled"):
+        utils.rotate_log_file()
+
+    log_file = tmp_path / "sidecar.log"
+    utils.configure_logging(level="INFO", log_file=log_file)
+    utils.logger.info("before rotation")
+
+    paths = utils.rotate_log_file()
+    assert paths["log_file"] == str(log_file)
+    assert "before rotation" in (tmp_path / paths["rotated_to"]).read_text()
+    assert "before rotation" not in log_file.read_text()
+    utils.configure_logging(level="INFO")
@@ -431,6 +431,72 @@ class TestCommandRegistry:
         # Check that original error is preserved/referenced
         assert "Something went wrong" in str(exc_info.value)
 
+    @pytest.mark.asyncio
+    async def test_before_close_reports_unsaved_changes(self, brain):
+        """Test closing is only approved when no plugin has unsaved work."""
+        clean = Mock()
+        clean.unsaved_changes = AsyncMock(return_value=[])
+        brain.plugins = {"clean": clean, "legacy": object()}
+        assert await brain.before_close() == {"status": "success", "approve": True, "unsaved": []}
+
+        dirty = Mock()
+        dirty.unsaved_changes = AsyncMock(return_value=["Draft in chat 'Ideas'"])
+        brain.plugins["dirty"] = dirty
+        result = await brain.before_close()
+        assert result["approve"] is False
+        assert result["unsaved"] == ["Draft in chat 'Ideas'"]
+
+    @pytest.mark.asyncio
+    async def test_pause_and_resume(self, brain):
+        """Test pausing notifies plugins once and resuming undoes it."""
+        plugin = Mock()
+        plugin.on_pause = AsyncMock()
+        plugin.on_resume = AsyncMock()
+        brain.plugins = {"worker": plugin, "legacy": object()}
+
+        assert await brain.pause() == {"status": "success", "paused": True}
+        await brain.pause()
+        plugin.on_pause.assert_awaited_once()
+
+        assert await brain.resume() == {"status": "success", "paused": False}
+        plugin.on_resume.assert_awaited_once()
+
+    @pytest.mark.asyncio
+    async def test_list_entries_skips_nested_vaults(self, brain):
+        """Test a vault inside this one is left to its own sidecar."""
+        (brain.vault_path / "notes").mkdir()
+        (brain.vault_path / "notes" / "a.md").write_text("a")
+        (brain.vault_path / "subvault").mkdir()
+        (brain.vault_path / "subvault" / ".vault.toml").write_text("")
+        (brain.vault_path / "subvault" / "b.md").write_text("b")
+
+        result = await brain.list_entries()
+        paths = [e["path"] for e in result["entries"]]
+        assert "notes/a.md" in paths
+        assert "subvault" not in paths and "subvault/b.md" not in paths
+
+    @pytest.mark.asyncio
+    async def test_check_files_changed(self, brain):
+        """Test the watcher reports changed paths and does not follow symlinks."""
+        outside = brain.vault_path.parent / "outside"
+        outside.mkdir()
+        (outside / "secret.md").write_text("s")
+        (brain.vault_path / "link").symlink_to(outside)
+        brain.ws_server.is_connected = Mock(return_value=True)
+        brain.notify_files_changed = Mock()
+
+        await brain.check_files_changed()
+        brain.notify_files_changed.assert_not_called()
+
+        (brain.vault_path / "new.md").write_text("n")
+        await brain.check_files_changed()
+        brain.notify_files_changed.assert_called_once_with(["new.md"])
+
+        result = await brain.list_entries()
+        link = next(e for e in result["entries"] if e["path"] == "link")
+        assert link["type"] == "file"
+        assert "link/secret.md" not in [e["path"] for e in result["entries"]]
+
     def test_get_commands(self, brain):
         """Test listing commands."""
 
@@ -4,8 +4,10 @@ Unit tests for WebSocketServer module.
 Tests connection handling, JSON-RPC message processing, and command registration.
 """
 
+import asyncio
 import pytest
 import json
+import zlib
 from unittest.mock import Mock, AsyncMock, patch, MagicMock
 from sidecar.websocket_server import WebSocketServer
 from sidecar import exceptions
@@ -74,6 +76,189 @@ class TestWebSocketServer:
             assert response["result"] == {"status": "ok"}
             assert response["id"] == "1"
 
+    @pytest.mark.asyncio
+    async def test_handle_message_compressed_response(self, server):
+        """Test large responses are compressed when the client asks for it."""
+        mock_brain = MagicMock()
+        mock_brain.execute_command = AsyncMock(return_value={"data": "x" * 1000})
+
+        with patch.dict(
+            "sys.modules",
+            {
+                "sidecar.vault_brain": MagicMock(
+                    VaultBrain=MagicMock(get=MagicMock(return_value=mock_brain))
+                )
+            },
+        ):
+            server.connection = Mock()
+            server.connection.send = AsyncMock()
+            server.connection.close = AsyncMock()
+
+            request = utils.build_request("test.big", {}, request_id="1")
+            request["compress"] = 100
+
+            await server.handle_message(json.dumps(request))
+
+            payload = server.connection.send.call_args[0][0]
+            assert isinstance(payload, bytes)
+            response = json.loads(zlib.decompress(payload))
+            assert response["result"] == {"data": "x" * 1000}
+
+    @pytest.mark.asyncio
+    async def test_handle_message_batch(self, server):
+        """Test a batch request is answered with one array of responses."""
+        mock_brain = MagicMock()
+        mock_brain.execute_command = AsyncMock(side_effect=[{"n": 1}, {"n": 2}])
+
+        with patch.dict(
+            "sys.modules",
+            {
+                "sidecar.vault_brain": MagicMock(
+                    VaultBrain=MagicMock(get=MagicMock(return_value=mock_brain))
+                )
+            },
+        ):
+            server.connection = Mock()
+            server.connection.send = AsyncMock()
+            server.connection.close = AsyncMock()
+
+            batch = [
+                utils.build_request("test.one", {}, request_id="a"),
+                utils.build_request("test.two", {}, request_id="b"),
+            ]
+
+            await server.handle_message(json.dumps(batch))
+
+            server.connection.send.assert_called_once()
+            responses = json.loads(server.connection.send.call_args[0][0])
+            assert [r["id"] for r in responses] == ["a", "b"]
+            assert [r["result"] for r in responses] == [{"n": 1}, {"n": 2}]
+
+    @pytest.mark.asyncio
+    async def test_handle_message_batch_invalid_entries(self, server):
+        """Test invalid batch entries each get an Invalid Request error."""
+        mock_brain = MagicMock()
+        mock_brain.execute_command = AsyncMock(return_value={"n": 1})
+
+        with patch.dict(
+            "sys.modules",
+            {
+                "sidecar.vault_brain": MagicMock(
+                    VaultBrain=MagicMock(get=MagicMock(return_value=mock_brain))
+                )
+            },
+        ):
+            server.connection = Mock()
+            server.connection.send = AsyncMock()
+            server.connection.close = AsyncMock()
+
+            batch = [
+                utils.build_request("test.one", {}, request_id="a"),
+                1,
+                {"jsonrpc": "2.0", "id": "c"},
+                {"method": "test.two", "id": "d"},
+            ]
+
+            await server.handle_message(json.dumps(batch))
+
+            responses = json.loads(server.connection.send.call_args[0][0])
+            assert [r["id"] for r in responses] == ["a", None, "c", "d"]
+            assert responses[0]["result"] == {"n": 1}
+            for response in responses[1:]:
+                assert response["error"]["code"] == constants.JSONRPC_INVALID_REQUEST
+            mock_brain.execute_command.assert_awaited_once()
+
+    @pytest.mark.asyncio
+    async def test_handle_message_empty_batch(self, server):
+        """Test an empty batch is answered with a single Invalid Request error."""
+        server.connection = Mock()
+        server.connection.send = AsyncMock()
+
+        await server.handle_message("[]")
+
+        server.connection.send.assert_called_once()
+        response = json.loads(server.connection.send.call_args[0][0])
+        assert response["error"]["code"] == constants.JSONRPC_INVALID_REQUEST
+        assert response["id"] is None
+
+    @pytest.mark.asyncio
+    async def test_handle_message_streaming(self, server):
+        """Test a streaming handler sends chunks before the final response."""
+
+        async def export():
+            for n in range(3):
+                yield {"n": n}
+
+        mock_brain = MagicMock()
+        mock_brain.execute_command = AsyncMock(return_value=export())
+
+        with patch.dict(
+            "sys.modules",
+            {
+                "sidecar.vault_brain": MagicMock(
+                    VaultBrain=MagicMock(get=MagicMock(return_value=mock_brain))
+                )
+            },
+        ):
+            server.connection = Mock()
+            server.connection.send = AsyncMock()
+            server.connection.close = AsyncMock()
+
+            request = utils.build_request("test.export", {}, request_id="s1")
+            request["stream"] = True
+            await server.handle_message(json.dumps(request))
+
+            messages = [
+                json.loads(call[0][0])
+                for call in server.connection.send.call_args_list
+            ]
+            assert len(messages) == 4
+            for n, message in enumerate(messages[:3]):
+                assert message["method"] == constants.STREAM_CHUNK_METHOD
+                assert message["params"] == {"id": "s1", "chunk": {"n": n}}
+            assert messages[3]["id"] == "s1"
+            assert messages[3]["result"] == {"chunks": 3}
+
+    @pytest.mark.asyncio
+    async def test_handle_message_binary_streaming(self, server):
+        """Test bytes chunks go out as tagged binary frames when asked for."""
+
+        async def export():
+            yield b"PK\x03\x04"
+            yield b"rest"
+
+        mock_brain = MagicMock()
+        mock_brain.execute_command = AsyncMock(side_effect=lambda *a, **k: export())
+
+        with patch.dict(
+            "sys.modules",
+            {
+                "sidecar.vault_brain": MagicMock(
+                    VaultBrain=MagicMock(get=MagicMock(return_value=mock_brain))
+                )
+            },
+        ):
+            server.connection = Mock()
+            server.connection.send = AsyncMock()
+            server.connection.close = AsyncMock()
+
+            request = utils.build_request("test.export", {}, request_id="s1")
+            request["stream"] = True
+            request["binary"] = True
+            await server.handle_message(json.dumps(request))
+
+            frames = [call[0][0] for call in server.connection.send.call_args_list]
+            assert frames[0] == bytes([constants.BINARY_CHUNK_TAG, 0, 2]) + b"s1PK\x03\x04"
+            assert frames[1].endswith(b"s1rest")
+            assert json.loads(frames[2])["result"] == {"chunks": 2}
+
+            # Without the flag the bytes are base64 in stream.chunk
+            server.connection.send.reset_mock()
+            del request["binary"]
+            await server.handle_message(json.dumps(request))
+            first = json.loads(server.connection.send.call_args_list[0][0][0])
+            assert first["params"]["chunk"] == "UEsDBA=="
+
     @pytest.mark.asyncio
     async def test_handle_message_method_not_found(self, server):
         """Test unknown method."""
@@ -155,6 +340,21 @@ class TestWebSocketServer:
                 or "ValueError" in response["error"]["message"]
             )
 
+    @pytest.mark.asyncio
+    async def test_send_error_closes_as_internal_error(self, server):
+        """Test a failed send closes with 1011 rather than a clean 1000."""
+        server.connection = Mock()
+        server.connection.send = AsyncMock(side_effect=OSError("broken pipe"))
+        server.connection.close = AsyncMock()
+        server.is_connected = Mock(return_value=True)
+
+        await server.send({"jsonrpc": "2.0", "result": {}, "id": "1"})
+        await asyncio.sleep(0)
+
+        server.connection.close.assert_awaited_once_with(
+            constants.WEBSOCKET_CLOSE_INTERNAL_ERROR, "send failed"
+        )
+
     @pytest.mark.asyncio
     async def test_handle_connection(self, server, mock_ws):
         """Test that new connection is stored."""
@@ -169,3 +369,41 @@ class TestWebSocketServer:
 
         # Should have set connection, then cleared it in finally block
         assert server.connection is None
+
+    @pytest.mark.asyncio
+    async def test_call_host(self, server, mock_ws):
+        """Test a host request waits for its response, handling other traffic."""
+        from sidecar import websocket_server
+
+        async def recv():
+            request = json.loads(mock_ws.send.call_args[0][0])
+            if mock_ws.recv.call_count == 1:
+                return json.dumps(utils.build_request("other.command", {}, request_id="2"))
+            return json.dumps({"jsonrpc": "2.0", "result": "/tmp/out.md", "id": request["id"]})
+
+        mock_ws.recv = AsyncMock(side_effect=recv)
+        server.handle_message = AsyncMock()
+
+        with pytest.raises(exceptions.WebSocketError):
+            await server.call_host("save_dialog")
+
+        websocket_server._current_connection.set(mock_ws)
+        result = await server.call_host("save_dialog", {"title": "Export"})
+
+        request = json.loads(mock_ws.send.call_args[0][0])
+        assert request["method"] == "host.save_dialog"
+        assert request["params"] == {"title": "Export"}
+        assert result == "/tmp/out.md"
+        server.handle_message.assert_called_once()
+
+        async def recv_error():
+            request = json.loads(mock_ws.send.call_args[0][0])
+            return json.dumps({
+                "jsonrpc": "2.0",
+                "error": {"code": -32000, "message": "Vault is not permitted to show dialogs"},
+                "id": request["id"],
+            })
+
+        mock_ws.recv = AsyncMock(side_effect=recv_error)
+        with pytest.raises(exceptions.HostRequestError):
+            await server.call_host("host.open_dialog")
@@ -26,6 +26,10 @@ from . import exceptions
 
 from loguru import logger
 
+# Log file and level in use, kept so logging can be reconfigured at runtime
+_log_file: Optional[Path] = None
+_log_level: Optional[str] = None
+
 
 def configure_logging(
     level: Optional[str] = None,
@@ -35,6 +39,9 @@ def configure_logging(
     """
     Configure logging using Loguru.
     """
+    global _log_file, _log_level
+    _log_file = log_file
+
     # Remove default handler
     logger.remove()
 
@@ -48,6 +55,8 @@ def configure_logging(
             constants.ENV_LOG_LEVEL, constants.DEFAULT_LOG_LEVEL
         ).upper()
 
+    _log_level = log_level
+
     # Define detailed format
     format_str = (
         "<green>{time:YYYY-MM-DD HH:mm:ss}</green> | "
@@ -82,6 +91,56 @@ def configure_logging(
     logger.info(f"Logging configured at {log_level} level")
 
 
+def set_log_level(level: str) -> str:
+    """
+    Change the level of the console and file handlers at runtime.
+
+    Returns:
+        The level now in effect, upper-cased
+    """
+    log_level = level.upper()
+    if log_level not in constants.LOG_LEVELS:
+        raise ValueError(
+            f"Unknown log level '{level}' (expected one of {', '.join(constants.LOG_LEVELS)})"
+        )
+    configure_logging(level=log_level, log_file=_log_file)
+    return log_level
+
+
+def rotate_log_file() -> Dict[str, str]:
+    """
+    Flush and close the log file, move it aside with a timestamped name and
+    start a fresh one at the same path.
+
+    Returns:
+        The new log file's path and where the previous one was moved
+
+    Raises:
+        RuntimeError: If logging to a file is not configured
+    """
+    if _log_file is None:
+        raise RuntimeError("File logging is disabled")
+
+    stamp = time.strftime("%Y%m%d-%H%M%S")
+    rotated = _log_file.with_name(f"{_log_file.stem}.{stamp}{_log_file.suffix}")
+    counter = 1
+    while rotated.exists():
+        rotated = _log_file.with_name(
+            f"{_log_file.stem}.{stamp}-{counter}{_log_file.suffix}"
+        )
+        counter += 1
+
+    # Removing the handlers flushes and closes the file
+    logger.remove()
+    try:
+        if _log_file.exists():
+            _log_file.replace(rotated)
+    finally:
+        configure_logging(level=_log_level, log_file=_log_file)
+    logger.info(f"Rotated log file; previous log moved to {rotated}")
+    return {"log_file": str(_log_file), "rotated_to": str(rotated)}
+
+
 # =============================================================================
 # JSON-RPC Utilities
 # =============================================================================
@@ -326,6 +385,22 @@ def get_plugins_dir(vault_path: Path) -> Optional[Path]:
     return plugins_path if plugins_path.exists() and plugins_path.is_dir() else None
 
 
+def get_venv_python(vault_path: Path) -> Optional[Path]:
+    """Get the interpreter of a vault's virtual environment, if it has one."""
+    venv = vault_path / constants.VENV_DIR
+    for python in (venv / "Scripts" / "python.exe", venv / "bin" / "python"):
+        if python.exists():
+            return python
+    return None
+
+
+def get_venv_site_packages(vault_path: Path) -> Optional[Path]:
+    """Get the site-packages of a vault's virtual environment, if it has one."""
+    venv = vault_path / constants.VENV_DIR
+    candidates = [venv / "Lib" / "site-packages", *sorted(venv.glob("lib/python*/site-packages"))]
+    return next((path for path in candidates if path.is_dir()), None)
+
+
 # =============================================================================
 # ID Generation / Info Utilities
 # =============================================================================
@@ -7,6 +7,7 @@ Acts as the central Event/Command hub.
 
 import asyncio
 import json
+import os
 import tomllib
 import tomli_w
 import importlib.util
@@ -15,6 +16,7 @@ import inspect
 from pathlib import Path
 from typing import Dict, Any, Optional, Callable, Awaitable, List
 
+from . import __version__
 from . import utils
 from . import constants
 from . import exceptions
@@ -96,6 +98,12 @@ class VaultBrain:
         # Active stream tracking for cancellation
         self._active_streams: Dict[str, bool] = {}  # stream_id -> should_cancel
 
+        # Set while the host has asked us to hold background work ($/pause)
+        self._paused = False
+
+        # Last vault listing seen by the file watcher (path -> (type, size, mtime))
+        self._entries_snapshot: Optional[Dict[str, tuple]] = None
+
         self._initialized = True
         logger.info(f"VaultBrain Singleton created for: {self.vault_path}")
 
@@ -423,7 +431,12 @@ class VaultBrain:
             raise exceptions.CommandNotFoundError(command_id, all_commands)
 
         try:
-            result = await handler(**kwargs)
+            # Async generator handlers stream their results; the caller
+            # iterates the returned generator
+            if inspect.isasyncgenfunction(handler):
+                result = handler(**kwargs)
+            else:
+                result = await handler(**kwargs)
 
             # Emit command executed event (fire and forget)
             asyncio.create_task(
@@ -475,6 +488,82 @@ class VaultBrain:
             }
         return {"response": f"Echo: {message}", "status": "success"}
 
+    @command("system.initialize", constants.CORE_PLUGIN_NAME)
+    async def initialize_handshake(
+        self, host: Optional[Dict[str, Any]] = None, **kwargs
+    ) -> Dict[str, Any]:
+        """Advertise this sidecar's version and what it supports to the host."""
+        if host:
+            logger.info(
+                f"Host is Tailor {host.get('version')} ({host.get('build_hash')}), "
+                f"sidecar is {__version__}"
+            )
+        return {
+            "version": __version__,
+            "capabilities": {
+                "methods": sorted(self.commands.keys()),
+                "plugins": sorted(self.plugins.keys()),
+                "streaming": True,
+            }
+        }
+
+    @command("system.set_log_level", constants.CORE_PLUGIN_NAME)
+    async def set_log_level(self, level: str = "", **kwargs) -> Dict[str, Any]:
+        """Change this sidecar's log verbosity without restarting it."""
+        try:
+            log_level = utils.set_log_level(level)
+        except ValueError as e:
+            return {"status": "error", "error": str(e)}
+        return {"status": "success", "level": log_level}
+
+    @command("system.rotate_log", constants.CORE_PLUGIN_NAME)
+    async def rotate_log(self, **kwargs) -> Dict[str, Any]:
+        """Start a fresh log file, moving the current one aside."""
+        try:
+            paths = utils.rotate_log_file()
+        except (RuntimeError, OSError) as e:
+            return {"status": "error", "error": str(e)}
+        return {"status": "success", **paths}
+
+    @command("system.before_close", constants.CORE_PLUGIN_NAME)
+    async def before_close(self, **kwargs) -> Dict[str, Any]:
+        """Tell the host whether closing the vault now would lose work."""
+        unsaved: List[str] = []
+        for name, plugin in self.plugins.items():
+            unsaved_changes = getattr(plugin, "unsaved_changes", None)
+            if unsaved_changes is None:
+                continue
+            try:
+                unsaved.extend(await unsaved_changes())
+            except Exception as e:
+                logger.error(f"Plugin '{name}' failed to report unsaved changes: {e}")
+        return {"status": "success", "approve": not unsaved, "unsaved": unsaved}
+
+    @command("$/pause", constants.CORE_PLUGIN_NAME)
+    async def pause(self, **kwargs) -> Dict[str, Any]:
+        """Stop ticking and ask plugins to hold background work, keeping state."""
+        return await self._set_paused(True)
+
+    @command("$/resume", constants.CORE_PLUGIN_NAME)
+    async def resume(self, **kwargs) -> Dict[str, Any]:
+        """Carry on with background work after $/pause."""
+        return await self._set_paused(False)
+
+    async def _set_paused(self, paused: bool) -> Dict[str, Any]:
+        if self._paused != paused:
+            self._paused = paused
+            hook = "on_pause" if paused else "on_resume"
+            for name, plugin in self.plugins.items():
+                handler = getattr(plugin, hook, None)
+                if handler is None:
+                    continue
+                try:
+                    await handler()
+                except Exception as e:
+                    logger.error(f"Plugin '{name}' failed in {hook}: {e}")
+            logger.info("Background work paused" if paused else "Background work resumed")
+        return {"status": "success", "paused": self._paused}
+
     @command("system.info", constants.CORE_PLUGIN_NAME)
     async def get_info(self) -> Dict[str, Any]:
         return {"vault": self.config.get("name"), "plugins": list(self.plugins.keys())}
@@ -484,6 +573,83 @@ class VaultBrain:
         """List all registered commands."""
         return {"status": "success", "commands": list(self.commands.keys())}
 
+    @command("vault.list_entries", constants.CORE_PLUGIN_NAME)
+    async def list_entries(
+        self, path: str = "", recursive: bool = True, **kwargs
+    ) -> Dict[str, Any]:
+        """List files and directories in the vault, skipping hidden entries, lib/
+        and vaults nested inside this one (they are listed by their own sidecar)."""
+        root = (self.vault_path / path).resolve()
+        vault_root = self.vault_path.resolve()
+        if not root.is_relative_to(vault_root) or not root.is_dir():
+            return {"status": "error", "error": f"Not a directory in the vault: {path}"}
+
+        return {"status": "success", "entries": self._scan_entries(root, recursive)}
+
+    def _scan_entries(self, root: Path, recursive: bool = True) -> List[Dict[str, Any]]:
+        """Walk the vault from root without following symlinks, which could
+        otherwise lead out of the vault or loop."""
+        vault_root = self.vault_path.resolve()
+        entries = []
+        pending = [root]
+        while pending:
+            directory = pending.pop()
+            try:
+                with os.scandir(directory) as it:
+                    children = sorted(it, key=lambda e: e.name)
+            except OSError:
+                continue
+            for entry in children:
+                if entry.name.startswith(".") or entry.name == "__pycache__":
+                    continue
+                if directory == vault_root and entry.name == constants.LIB_DIR:
+                    continue
+                path = Path(entry.path)
+                is_dir = entry.is_dir(follow_symlinks=False)
+                if is_dir and (path / constants.VAULT_CONFIG_FILE).is_file():
+                    continue
+                try:
+                    stat = entry.stat(follow_symlinks=False)
+                except OSError:
+                    continue
+                entries.append(
+                    {
+                        "path": path.relative_to(vault_root).as_posix(),
+                        "type": "dir" if is_dir else "file",
+                        "size": 0 if is_dir else stat.st_size,
+                        "modified": stat.st_mtime,
+                    }
+                )
+                if is_dir and recursive:
+                    pending.append(path)
+
+        entries.sort(key=lambda e: e["path"])
+        return entries
+
+    async def check_files_changed(self) -> None:
+        """Compare the vault against the last scan and send `file-changed` for
+        anything added, removed or modified since, so the host's cached
+        listings stay fresh whoever wrote the files."""
+        if not self.is_client_connected:
+            # Keep the old snapshot so changes made meanwhile are reported later
+            return
+        entries = await asyncio.to_thread(self._scan_entries, self.vault_path.resolve())
+        snapshot = {e["path"]: (e["type"], e["size"], e["modified"]) for e in entries}
+        previous, self._entries_snapshot = self._entries_snapshot, snapshot
+        if previous is None:
+            return
+        changed = sorted(
+            path
+            for path in previous.keys() | snapshot.keys()
+            if previous.get(path) != snapshot.get(path)
+        )
+        if changed:
+            self.notify_files_changed(changed)
+
+    def notify_files_changed(self, paths: List[str]) -> None:
+        """Tell the host and frontend that vault files changed."""
+        self.emit_to_frontend(constants.EventType.FILE_CHANGED, {"paths": paths})
+
     # =========================================================================
     # Settings API Commands
     # =========================================================================
@@ -1247,6 +1413,14 @@ class VaultBrain:
     # Event System (Frontend Notification + Pub/Sub)
     # =========================================================================
 
+    async def call_host(self, method: str, **params: Any) -> Any:
+        """
+        Call a host method (e.g. "save_dialog", "get_setting") and wait for
+        its result. The user is asked to allow each kind of request the
+        first time the vault makes it; denied requests raise HostRequestError.
+        """
+        return await self.ws_server.call_host(method, params)
+
     def notify_frontend(
         self, message: str, severity: str = constants.Severity.INFO
     ) -> None:
@@ -1266,6 +1440,7 @@ class VaultBrain:
         event_type: str,
         data: Dict[str, Any],
         scope: str = constants.EventScope.WINDOW,
+        coalesce_key: Optional[str] = None,
     ) -> None:
         """
         Send a raw event to the Frontend via WebSocket.
@@ -1274,15 +1449,19 @@ class VaultBrain:
             logger.debug(f"Skipping '{event_type}': Client not connected")
             return
 
+        params: Dict[str, Any] = {
+            "event_type": event_type,
+            "scope": scope,
+            "data": data,
+            "timestamp": time.time(),
+        }
+        if coalesce_key is not None:
+            params["coalesce_key"] = coalesce_key
+
         # Construct JSON-RPC notification
         msg = utils.build_request(
             method="trigger_event",
-            params={
-                "event_type": event_type,
-                "scope": scope,
-                "data": data,
-                "timestamp": time.time(),
-            },
+            params=params,
             request_id=utils.generate_id("evt_"),
         )
         self.ws_server.send_to_rust(msg)
@@ -1310,6 +1489,11 @@ class VaultBrain:
         logger.info("Starting tick loop...")
         while True:
             await asyncio.sleep(constants.DEFAULT_TICK_INTERVAL)
-            await self.publish(constants.CoreEvents.TICK)
+            try:
+                await self.check_files_changed()
+            except Exception as e:
+                logger.error(f"File watch error: {e}")
+            if not self._paused:
+                await self.publish(constants.CoreEvents.TICK)
 
     # Removed explicit _tick_plugins iteration
@@ -6,7 +6,13 @@ manages command/event exchange using JSON-RPC 2.0 protocol.
 """
 
 import asyncio
+import base64
+import contextlib
+import contextvars
 import json
+import os
+import zlib
+from pathlib import Path
 from typing import Optional, Dict, Any, Callable, Awaitable
 import websockets
 from websockets.exceptions import ConnectionClosed
@@ -21,6 +27,12 @@ CommandHandler = Callable[[Dict[str, Any]], Awaitable[Dict[str, Any]]]
 
 logger = logger.bind(name=__name__)
 
+# Connection whose message is being handled, so host requests made while
+# handling it go back to the same client
+_current_connection: contextvars.ContextVar[Any] = contextvars.ContextVar(
+    "current_connection", default=None
+)
+
 
 class WebSocketServer:
     """
@@ -36,16 +48,30 @@ class WebSocketServer:
         >>> await server.start()
     """
 
-    def __init__(self, port: int, host: str = constants.DEFAULT_WEBSOCKET_HOST):
+    def __init__(
+        self,
+        port: int,
+        host: str = constants.DEFAULT_WEBSOCKET_HOST,
+        ping_interval: Optional[float] = constants.WEBSOCKET_PING_INTERVAL,
+        uds_path: Optional[Path] = None,
+    ):
         """
         Initialize WebSocket server.
 
         Args:
             port: Port to listen on
             host: Host address to bind to (default: localhost)
+            ping_interval: Seconds between keepalive pings; a client that does
+                not answer within the same interval is disconnected. None disables.
+            uds_path: Unix domain socket to accept connections on as well, so
+                the host's commands avoid TCP. The port is still opened: the
+                frontend is a webview, which can only reach the sidecar with
+                a ws:// URL
         """
         self.port = port
         self.host = host
+        self.ping_interval = ping_interval
+        self.uds_path = uds_path
         self.connection: Optional[Any] = None
         self.message_queue: asyncio.Queue = asyncio.Queue()
         self.pending_messages: list[Dict[str, Any]] = []
@@ -62,11 +88,30 @@ class WebSocketServer:
         """
         logger.info(f"Starting WebSocket server on ws://{self.host}:{self.port}")
 
-        async with websockets.serve(
-            self.handle_connection,
-            self.host,
-            self.port,
-        ):
+        async with contextlib.AsyncExitStack() as servers:
+            # The socket is up before the port, which the host probes for
+            # readiness. The port is not skipped when the socket is in use:
+            # the frontend's WebSocket has no way to open a Unix socket.
+            if self.uds_path:
+                await servers.enter_async_context(
+                    websockets.unix_serve(
+                        self.handle_connection,
+                        str(self.uds_path),
+                        ping_interval=self.ping_interval,
+                        ping_timeout=self.ping_interval,
+                    )
+                )
+                os.chmod(self.uds_path, 0o600)
+                logger.info(f"WebSocket server listening on {self.uds_path}")
+            await servers.enter_async_context(
+                websockets.serve(
+                    self.handle_connection,
+                    self.host,
+                    self.port,
+                    ping_interval=self.ping_interval,
+                    ping_timeout=self.ping_interval,
+                )
+            )
             logger.info(f"WebSocket server listening on ws://{self.host}:{self.port}")
 
             # Send any pending messages that were queued before server started
@@ -89,6 +134,7 @@ class WebSocketServer:
         client_addr = websocket.remote_address
         logger.info(f"Client connected from {client_addr}")
         self.connection = websocket
+        _current_connection.set(websocket)
 
         try:
             async for message in websocket:
@@ -114,8 +160,6 @@ class WebSocketServer:
         Args:
             message: JSON-RPC message string
         """
-        request_id: Optional[str] = None
-
         try:
             # Parse JSON
             try:
@@ -126,51 +170,41 @@ class WebSocketServer:
                     message, f"JSON parse error: {e}"
                 )
 
-            # Validate JSON-RPC structure
-            try:
-                utils.validate_jsonrpc_message(data)
-            except exceptions.JSONRPCError as e:
-                logger.error(f"Invalid JSON-RPC message: {e.message}")
-                raise
-
-            # Extract message components
-            request_id = utils.get_request_id(data)
-            method = utils.get_method(data)
-            params = utils.get_params(data)
-
-            if not method:
-                logger.error(f"Message missing method: {data}")
-                return
-
-            logger.debug(f"Received command: {method}")
+            # Extension field: the Rust client accepts compressed responses
+            # at or above this many bytes
+            first = data[0] if isinstance(data, list) and data else data
+            compress_threshold = (
+                first.get("compress") if isinstance(first, dict) else None
+            )
 
-            try:
-                result = await self._execute_request(method, params, request_id)
-
-                # Send success response
-                response = utils.build_response(result, request_id=request_id)
-                await self.send(response)
-                logger.debug(f"Command '{method}' executed successfully")
-
-            except exceptions.MethodNotFoundError:
-                logger.warning(f"No handler registered for method: {method}")
-                error_response = utils.build_method_not_found(
-                    method=method,
-                    request_id=request_id,
-                )
-                await self.send(error_response)
+            # A JSON-RPC batch is answered with one array of responses
+            if isinstance(data, list):
+                if not data:
+                    logger.error("Empty JSON-RPC batch")
+                    await self.send(
+                        utils.build_error(
+                            constants.JSONRPC_INVALID_REQUEST, "Invalid Request"
+                        )
+                    )
+                    return
+                responses = []
+                for item in data:
+                    response = await self._dispatch_batch_item(item)
+                    if response is not None:
+                        responses.append(response)
+                logger.debug(f"Batch of {len(data)} executed")
+                if responses:
+                    await self.send(responses, compress_threshold=compress_threshold)
+                return
 
-            except Exception as e:
-                logger.exception(f"Execution error for '{method}': {e}")
-                error_response = utils.build_internal_error(
-                    message=str(e),
-                    details={
-                        "method": method,
-                        "error_type": type(e).__name__,
-                    },
-                    request_id=request_id,
-                )
-                await self.send(error_response)
+            response = await self._dispatch(
+                data,
+                stream=data.get("stream") is True,
+                binary=data.get("binary") is True,
+                compress_threshold=compress_threshold,
+            )
+            if response is not None:
+                await self.send(response, compress_threshold=compress_threshold)
 
         except exceptions.WebSocketMessageError as e:
             logger.error(f"Message handling error: {e.message}")
@@ -180,7 +214,136 @@ class WebSocketServer:
 
         except Exception as e:
             logger.exception(f"Unexpected error handling message: {e}")
-            self.close()
+            self.close(constants.WEBSOCKET_CLOSE_INTERNAL_ERROR, "internal error")
+
+    async def _dispatch(
+        self,
+        data: Dict[str, Any],
+        stream: bool = False,
+        binary: bool = False,
+        compress_threshold: Optional[int] = None,
+    ) -> Optional[Dict[str, Any]]:
+        """
+        Validate and execute a single JSON-RPC request.
+
+        Args:
+            data: The JSON-RPC request
+            stream: Whether the client accepts streamed results. If set, each
+                item produced by an async generator handler is sent as a
+                stream.chunk notification before the final response.
+            binary: Whether the client accepts bytes chunks as binary frames
+                rather than base64 strings
+            compress_threshold: Compression threshold for streamed chunks
+
+        Returns:
+            The response to send, or None if the message had no method
+        """
+        # Validate JSON-RPC structure
+        try:
+            utils.validate_jsonrpc_message(data)
+        except exceptions.JSONRPCError as e:
+            logger.error(f"Invalid JSON-RPC message: {e.message}")
+            raise
+
+        # Extract message components
+        request_id = utils.get_request_id(data)
+        method = utils.get_method(data)
+        params = utils.get_params(data)
+
+        if not method:
+            logger.error(f"Message missing method: {data}")
+            return None
+
+        logger.debug(f"Received command: {method}")
+
+        try:
+            result = await self._execute_request(method, params, request_id)
+            if hasattr(result, "__aiter__"):
+                result = await self._collect_stream(
+                    result, request_id, stream, binary, compress_threshold
+                )
+            logger.debug(f"Command '{method}' executed successfully")
+            return utils.build_response(result, request_id=request_id)
+
+        except exceptions.MethodNotFoundError:
+            logger.warning(f"No handler registered for method: {method}")
+            return utils.build_method_not_found(
+                method=method,
+                request_id=request_id,
+            )
+
+        except Exception as e:
+            logger.exception(f"Execution error for '{method}': {e}")
+            return utils.build_internal_error(
+                message=str(e),
+                details={
+                    "method": method,
+                    "error_type": type(e).__name__,
+                },
+                request_id=request_id,
+            )
+
+    async def _dispatch_batch_item(self, item: Any) -> Optional[Dict[str, Any]]:
+        """
+        Execute one entry of a batch. Entries that are not valid requests
+        are answered with an Invalid Request error rather than dropped.
+
+        Returns:
+            The response for the entry
+        """
+        request_id = item.get("id") if isinstance(item, dict) else None
+        if not isinstance(request_id, (str, int)) or isinstance(request_id, bool):
+            request_id = None
+        if not isinstance(item, dict) or not isinstance(item.get("method"), str):
+            logger.error(f"Invalid JSON-RPC request in batch: {str(item)[:100]}")
+            return utils.build_error(
+                constants.JSONRPC_INVALID_REQUEST,
+                "Invalid Request",
+                request_id=request_id,
+            )
+        try:
+            return await self._dispatch(item)
+        except exceptions.JSONRPCError as e:
+            logger.error(f"Invalid JSON-RPC message in batch: {e.message}")
+            return utils.build_error(e.code, e.message, request_id=request_id)
+
+    async def _collect_stream(
+        self,
+        chunks: Any,
+        request_id: Optional[str],
+        stream: bool,
+        binary: bool,
+        compress_threshold: Optional[int],
+    ) -> Any:
+        """
+        Drain a streaming handler's results. Bytes chunks go out as tagged
+        binary frames if the client asked for them, otherwise as base64.
+
+        Returns:
+            The number of chunks sent if streaming, otherwise the list of
+            all chunks as a single result
+        """
+        if not stream:
+            return [chunk async for chunk in chunks]
+
+        count = 0
+        async for chunk in chunks:
+            if isinstance(chunk, (bytes, bytearray)):
+                if binary:
+                    await self.send_binary_chunk(request_id, bytes(chunk))
+                    count += 1
+                    continue
+                chunk = base64.b64encode(chunk).decode("ascii")
+            await self.send(
+                {
+                    "jsonrpc": constants.JSONRPC_VERSION,
+                    "method": constants.STREAM_CHUNK_METHOD,
+                    "params": {"id": request_id, "chunk": chunk},
+                },
+                compress_threshold=compress_threshold,
+            )
+            count += 1
+        return {"chunks": count}
 
     async def _execute_request(
         self, method: str, params: Dict[str, Any], request_id: Optional[str]
@@ -199,33 +362,131 @@ class WebSocketServer:
         except exceptions.CommandNotFoundError:
             raise exceptions.MethodNotFoundError(method)
 
-    async def send(self, data: Dict[str, Any]) -> None:
+    async def call_host(
+        self,
+        method: str,
+        params: Optional[Dict[str, Any]] = None,
+        timeout: float = constants.HOST_REQUEST_TIMEOUT,
+    ) -> Any:
+        """
+        Call a host method and wait for its result.
+
+        Only valid while handling a command: the request goes to the client
+        that sent it, and other messages that arrive on that connection
+        before the response are handled in order while waiting.
+
+        Args:
+            method: Host method, with or without the "host." prefix
+                (e.g. "save_dialog")
+            params: Method parameters
+            timeout: Seconds to wait for the response
+
+        Returns:
+            The result of the call
+
+        Raises:
+            HostRequestError: If the host answers with an error
+            WebSocketError: If not called while handling a command
+            asyncio.TimeoutError: If no response arrives in time
+        """
+        websocket = _current_connection.get()
+        if websocket is None:
+            raise exceptions.WebSocketError(
+                "Host requests can only be made while handling a command"
+            )
+
+        if not method.startswith(constants.HOST_METHOD_PREFIX):
+            method = constants.HOST_METHOD_PREFIX + method
+        request_id = utils.generate_id("host_")
+        request = utils.build_request(method, params or {}, request_id=request_id)
+        await websocket.send(json.dumps(request))
+        logger.debug(f"Sent host request: {method}")
+
+        async def wait_for_response() -> Dict[str, Any]:
+            while True:
+                message = await websocket.recv()
+                try:
+                    data = json.loads(message)
+                except (json.JSONDecodeError, TypeError):
+                    data = None
+                if (
+                    isinstance(data, dict)
+                    and data.get("id") == request_id
+                    and "method" not in data
+                ):
+                    return data
+                await self.handle_message(message)
+
+        response = await asyncio.wait_for(wait_for_response(), timeout)
+        error = response.get("error")
+        if error:
+            raise exceptions.HostRequestError(
+                method,
+                error.get("message", str(error)),
+                error.get("code", -32000),
+            )
+        return response.get("result")
+
+    async def send(
+        self, data: Any, compress_threshold: Optional[int] = None
+    ) -> None:
         """
         Send message to Rust.
 
         Args:
-            data: Message data (will be JSON encoded)
+            data: Message data or batch of messages (will be JSON encoded)
+            compress_threshold: If set, payloads of at least this many bytes
+                are sent as a zlib-compressed binary frame
         """
         if self.is_connected():
             try:
-                await self.connection.send(json.dumps(data))
-                logger.debug(f"Sent message: {data.get('method', 'response')}")
+                payload = json.dumps(data)
+                if compress_threshold is not None and len(payload) >= compress_threshold:
+                    compressed = zlib.compress(payload.encode("utf-8"))
+                    logger.debug(
+                        f"Compressed response: {len(payload)} -> {len(compressed)} bytes"
+                    )
+                    await self.connection.send(compressed)
+                else:
+                    await self.connection.send(payload)
+                if isinstance(data, dict):
+                    logger.debug(f"Sent message: {data.get('method', 'response')}")
             except Exception as e:
                 logger.exception(f"Send error: {e}")
-                self.close()
+                self.close(constants.WEBSOCKET_CLOSE_INTERNAL_ERROR, "send failed")
         else:
             logger.warning("No active connection, cannot send message")
 
-    def close(self) -> None:
+    async def send_binary_chunk(self, request_id: Optional[str], data: bytes) -> None:
+        """
+        Send raw bytes of a streaming request as a binary frame tagged with
+        its id (see constants.BINARY_CHUNK_TAG).
+        """
+        if not self.is_connected():
+            logger.warning("No active connection, cannot send binary chunk")
+            return
+        tag = str(request_id or "").encode("utf-8")
+        frame = bytes([constants.BINARY_CHUNK_TAG]) + len(tag).to_bytes(2, "big") + tag + data
+        try:
+            await self.connection.send(frame)
+        except Exception as e:
+            logger.exception(f"Send error: {e}")
+            self.close(constants.WEBSOCKET_CLOSE_INTERNAL_ERROR, "send failed")
+
+    def close(
+        self, code: int = constants.WEBSOCKET_CLOSE_NORMAL, reason: str = ""
+    ) -> None:
         """
-        Close the WebSocket connection.
+        Close the WebSocket connection. Error paths pass
+        WEBSOCKET_CLOSE_INTERNAL_ERROR so the host does not take the close
+        for a deliberate shutdown.
         """
         if self.connection:
             try:
                 # Create a task to close the connection asynchronously
                 try:
                     loop = asyncio.get_running_loop()
-                    asyncio.create_task(self.connection.close())
+                    asyncio.create_task(self.connection.close(code, reason))
                 except RuntimeError:
                     # No running loop, connection will be closed when event loop ends
                     pass
@@ -0,0 +1,38 @@
+use serde::Serialize;
+
+/// Version and build details of the running backend, for bug reports and
+/// for the frontend and sidecars to check compatibility
+#[derive(Debug, Clone, Serialize)]
+pub struct AppInfo {
+    pub version: &'static str,
+    /// Short git commit hash the binary was built from, or "unknown"
+    pub build_hash: &'static str,
+    pub tauri_version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+}
+
+impl AppInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            build_hash: env!("TAILOR_BUILD_HASH"),
+            tauri_version: tauri::VERSION,
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_current() {
+        let info = AppInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.build_hash.is_empty());
+        assert!(info.tauri_version.starts_with("2."));
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+/// What an in-flight operation is doing, for reporting what was cancelled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    /// A sidecar command awaiting its response
+    Command,
+    /// A sidecar command streaming partial results or bytes
+    Stream,
+    /// pip (and any post-install script) installing a vault's requirements
+    DependencyInstall,
+}
+
+/// What `cancel_all` aborted for a window
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CancelReport {
+    pub cancelled: usize,
+    pub commands: usize,
+    pub streams: usize,
+    pub dependency_installs: usize,
+}
+
+struct Operation {
+    kind: OperationKind,
+    cancel: oneshot::Sender<()>,
+}
+
+/// Operations in flight on behalf of each window, so everything a window
+/// started can be aborted at once, e.g. when the user navigates away.
+/// Cancelling drops the operation's future, which closes its sidecar
+/// connection or kills its child processes.
+#[derive(Default)]
+pub struct OperationRegistry {
+    next_id: AtomicU64,
+    operations: Mutex<HashMap<String, HashMap<u64, Operation>>>,
+}
+
+/// Removes an operation from the registry once it finishes either way
+struct Registration<'a> {
+    registry: &'a OperationRegistry,
+    window_label: &'a str,
+    id: u64,
+}
+
+impl Drop for Registration<'_> {
+    fn drop(&mut self) {
+        let mut operations = self.registry.operations.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(window) = operations.get_mut(self.window_label) {
+            window.remove(&self.id);
+            if window.is_empty() {
+                operations.remove(self.window_label);
+            }
+        }
+    }
+}
+
+impl OperationRegistry {
+    /// Run `operation` for a window until it completes or the window's
+    /// operations are cancelled. Returns None if it was cancelled.
+    pub async fn run<T>(&self, window_label: &str, kind: OperationKind, operation: impl Future<Output = T>) -> Option<T> {
+        let (cancel, cancelled) = oneshot::channel();
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.operations.lock().unwrap_or_else(|e| e.into_inner())
+            .entry(window_label.to_string())
+            .or_default()
+            .insert(id, Operation { kind, cancel });
+        let _registration = Registration { registry: self, window_label, id };
+
+        tokio::select! {
+            output = operation => Some(output),
+            _ = cancelled => None,
+        }
+    }
+
+    /// Abort every operation in flight for a window
+    pub fn cancel_all(&self, window_label: &str) -> CancelReport {
+        let operations = self.operations.lock().unwrap_or_else(|e| e.into_inner())
+            .remove(window_label)
+            .unwrap_or_default();
+        let mut report = CancelReport::default();
+        for operation in operations.into_values() {
+            // The operation may have finished in the meantime
+            if operation.cancel.send(()).is_err() {
+                continue;
+            }
+            report.cancelled += 1;
+            match operation.kind {
+                OperationKind::Command => report.commands += 1,
+                OperationKind::Stream => report.streams += 1,
+                OperationKind::DependencyInstall => report.dependency_installs += 1,
+            }
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn in_flight(registry: &OperationRegistry, window_label: &str) -> usize {
+        registry.operations.lock().unwrap().get(window_label).map_or(0, HashMap::len)
+    }
+
+    #[tokio::test]
+    async fn test_cancel_all_aborts_window_operations() {
+        let registry = Arc::new(OperationRegistry::default());
+        let pending = |kind| {
+            let registry = registry.clone();
+            tokio::spawn(async move {
+                registry.run("w1", kind, std::future::pending::<()>()).await
+            })
+        };
+        let command = pending(OperationKind::Command);
+        let install = pending(OperationKind::DependencyInstall);
+        let (finish, finished) = oneshot::channel::<()>();
+        let other = {
+            let registry = registry.clone();
+            tokio::spawn(async move {
+                registry.run("w2", OperationKind::Stream, async { finished.await.unwrap() }).await
+            })
+        };
+        while in_flight(&registry, "w1") < 2 || in_flight(&registry, "w2") < 1 {
+            tokio::task::yield_now().await;
+        }
+
+        let report = registry.cancel_all("w1");
+        assert_eq!(report, CancelReport { cancelled: 2, commands: 1, streams: 0, dependency_installs: 1 });
+        assert_eq!(command.await.unwrap(), None);
+        assert_eq!(install.await.unwrap(), None);
+        assert_eq!(in_flight(&registry, "w1"), 0);
+
+        // Other windows' operations run to completion
+        finish.send(()).unwrap();
+        assert_eq!(other.await.unwrap(), Some(()));
+        assert_eq!(in_flight(&registry, "w2"), 0);
+        assert_eq!(registry.cancel_all("w2"), CancelReport::default());
+    }
+}
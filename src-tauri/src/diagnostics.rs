@@ -0,0 +1,281 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::AppState;
+use crate::app_info::AppInfo;
+use crate::config::{AppConfig, OutputCapture};
+use crate::dependency_checker::DependencyChecker;
+use crate::ipc_router;
+use crate::sidecar_manager::SidecarManager;
+
+/// Most bytes taken from the end of a vault's sidecar log file
+const LOG_FILE_TAIL_BYTES: u64 = 256 * 1024;
+
+/// Substrings of a key name that mark its value as a secret
+const SECRET_KEYS: &[&str] = &[
+    "token", "secret", "password", "passwd", "api_key", "apikey",
+    "authorization", "credential", "private_key",
+];
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Host and interpreter details included in a diagnostic bundle
+#[derive(Serialize)]
+struct EnvironmentInfo {
+    #[serde(flatten)]
+    app: AppInfo,
+    python_executable: Option<String>,
+    python_version: Option<String>,
+    config: AppConfig,
+}
+
+/// Named text files to be zipped into a diagnostic bundle. Everything added
+/// is redacted first.
+#[derive(Default)]
+pub struct DiagnosticBundle {
+    entries: Vec<(String, String)>,
+    /// Exact values to mask wherever they appear, e.g. vault secrets
+    known_secrets: Vec<String>,
+}
+
+impl DiagnosticBundle {
+    /// Also mask these values, beyond what looks like a secret
+    pub fn with_known_secrets(mut self, secrets: Vec<String>) -> Self {
+        self.known_secrets = secrets;
+        self
+    }
+
+    pub fn add_text(&mut self, name: impl Into<String>, text: &str) {
+        let text = redact_text(&self.mask_known_secrets(text));
+        self.entries.push((name.into(), text));
+    }
+
+    pub fn add_json(&mut self, name: impl Into<String>, value: &impl Serialize) -> Result<()> {
+        let mut value = serde_json::to_value(value)?;
+        redact_json(&mut value);
+        let text = self.mask_known_secrets(&serde_json::to_string_pretty(&value)?);
+        self.entries.push((name.into(), text));
+        Ok(())
+    }
+
+    fn mask_known_secrets(&self, text: &str) -> String {
+        self.known_secrets.iter()
+            // Very short values would mask unrelated text
+            .filter(|secret| secret.len() >= 4)
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED))
+    }
+
+    pub fn write_zip(&self, path: &Path) -> Result<()> {
+        let file = fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+        for (name, contents) in &self.entries {
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(contents.as_bytes())?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Gather logs, metrics, sidecar statuses, environment info and effective
+/// settings into a zip in the temp directory. Returns the zip's path.
+pub async fn export(app: &AppHandle, state: &AppState) -> Result<PathBuf> {
+    let vault_secrets = state.vault_secrets.clone();
+    let known_secrets = tokio::task::spawn_blocking(move || vault_secrets.all_values()).await?;
+    let mut bundle = DiagnosticBundle::default().with_known_secrets(known_secrets);
+
+    bundle.add_json("metrics.json", &state.metrics.snapshot())?;
+    bundle.add_json("sidecars.json", &state.sidecar_manager.all_status().await)?;
+    bundle.add_json("environment.json", &environment_info(app).await?)?;
+
+    for logs in state.sidecar_manager.logs().await {
+        let dir = format!("sidecars/{}", logs.window_label);
+        if logs.output_capture == OutputCapture::Capture {
+            bundle.add_text(format!("{}/stdout.log", dir), &logs.stdout);
+            bundle.add_text(format!("{}/stderr.log", dir), &logs.stderr);
+        } else {
+            bundle.add_text(format!("{}/output.txt", dir), &format!(
+                "Output was not captured (sidecar.output_capture = \"{}\")\n",
+                logs.output_capture.as_str(),
+            ));
+        }
+
+        let log_file = Path::new(&logs.vault_path).join(".tailor").join("logs").join("sidecar.log");
+        if let Some(tail) = read_tail(&log_file, LOG_FILE_TAIL_BYTES) {
+            bundle.add_text(format!("{}/sidecar.log", dir), &tail);
+        }
+    }
+
+    let windows: Vec<(String, String)> = {
+        let window_manager = state.window_manager.lock().await;
+        window_manager.get_active_windows()
+            .into_iter()
+            .filter_map(|label| Some((label.clone(), window_manager.get_vault_path(&label)?.clone())))
+            .collect()
+    };
+    for (window_label, vault_path) in windows {
+        match ipc_router::get_effective_settings(vault_path, app.clone()).await {
+            Ok(settings) => bundle.add_json(format!("settings/{}.json", window_label), &settings)?,
+            Err(e) => bundle.add_text(format!("settings/{}.error.txt", window_label), &e),
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "tailor-diagnostics-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    bundle.write_zip(&path)?;
+    println!("Exported diagnostics to {}", path.display());
+    Ok(path)
+}
+
+async fn environment_info(app: &AppHandle) -> Result<EnvironmentInfo> {
+    let config_path = app.path().app_config_dir()?.join("tailor.toml");
+    Ok(EnvironmentInfo {
+        app: AppInfo::current(),
+        python_executable: SidecarManager::get_python_executable().ok(),
+        python_version: DependencyChecker::python_version().await.ok(),
+        config: AppConfig::load(&config_path),
+    })
+}
+
+/// The last `max_bytes` of a file, starting at a line boundary
+fn read_tail(path: &Path, max_bytes: u64) -> Option<String> {
+    use std::io::{Read, Seek, SeekFrom};
+
+    let mut file = fs::File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let start = len.saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start)).ok()?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+
+    let text = String::from_utf8_lossy(&bytes).into_owned();
+    if start == 0 {
+        return Some(text);
+    }
+    Some(text.split_once('\n').map(|(_, rest)| rest.to_string()).unwrap_or(text))
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    SECRET_KEYS.iter().any(|secret| key.contains(secret))
+}
+
+/// Replace the values of secret-looking keys throughout a JSON value
+pub fn redact_json(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if is_secret_key(key) && !value.is_object() && !value.is_null() {
+                    *value = REDACTED.into();
+                } else {
+                    redact_json(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_json),
+        serde_json::Value::String(text) => *text = redact_text(text),
+        _ => {}
+    }
+}
+
+/// Mask likely secrets in free text: bearer tokens, `sk-` style API keys and
+/// values assigned to secret-looking keys (`token=...`, `"api_key": "..."`)
+pub fn redact_text(text: &str) -> String {
+    text.lines()
+        .map(redact_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn redact_line(line: &str) -> String {
+    let mut out = Vec::new();
+    let mut mask_next = false;
+
+    for word in line.split(' ') {
+        let trimmed = word.trim_matches(|c: char| "\"',;{}()[]".contains(c));
+        // Byte range of the trimmed word within `word`, to keep punctuation
+        let start = word.find(trimmed).unwrap_or_default();
+        let end = start + trimmed.len();
+        let key = trimmed.split(['=', ':']).next().unwrap_or_default();
+        let value = trimmed[key.len()..].trim_start_matches(['=', ':', '"', '\'']);
+
+        if trimmed.is_empty() {
+            out.push(word.to_string());
+        } else if mask_next {
+            // `Authorization: Bearer <token>` masks the token, not "Bearer"
+            mask_next = trimmed.eq_ignore_ascii_case("bearer");
+            if mask_next {
+                out.push(word.to_string());
+            } else {
+                out.push(format!("{}{}{}", &word[..start], REDACTED, &word[end..]));
+            }
+        } else if trimmed.eq_ignore_ascii_case("bearer") {
+            out.push(word.to_string());
+            mask_next = true;
+        } else if trimmed.starts_with("sk-") && trimmed.len() >= 20 {
+            out.push(format!("{}{}{}", &word[..start], REDACTED, &word[end..]));
+        } else if is_secret_key(key) && !value.is_empty() {
+            let value_start = end - value.len();
+            out.push(format!("{}{}{}", &word[..value_start], REDACTED, &word[end..]));
+        } else if is_secret_key(key) && trimmed.len() > key.len() {
+            // `key: value` with the value in the next word
+            out.push(word.to_string());
+            mask_next = true;
+        } else {
+            out.push(word.to_string());
+        }
+    }
+    out.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_text() {
+        assert_eq!(redact_text("Authorization: Bearer abc.def"), "Authorization: Bearer [REDACTED]");
+        assert_eq!(redact_text("using key sk-0123456789abcdefghij now"), "using key [REDACTED] now");
+        assert_eq!(redact_text("GITHUB_TOKEN=ghp_123 started"), "GITHUB_TOKEN=[REDACTED] started");
+        assert_eq!(redact_text("{\"api_key\": \"xyz\"}"), "{\"api_key\": \"[REDACTED]\"}");
+        assert_eq!(redact_text("loaded 3 plugins"), "loaded 3 plugins");
+    }
+
+    #[test]
+    fn test_redact_json() {
+        let mut value = serde_json::json!({
+            "theme": "dark",
+            "providers": { "openai": { "api_key": "sk-secret", "model": "gpt" } },
+            "headers": ["Bearer abc"]
+        });
+        redact_json(&mut value);
+        assert_eq!(value["theme"], "dark");
+        assert_eq!(value["providers"]["openai"]["api_key"], REDACTED);
+        assert_eq!(value["providers"]["openai"]["model"], "gpt");
+        assert_eq!(value["headers"][0], "Bearer [REDACTED]");
+    }
+
+    #[test]
+    fn test_write_zip() {
+        let path = std::env::temp_dir().join(format!("tailor_diag_{}.zip", uuid::Uuid::new_v4()));
+        let mut bundle = DiagnosticBundle::default().with_known_secrets(vec!["vault-secret-1".to_string()]);
+        bundle.add_text("notes.txt", "password=hunter2 sent vault-secret-1");
+        bundle.write_zip(&path).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        let mut contents = String::new();
+        std::io::Read::read_to_string(&mut archive.by_name("notes.txt").unwrap(), &mut contents).unwrap();
+        assert_eq!(contents, "password=[REDACTED] sent [REDACTED]");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
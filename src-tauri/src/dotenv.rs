@@ -0,0 +1,179 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::vault_secrets::is_reserved_env_name;
+
+/// File in a vault whose variables are added to its sidecar's environment
+pub const DOTENV_FILE: &str = ".env";
+
+/// Variables from a vault's `.env` for its sidecar, or none if it has no
+/// such file. Lines that cannot be parsed, and variables it may not set,
+/// are reported and skipped.
+pub fn load(vault_path: &Path) -> BTreeMap<String, String> {
+    let path = vault_path.join(DOTENV_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return BTreeMap::new(),
+        Err(e) => {
+            eprintln!("Ignoring {}: {}", path.display(), e);
+            return BTreeMap::new();
+        }
+    };
+    let (mut vars, mut problems) = parse(&contents);
+    problems.extend(drop_disallowed(&mut vars, |name| std::env::var_os(name).is_some()));
+    for problem in problems {
+        eprintln!("Ignoring {} {}", path.display(), problem);
+    }
+    vars
+}
+
+/// Remove the variables a vault may not set: those that change how the
+/// sidecar is loaded or run (`LD_*`, `PYTHON*`, `PATH`, ...), and those
+/// already `inherited` from the host, which win. Returns why each went.
+pub fn drop_disallowed(vars: &mut BTreeMap<String, String>, inherited: impl Fn(&str) -> bool) -> Vec<String> {
+    let mut problems = Vec::new();
+    vars.retain(|name, _| {
+        let problem = if is_reserved_env_name(name) {
+            "reserved name"
+        } else if inherited(name) {
+            "already set by the host"
+        } else {
+            return true;
+        };
+        problems.push(format!("{}: {}", name, problem));
+        false
+    });
+    problems
+}
+
+/// Parse `KEY=VALUE` lines as python-dotenv does for the common cases:
+/// blank lines and `#` comments are skipped, an `export ` prefix is
+/// allowed, double-quoted values take `\n`, `\"` and `\\` escapes,
+/// single-quoted values are literal, and unquoted values end at ` #`.
+/// Later assignments win. Returns the variables and each unparsable line.
+pub fn parse(contents: &str) -> (BTreeMap<String, String>, Vec<String>) {
+    let mut vars = BTreeMap::new();
+    let mut problems = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").map_or(line, str::trim_start);
+        let Some((key, value)) = line.split_once('=') else {
+            problems.push(format!("line {}: expected KEY=VALUE", index + 1));
+            continue;
+        };
+        let key = key.trim();
+        let valid_key = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_key {
+            problems.push(format!("line {}: invalid variable name '{}'", index + 1, key));
+            continue;
+        }
+        match parse_value(value.trim()) {
+            Some(value) => {
+                vars.insert(key.to_string(), value);
+            }
+            None => problems.push(format!("line {}: unterminated quote in {}", index + 1, key)),
+        }
+    }
+    (vars, problems)
+}
+
+/// The value of an assignment; None if a quote is never closed
+fn parse_value(raw: &str) -> Option<String> {
+    if let Some(rest) = raw.strip_prefix('\'') {
+        return rest.find('\'').map(|end| rest[..end].to_string());
+    }
+    if let Some(rest) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = rest.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => return Some(value),
+                '\\' => match chars.next()? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    other @ ('"' | '\\') => value.push(other),
+                    other => {
+                        value.push('\\');
+                        value.push(other);
+                    }
+                },
+                c => value.push(c),
+            }
+        }
+        return None;
+    }
+    // An inline comment needs whitespace before it, so `a#b` stays whole
+    let end = raw.char_indices()
+        .find(|&(i, c)| c == '#' && raw[..i].ends_with(char::is_whitespace))
+        .map_or(raw.len(), |(i, _)| i);
+    Some(raw[..end].trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dotenv() {
+        let contents = "\
+# Model settings
+OPENAI_API_KEY=sk-123
+export HF_HOME = /data/hf   # shared cache
+GREETING=\"Hello \\\"there\\\"\\nfriend\" # quoted
+RAW='no $expansion \\n here'
+COLOR=#fff
+EMPTY=
+OPENAI_API_KEY=sk-456
+
+not an assignment
+1BAD=x
+OPEN=\"never closed
+";
+        let (vars, problems) = parse(contents);
+        assert_eq!(vars["OPENAI_API_KEY"], "sk-456");
+        assert_eq!(vars["HF_HOME"], "/data/hf");
+        assert_eq!(vars["GREETING"], "Hello \"there\"\nfriend");
+        assert_eq!(vars["RAW"], "no $expansion \\n here");
+        assert_eq!(vars["COLOR"], "#fff");
+        assert_eq!(vars["EMPTY"], "");
+        assert_eq!(vars.len(), 6);
+        assert_eq!(problems, [
+            "line 10: expected KEY=VALUE",
+            "line 11: invalid variable name '1BAD'",
+            "line 12: unterminated quote in OPEN",
+        ]);
+    }
+
+    #[test]
+    fn test_load_skips_disallowed_variables() {
+        let dir = std::env::temp_dir().join(format!("tailor_dotenv_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Any variable this process inherited stands in for the host's own
+        let inherited = std::env::vars()
+            .map(|(name, _)| name)
+            .find(|name| !is_reserved_env_name(name) && parse(&format!("{}=x", name)).0.contains_key(name))
+            .unwrap();
+        std::fs::write(dir.join(DOTENV_FILE), format!("\
+LD_PRELOAD=/tmp/evil.so
+DYLD_INSERT_LIBRARIES=/tmp/evil.dylib
+PYTHONPATH=/tmp
+path=/tmp
+{}=overridden
+OPENAI_API_KEY=sk-123
+", inherited)).unwrap();
+
+        let vars = load(&dir);
+        assert_eq!(vars.keys().collect::<Vec<_>>(), ["OPENAI_API_KEY"]);
+
+        let mut vars = BTreeMap::from([("HOME".to_string(), "/tmp".to_string()), ("TOKEN".to_string(), "t".to_string())]);
+        let problems = drop_disallowed(&mut vars, |name| name == "TOKEN");
+        assert!(vars.is_empty());
+        assert_eq!(problems, ["HOME: reserved name", "TOKEN: already set by the host"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
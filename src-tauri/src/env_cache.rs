@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+/// Marker touched whenever a cached environment is used, for LRU eviction
+const LAST_USED_FILE: &str = ".last-used";
+
+/// Content-addressed cache of installed plugin `lib/` directories, keyed by
+/// the hash of the requirements they were built from. Vaults with identical
+/// requirements copy a prebuilt directory instead of running pip again.
+pub struct EnvCache {
+    dir: PathBuf,
+    max_bytes: u64,
+}
+
+impl EnvCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self { dir, max_bytes }
+    }
+
+    /// Hash identifying a set of requirements
+    pub fn key(requirements: &str) -> String {
+        let digest = Sha256::digest(requirements.as_bytes());
+        digest.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Replace `lib_dir` with the cached environment for `key`.
+    /// Returns false if nothing is cached for it.
+    pub fn restore(&self, key: &str, lib_dir: &Path) -> Result<bool> {
+        let entry = self.dir.join(key);
+        if !entry.is_dir() {
+            return Ok(false);
+        }
+
+        if lib_dir.exists() {
+            fs::remove_dir_all(lib_dir).context("Failed to clear lib directory")?;
+        }
+        copy_dir_all(&entry, lib_dir, &[LAST_USED_FILE]).context("Failed to copy cached environment")?;
+        Self::touch(&entry)?;
+        Ok(true)
+    }
+
+    /// Save a freshly built `lib_dir` under `key`, then evict the least
+    /// recently used environments until the cache fits its size cap
+    ///
+    /// The copy is built beside the entry and renamed into place, so a
+    /// concurrent `restore` never sees it half-written.
+    pub fn store(&self, key: &str, lib_dir: &Path) -> Result<()> {
+        let entry = self.dir.join(key);
+        let staging = self.dir.join(format!(".{}.{}", key, uuid::Uuid::new_v4()));
+        let built = copy_dir_all(lib_dir, &staging, &[])
+            .context("Failed to cache environment")
+            .and_then(|_| Self::touch(&staging));
+        if let Err(e) = built {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+
+        // A directory cannot be renamed over a non-empty one; move the old
+        // entry aside first and drop it once the new one is in place
+        let replaced = self.dir.join(format!(".{}.{}", key, uuid::Uuid::new_v4()));
+        let had_entry = match fs::rename(&entry, &replaced) {
+            Ok(()) => true,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&staging);
+                return Err(e).context("Failed to replace cached environment");
+            }
+        };
+        if let Err(e) = fs::rename(&staging, &entry) {
+            let _ = fs::remove_dir_all(&staging);
+            if had_entry {
+                let _ = fs::rename(&replaced, &entry);
+            }
+            return Err(e).context("Failed to cache environment");
+        }
+        if had_entry {
+            fs::remove_dir_all(&replaced).context("Failed to remove replaced cached environment")?;
+        }
+        self.evict(key)
+    }
+
+    /// Drop the cached environment for `key`, e.g. one a vault's reset
+    /// showed to be broken. Returns whether there was one.
+    pub fn remove(&self, key: &str) -> Result<bool> {
+        let entry = self.dir.join(key);
+        if !entry.is_dir() {
+            return Ok(false);
+        }
+        fs::remove_dir_all(&entry).context("Failed to remove cached environment")?;
+        Ok(true)
+    }
+
+    /// Remove least recently used entries (never `keep`) while over the cap
+    fn evict(&self, keep: &str) -> Result<()> {
+        let mut entries = Vec::new();
+        let mut total = 0;
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            // Hidden directories are copies `store` has not renamed into place yet
+            let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if !path.is_dir() || hidden {
+                continue;
+            }
+            let size = dir_size(&path)?;
+            let last_used = fs::metadata(path.join(LAST_USED_FILE))
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            total += size;
+            entries.push((last_used, size, path));
+        }
+
+        entries.sort_by_key(|(last_used, _, _)| *last_used);
+        for (_, size, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if path.file_name().is_some_and(|name| name == keep) {
+                continue;
+            }
+            println!("Evicting cached environment: {}", path.display());
+            fs::remove_dir_all(&path)?;
+            total -= size;
+        }
+        Ok(())
+    }
+
+    fn touch(entry: &Path) -> Result<()> {
+        fs::write(entry.join(LAST_USED_FILE), chrono::Utc::now().to_rfc3339())
+            .context("Failed to mark cached environment as used")
+    }
+}
+
+/// Recursively copy `src` to `dst`, skipping top-level names in `skip`
+fn copy_dir_all(src: &Path, dst: &Path, skip: &[&str]) -> std::io::Result<()> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        if skip.iter().any(|name| entry.file_name() == *name) {
+            continue;
+        }
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target, &[])?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() { dir_size(&entry.path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fake_lib(root: &Path, name: &str, bytes: usize) -> PathBuf {
+        let lib = root.join(name);
+        fs::create_dir_all(lib.join("pkg")).unwrap();
+        fs::write(lib.join("pkg").join("__init__.py"), "x".repeat(bytes)).unwrap();
+        lib
+    }
+
+    #[test]
+    fn test_store_and_restore() {
+        let root = std::env::temp_dir().join(format!("tailor_env_cache_{}", uuid::Uuid::new_v4()));
+        let cache = EnvCache::new(root.join("cache"), 1024 * 1024);
+        let key = EnvCache::key("requests==2.31.0\n");
+        assert_eq!(key.len(), 64);
+
+        let target = root.join("vault_b").join("lib");
+        assert!(!cache.restore(&key, &target).unwrap());
+
+        cache.store(&key, &fake_lib(&root, "vault_a_lib", 10)).unwrap();
+        assert!(cache.restore(&key, &target).unwrap());
+        assert!(target.join("pkg").join("__init__.py").exists());
+        assert!(!target.join(LAST_USED_FILE).exists());
+
+        assert!(cache.remove(&key).unwrap());
+        assert!(!cache.remove(&key).unwrap());
+        assert!(!cache.restore(&key, &target).unwrap());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_store_replaces_entry() {
+        let root = std::env::temp_dir().join(format!("tailor_env_replace_{}", uuid::Uuid::new_v4()));
+        let cache = EnvCache::new(root.join("cache"), 1024 * 1024);
+
+        cache.store("key", &fake_lib(&root, "a", 10)).unwrap();
+        cache.store("key", &fake_lib(&root, "b", 20)).unwrap();
+        let contents = fs::read_to_string(root.join("cache").join("key").join("pkg").join("__init__.py")).unwrap();
+        assert_eq!(contents.len(), 20);
+        // Only the entry is left; no staged or replaced copies
+        assert_eq!(fs::read_dir(root.join("cache")).unwrap().count(), 1);
+
+        // A failed copy leaves the old entry alone
+        assert!(cache.store("key", &root.join("missing")).is_err());
+        assert!(root.join("cache").join("key").join("pkg").exists());
+        assert_eq!(fs::read_dir(root.join("cache")).unwrap().count(), 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let root = std::env::temp_dir().join(format!("tailor_env_evict_{}", uuid::Uuid::new_v4()));
+        let cache = EnvCache::new(root.join("cache"), 1500);
+
+        cache.store("old", &fake_lib(&root, "a", 1000)).unwrap();
+        cache.store("new", &fake_lib(&root, "b", 1000)).unwrap();
+
+        assert!(!root.join("cache").join("old").exists());
+        assert!(root.join("cache").join("new").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
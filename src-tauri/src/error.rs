@@ -0,0 +1,244 @@
+use std::fmt;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Errors with enough structure for the frontend to present them usefully
+#[derive(Debug, Clone, PartialEq)]
+pub enum TailorError {
+    /// The sidecar process exited, or was killed for hanging, while starting up
+    SidecarSpawnFailed { stderr: String },
+    /// Neither a pip binary nor `python -m pip` could be run
+    PipNotFound { attempted: Vec<String> },
+    /// pip found no distribution of a requirement that installs on this
+    /// machine, typically a wheel pinned for another OS or CUDA build.
+    /// `package` is the requirement as pip reported it, e.g. `torch==2.0.1+cu118`.
+    PlatformIncompatibleDependency { package: String },
+    /// The command never got a response (connection failure, timeout, ...)
+    SidecarUnreachable { message: String },
+    /// No tracked sidecar for the window is listening on the given port
+    SidecarNotFound { window_label: String, ws_port: u16 },
+    /// A wait gave up before the awaited state was reached
+    Timeout { message: String },
+    /// Aborted by `cancel_all` before it finished
+    Cancelled { operation: String },
+    /// The method is not among those the sidecar advertised in its handshake
+    MethodNotSupported { method: String },
+    /// A command or response exceeded the configured size limit
+    PayloadTooLarge { size: usize, limit: usize },
+    /// JSON-RPC -32700: the sidecar could not parse the request
+    ParseError { message: String, data: Option<serde_json::Value> },
+    /// JSON-RPC -32600: the request was not a valid JSON-RPC request
+    InvalidRequest { message: String, data: Option<serde_json::Value> },
+    /// JSON-RPC -32601: the sidecar has no handler for the method
+    MethodNotFound { message: String, data: Option<serde_json::Value> },
+    /// JSON-RPC -32602: the handler rejected the parameters
+    InvalidParams { message: String, data: Option<serde_json::Value> },
+    /// Any other JSON-RPC error returned by the sidecar
+    SidecarError { code: i64, message: String, data: Option<serde_json::Value> },
+    /// Opening a vault failed for another reason (a missing directory, a
+    /// failed install, ...)
+    OpenFailed { message: String },
+}
+
+/// The `error` member of a JSON-RPC response
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+    #[serde(default)]
+    data: Option<serde_json::Value>,
+}
+
+impl TailorError {
+    /// Map the `error` object of a JSON-RPC response
+    pub fn from_rpc_error(error: &serde_json::Value) -> Self {
+        let RpcError { code, message, data } = match serde_json::from_value(error.clone()) {
+            Ok(error) => error,
+            Err(_) => return TailorError::SidecarError {
+                code: -32603,
+                message: format!("Malformed error from sidecar: {}", error),
+                data: None,
+            },
+        };
+
+        match code {
+            -32700 => TailorError::ParseError { message, data },
+            -32600 => TailorError::InvalidRequest { message, data },
+            -32601 => TailorError::MethodNotFound { message, data },
+            -32602 => TailorError::InvalidParams { message, data },
+            _ => TailorError::SidecarError { code, message, data },
+        }
+    }
+
+    /// Map a failure to exchange a command with the sidecar, keeping
+    /// structured errors (e.g. an oversized payload) as they are
+    pub fn from_transport_error(error: anyhow::Error) -> Self {
+        match error.downcast::<TailorError>() {
+            Ok(error) => error,
+            Err(error) => TailorError::SidecarUnreachable { message: error.to_string() },
+        }
+    }
+
+    /// Short machine-readable name of the variant
+    fn kind(&self) -> &'static str {
+        match self {
+            TailorError::SidecarSpawnFailed { .. } => "sidecar_spawn_failed",
+            TailorError::PipNotFound { .. } => "pip_not_found",
+            TailorError::PlatformIncompatibleDependency { .. } => "platform_incompatible_dependency",
+            TailorError::SidecarUnreachable { .. } => "sidecar_unreachable",
+            TailorError::SidecarNotFound { .. } => "sidecar_not_found",
+            TailorError::Timeout { .. } => "timeout",
+            TailorError::Cancelled { .. } => "cancelled",
+            TailorError::MethodNotSupported { .. } => "method_not_supported",
+            TailorError::PayloadTooLarge { .. } => "payload_too_large",
+            TailorError::ParseError { .. } => "parse_error",
+            TailorError::InvalidRequest { .. } => "invalid_request",
+            TailorError::MethodNotFound { .. } => "method_not_found",
+            TailorError::InvalidParams { .. } => "invalid_params",
+            TailorError::SidecarError { .. } => "sidecar_error",
+            TailorError::OpenFailed { .. } => "open_failed",
+        }
+    }
+
+    /// JSON-RPC error code, for errors reported by the sidecar
+    fn code(&self) -> Option<i64> {
+        match self {
+            TailorError::SidecarSpawnFailed { .. }
+            | TailorError::PipNotFound { .. }
+            | TailorError::PlatformIncompatibleDependency { .. }
+            | TailorError::SidecarUnreachable { .. }
+            | TailorError::SidecarNotFound { .. }
+            | TailorError::Timeout { .. }
+            | TailorError::Cancelled { .. }
+            | TailorError::MethodNotSupported { .. }
+            | TailorError::PayloadTooLarge { .. }
+            | TailorError::OpenFailed { .. } => None,
+            TailorError::ParseError { .. } => Some(-32700),
+            TailorError::InvalidRequest { .. } => Some(-32600),
+            TailorError::MethodNotFound { .. } => Some(-32601),
+            TailorError::InvalidParams { .. } => Some(-32602),
+            TailorError::SidecarError { code, .. } => Some(*code),
+        }
+    }
+
+    fn data(&self) -> Option<&serde_json::Value> {
+        match self {
+            TailorError::ParseError { data, .. }
+            | TailorError::InvalidRequest { data, .. }
+            | TailorError::MethodNotFound { data, .. }
+            | TailorError::InvalidParams { data, .. }
+            | TailorError::SidecarError { data, .. } => data.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TailorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TailorError::SidecarSpawnFailed { stderr } if stderr.is_empty() => {
+                write!(f, "Sidecar failed during startup")
+            }
+            TailorError::SidecarSpawnFailed { stderr } => {
+                write!(f, "Sidecar failed during startup:\n{}", stderr)
+            }
+            TailorError::PipNotFound { attempted } => {
+                write!(f, "pip not found (tried: {})", attempted.join(", "))
+            }
+            TailorError::PlatformIncompatibleDependency { package } => write!(
+                f,
+                "'{}' is not available for this platform ({} {}). The vault's requirements.txt \
+                 may pin a build for another OS or CUDA version; relax that pin to install it here.",
+                package,
+                std::env::consts::OS,
+                std::env::consts::ARCH
+            ),
+            TailorError::SidecarUnreachable { message } => write!(f, "Sidecar error: {}", message),
+            TailorError::SidecarNotFound { window_label, ws_port } => {
+                write!(f, "No sidecar for window '{}' is listening on port {}", window_label, ws_port)
+            }
+            TailorError::Timeout { message } => write!(f, "Timed out: {}", message),
+            TailorError::Cancelled { operation } => write!(f, "Cancelled: {}", operation),
+            TailorError::MethodNotSupported { method } => {
+                write!(f, "Sidecar does not support '{}'", method)
+            }
+            TailorError::PayloadTooLarge { size, limit } => {
+                write!(f, "Payload of {} bytes exceeds the {} byte limit", size, limit)
+            }
+            TailorError::ParseError { message, .. }
+            | TailorError::InvalidRequest { message, .. }
+            | TailorError::MethodNotFound { message, .. }
+            | TailorError::InvalidParams { message, .. }
+            | TailorError::SidecarError { message, .. } => {
+                write!(f, "Sidecar error {}: {}", self.code().unwrap_or_default(), message)
+            }
+            TailorError::OpenFailed { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for TailorError {}
+
+/// Steps of the open pipeline still report failures as plain messages
+impl From<String> for TailorError {
+    fn from(message: String) -> Self {
+        TailorError::OpenFailed { message }
+    }
+}
+
+/// Serialized as `{ kind, code, message, data }` for the frontend
+impl Serialize for TailorError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Body<'a> {
+            kind: &'static str,
+            code: Option<i64>,
+            message: String,
+            data: Option<&'a serde_json::Value>,
+        }
+
+        Body {
+            kind: self.kind(),
+            code: self.code(),
+            message: self.to_string(),
+            data: self.data(),
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maps_standard_rpc_codes() {
+        let error = TailorError::from_rpc_error(&serde_json::json!({
+            "code": -32601,
+            "message": "Method not found: notes.tag"
+        }));
+        assert_eq!(error, TailorError::MethodNotFound {
+            message: "Method not found: notes.tag".to_string(),
+            data: None,
+        });
+
+        let error = TailorError::from_rpc_error(&serde_json::json!({
+            "code": -32000,
+            "message": "Vault locked",
+            "data": { "retry": true }
+        }));
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "sidecar_error");
+        assert_eq!(json["code"], -32000);
+        assert_eq!(json["data"]["retry"], true);
+    }
+
+    #[test]
+    fn test_open_failures_keep_their_message() {
+        let error = TailorError::from("Vault directory does not exist".to_string());
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "open_failed");
+        assert_eq!(json["message"], "Vault directory does not exist");
+
+        let error = TailorError::Timeout { message: "opening /tmp/vault did not finish within 5s".to_string() };
+        assert_eq!(serde_json::to_value(&error).unwrap()["kind"], "timeout");
+    }
+}
@@ -1,9 +1,30 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Manager};
 
+use crate::config::SchemaValidation;
+use crate::event_log::EventLog;
+use crate::event_schema::EventSchemas;
+use crate::metrics::Metrics;
+
+/// Most events held for a paused window before the oldest are dropped
+const PAUSED_EVENT_CAP: usize = 256;
+
+/// How long to wait for the frontend to acknowledge an event before re-emitting it
+const ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Times an unacknowledged event is re-emitted before it is given up on
+const ACK_RETRIES: u32 = 2;
+
+/// How long events for a window are collected before being emitted together
+/// as one `sidecar-events` array (about one animation frame)
+const BATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(16);
+
+/// Batch size at which a window's batch is emitted without waiting
+const BATCH_MAX_EVENTS: usize = 256;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct Event {
@@ -11,6 +32,34 @@ pub struct Event {
     pub scope: EventScope,
     pub data: serde_json::Value,
     pub timestamp: f64,
+    /// Identifies the event in `ack_event`; assigned on delivery if missing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Re-emit until the frontend calls `ack_event` for it
+    #[serde(default)]
+    pub requires_ack: bool,
+    /// Emit on its own as `sidecar-event` right away rather than in the next
+    /// `sidecar-events` batch. Events that require an ack are always immediate.
+    #[serde(default)]
+    pub immediate: bool,
+    /// Window whose sidecar sent the event; assigned on routing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_window: Option<String>,
+    /// Position among the events routed from `source_window`'s sidecar to
+    /// the receiving window, starting at 1 and without gaps, so receivers
+    /// can detect lost or reordered events. Restarts at 1 after
+    /// `event-sequence-reset`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    /// Events of the same type and key from the same sidecar supersede one
+    /// another while waiting for delivery, so only the newest is emitted.
+    /// Set by the policy if the event does not declare one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coalesce_key: Option<String>,
+    /// Sequence numbers of the events this one superseded, so receivers do
+    /// not take them for lost events
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub superseded: Vec<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,31 +71,325 @@ pub enum EventScope {
     Vault(String),
 }
 
+/// What to do with a `Global` event whose type the vault policy disallows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DisallowedAction {
+    /// Deliver to the source window only
+    #[default]
+    Downgrade,
+    /// Discard the event entirely
+    Drop,
+}
+
+/// Per-vault restrictions on which event types a sidecar may broadcast globally.
+/// Read from the `[events]` table of `.vault.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventPolicy {
+    /// If set, only these event types may use `Global` scope
+    pub global_allow: Option<Vec<String>>,
+    /// Event types that may never use `Global` scope
+    pub global_deny: Vec<String>,
+    pub on_disallowed: DisallowedAction,
+    /// Event types where only the latest value matters (cursor position,
+    /// progress), each mapped to the data field whose value keys the
+    /// coalescing, e.g. "editor_id"; "" coalesces every event of the type
+    pub coalesce: HashMap<String, String>,
+}
+
+impl EventPolicy {
+    /// Check whether an event type may be broadcast to all windows
+    pub fn allows_global(&self, event_type: &str) -> bool {
+        if self.global_deny.iter().any(|t| t == event_type) {
+            return false;
+        }
+        match &self.global_allow {
+            Some(allowed) => allowed.iter().any(|t| t == event_type),
+            None => true,
+        }
+    }
+
+    /// Coalescing key for an event that does not declare one. None if its
+    /// type is not coalesced or the data lacks the key field.
+    pub fn coalesce_key(&self, event_type: &str, data: &serde_json::Value) -> Option<String> {
+        let field = self.coalesce.get(event_type)?;
+        if field.is_empty() {
+            return Some(String::new());
+        }
+        match data.get(field)? {
+            serde_json::Value::String(value) => Some(value.clone()),
+            value => Some(value.to_string()),
+        }
+    }
+}
+
+/// Whether a pending event is superseded by `event`: same source, type and
+/// coalescing key, and neither needs an ack. If so, its sequence numbers
+/// move to `event`.
+fn supersedes(event: &mut Event, pending: &Event) -> bool {
+    let superseded = event.coalesce_key.is_some()
+        && !event.requires_ack
+        && !pending.requires_ack
+        && pending.coalesce_key == event.coalesce_key
+        && pending.event_type == event.event_type
+        && pending.source_window == event.source_window;
+    if superseded {
+        event.superseded.extend(pending.seq.iter().chain(&pending.superseded));
+        event.superseded.sort_unstable();
+    }
+    superseded
+}
+
+/// What became of an event routed to one window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    Sent,
+    /// Queued for the window's next `sidecar-events` batch. Not logged;
+    /// the outcome of emitting the batch is logged instead.
+    Batched,
+    /// Queued because delivery to the window is paused
+    Held,
+    /// The window no longer exists and was unregistered
+    WindowGone,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Delivery {
+    pub window_label: String,
+    pub outcome: DeliveryOutcome,
+    /// Sequence number the event carried to this window
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// What the EventBus knows about one window, for debugging routing
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutingEntry {
+    pub window_label: String,
+    /// None if the window has state here but is not registered, so it
+    /// receives neither `Global` nor `Vault` events
+    pub vault_id: Option<String>,
+    /// Scopes of events routed to this window: "window", "global" and
+    /// "vault:<id>" for its active vault
+    pub subscriptions: Vec<String>,
+    /// Vault whose `Vault` events the window receives, if not its own
+    pub active_vault: Option<String>,
+    /// Other vaults the window is attached to
+    pub attached_vaults: Vec<String>,
+    /// Global-emission policy of the window's vault, if one is set
+    pub policy: Option<EventPolicy>,
+    pub paused: bool,
+    /// Events held while paused
+    pub held_events: usize,
+    /// Events waiting for the next batch emit
+    pub batched_events: usize,
+    pub pending_acks: usize,
+    /// Last sequence number given to events delivered to this window, by
+    /// the window whose sidecar sent them
+    pub last_seqs: BTreeMap<String, u64>,
+}
+
+/// One line of the event log
+#[derive(Serialize)]
+struct RoutedEvent<'a> {
+    logged_at: String,
+    source_window: &'a str,
+    /// Discarded by the source vault's event policy
+    dropped: bool,
+    event: &'a Event,
+    deliveries: &'a [Delivery],
+}
+
 pub struct EventBus {
     // Map window labels to their vault IDs
-    #[allow(dead_code)]
     window_vaults: Arc<Mutex<HashMap<String, String>>>,
+    // Map vault IDs to their global-emission policy
+    policies: Arc<Mutex<HashMap<String, EventPolicy>>>,
+    // Events held for windows whose delivery is paused (e.g. during reload)
+    paused: Arc<Mutex<HashMap<String, VecDeque<Event>>>>,
+    // Delivered events awaiting acknowledgement, by (window label, event id)
+    pending_acks: Arc<Mutex<HashMap<(String, String), Event>>>,
+    metrics: Arc<Metrics>,
+    // Where routed events are recorded, when `debug.event_log` is set
+    event_log: Option<Arc<EventLog>>,
+    // Last sequence number assigned to events, by (source window, target window)
+    sequences: Arc<Mutex<HashMap<(String, String), u64>>>,
+    // Events waiting to be emitted together, by window label
+    batches: Arc<Mutex<HashMap<String, Vec<Event>>>>,
+    // Whether and how event data is checked against its schema
+    schema_validation: SchemaValidation,
+    // Schemas for vaults whose manifest adds some, by vault ID
+    vault_schemas: Arc<Mutex<HashMap<String, Arc<EventSchemas>>>>,
+    builtin_schemas: Arc<EventSchemas>,
+    // Vaults windows are attached to besides their own, by window label
+    attached_vaults: Arc<Mutex<HashMap<String, BTreeSet<String>>>>,
+    // Attached vault a window has switched to, by window label; windows
+    // without one are on their own vault
+    active_vaults: Arc<Mutex<HashMap<String, String>>>,
 }
 
 impl Default for EventBus {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::default())
     }
 }
 
 #[allow(dead_code)]
 impl EventBus {
-    pub fn new() -> Self {
+    pub fn new(metrics: Arc<Metrics>) -> Self {
         Self {
             window_vaults: Arc::new(Mutex::new(HashMap::new())),
+            policies: Arc::new(Mutex::new(HashMap::new())),
+            paused: Arc::new(Mutex::new(HashMap::new())),
+            pending_acks: Arc::new(Mutex::new(HashMap::new())),
+            metrics,
+            event_log: None,
+            sequences: Arc::new(Mutex::new(HashMap::new())),
+            batches: Arc::new(Mutex::new(HashMap::new())),
+            schema_validation: SchemaValidation::Off,
+            vault_schemas: Arc::new(Mutex::new(HashMap::new())),
+            builtin_schemas: Arc::new(EventSchemas::builtin()),
+            attached_vaults: Arc::new(Mutex::new(HashMap::new())),
+            active_vaults: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
+    /// Check routed events against their schemas
+    pub fn with_schema_validation(mut self, schema_validation: SchemaValidation) -> Self {
+        self.schema_validation = schema_validation;
+        self
+    }
+
+    /// Record every routed event in `event_log`
+    pub fn with_event_log(mut self, event_log: EventLog) -> Self {
+        self.event_log = Some(Arc::new(event_log));
+        self
+    }
+
+    /// The file routed events are being recorded in, if any
+    pub fn event_log_path(&self) -> Option<&std::path::Path> {
+        self.event_log.as_deref().map(EventLog::path)
+    }
+
     /// Register a window with its vault ID
     pub async fn register_window(&self, window_label: String, vault_id: String) {
         self.window_vaults.lock().await.insert(window_label, vault_id);
     }
 
+    /// Attach a window to another vault, so it can make that vault active
+    pub async fn attach_vault(&self, window_label: &str, vault_id: String) -> anyhow::Result<()> {
+        let own = self.window_vaults.lock().await.get(window_label).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Window '{}' is not registered", window_label))?;
+        if own != vault_id {
+            self.attached_vaults.lock().await
+                .entry(window_label.to_string())
+                .or_default()
+                .insert(vault_id);
+        }
+        Ok(())
+    }
+
+    /// Detach a window from a vault; if it was active, the window goes back
+    /// to its own. Returns false if the window was not attached to it.
+    pub async fn detach_vault(&self, window_label: &str, vault_id: &str) -> bool {
+        let mut attached_vaults = self.attached_vaults.lock().await;
+        let Some(attached) = attached_vaults.get_mut(window_label) else {
+            return false;
+        };
+        if !attached.remove(vault_id) {
+            return false;
+        }
+        if attached.is_empty() {
+            attached_vaults.remove(window_label);
+        }
+        let mut active_vaults = self.active_vaults.lock().await;
+        if active_vaults.get(window_label).is_some_and(|active| active == vault_id) {
+            active_vaults.remove(window_label);
+        }
+        true
+    }
+
+    /// Choose which of a window's vaults (its own or an attached one) it
+    /// receives `Vault` events for and sends commands to
+    pub async fn set_active_vault(&self, window_label: &str, vault_id: &str) -> anyhow::Result<()> {
+        let own = self.window_vaults.lock().await.get(window_label).cloned()
+            .ok_or_else(|| anyhow::anyhow!("Window '{}' is not registered", window_label))?;
+        if own == vault_id {
+            self.active_vaults.lock().await.remove(window_label);
+            return Ok(());
+        }
+        let attached = self.attached_vaults.lock().await
+            .get(window_label)
+            .is_some_and(|attached| attached.contains(vault_id));
+        if !attached {
+            anyhow::bail!("Window '{}' is not attached to vault {}", window_label, vault_id);
+        }
+        self.active_vaults.lock().await.insert(window_label.to_string(), vault_id.to_string());
+        Ok(())
+    }
+
+    /// Whether `vault_id` is the window's own vault or one it is attached to
+    pub async fn can_use_vault(&self, window_label: &str, vault_id: &str) -> bool {
+        if self.window_vaults.lock().await.get(window_label).is_some_and(|own| own == vault_id) {
+            return true;
+        }
+        self.attached_vaults.lock().await
+            .get(window_label)
+            .is_some_and(|attached| attached.contains(vault_id))
+    }
+
+    /// The vault a window is currently working with
+    pub async fn active_vault(&self, window_label: &str) -> Option<String> {
+        let own = self.window_vaults.lock().await.get(window_label).cloned()?;
+        Some(self.active_vaults.lock().await.get(window_label).cloned().unwrap_or(own))
+    }
+
+    /// Label of the window whose sidecar serves `window_label`'s active
+    /// vault: the window itself unless it switched to an attached vault.
+    /// None if that vault is no longer open in any window.
+    pub async fn sidecar_window(&self, window_label: &str) -> Option<String> {
+        let Some(active) = self.active_vaults.lock().await.get(window_label).cloned() else {
+            return Some(window_label.to_string());
+        };
+        self.window_for_vault(&active).await
+    }
+
+    /// A window that has `vault_id` open as its own vault
+    pub async fn window_for_vault(&self, vault_id: &str) -> Option<String> {
+        self.window_vaults.lock().await
+            .iter()
+            .filter(|(_, vid)| *vid == vault_id)
+            .map(|(label, _)| label.clone())
+            .min()
+    }
+
+    /// Set the global-emission policy for a vault
+    pub async fn set_vault_policy(&self, vault_id: String, policy: EventPolicy) {
+        self.policies.lock().await.insert(vault_id, policy);
+    }
+
+    /// Use a vault's own event schemas alongside the built-in ones
+    pub async fn set_vault_schemas(&self, vault_id: String, schemas: &HashMap<String, serde_json::Value>) {
+        if self.schema_validation == SchemaValidation::Off {
+            return;
+        }
+        if schemas.is_empty() {
+            self.vault_schemas.lock().await.remove(&vault_id);
+            return;
+        }
+        match EventSchemas::builtin().with_schemas(schemas) {
+            Ok(schemas) => {
+                self.vault_schemas.lock().await.insert(vault_id, Arc::new(schemas));
+            }
+            Err(e) => eprintln!("Ignoring event schemas of vault {}: {}", vault_id, e),
+        }
+    }
+
     /// Route event from sidecar to appropriate window(s)
     pub async fn route_from_sidecar(
         &self,
@@ -54,56 +397,495 @@ impl EventBus {
         source_window: String,
         event: Event,
     ) -> anyhow::Result<()> {
-        match &event.scope {
-            EventScope::Window => {
-                // Route to source window only
-                self.send_to_window(app, &source_window, &event).await?;
-            }
-            EventScope::Global => {
-                // Broadcast to all windows
-                let window_vaults = self.window_vaults.lock().await;
-                for window_label in window_vaults.keys() {
-                    self.send_to_window(app, window_label, &event).await?;
+        if !self.conforms(app, &source_window, &event).await {
+            self.log_event(&source_window, &event, true, &[]);
+            return Ok(());
+        }
+
+        // Policy may drop the event; keep a copy to log if so
+        let original = self.event_log.as_ref().map(|_| event.clone());
+        let mut event = match self.apply_policy(&source_window, event).await {
+            Some(event) => event,
+            None => {
+                if let Some(original) = &original {
+                    self.log_event(&source_window, original, true, &[]);
                 }
+                return Ok(());
             }
+        };
+        if event.requires_ack && event.id.is_none() {
+            event.id = Some(uuid::Uuid::new_v4().to_string());
+        }
+        event.source_window = Some(source_window.clone());
+        Metrics::incr(&self.metrics.events_routed);
+
+        // Collect targets first: delivery may unregister windows that are gone
+        let targets: Vec<String> = match &event.scope {
+            // Route to source window only
+            EventScope::Window => vec![source_window.clone()],
+            // Broadcast to all windows
+            EventScope::Global => self.window_vaults.lock().await.keys().cloned().collect(),
+            // Send to all windows whose active vault matches
             EventScope::Vault(vault_id) => {
-                // Send to all windows with matching vault
                 let window_vaults = self.window_vaults.lock().await;
-                for (window_label, vid) in window_vaults.iter() {
-                    if vid == vault_id {
-                        self.send_to_window(app, window_label, &event).await?;
-                    }
+                let active_vaults = self.active_vaults.lock().await;
+                window_vaults.iter()
+                    .filter(|&(label, own)| active_vaults.get(label).unwrap_or(own) == vault_id)
+                    .map(|(window_label, _)| window_label.clone())
+                    .collect()
+            }
+        };
+
+        let mut deliveries = Vec::with_capacity(targets.len());
+        let mut result = Ok(());
+        for window_label in targets {
+            // Numbered per receiver, so each sees its events without gaps
+            let event = Event { seq: Some(self.next_sequence(&source_window, &window_label).await), ..event.clone() };
+            match self.deliver(app, &window_label, &event).await {
+                Ok(outcome) => deliveries.push(Delivery { window_label, outcome, seq: event.seq, error: None }),
+                Err(e) => {
+                    deliveries.push(Delivery {
+                        window_label,
+                        outcome: DeliveryOutcome::Failed,
+                        seq: event.seq,
+                        error: Some(e.to_string()),
+                    });
+                    result = Err(e);
+                    break;
                 }
             }
         }
 
-        Ok(())
+        // Batched deliveries are logged once their batch has been emitted
+        let targeted = !deliveries.is_empty();
+        deliveries.retain(|delivery| delivery.outcome != DeliveryOutcome::Batched);
+        if !targeted || !deliveries.is_empty() {
+            self.log_event(&source_window, &event, false, &deliveries);
+        }
+        result
+    }
+
+    /// Next sequence number for events from a source window to a target window
+    async fn next_sequence(&self, source_window: &str, target_window: &str) -> u64 {
+        let mut sequences = self.sequences.lock().await;
+        let seq = sequences.entry((source_window.to_string(), target_window.to_string())).or_insert(0);
+        *seq += 1;
+        *seq
+    }
+
+    /// Start the source window's sequence over, e.g. because its sidecar
+    /// was restarted, and tell every window with `event-sequence-reset`
+    /// so the new numbering is not mistaken for lost events
+    pub async fn reset_sequence(&self, app: &AppHandle, source_window: &str) {
+        let mut sequences = self.sequences.lock().await;
+        let before = sequences.len();
+        sequences.retain(|(source, _), _| source != source_window);
+        let removed = sequences.len() != before;
+        drop(sequences);
+        if !removed {
+            return;
+        }
+        use tauri::Emitter;
+        let payload = serde_json::json!({ "source_window": source_window });
+        for window_label in self.window_vaults.lock().await.keys() {
+            if let Err(e) = app.emit_to(window_label, "event-sequence-reset", &payload) {
+                eprintln!("Failed to emit event-sequence-reset: {}", e);
+            }
+        }
+    }
+
+    /// Every window the bus has state for, sorted by label
+    pub async fn routing_table(&self) -> Vec<RoutingEntry> {
+        let window_vaults = self.window_vaults.lock().await.clone();
+        let policies = self.policies.lock().await.clone();
+        let paused = self.paused.lock().await;
+        let batches = self.batches.lock().await;
+        let pending_acks = self.pending_acks.lock().await;
+        let sequences = self.sequences.lock().await;
+        let attached_vaults = self.attached_vaults.lock().await;
+        let active_vaults = self.active_vaults.lock().await;
+
+        let mut labels: Vec<&String> = window_vaults.keys()
+            .chain(paused.keys())
+            .chain(batches.keys())
+            .chain(pending_acks.keys().map(|(label, _)| label))
+            .chain(sequences.keys().map(|(_, target)| target))
+            .collect();
+        labels.sort();
+        labels.dedup();
+
+        labels.into_iter()
+            .map(|label| {
+                let vault_id = window_vaults.get(label).cloned();
+                let active_vault = active_vaults.get(label).cloned();
+                let mut subscriptions = vec!["window".to_string()];
+                if let Some(vault_id) = &vault_id {
+                    subscriptions.push("global".to_string());
+                    subscriptions.push(format!("vault:{}", active_vault.as_ref().unwrap_or(vault_id)));
+                }
+                RoutingEntry {
+                    window_label: label.clone(),
+                    policy: vault_id.as_ref().and_then(|vault_id| policies.get(vault_id).cloned()),
+                    vault_id,
+                    subscriptions,
+                    active_vault,
+                    attached_vaults: attached_vaults.get(label).map(|a| a.iter().cloned().collect()).unwrap_or_default(),
+                    paused: paused.contains_key(label),
+                    held_events: paused.get(label).map_or(0, VecDeque::len),
+                    batched_events: batches.get(label).map_or(0, Vec::len),
+                    pending_acks: pending_acks.keys().filter(|(l, _)| l == label).count(),
+                    last_seqs: sequences.iter()
+                        .filter(|((_, target), _)| target == label)
+                        .map(|((source, _), seq)| (source.clone(), *seq))
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    /// Append a routed event to the event log, if one is configured
+    fn log_event(&self, source_window: &str, event: &Event, dropped: bool, deliveries: &[Delivery]) {
+        append_to_log(self.event_log.as_deref(), source_window, event, dropped, deliveries);
+    }
+
+    /// Check an event's data against the schema for its type. Returns false
+    /// if the event should be dropped, which only strict validation does;
+    /// the source window is then sent a `protocol-violation` event.
+    async fn conforms(&self, app: &AppHandle, source_window: &str, event: &Event) -> bool {
+        if self.schema_validation == SchemaValidation::Off {
+            return true;
+        }
+        let schemas = match self.window_vaults.lock().await.get(source_window) {
+            Some(vault_id) => self.vault_schemas.lock().await.get(vault_id).cloned(),
+            None => None,
+        };
+        let violations = schemas.as_deref()
+            .unwrap_or(&self.builtin_schemas)
+            .violations(&event.event_type, &event.data);
+        if violations.is_empty() {
+            return true;
+        }
+
+        eprintln!("Event '{}' from window '{}' does not match its schema: {}",
+                  event.event_type, source_window, violations.join("; "));
+        if self.schema_validation != SchemaValidation::Strict {
+            return true;
+        }
+        use tauri::Emitter;
+        let payload = serde_json::json!({
+            "event_type": event.event_type,
+            "source_window": source_window,
+            "violations": violations,
+            "data": event.data,
+        });
+        if let Err(e) = app.emit_to(source_window, "protocol-violation", &payload) {
+            eprintln!("Failed to emit protocol-violation: {}", e);
+        }
+        false
+    }
+
+    /// Enforce the source vault's policy: assign the event's coalescing key
+    /// and restrict `Global` events. Returns `None` if the event should be
+    /// dropped.
+    async fn apply_policy(&self, source_window: &str, mut event: Event) -> Option<Event> {
+        let vault_id = match self.window_vaults.lock().await.get(source_window) {
+            Some(vault_id) => vault_id.clone(),
+            None => return Some(event),
+        };
+        let policies = self.policies.lock().await;
+        let policy = match policies.get(&vault_id) {
+            Some(policy) => policy,
+            None => return Some(event),
+        };
+
+        if event.coalesce_key.is_none() {
+            event.coalesce_key = policy.coalesce_key(&event.event_type, &event.data);
+        }
+        if !matches!(event.scope, EventScope::Global) || policy.allows_global(&event.event_type) {
+            return Some(event);
+        }
+
+        match policy.on_disallowed {
+            DisallowedAction::Downgrade => {
+                eprintln!("Event '{}' from window '{}' not allowed at global scope, downgrading to window scope",
+                          event.event_type, source_window);
+                event.scope = EventScope::Window;
+                Some(event)
+            }
+            DisallowedAction::Drop => {
+                eprintln!("Event '{}' from window '{}' not allowed at global scope, dropping",
+                          event.event_type, source_window);
+                None
+            }
+        }
+    }
+
+    /// Stop emitting to a window, holding its events until resumed. Events
+    /// already batched for it are held too, ahead of later ones.
+    pub async fn pause_window(&self, window_label: &str) {
+        let batch = self.take_batch(window_label).await.unwrap_or_default();
+        let mut paused = self.paused.lock().await;
+        let queue = paused.entry(window_label.to_string()).or_default();
+        for event in batch.into_iter().rev() {
+            queue.push_front(event);
+        }
+        // Like `hold_if_paused`, keep the newest events
+        if queue.len() > PAUSED_EVENT_CAP {
+            let excess = queue.len() - PAUSED_EVENT_CAP;
+            queue.drain(..excess);
+            eprintln!("Paused window '{}' event buffer full, dropping {} oldest event(s)", window_label, excess);
+        }
+        println!("Paused event delivery to window '{}'", window_label);
     }
 
-    /// Send event to a specific window
+    /// Resume emitting to a window, flushing any held events.
+    /// Returns the number of events flushed.
+    pub async fn resume_window(&self, app: &AppHandle, window_label: &str) -> anyhow::Result<usize> {
+        let held = self.take_paused(window_label).await;
+        let count = held.len();
+        for event in held {
+            self.send_to_window(app, window_label, &event).await?;
+        }
+        println!("Resumed event delivery to window '{}' ({} held events flushed)", window_label, count);
+        Ok(count)
+    }
+
+    /// Send to a window, or hold the event if the window is paused
+    async fn deliver(&self, app: &AppHandle, window_label: &str, event: &Event) -> anyhow::Result<DeliveryOutcome> {
+        if self.hold_if_paused(window_label, event).await {
+            return Ok(DeliveryOutcome::Held);
+        }
+        self.send_to_window(app, window_label, event).await
+    }
+
+    /// Queue an event for a paused window, in place of any held events it
+    /// supersedes. Returns false if not paused.
+    async fn hold_if_paused(&self, window_label: &str, event: &Event) -> bool {
+        let mut paused = self.paused.lock().await;
+        let Some(queue) = paused.get_mut(window_label) else {
+            return false;
+        };
+        let mut event = event.clone();
+        let before = queue.len();
+        queue.retain(|pending| !supersedes(&mut event, pending));
+        Metrics::add(&self.metrics.events_coalesced, (before - queue.len()) as u64);
+        if queue.len() >= PAUSED_EVENT_CAP {
+            queue.pop_front();
+            eprintln!("Paused window '{}' event buffer full, dropping oldest event", window_label);
+        }
+        queue.push_back(event);
+        true
+    }
+
+    /// Stop holding events for a window and return what was held
+    async fn take_paused(&self, window_label: &str) -> Vec<Event> {
+        self.paused.lock().await
+            .remove(window_label)
+            .map(Vec::from)
+            .unwrap_or_default()
+    }
+
+    /// Send event to a specific window, batching it unless it is immediate.
+    /// A window that no longer exists is unregistered, so a window destroyed
+    /// without `close_vault` stops being routed to.
     async fn send_to_window(
         &self,
         app: &AppHandle,
         window_label: &str,
         event: &Event,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<DeliveryOutcome> {
         if let Some(window) = app.get_webview_window(window_label) {
-            // Use Emitter trait method
-            use tauri::Emitter;
-            window.emit("sidecar-event", event)
-                .map_err(|e| anyhow::anyhow!("Failed to emit event: {}", e))?;
+            if !(event.immediate || event.requires_ack) {
+                self.batch(window, window_label, event).await?;
+                return Ok(DeliveryOutcome::Batched);
+            }
+
+            // Tracked before it is emitted, so an ack that arrives straight
+            // away is not reported as unknown and the event re-emitted
+            let event_id = self.track_ack(window_label, event).await;
+
+            // Earlier batched events go first so order is kept. Batches are
+            // only ever emitted under this lock, so a batch timer cannot
+            // emit them after this event.
+            let mut batches = self.batches.lock().await;
+            let emitted = match batches.remove(window_label) {
+                Some(batch) => emit_batch(&window, &batch, &self.metrics, self.event_log.as_deref()),
+                None => Ok(()),
+            }.and_then(|_| {
+                // Use Emitter trait method
+                use tauri::Emitter;
+                window.emit("sidecar-event", event)
+                    .map_err(|e| anyhow::anyhow!("Failed to emit event: {}", e))
+            });
+            drop(batches);
+            if let Err(e) = emitted {
+                if let Some(event_id) = event_id {
+                    self.pending_acks.lock().await.remove(&(window_label.to_string(), event_id));
+                }
+                return Err(e);
+            }
+            Metrics::incr(&self.metrics.event_emits);
             println!("Sent event '{}' to window '{}'", event.event_type, window_label);
+
+            if let Some(event_id) = event_id {
+                self.watch_ack(window, window_label.to_string(), event_id);
+            }
+            Ok(DeliveryOutcome::Sent)
         } else {
-            eprintln!("Window '{}' not found", window_label);
+            eprintln!("Window '{}' not found, unregistering it", window_label);
+            self.unregister_window(window_label).await;
+            Ok(DeliveryOutcome::WindowGone)
         }
+    }
 
+    /// Add an event to the window's batch. The first event of a batch
+    /// schedules its emission after `BATCH_INTERVAL`; a full batch is
+    /// emitted straight away. Either way the batch is emitted while
+    /// `batches` is locked, which keeps it in order with immediate events.
+    async fn batch(&self, window: tauri::WebviewWindow, window_label: &str, event: &Event) -> anyhow::Result<()> {
+        let (first, full) = self.push_batch(window_label, event).await;
+        if full {
+            let mut batches = self.batches.lock().await;
+            if let Some(batch) = batches.remove(window_label) {
+                emit_batch(&window, &batch, &self.metrics, self.event_log.as_deref())?;
+            }
+        } else if first {
+            let batches = self.batches.clone();
+            let metrics = self.metrics.clone();
+            let event_log = self.event_log.clone();
+            let window_label = window_label.to_string();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(BATCH_INTERVAL).await;
+                let mut batches = batches.lock().await;
+                let Some(batch) = batches.remove(&window_label) else {
+                    return;
+                };
+                if let Err(e) = emit_batch(&window, &batch, &metrics, event_log.as_deref()) {
+                    eprintln!("{}", e);
+                }
+            });
+        }
         Ok(())
     }
 
+    /// Queue an event in its window's batch, in place of any queued events
+    /// it supersedes. Returns whether it started the batch and whether the
+    /// batch is now full.
+    async fn push_batch(&self, window_label: &str, event: &Event) -> (bool, bool) {
+        let mut batches = self.batches.lock().await;
+        let batch = batches.entry(window_label.to_string()).or_default();
+        let mut event = event.clone();
+        let before = batch.len();
+        batch.retain(|pending| !supersedes(&mut event, pending));
+        Metrics::add(&self.metrics.events_coalesced, (before - batch.len()) as u64);
+        batch.push(event);
+        (batch.len() == 1, batch.len() >= BATCH_MAX_EVENTS)
+    }
+
+    async fn take_batch(&self, window_label: &str) -> Option<Vec<Event>> {
+        self.batches.lock().await.remove(window_label)
+    }
+
+    /// Record a delivered event that needs acknowledging. Returns its id.
+    async fn track_ack(&self, window_label: &str, event: &Event) -> Option<String> {
+        if !event.requires_ack {
+            return None;
+        }
+        let event_id = event.id.clone()?;
+        self.pending_acks.lock().await
+            .insert((window_label.to_string(), event_id.clone()), event.clone());
+        Some(event_id)
+    }
+
+    /// Re-emit an event each time `ACK_TIMEOUT` passes without an ack, and
+    /// give up after `ACK_RETRIES` re-emissions
+    fn watch_ack(&self, window: tauri::WebviewWindow, window_label: String, event_id: String) {
+        let pending_acks = self.pending_acks.clone();
+        tauri::async_runtime::spawn(async move {
+            use tauri::Emitter;
+            let key = (window_label, event_id);
+            for attempt in 0..=ACK_RETRIES {
+                tokio::time::sleep(ACK_TIMEOUT).await;
+                let mut pending = pending_acks.lock().await;
+                let Some(event) = pending.get(&key) else {
+                    return;
+                };
+                if attempt == ACK_RETRIES {
+                    eprintln!("Event '{}' ({}) was never acknowledged by window '{}'",
+                              event.event_type, key.1, key.0);
+                    pending.remove(&key);
+                    return;
+                }
+                eprintln!("Event '{}' ({}) not acknowledged by window '{}', re-emitting",
+                          event.event_type, key.1, key.0);
+                if let Err(e) = window.emit("sidecar-event", event) {
+                    eprintln!("Failed to re-emit event: {}", e);
+                }
+            }
+        });
+    }
+
+    /// Mark an event as received by a window. Returns false if it was not
+    /// awaiting acknowledgement (already acked, given up on, or unknown).
+    pub async fn ack(&self, window_label: &str, event_id: &str) -> bool {
+        self.pending_acks.lock().await
+            .remove(&(window_label.to_string(), event_id.to_string()))
+            .is_some()
+    }
+
     /// Unregister a window
-    #[allow(dead_code)]
     pub async fn unregister_window(&self, window_label: &str) {
         self.window_vaults.lock().await.remove(window_label);
+        self.paused.lock().await.remove(window_label);
+        self.pending_acks.lock().await.retain(|(label, _), _| label != window_label);
+        self.sequences.lock().await.retain(|(source, target), _| source != window_label && target != window_label);
+        self.batches.lock().await.remove(window_label);
+        self.attached_vaults.lock().await.remove(window_label);
+        self.active_vaults.lock().await.remove(window_label);
+    }
+}
+
+/// Emit a window's batched events as one `sidecar-events` array, then log
+/// how each one's delivery ended
+fn emit_batch(
+    window: &tauri::WebviewWindow,
+    batch: &[Event],
+    metrics: &Metrics,
+    event_log: Option<&EventLog>,
+) -> anyhow::Result<()> {
+    use tauri::Emitter;
+    let result = window.emit("sidecar-events", batch)
+        .map_err(|e| anyhow::anyhow!("Failed to emit events: {}", e));
+    if result.is_ok() {
+        Metrics::incr(&metrics.event_emits);
+        println!("Sent {} batched event(s) to window '{}'", batch.len(), window.label());
+    }
+    for event in batch {
+        let delivery = Delivery {
+            window_label: window.label().to_string(),
+            outcome: if result.is_ok() { DeliveryOutcome::Sent } else { DeliveryOutcome::Failed },
+            seq: event.seq,
+            error: result.as_ref().err().map(ToString::to_string),
+        };
+        append_to_log(event_log, event.source_window.as_deref().unwrap_or_default(), event, false, &[delivery]);
+    }
+    result
+}
+
+/// Record a routed event and what became of it in `event_log`, if any
+fn append_to_log(event_log: Option<&EventLog>, source_window: &str, event: &Event, dropped: bool, deliveries: &[Delivery]) {
+    let Some(event_log) = event_log else {
+        return;
+    };
+    let entry = RoutedEvent {
+        logged_at: chrono::Local::now().to_rfc3339(),
+        source_window,
+        dropped,
+        event,
+        deliveries,
+    };
+    if let Err(e) = event_log.append(&entry) {
+        eprintln!("Failed to write event log: {}", e);
     }
 }
 
@@ -113,7 +895,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_event_bus_registration() {
-        let bus = EventBus::new();
+        let bus = EventBus::default();
         
         bus.register_window("main".to_string(), "vault_123".to_string()).await;
         
@@ -130,5 +912,240 @@ mod tests {
             assert!(map.is_empty());
         } // Drop lock
     }
+
+    fn global_event(event_type: &str) -> Event {
+        Event {
+            event_type: event_type.to_string(),
+            scope: EventScope::Global,
+            data: serde_json::json!({}),
+            timestamp: 0.0,
+            id: None,
+            requires_ack: false,
+            immediate: false,
+            source_window: None,
+            seq: None,
+            coalesce_key: None,
+            superseded: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_routing_table() {
+        let bus = EventBus::default();
+        bus.register_window("w1".to_string(), "vault_a".to_string()).await;
+        bus.pause_window("w2").await;
+        bus.next_sequence("w2", "w1").await;
+
+        let table = bus.routing_table().await;
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[0].window_label, "w1");
+        assert_eq!(table[0].subscriptions, ["window", "global", "vault:vault_a"]);
+        assert_eq!(table[0].last_seqs, BTreeMap::from([("w2".to_string(), 1)]));
+        // Paused but never registered: only its own window events reach it
+        assert_eq!(table[1].vault_id, None);
+        assert!(table[1].paused);
+        assert_eq!(table[1].subscriptions, ["window"]);
+    }
+
+    #[tokio::test]
+    async fn test_active_vault() {
+        let bus = EventBus::default();
+        bus.register_window("w1".to_string(), "vault_a".to_string()).await;
+        bus.register_window("w2".to_string(), "vault_b".to_string()).await;
+        assert_eq!(bus.active_vault("w1").await.as_deref(), Some("vault_a"));
+
+        assert!(bus.set_active_vault("w1", "vault_b").await.is_err());
+        assert!(bus.can_use_vault("w1", "vault_a").await);
+        assert!(!bus.can_use_vault("w1", "vault_b").await);
+        bus.attach_vault("w1", "vault_b".to_string()).await.unwrap();
+        assert!(bus.can_use_vault("w1", "vault_b").await);
+        bus.set_active_vault("w1", "vault_b").await.unwrap();
+        assert_eq!(bus.active_vault("w1").await.as_deref(), Some("vault_b"));
+        // Commands go to the sidecar of the window that has vault_b open
+        assert_eq!(bus.sidecar_window("w1").await.as_deref(), Some("w2"));
+        assert_eq!(bus.routing_table().await[0].subscriptions, ["window", "global", "vault:vault_b"]);
+
+        assert!(bus.detach_vault("w1", "vault_b").await);
+        assert!(!bus.can_use_vault("w1", "vault_b").await);
+        assert_eq!(bus.active_vault("w1").await.as_deref(), Some("vault_a"));
+        assert_eq!(bus.sidecar_window("w1").await.as_deref(), Some("w1"));
+        assert!(!bus.detach_vault("w1", "vault_b").await);
+    }
+
+    #[tokio::test]
+    async fn test_batches_per_window() {
+        let bus = EventBus::default();
+        assert_eq!(bus.push_batch("w1", &global_event("a")).await, (true, false));
+        assert_eq!(bus.push_batch("w1", &global_event("b")).await, (false, false));
+        assert_eq!(bus.push_batch("w2", &global_event("c")).await, (true, false));
+
+        let batch = bus.take_batch("w1").await.unwrap();
+        assert_eq!(batch.iter().map(|e| e.event_type.as_str()).collect::<Vec<_>>(), ["a", "b"]);
+        assert!(bus.take_batch("w1").await.is_none());
+
+        for i in 1..BATCH_MAX_EVENTS {
+            assert_eq!(bus.push_batch("w3", &global_event("x")).await, (i == 1, false));
+        }
+        assert_eq!(bus.push_batch("w3", &global_event("x")).await, (false, true));
+
+        bus.unregister_window("w2").await;
+        assert!(bus.take_batch("w2").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_coalesces_pending_events() {
+        let bus = EventBus::default();
+        let cursor = |seq: u64, editor: &str| Event {
+            seq: Some(seq),
+            coalesce_key: Some(editor.to_string()),
+            data: serde_json::json!({ "line": seq }),
+            ..global_event("cursor")
+        };
+        bus.push_batch("w1", &cursor(1, "left")).await;
+        bus.push_batch("w1", &Event { seq: Some(2), ..global_event("saved") }).await;
+        bus.push_batch("w1", &cursor(3, "right")).await;
+        bus.push_batch("w1", &cursor(4, "left")).await;
+        bus.push_batch("w1", &cursor(5, "left")).await;
+
+        let batch = bus.take_batch("w1").await.unwrap();
+        let seqs: Vec<u64> = batch.iter().filter_map(|e| e.seq).collect();
+        assert_eq!(seqs, [2, 3, 5]);
+        assert_eq!(batch[2].superseded, [1, 4]);
+        assert_eq!(bus.metrics.snapshot().events_coalesced, 2);
+
+        // Held events coalesce too, but never ones awaiting an ack
+        bus.pause_window("w1").await;
+        bus.hold_if_paused("w1", &Event { requires_ack: true, ..cursor(6, "left") }).await;
+        bus.hold_if_paused("w1", &cursor(7, "left")).await;
+        bus.hold_if_paused("w1", &cursor(8, "left")).await;
+        let held: Vec<u64> = bus.take_paused("w1").await.iter().filter_map(|e| e.seq).collect();
+        assert_eq!(held, [6, 8]);
+    }
+
+    #[tokio::test]
+    async fn test_sequence_per_source_and_target() {
+        let bus = EventBus::default();
+        assert_eq!(bus.next_sequence("w1", "w1").await, 1);
+        assert_eq!(bus.next_sequence("w1", "w1").await, 2);
+        // A window that only gets some of w1's events sees no gaps
+        assert_eq!(bus.next_sequence("w1", "w2").await, 1);
+        assert_eq!(bus.next_sequence("w2", "w2").await, 1);
+
+        // A window that goes away starts over when it is reused, as a source
+        // and as a target
+        bus.unregister_window("w1").await;
+        assert_eq!(bus.next_sequence("w1", "w1").await, 1);
+        assert_eq!(bus.next_sequence("w1", "w2").await, 1);
+        assert_eq!(bus.next_sequence("w2", "w2").await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_paused_window_holds_events() {
+        let bus = EventBus::default();
+        assert!(!bus.hold_if_paused("w1", &global_event("a")).await);
+
+        // Batched events not yet emitted are held rather than lost
+        bus.push_batch("w2", &global_event("batched")).await;
+        bus.pause_window("w2").await;
+        assert!(bus.hold_if_paused("w2", &global_event("later")).await);
+        assert!(bus.take_batch("w2").await.is_none());
+        let held: Vec<String> = bus.take_paused("w2").await.into_iter().map(|e| e.event_type).collect();
+        assert_eq!(held, ["batched", "later"]);
+
+        bus.pause_window("w1").await;
+        for i in 0..PAUSED_EVENT_CAP + 2 {
+            assert!(bus.hold_if_paused("w1", &global_event(&format!("e{}", i))).await);
+        }
+
+        let held = bus.take_paused("w1").await;
+        assert_eq!(held.len(), PAUSED_EVENT_CAP);
+        assert_eq!(held[0].event_type, "e2");
+
+        // A full buffer keeps its newest events when a batch joins it
+        bus.pause_window("w3").await;
+        for i in 0..PAUSED_EVENT_CAP {
+            bus.hold_if_paused("w3", &global_event(&format!("e{}", i))).await;
+        }
+        bus.push_batch("w3", &global_event("batched")).await;
+        bus.pause_window("w3").await;
+        let held = bus.take_paused("w3").await;
+        assert_eq!(held.len(), PAUSED_EVENT_CAP);
+        assert_eq!(held[0].event_type, "e0");
+        assert_eq!(held[PAUSED_EVENT_CAP - 1].event_type, format!("e{}", PAUSED_EVENT_CAP - 1));
+
+        // Resumed windows no longer hold events
+        assert!(!bus.hold_if_paused("w1", &global_event("b")).await);
+    }
+
+    #[tokio::test]
+    async fn test_ack_clears_pending_event() {
+        let bus = EventBus::default();
+        assert!(bus.track_ack("w1", &global_event("a")).await.is_none());
+
+        let event = Event {
+            id: Some("e1".to_string()),
+            requires_ack: true,
+            ..global_event("unsaved-changes")
+        };
+        assert_eq!(bus.track_ack("w1", &event).await.as_deref(), Some("e1"));
+        assert!(!bus.ack("w2", "e1").await);
+        assert!(bus.ack("w1", "e1").await);
+        assert!(!bus.ack("w1", "e1").await);
+
+        bus.track_ack("w1", &event).await;
+        bus.unregister_window("w1").await;
+        assert!(!bus.ack("w1", "e1").await);
+    }
+
+    #[test]
+    fn test_event_policy_allows_global() {
+        let open = EventPolicy::default();
+        assert!(open.allows_global("anything"));
+
+        let policy = EventPolicy {
+            global_allow: Some(vec!["theme-changed".to_string(), "secret".to_string()]),
+            global_deny: vec!["secret".to_string()],
+            on_disallowed: DisallowedAction::Drop,
+            coalesce: HashMap::new(),
+        };
+        assert!(policy.allows_global("theme-changed"));
+        assert!(!policy.allows_global("secret"));
+        assert!(!policy.allows_global("other"));
+    }
+
+    #[tokio::test]
+    async fn test_apply_policy() {
+        let bus = EventBus::default();
+        bus.register_window("w1".to_string(), "vault_a".to_string()).await;
+
+        // No policy configured: global events pass through untouched
+        let event = bus.apply_policy("w1", global_event("note-saved")).await.unwrap();
+        assert!(matches!(event.scope, EventScope::Global));
+
+        bus.set_vault_policy("vault_a".to_string(), EventPolicy {
+            global_deny: vec!["note-saved".to_string()],
+            ..Default::default()
+        }).await;
+        let event = bus.apply_policy("w1", global_event("note-saved")).await.unwrap();
+        assert!(matches!(event.scope, EventScope::Window));
+
+        bus.set_vault_policy("vault_a".to_string(), EventPolicy {
+            global_deny: vec!["note-saved".to_string()],
+            on_disallowed: DisallowedAction::Drop,
+            ..Default::default()
+        }).await;
+        assert!(bus.apply_policy("w1", global_event("note-saved")).await.is_none());
+
+        // The policy keys coalesced event types by a data field
+        bus.set_vault_policy("vault_a".to_string(), EventPolicy {
+            coalesce: HashMap::from([("cursor".to_string(), "editor_id".to_string())]),
+            ..Default::default()
+        }).await;
+        let cursor = Event { data: serde_json::json!({ "editor_id": 3 }), ..global_event("cursor") };
+        let event = bus.apply_policy("w1", cursor).await.unwrap();
+        assert_eq!(event.coalesce_key.as_deref(), Some("3"));
+        let event = bus.apply_policy("w1", global_event("cursor")).await.unwrap();
+        assert!(event.coalesce_key.is_none());
+    }
 }
 
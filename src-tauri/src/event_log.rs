@@ -0,0 +1,153 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// Session event logs kept in the log directory; older ones are deleted
+const SESSIONS_KEPT: usize = 5;
+
+const FILE_PREFIX: &str = "events-";
+const FILE_EXTENSION: &str = "ndjson";
+
+/// Append-only NDJSON file of routed events for one app session. When the
+/// file grows past its size cap it is moved to `<name>.1` (replacing the
+/// previous one) and a fresh file is started.
+pub struct EventLog {
+    path: PathBuf,
+    max_bytes: u64,
+    // Open file and its current length
+    file: Mutex<(File, u64)>,
+}
+
+impl EventLog {
+    /// Start a new session log in `dir`, pruning all but the most recent
+    /// earlier sessions
+    pub fn create(dir: &Path, max_bytes: u64) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        prune_sessions(dir, SESSIONS_KEPT.saturating_sub(1));
+
+        let name = format!(
+            "{}{}-{}.{}",
+            FILE_PREFIX,
+            chrono::Local::now().format("%Y%m%d-%H%M%S"),
+            std::process::id(),
+            FILE_EXTENSION
+        );
+        let path = dir.join(name);
+        let file = open_append(&path)?;
+        println!("Logging routed events to {}", path.display());
+        Ok(Self { path, max_bytes, file: Mutex::new((file, 0)) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write one entry as a single JSON line
+    pub fn append(&self, entry: &impl Serialize) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+
+        let mut guard = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let (file, len) = &mut *guard;
+        if *len > 0 && *len + line.len() as u64 > self.max_bytes {
+            let mut rolled = self.path.as_os_str().to_owned();
+            rolled.push(".1");
+            fs::rename(&self.path, PathBuf::from(rolled))
+                .with_context(|| format!("Failed to roll {}", self.path.display()))?;
+            *file = open_append(&self.path)?;
+            *len = 0;
+        }
+        file.write_all(&line)?;
+        *len += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))
+}
+
+/// Delete session logs (and their rolled files) beyond the newest `keep`
+fn prune_sessions(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut sessions: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == FILE_EXTENSION)
+                && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(FILE_PREFIX))
+        })
+        .collect();
+    // Names start with the session's timestamp, so they sort by age
+    sessions.sort();
+
+    let stale = sessions.len().saturating_sub(keep);
+    for path in sessions.into_iter().take(stale) {
+        let mut rolled = path.as_os_str().to_owned();
+        rolled.push(".1");
+        let _ = fs::remove_file(PathBuf::from(rolled));
+        if let Err(e) = fs::remove_file(&path) {
+            eprintln!("Failed to remove old event log {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_rolls_at_size_cap() {
+        let dir = std::env::temp_dir().join(format!("tailor_event_log_{}", uuid::Uuid::new_v4()));
+        let log = EventLog::create(&dir, 64).unwrap();
+
+        for i in 0..3 {
+            log.append(&serde_json::json!({ "event_type": format!("event-{}", i), "pad": "x".repeat(20) })).unwrap();
+        }
+
+        let current = fs::read_to_string(log.path()).unwrap();
+        let mut rolled = log.path().as_os_str().to_owned();
+        rolled.push(".1");
+        let rolled = fs::read_to_string(PathBuf::from(rolled)).unwrap();
+
+        // Each line is a complete JSON value; the newest stays in the current file
+        let last: serde_json::Value = serde_json::from_str(current.lines().last().unwrap()).unwrap();
+        assert_eq!(last["event_type"], "event-2");
+        assert!(rolled.lines().all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok()));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_prune_sessions() {
+        let dir = std::env::temp_dir().join(format!("tailor_event_log_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        for day in 1..=4 {
+            fs::write(dir.join(format!("events-2026010{}-000000-1.ndjson", day)), "").unwrap();
+        }
+        fs::write(dir.join("events-20260101-000000-1.ndjson.1"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        prune_sessions(&dir, 2);
+        let mut left: Vec<String> = fs::read_dir(&dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        left.sort();
+        assert_eq!(left, [
+            "events-20260103-000000-1.ndjson",
+            "events-20260104-000000-1.ndjson",
+            "notes.txt",
+        ]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+
+/// JSON schemas the `data` of known event types must match. Event types
+/// without a schema are not checked.
+pub struct EventSchemas {
+    validators: HashMap<String, jsonschema::Validator>,
+}
+
+impl Default for EventSchemas {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl EventSchemas {
+    /// Schemas for the events the bundled sidecar and the host itself send
+    pub fn builtin() -> Self {
+        let builtin = [
+            ("NOTIFY", serde_json::json!({
+                "type": "object",
+                "required": ["message"],
+                "properties": {
+                    "message": { "type": "string" },
+                    "severity": { "type": "string" },
+                },
+            })),
+            ("PROGRESS", serde_json::json!({
+                "type": "object",
+                "required": ["percentage"],
+                "properties": {
+                    "percentage": { "type": "number", "minimum": 0, "maximum": 100 },
+                    "message": { "type": "string" },
+                },
+            })),
+            ("sidecar-error", serde_json::json!({
+                "type": "object",
+                "required": ["message"],
+                "properties": { "message": { "type": "string" } },
+            })),
+        ];
+        let validators = builtin.into_iter()
+            .map(|(event_type, schema)| {
+                let validator = jsonschema::validator_for(&schema).expect("built-in event schemas are valid");
+                (event_type.to_string(), validator)
+            })
+            .collect();
+        Self { validators }
+    }
+
+    /// Add schemas by event type, e.g. from a vault manifest, replacing
+    /// built-in ones of the same type
+    pub fn with_schemas(mut self, schemas: &HashMap<String, serde_json::Value>) -> Result<Self> {
+        for (event_type, schema) in schemas {
+            let validator = jsonschema::validator_for(schema)
+                .map_err(|e| anyhow!("Invalid schema for event '{}': {}", event_type, e))?;
+            self.validators.insert(event_type.clone(), validator);
+        }
+        Ok(self)
+    }
+
+    /// Check an event's data against the schema for its type. Returns each
+    /// way it does not conform, or nothing if it does or has no schema.
+    pub fn violations(&self, event_type: &str, data: &serde_json::Value) -> Vec<String> {
+        let Some(validator) = self.validators.get(event_type) else {
+            return Vec::new();
+        };
+        validator.iter_errors(data)
+            .map(|error| {
+                let path = error.instance_path.to_string();
+                if path.is_empty() {
+                    error.to_string()
+                } else {
+                    format!("{}: {}", path, error)
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_schemas() {
+        let schemas = EventSchemas::builtin();
+        assert!(schemas.violations("NOTIFY", &serde_json::json!({"message": "Saved", "severity": "info"})).is_empty());
+        assert_eq!(schemas.violations("NOTIFY", &serde_json::json!({"severity": 3})).len(), 2);
+
+        let violations = schemas.violations("PROGRESS", &serde_json::json!({"percentage": 140}));
+        assert_eq!(violations.len(), 1);
+        assert!(violations[0].starts_with("/percentage: "));
+
+        // Types without a schema are not checked
+        assert!(schemas.violations("CHAT_TOKEN", &serde_json::json!(42)).is_empty());
+    }
+
+    #[test]
+    fn test_with_schemas() {
+        let mut schemas = HashMap::new();
+        schemas.insert("indexer.done".to_string(), serde_json::json!({"type": "object", "required": ["files"]}));
+        let vault = EventSchemas::builtin().with_schemas(&schemas).unwrap();
+        assert!(vault.violations("indexer.done", &serde_json::json!({"files": 3})).is_empty());
+        assert_eq!(vault.violations("indexer.done", &serde_json::json!({})).len(), 1);
+
+        schemas.insert("broken".to_string(), serde_json::json!({"type": "not-a-type"}));
+        let error = EventSchemas::builtin().with_schemas(&schemas).err().unwrap();
+        assert!(error.to_string().contains("'broken'"));
+    }
+}
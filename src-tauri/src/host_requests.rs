@@ -0,0 +1,283 @@
+use std::path::PathBuf;
+use serde::Deserialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+
+use crate::AppState;
+use crate::config::AppConfig;
+use crate::ipc_router;
+use crate::sidecar_manager::{HostCall, HOST_METHOD_PREFIX};
+use crate::vault_permissions::HostCapability;
+use crate::window_manager::{WindowManager, WindowPlacement};
+
+/// Methods a sidecar may call, without the `host.` prefix
+const HOST_METHODS: &[&str] = &["open_vault", "save_dialog", "open_dialog", "get_setting"];
+
+/// Requests a sidecar may make of the host, as `host.<method>` JSON-RPC calls
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum HostRequest {
+    /// Open another (e.g. linked) vault in a new window
+    OpenVault { path: String },
+    /// Ask the user where to save a file. Resolves to the chosen path, or
+    /// null if the dialog was cancelled.
+    SaveDialog(#[serde(default)] DialogOptions),
+    /// Ask the user to pick an existing file. Resolves like `SaveDialog`.
+    OpenDialog(#[serde(default)] DialogOptions),
+    /// Read a value from the host's `tailor.toml` by dotted key, e.g.
+    /// "sidecar.compression"
+    GetSetting { key: String },
+}
+
+/// Presentation of a file dialog requested by a sidecar or the frontend
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct DialogOptions {
+    pub title: Option<String>,
+    /// Directory the dialog starts in
+    pub directory: Option<String>,
+    /// Suggested file name (save dialogs)
+    pub file_name: Option<String>,
+    pub filters: Vec<DialogFilter>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct DialogFilter {
+    pub name: String,
+    /// Extensions without the leading dot
+    pub extensions: Vec<String>,
+}
+
+/// Check that the user allowed the vault at `vault_path` a capability,
+/// asking them over `source_window` the first time it is used. Decisions
+/// are kept app-side, so a vault cannot grant itself anything.
+async fn ensure_allowed(
+    app: &AppHandle,
+    state: &AppState,
+    source_window: &str,
+    vault_path: &str,
+    capability: HostCapability,
+) -> Result<(), String> {
+    let vault_id = WindowManager::vault_id(vault_path);
+    let allowed = match state.vault_permissions.decision(&vault_id, capability) {
+        Some(allowed) => allowed,
+        None => {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            let mut dialog = app.dialog()
+                .message(format!(
+                    "The vault at {} wants to {}.\n\nOnly allow this for vaults you trust.",
+                    vault_path, capability.describe(),
+                ))
+                .title("Allow vault access?")
+                .kind(MessageDialogKind::Warning)
+                .buttons(MessageDialogButtons::OkCancelCustom("Allow".to_string(), "Deny".to_string()));
+            if let Some(window) = app.get_webview_window(source_window) {
+                dialog = dialog.parent(&window);
+            }
+            dialog.show(move |allowed| {
+                let _ = tx.send(allowed);
+            });
+            let allowed = rx.await.unwrap_or(false);
+            if let Err(e) = state.vault_permissions.set(&vault_id, capability, Some(allowed)) {
+                eprintln!("Failed to save vault permission: {}", e);
+            }
+            allowed
+        }
+    };
+    if !allowed {
+        println!("Denied {:?} request from '{}': permission not granted", capability, source_window);
+        return Err(format!("Vault is not permitted to {}", capability.describe()));
+    }
+    Ok(())
+}
+
+/// Handle a request from the sidecar attached to `source_window`
+pub async fn handle(
+    app: &AppHandle,
+    source_window: &str,
+    request: HostRequest,
+) -> Result<serde_json::Value, String> {
+    let state = app.state::<AppState>();
+    let source_vault = state.window_manager
+        .lock()
+        .await
+        .get_vault_path(source_window)
+        .cloned()
+        .ok_or_else(|| format!("No vault open in window: {}", source_window))?;
+    let capability = match &request {
+        HostRequest::OpenVault { .. } => HostCapability::OpenVault,
+        HostRequest::SaveDialog(_) | HostRequest::OpenDialog(_) => HostCapability::Dialogs,
+        HostRequest::GetSetting { .. } => HostCapability::ReadSettings,
+    };
+    ensure_allowed(app, &state, source_window, &source_vault, capability).await?;
+
+    match request {
+        HostRequest::OpenVault { path } => {
+            validate_vault_dir(&path)?;
+
+            println!("Sidecar in '{}' requested to open vault: {}", source_window, path);
+            let result = ipc_router::open_vault_path(app, &state, path, WindowPlacement::default())
+                .await
+                .map_err(|e| e.to_string())?;
+            serde_json::to_value(result.info)
+                .map_err(|e| format!("Failed to serialize vault info: {}", e))
+        }
+        HostRequest::SaveDialog(options) => {
+            let path = show_file_dialog(app, source_window, options, true).await?;
+            Ok(path.into())
+        }
+        HostRequest::OpenDialog(options) => {
+            let path = show_file_dialog(app, source_window, options, false).await?;
+            Ok(path.into())
+        }
+        HostRequest::GetSetting { key } => {
+            let config_path = app.path().app_config_dir()
+                .map_err(|e| format!("Failed to locate config directory: {}", e))?
+                .join("tailor.toml");
+            setting_value(&AppConfig::load(&config_path), &key)
+        }
+    }
+}
+
+/// Answer a JSON-RPC request the sidecar of `call.window_label` sent the
+/// host, replying with a JSON-RPC response
+pub async fn answer(app: &AppHandle, call: HostCall) {
+    let HostCall { window_label, request, reply } = call;
+    let id = request.get("id").cloned().unwrap_or(serde_json::Value::Null);
+    let method = request.get("method")
+        .and_then(|m| m.as_str())
+        .unwrap_or_default()
+        .strip_prefix(HOST_METHOD_PREFIX)
+        .unwrap_or_default()
+        .to_string();
+
+    let response = match parse_request(&method, request.get("params").cloned()) {
+        Err((code, message)) => rpc_error(id, code, message),
+        Ok(request) => match handle(app, &window_label, request).await {
+            Ok(result) => serde_json::json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err(message) => rpc_error(id, -32000, message),
+        },
+    };
+    // The exchange may have timed out and stopped waiting
+    let _ = reply.send(response);
+}
+
+/// Build a `HostRequest` from a method (without prefix) and its params.
+/// Failures carry a JSON-RPC error code.
+pub fn parse_request(method: &str, params: Option<serde_json::Value>) -> Result<HostRequest, (i64, String)> {
+    if !HOST_METHODS.contains(&method) {
+        return Err((-32601, format!("Unknown host method: {}{}", HOST_METHOD_PREFIX, method)));
+    }
+    serde_json::from_value(serde_json::json!({
+        "method": method,
+        "params": params.filter(|p| !p.is_null()).unwrap_or_else(|| serde_json::json!({})),
+    }))
+    .map_err(|e| (-32602, format!("Invalid params for {}{}: {}", HOST_METHOD_PREFIX, method, e)))
+}
+
+fn rpc_error(id: serde_json::Value, code: i64, message: String) -> serde_json::Value {
+    serde_json::json!({ "jsonrpc": "2.0", "error": { "code": code, "message": message }, "id": id })
+}
+
+/// Show a file dialog attached to the source window and wait for the user.
+/// Resolves to the chosen path, or `None` if cancelled.
+pub async fn show_file_dialog(
+    app: &AppHandle,
+    source_window: &str,
+    options: DialogOptions,
+    save: bool,
+) -> Result<Option<String>, String> {
+    let mut dialog = app.dialog().file();
+    if let Some(window) = app.get_webview_window(source_window) {
+        dialog = dialog.set_parent(&window);
+    }
+    if let Some(title) = options.title {
+        dialog = dialog.set_title(title);
+    }
+    if let Some(directory) = options.directory {
+        dialog = dialog.set_directory(directory);
+    }
+    if let Some(file_name) = options.file_name {
+        dialog = dialog.set_file_name(file_name);
+    }
+    for filter in &options.filters {
+        let extensions: Vec<&str> = filter.extensions.iter().map(String::as_str).collect();
+        dialog = dialog.add_filter(&filter.name, &extensions);
+    }
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let on_close = move |path: Option<tauri_plugin_dialog::FilePath>| {
+        let _ = tx.send(path.map(|path| path.to_string()));
+    };
+    if save {
+        dialog.save_file(on_close);
+    } else {
+        dialog.pick_file(on_close);
+    }
+    rx.await.map_err(|_| "File dialog closed unexpectedly".to_string())
+}
+
+/// Look up a dotted key in the effective host config
+fn setting_value(config: &AppConfig, key: &str) -> Result<serde_json::Value, String> {
+    let config = serde_json::to_value(config)
+        .map_err(|e| format!("Failed to serialize config: {}", e))?;
+    let pointer = format!("/{}", key.replace('.', "/"));
+    config.pointer(&pointer)
+        .cloned()
+        .ok_or_else(|| format!("Unknown setting: {}", key))
+}
+
+/// Ensure a requested path is an existing vault directory
+fn validate_vault_dir(path: &str) -> Result<(), String> {
+    let vault_path = PathBuf::from(path);
+    if !vault_path.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+    if !vault_path.join(".vault.toml").exists() {
+        return Err(format!("Not a vault (missing .vault.toml): {}", path));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_request() {
+        let request: HostRequest = serde_json::from_value(serde_json::json!({
+            "method": "open_vault",
+            "params": { "path": "/tmp/linked" }
+        })).unwrap();
+        assert_eq!(request, HostRequest::OpenVault { path: "/tmp/linked".to_string() });
+
+        assert_eq!(parse_request("save_dialog", None), Ok(HostRequest::SaveDialog(DialogOptions::default())));
+        assert_eq!(
+            parse_request("get_setting", Some(serde_json::json!({ "key": "sidecar.compression" }))),
+            Ok(HostRequest::GetSetting { key: "sidecar.compression".to_string() })
+        );
+        assert_eq!(parse_request("delete_vault", None).unwrap_err().0, -32601);
+        assert_eq!(parse_request("open_vault", Some(serde_json::json!({}))).unwrap_err().0, -32602);
+    }
+
+    #[test]
+    fn test_setting_value() {
+        let config = AppConfig::default();
+        assert_eq!(setting_value(&config, "sidecar.compression"), Ok(serde_json::json!(true)));
+        assert_eq!(setting_value(&config, "debug.event_log"), Ok(serde_json::json!(false)));
+        assert!(setting_value(&config, "sidecar.nonexistent").is_err());
+    }
+
+    #[test]
+    fn test_validate_vault_dir() {
+        let dir = std::env::temp_dir().join(format!("tailor_perm_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let vault_path = dir.to_string_lossy().to_string();
+        assert!(validate_vault_dir(&vault_path).is_err());
+
+        std::fs::write(dir.join(".vault.toml"), "name = \"Test\"\n").unwrap();
+        assert!(validate_vault_dir(&vault_path).is_ok());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,126 +1,2039 @@
-use crate::{AppState, dependency_checker::DependencyChecker};
+use crate::{AppState, event_bus::{Event, EventPolicy, EventScope, RoutingEntry}};
+use crate::window_manager::{MonitorInfo, NestedVault, Nesting, WindowManager, WindowPlacement, WindowPosition};
+use crate::config::AppConfig;
+use crate::metrics::{Metrics, MetricsSnapshot};
+use crate::host_requests;
+use crate::dependency_checker::{DependencyChecker, DependencyStatus, InstallReport, InstalledPackage, MigrationReport, PreflightReport};
+use crate::error::TailorError;
+use crate::sidecar_manager::{CloseCheck, PortReclaim, ShutdownReason, SidecarCommand, SpawnOptions, SidecarLogs, SidecarManager, SidecarNotification, SidecarStatus, StderrReport, HOST_METHOD_PREFIX};
+use crate::vault_archive::{self, Extraction};
+use crate::vault_manifest::VaultManifest;
+use crate::vault_permissions::{HostCapability, VaultGrants};
+use crate::diagnostics;
+use crate::app_info::AppInfo;
+use crate::cancellation::{CancelReport, OperationKind};
 use tauri::{AppHandle, State, Manager, Emitter};
+use tauri_plugin_dialog::{DialogExt, MessageDialogButtons, MessageDialogKind};
+use tauri::ipc::Channel;
 use serde::{Deserialize, Serialize};
-use anyhow::Result;
-use std::path::PathBuf;
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use std::fs;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// How long a pre-warmed sidecar waits to be adopted by `open_vault`
+const PREWARM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Pause between sidecars in `restart_all_sidecars`, so they do not all
+/// compete for ports and CPU at once
+const RESTART_ALL_STAGGER: std::time::Duration = std::time::Duration::from_millis(500);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VaultInfo {
     pub window_label: String,
     pub vault_path: String,
-    pub ws_port: u16,
+    /// Stable identity of the vault (hash of its canonical path)
+    pub vault_id: String,
+    pub ws_port: u16,
+    /// OS process id of the vault's sidecar
+    #[serde(default)]
+    pub pid: Option<u32>,
+}
+
+/// What `open_vault` returns: the opened vault plus anything worth telling
+/// the user that did not stop it from opening
+#[derive(Debug, Clone, Serialize)]
+pub struct VaultOpenResult {
+    pub info: VaultInfo,
+    /// Set when the vault declares plugin requirements
+    pub dependency_report: Option<InstallReport>,
+    /// Non-fatal issues, in the order the open steps met them
+    pub warnings: Vec<String>,
+}
+
+impl VaultOpenResult {
+    fn new(info: VaultInfo) -> Self {
+        Self { info, dependency_report: None, warnings: Vec::new() }
+    }
+}
+
+/// Open a new vault window
+///
+/// Concurrent calls for the same vault share a single open, and a vault that
+/// is already open just has its window focused. A new window is centred on
+/// `monitor_index` (see `list_monitors`) or placed at `position` if given.
+/// Failures come back as a structured `TailorError`, e.g. `timeout`.
+#[tauri::command]
+pub async fn open_vault(
+    app: AppHandle,
+    vault_path: String,
+    monitor_index: Option<usize>,
+    position: Option<WindowPosition>,
+    state: State<'_, AppState>,
+) -> Result<VaultOpenResult, TailorError> {
+    let placement = WindowPlacement { monitor_index, position };
+    open_vault_path(&app, &state, vault_path, placement).await
+}
+
+/// Outcome of opening one vault in `open_vaults`
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchOpenResult {
+    pub vault_path: String,
+    pub result: Option<VaultOpenResult>,
+    pub error: Option<String>,
+}
+
+/// Open several vaults, e.g. to restore a session. Only
+/// `vaults.max_concurrent_opens` are opened at a time, so their dependency
+/// installs and sidecar spawns do not all compete at once. A
+/// `vault-open-progress` event reports each vault starting and finishing.
+#[tauri::command]
+pub async fn open_vaults(
+    app: AppHandle,
+    vault_paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<BatchOpenResult>, String> {
+    let limit = state.window_manager.lock().await.max_concurrent_opens();
+    let slots = tokio::sync::Semaphore::new(limit);
+    let total = vault_paths.len();
+    let completed = std::sync::atomic::AtomicUsize::new(0);
+    println!("Opening {} vaults, {} at a time", total, limit);
+
+    let emit_progress = |vault_path: &str, status: &str, error: Option<&str>| {
+        let payload = serde_json::json!({
+            "vault_path": vault_path,
+            "status": status,
+            "error": error,
+            "completed": completed.load(std::sync::atomic::Ordering::SeqCst),
+            "total": total,
+        });
+        if let Err(e) = app.emit("vault-open-progress", payload) {
+            eprintln!("Failed to emit vault-open-progress: {}", e);
+        }
+    };
+
+    let opens = vault_paths.into_iter().map(|vault_path| async {
+        let _slot = slots.acquire().await.expect("open slots are never closed");
+        emit_progress(&vault_path, "opening", None);
+        let result = open_vault_path(&app, &state, vault_path.clone(), WindowPlacement::default()).await;
+        completed.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        match result {
+            Ok(result) => {
+                emit_progress(&vault_path, "opened", None);
+                BatchOpenResult { vault_path, result: Some(result), error: None }
+            }
+            Err(e) => {
+                let e = e.to_string();
+                emit_progress(&vault_path, "failed", Some(&e));
+                BatchOpenResult { vault_path, result: None, error: Some(e) }
+            }
+        }
+    });
+    Ok(futures::future::join_all(opens).await)
+}
+
+/// Run the open pipeline for a vault path (shared by commands and host requests)
+pub async fn open_vault_path(
+    app: &AppHandle,
+    state: &AppState,
+    vault_path: String,
+    placement: WindowPlacement,
+) -> Result<VaultOpenResult, TailorError> {
+    let vault_path = state.window_manager.lock().await.normalize_vault_path(&vault_path);
+
+    // A zipped vault opens from its extracted copy
+    if vault_archive::is_archive(Path::new(&vault_path)) {
+        let extraction = extract_vault_archive(state, PathBuf::from(&vault_path), None).await?;
+        let mut result = Box::pin(open_vault_path(app, state, extraction.vault_path.clone(), placement)).await?;
+        result.warnings.push(format!("Opened the archive's extracted copy at {}", extraction.vault_path));
+        return Ok(result);
+    }
+
+    let vault_id = WindowManager::vault_id(&vault_path);
+
+    if let Some(info) = focus_open_vault(app, &vault_id, state).await {
+        let mut result = VaultOpenResult::new(info);
+        result.warnings.push("Vault was already open; focused its window".to_string());
+        return Ok(result);
+    }
+
+    state.opening_vaults
+        .run(&vault_id.clone(), open_vault_with_timeout(app, vault_path, vault_id, placement, state))
+        .await
+}
+
+/// Open a vault distributed as a zip. It is extracted under the app data
+/// directory unless `destination` names a directory to extract it into, or
+/// `pick_destination` asks the user to choose one.
+#[tauri::command]
+pub async fn open_vault_archive(
+    app: AppHandle,
+    zip_path: String,
+    destination: Option<String>,
+    pick_destination: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<VaultOpenResult, TailorError> {
+    let zip_path = PathBuf::from(state.window_manager.lock().await.normalize_vault_path(&zip_path));
+    let destination = match (destination, pick_destination.unwrap_or(false)) {
+        (Some(destination), _) => Some(PathBuf::from(destination)),
+        (None, true) => {
+            let (tx, rx) = tokio::sync::oneshot::channel();
+            app.dialog().file()
+                .set_title("Extract vault to")
+                .pick_folder(move |folder| {
+                    let _ = tx.send(folder.map(|folder| PathBuf::from(folder.to_string())));
+                });
+            match rx.await {
+                Ok(Some(folder)) => Some(folder),
+                _ => return Err("No destination chosen".to_string().into()),
+            }
+        }
+        (None, false) => None,
+    };
+
+    let extraction = extract_vault_archive(&state, zip_path, destination).await?;
+    open_vault_path(&app, &state, extraction.vault_path, WindowPlacement::default()).await
+}
+
+/// Extract a vault archive off the async runtime
+async fn extract_vault_archive(
+    state: &AppState,
+    zip_path: PathBuf,
+    destination: Option<PathBuf>,
+) -> Result<Extraction, String> {
+    let archives = state.vault_archives.clone();
+    tokio::task::spawn_blocking(move || archives.extract(&zip_path, destination.as_deref()))
+        .await
+        .map_err(|e| format!("Extraction task failed: {}", e))?
+        .map_err(|e| format!("Failed to extract vault archive: {:#}", e))
+}
+
+/// Vaults extracted from zip archives
+#[tauri::command]
+pub async fn list_vault_extractions(state: State<'_, AppState>) -> Result<Vec<Extraction>, String> {
+    Ok(state.vault_archives.list())
+}
+
+/// Delete a vault's extracted copy. Refused while the vault is open.
+/// Returns false if the vault was not extracted from an archive.
+#[tauri::command]
+pub async fn remove_vault_extraction(
+    vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let vault_id = WindowManager::vault_id(&vault_path);
+    if let Some((window_label, _)) = state.window_manager.lock().await.find_window_for_vault(&vault_id) {
+        return Err(format!(
+            "Close the vault before removing its extracted copy (open in window '{}')",
+            window_label
+        ));
+    }
+    state.vault_archives
+        .remove(&vault_path)
+        .map_err(|e| format!("Failed to remove extracted vault: {}", e))
+}
+
+/// Run the open pipeline under the configured overall timeout. A failed or
+/// abandoned open is rolled back, so no half-open window or sidecar is left.
+async fn open_vault_with_timeout(
+    app: &AppHandle,
+    vault_path: String,
+    vault_id: String,
+    placement: WindowPlacement,
+    state: &AppState,
+) -> Result<VaultOpenResult, TailorError> {
+    let timeout = state.window_manager.lock().await.open_timeout();
+    let created = std::sync::Mutex::new(None);
+
+    let open = open_vault_inner(app, vault_path.clone(), vault_id, placement, state, &created);
+    let result = match tokio::time::timeout(timeout, open).await {
+        Ok(result) => result.map_err(TailorError::from),
+        Err(_) => Err(TailorError::Timeout {
+            message: format!("opening {} did not finish within {}s", vault_path, timeout.as_secs()),
+        }),
+    };
+
+    if result.is_err() {
+        let window_label = created.lock().unwrap().take();
+        if let Some(window_label) = window_label {
+            rollback_open(app, state, &window_label).await;
+        }
+    }
+    result
+}
+
+/// Undo a partially completed open: stop the window's sidecar if it got
+/// one, stop tracking the window and close it
+async fn rollback_open(app: &AppHandle, state: &AppState, window_label: &str) {
+    eprintln!("Rolling back partially opened vault window '{}'", window_label);
+    if state.sidecar_manager.get_ws_port(window_label).await.is_some() {
+        if let Err(e) = state.sidecar_manager.terminate_sidecar(window_label, ShutdownReason::OpenFailed).await {
+            eprintln!("Failed to terminate sidecar during rollback: {}", e);
+        }
+    }
+    state.window_manager.lock().await.remove_window(window_label);
+    state.event_bus.unregister_window(window_label).await;
+    if let Some(window) = app.get_webview_window(window_label) {
+        if let Err(e) = window.destroy() {
+            eprintln!("Failed to close window '{}' during rollback: {}", window_label, e);
+        }
+    }
+}
+
+/// Prepare a vault and start its sidecar ahead of `open_vault` (e.g. when the
+/// vault is hovered in the list), so opening only has to create the window.
+/// A pre-warmed sidecar that is not adopted within `PREWARM_TIMEOUT` is stopped.
+#[tauri::command]
+pub async fn prewarm_vault(
+    vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let vault_path = state.window_manager.lock().await.normalize_vault_path(&vault_path);
+    let vault_id = WindowManager::vault_id(&vault_path);
+    let label = SidecarManager::prewarm_label(&vault_id);
+
+    let already_open = state.window_manager.lock().await.find_window_for_vault(&vault_id).is_some();
+    if already_open || state.sidecar_manager.get_ws_port(&label).await.is_some() {
+        return Ok(());
+    }
+
+    state.prewarming
+        .run(&vault_id.clone(), prewarm_vault_inner(vault_path, label, &state))
+        .await
+        .map(|_| ())
+}
+
+async fn prewarm_vault_inner(
+    vault_path: String,
+    label: String,
+    state: &AppState,
+) -> Result<u16, String> {
+    println!("Pre-warming vault: {}", vault_path);
+    // Nobody asked for this open, so an unapproved post-install script fails it
+    let PreparedVault { spawn_options, .. } = prepare_vault(None, &vault_path, state).await?;
+    let ws_port = state.sidecar_manager
+        .spawn_sidecar(label.clone(), vault_path.clone(), spawn_options)
+        .await
+        .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
+
+    let sidecar_manager = state.sidecar_manager.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(PREWARM_TIMEOUT).await;
+        // Only stop this pre-warm, not a newer one under the same label
+        if sidecar_manager.get_ws_port(&label).await == Some(ws_port) {
+            println!("Pre-warmed sidecar for {} was not used, stopping it", vault_path);
+            if let Err(e) = sidecar_manager.terminate_sidecar(&label, ShutdownReason::PrewarmExpired).await {
+                eprintln!("Failed to stop pre-warmed sidecar: {}", e);
+            }
+        }
+    });
+
+    Ok(ws_port)
+}
+
+/// Focus the window for an already-open vault and return its info
+async fn focus_open_vault(
+    app: &AppHandle,
+    vault_id: &str,
+    state: &AppState,
+) -> Option<VaultInfo> {
+    let (window_label, vault_path) = state.window_manager
+        .lock()
+        .await
+        .find_window_for_vault(vault_id)?;
+    let ws_port = state.sidecar_manager.get_ws_port(&window_label).await?;
+
+    if let Some(window) = app.get_webview_window(&window_label) {
+        if let Err(e) = window.set_focus() {
+            println!("Warning: Failed to focus window '{}': {}", window_label, e);
+        }
+    }
+    println!("Vault already open, focused window: {}", window_label);
+
+    let pid = state.sidecar_manager.get_pid(&window_label).await;
+    Some(VaultInfo {
+        window_label,
+        vault_path,
+        vault_id: vault_id.to_string(),
+        ws_port,
+        pid,
+    })
+}
+
+async fn open_vault_inner(
+    app: &AppHandle,
+    vault_path: String,
+    vault_id: String,
+    placement: WindowPlacement,
+    state: &AppState,
+    created: &std::sync::Mutex<Option<String>>,
+) -> Result<VaultOpenResult, String> {
+    println!("Opening vault: {}", vault_path);
+
+    // A pre-warm already preparing this vault will leave a sidecar to adopt
+    let _ = state.prewarming.wait(&vault_id).await;
+
+    // Step 1: Read the manifest, check Python and install dependencies
+    let PreparedVault { manifest, spawn_options, dependency_report, mut warnings } = prepare_vault(Some(app), &vault_path, state).await?;
+
+    // Step 2: Create window
+    let window_label = state.window_manager
+        .lock()
+        .await
+        .create_vault_window(app, vault_path.clone(), vault_id.clone(), &manifest.window, placement)
+        .map_err(|e| format!("Failed to create window: {}", e))?;
+    *created.lock().unwrap() = Some(window_label.clone());
+    warnings.extend(report_nested_vaults(app, state, &window_label, &vault_path).await);
+
+    // Step 3: Adopt the pre-warmed sidecar, or spawn one
+    let prewarm_label = SidecarManager::prewarm_label(&vault_id);
+    let ws_port = match state.sidecar_manager.adopt(&prewarm_label, &window_label).await {
+        Some(ws_port) => {
+            warnings.push("Reused the pre-warmed sidecar; it started before this open".to_string());
+            ws_port
+        }
+        None => state.sidecar_manager
+            .spawn_sidecar(window_label.clone(), vault_path.clone(), spawn_options)
+            .await
+            .map_err(|e| format!("Failed to spawn sidecar: {}", e))?,
+    };
+
+    println!("Vault opened successfully: window={}, port={}", window_label, ws_port);
+    Metrics::incr(&state.metrics.vaults_opened);
+
+    let (vault_item, event_policy) = read_vault_metadata(&vault_path);
+    let event_policy = manifest.events.unwrap_or(event_policy);
+
+    // Register window with the EventBus and apply the vault's event policy
+    state.event_bus.register_window(window_label.clone(), vault_id.clone()).await;
+    state.event_bus.set_vault_policy(vault_id.clone(), event_policy).await;
+    state.event_bus.set_vault_schemas(vault_id.clone(), &manifest.event_schemas).await;
+
+    if let Err(e) = register_vault_in_registry(app, &vault_item).await {
+        println!("Warning: Failed to register vault in registry: {}", e);
+        warnings.push(format!("Vault was not added to the recent vaults list: {}", e));
+    }
+
+    let pid = state.sidecar_manager.get_pid(&window_label).await;
+    Ok(VaultOpenResult {
+        info: VaultInfo {
+            window_label,
+            vault_path,
+            vault_id,
+            ws_port,
+            pid,
+        },
+        dependency_report,
+        warnings,
+    })
+}
+
+/// A vault whose environment is ready for its sidecar
+struct PreparedVault {
+    manifest: VaultManifest,
+    /// How to spawn the sidecar, with the sandbox the user chose
+    spawn_options: SpawnOptions,
+    /// Set when the vault declares plugin requirements
+    dependency_report: Option<InstallReport>,
+    warnings: Vec<String>,
+}
+
+/// Load and validate a vault's manifest, then make sure the environment it
+/// declares is in place: the Python version and plugin dependencies. With
+/// `app`, the user is asked before a post-install script runs.
+async fn prepare_vault(app: Option<&AppHandle>, vault_path: &str, state: &AppState) -> Result<PreparedVault, String> {
+    let manifest = VaultManifest::load(vault_path)
+        .map_err(|e| format!("Failed to load vault manifest: {:#}", e))?;
+    let mut warnings = Vec::new();
+
+    match &manifest.sidecar.python_version {
+        Some(required) => DependencyChecker::check_python_version(required)
+            .await
+            .map_err(|e| format!("Unsupported Python: {}", e))?,
+        None => match DependencyChecker::python_version().await {
+            Ok(version) => warnings.extend(DependencyChecker::python_version_warning(&version)),
+            Err(e) => warnings.push(format!("Could not check the Python version: {}", e)),
+        },
+    }
+
+    let dependency_report = if DependencyChecker::has_plugin_requirements(vault_path) {
+        confirm_postinstall(app, state, vault_path, false).await?;
+        let report = state.dependency_checker
+            .install(vault_path)
+            .await
+            .map_err(|e| format!("Failed to install dependencies: {}", e))?;
+        if report.installed {
+            record_install(state, vault_path);
+        }
+        Some(report)
+    } else {
+        None
+    };
+
+    let sandbox = state.vault_permissions.grants(&WindowManager::vault_id(vault_path)).sandbox();
+    let spawn_options = manifest.spawn_options(vault_path, sandbox);
+    Ok(PreparedVault { manifest, spawn_options, dependency_report, warnings })
+}
+
+/// Make sure the user has allowed the vault's post-install script to run
+/// before an install that would run it, asking them if not. Unless
+/// `reinstalling`, nothing is asked when the dependencies are up to date.
+/// Approval is remembered until the script changes.
+async fn confirm_postinstall(
+    app: Option<&AppHandle>,
+    state: &AppState,
+    vault_path: &str,
+    reinstalling: bool,
+) -> Result<(), String> {
+    let Some(script) = state.dependency_checker.untrusted_postinstall(vault_path) else {
+        return Ok(());
+    };
+    if !reinstalling && !DependencyChecker::needs_update(vault_path).await.unwrap_or(true) {
+        return Ok(());
+    }
+    let Some(app) = app else {
+        return Err(format!("Post-install script {} has not been allowed to run", script.display()));
+    };
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .message(format!(
+            "The vault at {} wants to run {} after installing its plugin dependencies.\n\n\
+             Scripts can do anything your user account can. Only allow this for vaults you trust.",
+            vault_path, script.display(),
+        ))
+        .title("Run post-install script?")
+        .kind(MessageDialogKind::Warning)
+        .buttons(MessageDialogButtons::OkCancelCustom("Run script".to_string(), "Cancel".to_string()))
+        .show(move |allowed| {
+            let _ = tx.send(allowed);
+        });
+    if !rx.await.unwrap_or(false) {
+        return Err(format!("Post-install script {} was not allowed to run", script.display()));
+    }
+    state.dependency_checker
+        .trust_postinstall(vault_path)
+        .map_err(|e| format!("Failed to allow post-install script: {}", e))
+}
+
+/// Read a vault's registry entry and event policy from its `.vault.toml`
+fn read_vault_metadata(vault_path: &str) -> (VaultListItem, EventPolicy) {
+    let vault_path_buf = PathBuf::from(vault_path);
+    let config_path = vault_path_buf.join(".vault.toml");
+    
+    let mut name = vault_path_buf.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Unknown Vault".to_string());
+    let mut created = None;
+    let mut event_policy = EventPolicy::default();
+
+    if config_path.exists() {
+        if let Ok(contents) = fs::read_to_string(&config_path) {
+            if let Ok(config) = toml::from_str::<serde_json::Value>(&contents) {
+                 if let Some(n) = config.get("name").and_then(|v| v.as_str()) {
+                     name = n.to_string();
+                 }
+                 if let Some(c) = config.get("created").and_then(|v| v.as_str()) {
+                     created = Some(c.to_string());
+                 }
+                 if let Some(events) = config.get("events") {
+                     match serde_json::from_value::<EventPolicy>(events.clone()) {
+                         Ok(policy) => event_policy = policy,
+                         Err(e) => println!("Warning: Invalid event policy in vault config: {}", e),
+                     }
+                 }
+            }
+        }
+    }
+
+    let vault_item = VaultListItem {
+        name,
+        path: vault_path.to_string(),
+        created,
+    };
+    (vault_item, event_policy)
+}
+
+/// Replace the vault shown in an existing window
+///
+/// Starts a sidecar for the new vault, stops the window's current one once
+/// it is up, points the window at the new vault, then emits `vault-switched`
+/// to the window. If the new sidecar fails to start the window stays as it was.
+#[tauri::command]
+pub async fn switch_vault(
+    app: AppHandle,
+    window_label: String,
+    new_vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<VaultInfo, String> {
+    let new_vault_path = state.window_manager.lock().await.normalize_vault_path(&new_vault_path);
+    let vault_id = WindowManager::vault_id(&new_vault_path);
+
+    let existing = {
+        let window_manager = state.window_manager.lock().await;
+        if window_manager.get_vault_path(&window_label).is_none() {
+            return Err(format!("No vault open in window: {}", window_label));
+        }
+        window_manager.find_window_for_vault(&vault_id)
+    };
+    if let Some((label, _)) = existing {
+        if label == window_label {
+            return focus_open_vault(&app, &vault_id, &state)
+                .await
+                .ok_or_else(|| "Sidecar not found for this window".to_string());
+        }
+        return Err(format!("Vault is already open in window: {}", label));
+    }
+
+    let switch = async {
+        switch_vault_inner(&app, window_label, new_vault_path, vault_id.clone(), &state)
+            .await
+            .map_err(TailorError::from)
+    };
+    state.opening_vaults
+        .run(&vault_id, switch)
+        .await
+        .map(|result| result.info)
+        .map_err(|e| e.to_string())
+}
+
+async fn switch_vault_inner(
+    app: &AppHandle,
+    window_label: String,
+    vault_path: String,
+    vault_id: String,
+    state: &AppState,
+) -> Result<VaultOpenResult, String> {
+    println!("Switching window '{}' to vault: {}", window_label, vault_path);
+
+    // Prepare first so a failure leaves the current vault running
+    let PreparedVault { manifest, spawn_options, dependency_report, mut warnings } = prepare_vault(Some(app), &vault_path, state).await?;
+    warnings.extend(report_nested_vaults(app, state, &window_label, &vault_path).await);
+
+    // The new sidecar must be up before the current one is stopped, so a
+    // failed spawn leaves the window on its current vault
+    let ws_port = state.sidecar_manager
+        .replace_sidecar(&window_label, vault_path.clone(), spawn_options, ShutdownReason::VaultSwitched)
+        .await
+        .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
+    Metrics::incr(&state.metrics.vaults_opened);
+
+    state.window_manager
+        .lock()
+        .await
+        .switch_vault(app, &window_label, vault_path.clone(), vault_id.clone())
+        .map_err(|e| format!("Failed to switch window: {}", e))?;
+
+    let (vault_item, event_policy) = read_vault_metadata(&vault_path);
+    let event_policy = manifest.events.clone().unwrap_or(event_policy);
+    // Other windows must not take the new sidecar's numbering for lost events
+    state.event_bus.reset_sequence(app, &window_label).await;
+    state.event_bus.unregister_window(&window_label).await;
+    state.event_bus.register_window(window_label.clone(), vault_id.clone()).await;
+    state.event_bus.set_vault_policy(vault_id.clone(), event_policy).await;
+    state.event_bus.set_vault_schemas(vault_id.clone(), &manifest.event_schemas).await;
+
+    if let Err(e) = register_vault_in_registry(app, &vault_item).await {
+        println!("Warning: Failed to register vault in registry: {}", e);
+    }
+
+    let pid = state.sidecar_manager.get_pid(&window_label).await;
+    let info = VaultInfo {
+        window_label,
+        vault_path,
+        vault_id,
+        ws_port,
+        pid,
+    };
+    if let Err(e) = app.emit_to(&info.window_label, "vault-switched", &info) {
+        println!("Warning: Failed to emit vault-switched: {}", e);
+    }
+
+    println!("Vault switched successfully: window={}, port={}", info.window_label, ws_port);
+    Ok(VaultOpenResult { info, dependency_report, warnings })
+}
+
+/// Payload of `nested-vault`: a vault was opened inside or around others
+#[derive(Debug, Clone, Serialize)]
+pub struct NestedVaultWarning {
+    pub window_label: String,
+    pub vault_path: String,
+    pub nested: Vec<NestedVault>,
+}
+
+/// Find open vaults inside or around the one `window_label` is opening. Their
+/// sidecars see each other's files, so each window involved gets a
+/// `nested-vault` event and may close one of them; the open goes ahead.
+/// Returns a warning for each.
+async fn report_nested_vaults(app: &AppHandle, state: &AppState, window_label: &str, vault_path: &str) -> Vec<String> {
+    let nested = state.window_manager.lock().await.nested_vaults(vault_path, Some(window_label));
+    if nested.is_empty() {
+        return Vec::new();
+    }
+
+    let warnings = nested.iter()
+        .map(|other| match other.nesting {
+            Nesting::Inside => format!("Vault is inside the vault open at {}; both will see its files", other.vault_path),
+            Nesting::Contains => format!("Vault contains the vault open at {}; both will see its files", other.vault_path),
+        })
+        .collect();
+    let payload = NestedVaultWarning {
+        window_label: window_label.to_string(),
+        vault_path: vault_path.to_string(),
+        nested,
+    };
+    for label in std::iter::once(window_label).chain(payload.nested.iter().map(|other| other.window_label.as_str())) {
+        if let Err(e) = app.emit_to(label, "nested-vault", &payload) {
+            println!("Warning: Failed to emit nested-vault: {}", e);
+        }
+    }
+    warnings
+}
+
+/// Call a sidecar method and return just its result. The JSON-RPC envelope
+/// is built here; `params` must be an object or array, with null meaning no
+/// params. Errors come back as a structured `TailorError`.
+#[tauri::command]
+pub async fn call_sidecar(
+    window_label: String,
+    method: String,
+    params: serde_json::Value,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, TailorError> {
+    let params = rpc_params(&method, params)?;
+    let mut response = send_to_sidecar(window_label, method, params, None, state).await?;
+    Ok(response.get_mut("result").map(serde_json::Value::take).unwrap_or_default())
+}
+
+/// Check a method name and params before they are put in an envelope
+fn rpc_params(method: &str, params: serde_json::Value) -> Result<serde_json::Value, TailorError> {
+    let invalid = |message: &str| TailorError::InvalidRequest { message: message.to_string(), data: None };
+    if method.trim().is_empty() {
+        return Err(invalid("method must not be empty"));
+    }
+    match params {
+        serde_json::Value::Null => Ok(serde_json::json!({})),
+        serde_json::Value::Object(_) | serde_json::Value::Array(_) => Ok(params),
+        _ => Err(invalid("params must be an object or an array")),
+    }
+}
+
+/// Send command to sidecar and return the whole JSON-RPC response; the
+/// lower-level counterpart of `call_sidecar`. Goes to the sidecar of the
+/// window's active vault (see `set_active_vault`), or of `vault_id` if given.
+///
+/// A JSON-RPC error in the response is returned as a structured `TailorError`.
+#[tauri::command]
+pub async fn send_to_sidecar(
+    window_label: String,
+    method: String,
+    params: serde_json::Value,
+    vault_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, TailorError> {
+    // println!("Sending command to sidecar '{}': {}", window_label, method);
+    let target = target_sidecar(&state, &window_label, vault_id.as_deref()).await?;
+
+    state.sidecar_manager.check_method_supported(&target, &method).await?;
+
+    let command = state.sidecar_manager.send_command(&target, &method, params);
+    let response = cancellable(&state, &window_label, OperationKind::Command, &method, command)
+        .await?
+        .map_err(TailorError::from_transport_error)?;
+
+    match response.get("error") {
+        Some(error) => Err(TailorError::from_rpc_error(error)),
+        None => Ok(response),
+    }
+}
+
+/// Run an operation on behalf of a window so `cancel_all` can abort it
+async fn cancellable<T>(
+    state: &AppState,
+    window_label: &str,
+    kind: OperationKind,
+    description: &str,
+    operation: impl std::future::Future<Output = T>,
+) -> Result<T, TailorError> {
+    state.operations
+        .run(window_label, kind, operation)
+        .await
+        .ok_or_else(|| TailorError::Cancelled { operation: description.to_string() })
+}
+
+/// Abort everything in flight for the calling window: sidecar commands
+/// awaiting a response, streams, and dependency installs. Each aborted
+/// command fails with a `cancelled` error. A dependency install stopped
+/// partway is redone the next time the vault is opened.
+#[tauri::command]
+pub async fn cancel_all(window: tauri::Window, state: State<'_, AppState>) -> Result<CancelReport, String> {
+    let window_label = window.label();
+    let report = state.operations.cancel_all(window_label);
+    println!("Cancelled {} operation(s) for window '{}'", report.cancelled, window_label);
+    Ok(report)
+}
+
+/// Label of the window whose sidecar serves `vault_id`, or else the active
+/// vault of `window_label`. A window may only name its own vault or one it
+/// is attached to.
+async fn target_sidecar(state: &AppState, window_label: &str, vault_id: Option<&str>) -> Result<String, TailorError> {
+    if let Some(vault_id) = vault_id {
+        if !state.event_bus.can_use_vault(window_label, vault_id).await {
+            return Err(TailorError::InvalidRequest {
+                message: format!("window '{}' is not attached to vault {}", window_label, vault_id),
+                data: None,
+            });
+        }
+    }
+    let target = match vault_id {
+        Some(vault_id) => state.event_bus.window_for_vault(vault_id).await,
+        None => state.event_bus.sidecar_window(window_label).await,
+    };
+    target.ok_or_else(|| TailorError::SidecarUnreachable {
+        message: match vault_id {
+            Some(vault_id) => format!("vault {} is not open in any window", vault_id),
+            None => format!("the active vault of '{}' is no longer open", window_label),
+        },
+    })
+}
+
+/// Send a command whose sidecar handler streams partial results. Each chunk
+/// is forwarded to the frontend over `on_chunk` as it arrives; the final
+/// response is returned like `send_to_sidecar`, and goes to the same sidecar.
+#[tauri::command]
+pub async fn stream_from_sidecar(
+    window_label: String,
+    method: String,
+    params: serde_json::Value,
+    on_chunk: Channel<serde_json::Value>,
+    vault_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, TailorError> {
+    let target = target_sidecar(&state, &window_label, vault_id.as_deref()).await?;
+    let stream = state.sidecar_manager
+        .send_streaming(&target, &method, params, |chunk| {
+            if let Err(e) = on_chunk.send(chunk) {
+                eprintln!("Failed to forward stream chunk for '{}': {}", method, e);
+            }
+        });
+    let response = cancellable(&state, &window_label, OperationKind::Stream, &method, stream)
+        .await?
+        .map_err(TailorError::from_transport_error)?;
+
+    match response.get("error") {
+        Some(error) => Err(TailorError::from_rpc_error(error)),
+        None => Ok(response),
+    }
+}
+
+/// Where `save_sidecar_output` wrote a sidecar's output
+#[derive(Debug, Clone, Serialize)]
+pub struct SavedOutput {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// Payload of `sidecar-output-progress`, sent as each chunk is written
+#[derive(Debug, Clone, Serialize)]
+struct SaveProgress<'a> {
+    window_label: &'a str,
+    path: &'a str,
+    bytes_written: u64,
+}
+
+/// Export a file produced by the sidecar: ask the user where to save it,
+/// then call `method`, whose handler streams the file's bytes, and write them
+/// there with `sidecar-output-progress` events. The file only appears once
+/// the sidecar has finished. Resolves to None if the dialog was cancelled.
+/// The sidecar is chosen like `send_to_sidecar`'s.
+#[tauri::command]
+pub async fn save_sidecar_output(
+    app: AppHandle,
+    window_label: String,
+    method: String,
+    params: serde_json::Value,
+    dialog: Option<host_requests::DialogOptions>,
+    vault_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Option<SavedOutput>, String> {
+    let target = target_sidecar(&state, &window_label, vault_id.as_deref()).await
+        .map_err(|e| e.to_string())?;
+    let Some(path) = host_requests::show_file_dialog(&app, &window_label, dialog.unwrap_or_default(), true).await? else {
+        return Ok(None);
+    };
+    // Written beside the target and renamed into place once complete
+    let partial = PathBuf::from(format!("{}.part", path));
+    let mut file = std::io::BufWriter::new(
+        fs::File::create(&partial).map_err(|e| format!("Failed to create {}: {}", partial.display(), e))?
+    );
+
+    let mut bytes_written = 0u64;
+    let stream = state.sidecar_manager
+        .send_binary_stream(&target, &method, params, |data| {
+            use std::io::Write;
+            file.write_all(data).with_context(|| format!("Failed to write {}", partial.display()))?;
+            bytes_written += data.len() as u64;
+            let progress = SaveProgress { window_label: &window_label, path: &path, bytes_written };
+            let _ = app.emit_to(&window_label, "sidecar-output-progress", &progress);
+            Ok(())
+        });
+    let result = cancellable(&state, &window_label, OperationKind::Stream, &method, stream)
+        .await
+        .unwrap_or_else(|e| Err(e.into()));
+
+    let saved = match result {
+        Ok(response) => match response.get("error") {
+            Some(error) => Err(format!("Sidecar failed to produce output: {}", TailorError::from_rpc_error(error))),
+            None => file.into_inner()
+                .map_err(|e| e.into_error())
+                .and_then(|file| file.sync_all())
+                .and_then(|_| fs::rename(&partial, &path))
+                .map_err(|e| format!("Failed to save {}: {}", path, e)),
+        },
+        Err(e) => Err(format!("Failed to save sidecar output: {:#}", e)),
+    };
+    if let Err(e) = saved {
+        let _ = fs::remove_file(&partial);
+        return Err(e);
+    }
+    println!("Saved {} bytes of '{}' output to {}", bytes_written, method, path);
+    Ok(Some(SavedOutput { path, bytes: bytes_written }))
+}
+
+/// List files and directories in the window's vault. Results are cached per
+/// params until the sidecar reports a `file-changed` event.
+#[tauri::command]
+pub async fn list_vault_entries(
+    window_label: String,
+    params: Option<serde_json::Value>,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, TailorError> {
+    let listing = state.sidecar_manager
+        .list_entries(&window_label, params.unwrap_or_else(|| serde_json::json!({})));
+    let response = cancellable(&state, &window_label, OperationKind::Command, "list entries", listing)
+        .await?
+        .map_err(TailorError::from_transport_error)?;
+
+    match response.get("error") {
+        Some(error) => Err(TailorError::from_rpc_error(error)),
+        None => Ok(response),
+    }
+}
+
+/// Drop the window's cached vault listings, e.g. when the frontend sees a
+/// `file-changed` event the host did not
+#[tauri::command]
+pub async fn invalidate_vault_entries(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.sidecar_manager.invalidate_entries(&window_label).await;
+    Ok(())
+}
+
+/// Capabilities the window's sidecar advertised in its handshake, or null
+/// if it advertised none
+#[tauri::command]
+pub async fn sidecar_capabilities(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, String> {
+    state.sidecar_manager
+        .capabilities(&window_label)
+        .await
+        .map(Option::unwrap_or_default)
+        .map_err(|e| e.to_string())
+}
+
+/// Send several commands to the sidecar in one JSON-RPC batch. The sidecar
+/// is chosen like `send_to_sidecar`'s.
+#[tauri::command]
+pub async fn send_batch_to_sidecar(
+    window_label: String,
+    commands: Vec<serde_json::Value>,
+    vault_id: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<serde_json::Value>, String> {
+    let target = target_sidecar(&state, &window_label, vault_id.as_deref()).await
+        .map_err(|e| e.to_string())?;
+    let batch = state.sidecar_manager.send_batch(&target, commands);
+    cancellable(&state, &window_label, OperationKind::Command, "batch", batch)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Sidecar error: {}", e))
+}
+
+/// Resolve once the window's sidecar accepts connections, or fail with a
+/// `timeout` error after `timeout_ms`
+#[tauri::command]
+pub async fn wait_for_sidecar_ready(
+    window_label: String,
+    timeout_ms: u64,
+    state: State<'_, AppState>,
+) -> Result<(), TailorError> {
+    state.sidecar_manager
+        .wait_until_ready(&window_label, std::time::Duration::from_millis(timeout_ms))
+        .await
+}
+
+/// Get running state and the last recorded error for a window's sidecar
+#[tauri::command]
+pub async fn sidecar_status(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<SidecarStatus, String> {
+    state.sidecar_manager
+        .status(&window_label)
+        .await
+        .ok_or_else(|| format!("Sidecar not found for window: {}", window_label))
+}
+
+/// Recent stdout/stderr of a window's sidecar. When `output_capture` is not
+/// "capture" the output was never recorded and both logs are empty.
+#[tauri::command]
+pub async fn get_sidecar_logs(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<SidecarLogs, String> {
+    state.sidecar_manager
+        .logs_for(&window_label)
+        .await
+        .ok_or_else(|| format!("Sidecar not found for window: {}", window_label))
+}
+
+/// How a window's sidecar was launched, with secrets redacted, so it can be
+/// run by hand in a terminal. Also available after a failed start.
+#[tauri::command]
+pub async fn get_sidecar_command(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<SidecarCommand, String> {
+    state.sidecar_manager
+        .command_for(&window_label)
+        .ok_or_else(|| format!("No sidecar has been started for window: {}", window_label))
+}
+
+/// Get the state of every tracked sidecar in one call
+#[tauri::command]
+pub async fn all_sidecar_status(state: State<'_, AppState>) -> Result<Vec<SidecarStatus>, String> {
+    Ok(state.sidecar_manager.all_status().await)
+}
+
+/// Recover a sidecar port held by a process Tailor no longer tracks, e.g.
+/// an orphan left by a crash. Without `expected_pid` only reports what holds
+/// the port, so the user can check it; passing the reported PID then stops
+/// that process, and only that one.
+#[tauri::command]
+pub async fn reclaim_port(
+    port: u16,
+    expected_pid: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<PortReclaim, String> {
+    state.sidecar_manager
+        .reclaim_port(port, expected_pid)
+        .await
+        .map_err(|e| format!("Failed to reclaim port {}: {}", port, e))
+}
+
+/// Clear the last recorded error for a window's sidecar
+#[tauri::command]
+pub async fn clear_sidecar_error(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.sidecar_manager
+        .clear_error(&window_label)
+        .await
+        .map_err(|e| format!("Failed to clear sidecar error: {}", e))
+}
+
+/// Change a window's sidecar log level (DEBUG, INFO, WARNING, ERROR or
+/// CRITICAL) without restarting it. A restart keeps the new level.
+#[tauri::command]
+pub async fn set_sidecar_log_level(
+    window_label: String,
+    level: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.sidecar_manager
+        .set_log_level(&window_label, &level)
+        .await
+        .map_err(|e| format!("Failed to set log level: {}", e))
+}
+
+/// Flush a window's sidecar log file, move it aside and start a fresh one,
+/// e.g. to capture just a reproduction. Returns the new log file's path.
+#[tauri::command]
+pub async fn rotate_sidecar_log(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    state.sidecar_manager
+        .rotate_log(&window_label)
+        .await
+        .map_err(|e| format!("Failed to rotate sidecar log: {}", e))
+}
+
+/// Ask a window's sidecar to hold its background work (ticks, indexing, ...)
+/// without stopping it, e.g. to save battery. Unlike idle suspension the
+/// sidecar keeps its state.
+#[tauri::command]
+pub async fn pause_sidecar(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.sidecar_manager
+        .set_paused(&window_label, true)
+        .await
+        .map_err(|e| format!("Failed to pause sidecar: {}", e))
+}
+
+/// Let a paused sidecar carry on with its background work
+#[tauri::command]
+pub async fn resume_sidecar(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.sidecar_manager
+        .set_paused(&window_label, false)
+        .await
+        .map_err(|e| format!("Failed to resume sidecar: {}", e))
+}
+
+/// Restart a window's sidecar in place, e.g. to pick up newly installed
+/// dependencies. Commands sent meanwhile wait for the new sidecar. The
+/// window is told the new port with a `sidecar-restarted` event.
+#[tauri::command]
+pub async fn restart_sidecar(
+    app: AppHandle,
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<u16, String> {
+    restart_window_sidecar(&app, &state, &window_label).await
+}
+
+/// Outcome of restarting one window's sidecar in `restart_all_sidecars`
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarRestartResult {
+    pub window_label: String,
+    /// The new port, if the restart succeeded
+    pub ws_port: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// Restart every open window's sidecar one after another, e.g. after
+/// changing a setting sidecars read at startup. A `sidecars-restart-progress`
+/// event is emitted to all windows after each one.
+#[tauri::command]
+pub async fn restart_all_sidecars(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<SidecarRestartResult>, String> {
+    let mut window_labels = state.window_manager.lock().await.get_active_windows();
+    window_labels.sort();
+
+    let total = window_labels.len();
+    let mut results = Vec::with_capacity(total);
+    for (i, window_label) in window_labels.into_iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(RESTART_ALL_STAGGER).await;
+        }
+        let result = match restart_window_sidecar(&app, &state, &window_label).await {
+            Ok(ws_port) => SidecarRestartResult { window_label, ws_port: Some(ws_port), error: None },
+            Err(e) => {
+                eprintln!("Failed to restart sidecar for window '{}': {}", window_label, e);
+                SidecarRestartResult { window_label, ws_port: None, error: Some(e) }
+            }
+        };
+
+        let progress = serde_json::json!({ "completed": i + 1, "total": total, "result": &result });
+        if let Err(e) = app.emit("sidecars-restart-progress", progress) {
+            eprintln!("Failed to emit sidecars-restart-progress: {}", e);
+        }
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Restart a window's sidecar and tell the window its new port
+async fn restart_window_sidecar(app: &AppHandle, state: &AppState, window_label: &str) -> Result<u16, String> {
+    let ws_port = state.sidecar_manager
+        .restart_sidecar(window_label)
+        .await
+        .map_err(|e| e.to_string())?;
+    state.event_bus.reset_sequence(app, window_label).await;
+
+    let payload = serde_json::json!({ "ws_port": ws_port, "reason": ShutdownReason::Restart });
+    if let Err(e) = app.emit_to(window_label, "sidecar-restarted", payload) {
+        eprintln!("Failed to emit sidecar-restarted: {}", e);
+    }
+    Ok(ws_port)
+}
+
+/// Stop managing a window's sidecar but leave the process running, for
+/// attaching a debugger. Returns the process id.
+#[tauri::command]
+pub async fn detach_sidecar(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<u32, String> {
+    state.sidecar_manager
+        .detach_sidecar(&window_label)
+        .await
+        .map_err(|e| format!("Failed to detach sidecar: {}", e))
+}
+
+/// Replace a window's sidecar with one started by hand (e.g. under a
+/// debugger) on `ws_port`. Tailor never kills or restarts it; closing the
+/// vault leaves it running. The window is told the port with a
+/// `sidecar-restarted` event.
+#[tauri::command]
+pub async fn attach_sidecar(
+    app: AppHandle,
+    window_label: String,
+    ws_port: u16,
+    state: State<'_, AppState>,
+) -> Result<VaultInfo, String> {
+    let (vault_path, vault_id) = {
+        let window_manager = state.window_manager.lock().await;
+        let vault_path = window_manager.get_vault_path(&window_label)
+            .cloned()
+            .ok_or_else(|| format!("No vault open in window: {}", window_label))?;
+        let vault_id = WindowManager::vault_id(&vault_path);
+        (vault_path, vault_id)
+    };
+
+    // Replaces the current sidecar only once the external one has answered
+    state.sidecar_manager
+        .attach_sidecar(window_label.clone(), vault_path.clone(), ws_port)
+        .await
+        .map_err(|e| format!("Failed to attach sidecar: {}", e))?;
+    state.event_bus.register_window(window_label.clone(), vault_id.clone()).await;
+    state.event_bus.reset_sequence(&app, &window_label).await;
+
+    let payload = serde_json::json!({ "ws_port": ws_port, "reason": ShutdownReason::Attached });
+    if let Err(e) = app.emit_to(&window_label, "sidecar-restarted", payload) {
+        eprintln!("Failed to emit sidecar-restarted: {}", e);
+    }
+
+    let pid = state.sidecar_manager.get_pid(&window_label).await;
+    Ok(VaultInfo { window_label, vault_path, vault_id, ws_port, pid })
+}
+
+/// Handle a `host.*` request made by the window's sidecar and relayed by the frontend
+#[tauri::command]
+pub async fn handle_sidecar_request(
+    app: AppHandle,
+    window: tauri::Window,
+    method: String,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    let method = method.strip_prefix(HOST_METHOD_PREFIX).unwrap_or(&method);
+    let request = host_requests::parse_request(method, Some(params)).map_err(|(_, message)| message)?;
+
+    host_requests::handle(&app, window.label(), request).await
+}
+
+/// Deliver a block of sidecar stderr to the sidecar's window as a
+/// `sidecar-error` event. Output from a sidecar that no window owns yet
+/// (one being pre-warmed) is only logged.
+pub async fn forward_sidecar_stderr(app: &AppHandle, report: StderrReport) {
+    let state = app.state::<AppState>();
+    let Some(window_label) = state.sidecar_manager.label_for_port(report.ws_port).await else {
+        return;
+    };
+    if app.get_webview_window(&window_label).is_none() {
+        return;
+    }
+
+    let event = Event {
+        event_type: "sidecar-error".to_string(),
+        scope: EventScope::Window,
+        data: serde_json::json!({ "message": report.text }),
+        timestamp: chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+        id: None,
+        requires_ack: false,
+        immediate: true,
+        source_window: None,
+        seq: None,
+        coalesce_key: None,
+        superseded: Vec::new(),
+    };
+    if let Err(e) = state.event_bus.route_from_sidecar(app, window_label.clone(), event).await {
+        eprintln!("Failed to forward sidecar stderr to '{}': {}", window_label, e);
+    }
+}
+
+/// Route a notification a sidecar sent while answering a command through the
+/// EventBus, as if it had reached the frontend directly. `trigger_event`
+/// carries an event; any other method becomes an event of that type.
+pub async fn forward_sidecar_notification(app: &AppHandle, notification: SidecarNotification) {
+    let SidecarNotification { window_label, message } = notification;
+    let state = app.state::<AppState>();
+    if app.get_webview_window(&window_label).is_none() {
+        return;
+    }
+
+    let method = message["method"].as_str().unwrap_or_default().to_string();
+    let params = message.get("params").cloned().unwrap_or(serde_json::Value::Null);
+    let event = if method == "trigger_event" {
+        match serde_json::from_value::<Event>(params) {
+            Ok(event) => event,
+            Err(e) => {
+                eprintln!("Ignoring malformed event from sidecar for '{}': {}", window_label, e);
+                return;
+            }
+        }
+    } else {
+        Event {
+            event_type: method,
+            scope: EventScope::Window,
+            data: params,
+            timestamp: chrono::Utc::now().timestamp_millis() as f64 / 1000.0,
+            id: None,
+            requires_ack: false,
+            immediate: false,
+            source_window: None,
+            seq: None,
+            coalesce_key: None,
+            superseded: Vec::new(),
+        }
+    };
+    if let Err(e) = state.event_bus.route_from_sidecar(app, window_label.clone(), event).await {
+        eprintln!("Failed to forward sidecar notification to '{}': {}", window_label, e);
+    }
+}
+
+/// Emit `sidecar-killed-oom` to each window whose sidecar has exited after
+/// hitting the memory limit set in its vault's manifest
+pub async fn report_oom_kills(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    for kill in state.sidecar_manager.oom_kills().await {
+        eprintln!("Sidecar for window '{}' exceeded its memory limit of {} MiB ({})",
+                  kill.window_label, kill.limit_bytes / (1024 * 1024), kill.exit_status);
+        Metrics::incr(&state.metrics.sidecars_crashed);
+        if let Err(e) = app.emit_to(&kill.window_label, "sidecar-killed-oom", &kill) {
+            eprintln!("Failed to emit sidecar-killed-oom: {}", e);
+        }
+    }
+}
+
+/// Stop tracking sidecars that exited on their own and emit `sidecar-reaped`
+/// to their windows, or `sidecar-restarted` for those brought back up by
+/// `sidecar.auto_restart`
+pub async fn reap_dead_sidecars(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    for sidecar in state.sidecar_manager.reap_dead().await {
+        if sidecar.restarted_port.is_some() {
+            state.event_bus.reset_sequence(app, &sidecar.window_label).await;
+        }
+        let emitted = match sidecar.restarted_port {
+            Some(ws_port) => app.emit_to(
+                &sidecar.window_label,
+                "sidecar-restarted",
+                serde_json::json!({ "ws_port": ws_port, "reason": sidecar.reason }),
+            ),
+            None => app.emit_to(&sidecar.window_label, "sidecar-reaped", &sidecar),
+        };
+        if let Err(e) = emitted {
+            eprintln!("Failed to report exited sidecar: {}", e);
+        }
+    }
+}
+
+/// Temporarily stop emitting events to a window (e.g. while it reloads)
+#[tauri::command]
+pub async fn pause_events(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.event_bus.pause_window(&window_label).await;
+    Ok(())
+}
+
+/// Resume emitting events to a window, flushing those held while paused
+#[tauri::command]
+pub async fn resume_events(
+    app: AppHandle,
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<usize, String> {
+    state.event_bus
+        .resume_window(&app, &window_label)
+        .await
+        .map_err(|e| format!("Failed to flush held events: {}", e))
+}
+
+/// Reload a vault window's frontend, e.g. after editing plugin assets,
+/// keeping its sidecar running. Events are held from now on and its
+/// in-flight commands cancelled, since the page awaiting them goes away;
+/// the reloaded page's `reconnect` replays the held events.
+#[tauri::command]
+pub async fn reload_window(
+    app: AppHandle,
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let window = app.get_webview_window(&window_label)
+        .ok_or_else(|| format!("Window not found: {}", window_label))?;
+    if state.window_manager.lock().await.get_vault_path(&window_label).is_none() {
+        return Err(format!("No vault open in window: {}", window_label));
+    }
+
+    state.event_bus.pause_window(&window_label).await;
+    let cancelled = state.operations.cancel_all(&window_label);
+    if let Err(e) = window.eval("location.reload()") {
+        // Nothing will reconnect, so deliver what was held
+        let _ = state.event_bus.resume_window(&app, &window_label).await;
+        return Err(format!("Failed to reload window: {}", e));
+    }
+    println!("Reloading window '{}' ({} in-flight operations cancelled)", window_label, cancelled.cancelled);
+    Ok(())
+}
+
+/// Acknowledge a sidecar event that was emitted with `requires_ack`.
+/// Returns whether the event was still awaiting acknowledgement.
+#[tauri::command]
+pub async fn ack_event(
+    window_label: String,
+    event_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    Ok(state.event_bus.ack(&window_label, &event_id).await)
+}
+
+/// Connection info returned to a window re-establishing itself after a reload
+#[derive(Debug, Clone, Serialize)]
+pub struct ReconnectInfo {
+    pub vault_path: String,
+    pub vault_id: String,
+    /// Host to open the sidecar's WebSocket on, e.g. "127.0.0.1" or "[::1]"
+    pub ws_host: String,
+    pub ws_port: u16,
+    pub missed_event_count: usize,
+}
+
+/// Re-establish a reloaded window with the backend: re-register it with the
+/// EventBus, return its sidecar connection info and replay held events.
+/// Safe to call on every load. If the window still holds a `ws_port` from
+/// before the reload, it is checked against the tracked sidecar.
+#[tauri::command]
+pub async fn reconnect(
+    app: AppHandle,
+    window_label: String,
+    ws_port: Option<u16>,
+    state: State<'_, AppState>,
+) -> Result<ReconnectInfo, String> {
+    if let Some(port) = ws_port {
+        state.sidecar_manager
+            .validate_port(&window_label, port)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    let vault_path = state.window_manager
+        .lock()
+        .await
+        .get_vault_path(&window_label)
+        .cloned()
+        .ok_or_else(|| format!("No vault open in window: {}", window_label))?;
+
+    let ws_port = state.sidecar_manager
+        .get_ws_port(&window_label)
+        .await
+        .ok_or_else(|| format!("Sidecar not found for window: {}", window_label))?;
+
+    let vault_id = WindowManager::vault_id(&vault_path);
+    state.event_bus.register_window(window_label.clone(), vault_id.clone()).await;
+    let missed_event_count = state.event_bus
+        .resume_window(&app, &window_label)
+        .await
+        .map_err(|e| format!("Failed to replay missed events: {}", e))?;
+
+    println!("Window '{}' reconnected ({} missed events replayed)", window_label, missed_event_count);
+
+    let ws_host = match state.sidecar_manager.connect_addr(ws_port) {
+        std::net::SocketAddr::V4(addr) => addr.ip().to_string(),
+        std::net::SocketAddr::V6(addr) => format!("[{}]", addr.ip()),
+    };
+
+    Ok(ReconnectInfo {
+        vault_path,
+        vault_id,
+        ws_host,
+        ws_port,
+        missed_event_count,
+    })
+}
+
+/// Count an install that ran, and the time it took, in the session metrics
+fn record_install(state: &AppState, vault_path: &str) {
+    Metrics::incr(&state.metrics.dependency_installs);
+    if let Some(ms) = state.dependency_checker.last_install_ms(vault_path) {
+        Metrics::add(&state.metrics.dependency_install_ms, ms);
+    }
+}
+
+/// Check whether a vault's plugin dependencies are installed, without installing
+#[tauri::command]
+pub async fn dependencies_status(
+    vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<DependencyStatus, String> {
+    state.dependency_checker
+        .status(&vault_path)
+        .await
+        .map_err(|e| format!("Failed to check dependencies: {}", e))
+}
+
+/// Payload of the `dependencies-outdated` event
+#[derive(Debug, Clone, Serialize)]
+pub struct DependenciesOutdated {
+    pub window_label: String,
+    pub vault_path: String,
 }
 
-/// Open a new vault window
+/// Payload of `dependencies-reinstalled`, sent after an automatic reinstall
+#[derive(Debug, Clone, Serialize)]
+pub struct DependenciesReinstalled {
+    pub window_label: String,
+    pub vault_path: String,
+    /// Port of the restarted sidecar; None if the reinstall failed
+    pub ws_port: Option<u16>,
+    pub error: Option<String>,
+}
+
+/// Re-check each open vault's requirements against what is installed and
+/// emit `dependencies-outdated` to windows whose vault has just gone out of
+/// date. `outdated` holds the windows already told, so each change is
+/// reported once. Nothing is installed without `install_dependencies`,
+/// unless the vault's manifest sets `dependencies.auto_reinstall_on_change`.
+pub async fn check_dependency_updates(app: &AppHandle, outdated: &mut HashSet<String>) {
+    let state = app.state::<AppState>();
+    let windows: Vec<(String, String)> = {
+        let window_manager = state.window_manager.lock().await;
+        window_manager.get_active_windows()
+            .into_iter()
+            .filter_map(|label| Some((label.clone(), window_manager.get_vault_path(&label)?.clone())))
+            .collect()
+    };
+    outdated.retain(|label| windows.iter().any(|(open, _)| open == label));
+
+    for (window_label, vault_path) in windows {
+        match DependencyChecker::needs_update(&vault_path).await {
+            Ok(true) => {
+                if !outdated.insert(window_label.clone()) {
+                    continue;
+                }
+                println!("Dependencies of '{}' are out of date", vault_path);
+                let auto_reinstall = VaultManifest::load(&vault_path)
+                    .is_ok_and(|manifest| manifest.dependencies.auto_reinstall_on_change);
+                if auto_reinstall && reinstall_changed_dependencies(app, &state, &window_label, &vault_path).await {
+                    outdated.remove(&window_label);
+                    continue;
+                }
+                let payload = DependenciesOutdated { window_label: window_label.clone(), vault_path };
+                if let Err(e) = app.emit_to(&window_label, "dependencies-outdated", &payload) {
+                    eprintln!("Failed to emit dependencies-outdated: {}", e);
+                }
+            }
+            Ok(false) => {
+                outdated.remove(&window_label);
+            }
+            Err(e) => eprintln!("Failed to check dependencies of '{}': {}", vault_path, e),
+        }
+    }
+}
+
+/// Packages installed for a vault's plugins, e.g. for a dependencies panel
 #[tauri::command]
-pub async fn open_vault(
-    app: AppHandle,
+pub async fn list_installed_packages(
     vault_path: String,
     state: State<'_, AppState>,
-) -> Result<VaultInfo, String> {
-    println!("Opening vault: {}", vault_path);
-
-    // Step 1: Check and install dependencies
-    DependencyChecker::check_and_install(&vault_path)
+) -> Result<Vec<InstalledPackage>, String> {
+    state.dependency_checker
+        .list_installed_packages(&vault_path)
         .await
-        .map_err(|e| format!("Failed to install dependencies: {}", e))?;
+        .map_err(|e| format!("Failed to list installed packages: {}", e))
+}
 
-    // Step 2: Create window
-    let window_label = state.window_manager
+/// Reinstall a vault's changed requirements and restart its sidecar without
+/// asking, telling the window with `dependencies-reinstalling` and then
+/// `dependencies-reinstalled`. A post-install script must already be
+/// allowed to run. Returns whether it worked.
+async fn reinstall_changed_dependencies(app: &AppHandle, state: &AppState, window_label: &str, vault_path: &str) -> bool {
+    println!("Reinstalling dependencies of '{}' automatically", vault_path);
+    let payload = DependenciesOutdated { window_label: window_label.to_string(), vault_path: vault_path.to_string() };
+    if let Err(e) = app.emit_to(window_label, "dependencies-reinstalling", &payload) {
+        eprintln!("Failed to emit dependencies-reinstalling: {}", e);
+    }
+
+    let result = async {
+        confirm_postinstall(None, state, vault_path, false).await?;
+        let install = state.dependency_checker.check_and_install(vault_path);
+        let installed = cancellable(state, window_label, OperationKind::DependencyInstall, "dependency install", install)
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Failed to install dependencies: {}", e))?;
+        if installed {
+            record_install(state, vault_path);
+        }
+        restart_window_sidecar(app, state, window_label).await
+    }.await;
+
+    if let Err(e) = &result {
+        eprintln!("Automatic reinstall for '{}' failed: {}", vault_path, e);
+    }
+    let payload = DependenciesReinstalled {
+        window_label: window_label.to_string(),
+        vault_path: vault_path.to_string(),
+        ws_port: result.as_ref().ok().copied(),
+        error: result.as_ref().err().cloned(),
+    };
+    if let Err(e) = app.emit_to(window_label, "dependencies-reinstalled", &payload) {
+        eprintln!("Failed to emit dependencies-reinstalled: {}", e);
+    }
+    result.is_ok()
+}
+
+/// Install the window's vault dependencies if its requirements changed
+/// (e.g. after a `dependencies-outdated` prompt). Returns whether an install
+/// ran; the sidecar picks up new packages once restarted.
+#[tauri::command]
+pub async fn install_dependencies(
+    app: AppHandle,
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let vault_path = state.window_manager
         .lock()
         .await
-        .create_vault_window(&app, vault_path.clone())
-        .map_err(|e| format!("Failed to create window: {}", e))?;
+        .get_vault_path(&window_label)
+        .cloned()
+        .ok_or_else(|| format!("No vault open in window: {}", window_label))?;
+    confirm_postinstall(Some(&app), &state, &vault_path, false).await?;
 
-    // Step 3: Spawn sidecar
-    let ws_port = state.sidecar_manager
-        .spawn_sidecar(window_label.clone(), vault_path.clone())
+    let install = state.dependency_checker.check_and_install(&vault_path);
+    let installed = cancellable(&state, &window_label, OperationKind::DependencyInstall, "dependency install", install)
         .await
-        .map_err(|e| format!("Failed to spawn sidecar: {}", e))?;
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to install dependencies: {}", e))?;
+    if installed {
+        record_install(&state, &vault_path);
+    }
+    Ok(installed)
+}
 
-    println!("Vault opened successfully: window={}, port={}", window_label, ws_port);
+/// Restore `tailor.toml` to its defaults, keeping the old file as
+/// `tailor.toml.bak`. Applies from the next launch.
+#[tauri::command]
+pub async fn reset_settings(app: AppHandle) -> Result<(), String> {
+    let config_path = app.path().app_config_dir()
+        .map_err(|e| format!("Failed to get app config dir: {}", e))?
+        .join("tailor.toml");
+    AppConfig::reset(&config_path)
+        .map_err(|e| format!("Failed to reset settings: {}", e))
+}
 
-    // Register vault in registry
-    let vault_path_buf = PathBuf::from(&vault_path);
-    let config_path = vault_path_buf.join(".vault.toml");
-    
-    let mut name = vault_path_buf.file_name()
-        .map(|n| n.to_string_lossy().to_string())
-        .unwrap_or_else(|| "Unknown Vault".to_string());
-    let mut created = None;
+/// Check that Python and pip are available before any vault is opened
+/// (e.g. from onboarding), with guidance for anything missing
+#[tauri::command]
+pub async fn preflight_check() -> Result<PreflightReport, String> {
+    Ok(DependencyChecker::preflight().await)
+}
 
-    if config_path.exists() {
-        if let Ok(contents) = fs::read_to_string(&config_path) {
-            if let Ok(config) = toml::from_str::<serde_json::Value>(&contents) {
-                 if let Some(n) = config.get("name").and_then(|v| v.as_str()) {
-                     name = n.to_string();
-                 }
-                 if let Some(c) = config.get("created").and_then(|v| v.as_str()) {
-                     created = Some(c.to_string());
-                 }
+/// Why a directory is not usable as a vault, or what to know before opening it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultIssueKind {
+    NotFound,
+    NotADirectory,
+    Unreadable,
+    /// No `.vault.toml`; only ever a warning, since the vault still opens
+    MissingVaultConfig,
+    /// `.vault.toml` has a `[permissions]` table, which grants nothing: the
+    /// user decides what a vault may do, in Tailor
+    IgnoredPermissions,
+    InvalidManifest,
+    PythonUnavailable,
+    /// The vault declares requirements but pip cannot install them
+    PipUnavailable,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VaultIssue {
+    pub kind: VaultIssueKind,
+    pub message: String,
+}
+
+/// Result of `validate_vault`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VaultValidation {
+    pub valid: bool,
+    /// The path as `open_vault` would resolve it
+    pub vault_path: String,
+    pub issues: Vec<VaultIssue>,
+    /// Things worth knowing that do not stop the vault from opening
+    pub warnings: Vec<VaultIssue>,
+}
+
+/// Check whether a directory could be opened as a vault, without opening
+/// it or spawning a sidecar (e.g. for a green/red indicator in a picker)
+#[tauri::command]
+pub async fn validate_vault(
+    vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<VaultValidation, String> {
+    let vault_path = state.window_manager.lock().await.normalize_vault_path(&vault_path);
+    let mut issues = check_vault_dir(Path::new(&vault_path));
+    let mut warnings = Vec::new();
+
+    if issues.is_empty() {
+        warnings.extend(vault_dir_warnings(Path::new(&vault_path)));
+        match VaultManifest::load(&vault_path) {
+            Ok(manifest) => {
+                if let Some(required) = &manifest.sidecar.python_version {
+                    if let Err(e) = DependencyChecker::check_python_version(required).await {
+                        issues.push(VaultIssue { kind: VaultIssueKind::PythonUnavailable, message: e.to_string() });
+                    }
+                }
             }
+            Err(e) => issues.push(VaultIssue { kind: VaultIssueKind::InvalidManifest, message: format!("{:#}", e) }),
+        }
+
+        let preflight = DependencyChecker::preflight().await;
+        if !preflight.python_ok {
+            issues.push(VaultIssue {
+                kind: VaultIssueKind::PythonUnavailable,
+                message: preflight.issues.join("\n"),
+            });
+        } else if !preflight.pip_ok && DependencyChecker::has_plugin_requirements(&vault_path) {
+            issues.push(VaultIssue {
+                kind: VaultIssueKind::PipUnavailable,
+                message: preflight.issues.join("\n"),
+            });
         }
     }
 
-    let vault_item = VaultListItem {
-        name,
-        path: vault_path.clone(),
-        created,
-    };
+    Ok(VaultValidation { valid: issues.is_empty(), vault_path, issues, warnings })
+}
 
-    if let Err(e) = register_vault_in_registry(&app, &vault_item).await {
-        println!("Warning: Failed to register vault in registry: {}", e);
+/// Filesystem checks for `validate_vault`: the path exists and is a
+/// readable directory
+fn check_vault_dir(path: &Path) -> Vec<VaultIssue> {
+    let issue = |kind, message: String| vec![VaultIssue { kind, message }];
+    if !path.exists() {
+        return issue(VaultIssueKind::NotFound, format!("{} does not exist", path.display()));
+    }
+    if !path.is_dir() {
+        return issue(VaultIssueKind::NotADirectory, format!("{} is not a directory", path.display()));
+    }
+    if let Err(e) = fs::read_dir(path) {
+        return issue(VaultIssueKind::Unreadable, format!("Cannot read {}: {}", path.display(), e));
     }
+    Vec::new()
+}
 
-    Ok(VaultInfo {
-        window_label,
-        vault_path,
-        ws_port,
-    })
+/// Warnings for an openable vault directory: `open_vault` opens one without
+/// a `.vault.toml` too, named "Unknown Vault", and ignores any permissions
+/// the vault lists for itself there
+fn vault_dir_warnings(path: &Path) -> Vec<VaultIssue> {
+    let config_path = path.join(".vault.toml");
+    if !config_path.is_file() {
+        return vec![VaultIssue {
+            kind: VaultIssueKind::MissingVaultConfig,
+            message: format!("{} has no .vault.toml; it will open as \"Unknown Vault\"", path.display()),
+        }];
+    }
+    let lists_permissions = fs::read_to_string(&config_path)
+        .ok()
+        .and_then(|contents| toml::from_str::<toml::Table>(&contents).ok())
+        .is_some_and(|config| config.contains_key("permissions"));
+    if !lists_permissions {
+        return Vec::new();
+    }
+    vec![VaultIssue {
+        kind: VaultIssueKind::IgnoredPermissions,
+        message: "The [permissions] in .vault.toml are ignored; Tailor asks you before a vault may open \
+                  other vaults, show dialogs or read settings".to_string(),
+    }]
 }
 
-/// Send command to sidecar
+/// Zip recent sidecar logs, metrics, sidecar statuses, environment info and
+/// effective settings, with secrets redacted. Returns the zip's path.
 #[tauri::command]
-pub async fn send_to_sidecar(
+pub async fn export_diagnostics(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    diagnostics::export(&app, &state)
+        .await
+        .map(|path| path.to_string_lossy().to_string())
+        .map_err(|e| format!("Failed to export diagnostics: {}", e))
+}
+
+/// Get a snapshot of the backend's diagnostic counters
+#[tauri::command]
+pub async fn get_metrics(state: State<'_, AppState>) -> Result<MetricsSnapshot, String> {
+    Ok(state.metrics.snapshot())
+}
+
+/// Version and build details of the backend, for bug reports and feature checks
+#[tauri::command]
+pub fn app_info() -> AppInfo {
+    AppInfo::current()
+}
+
+/// Dump the EventBus's view of every window, for debugging events that do
+/// not arrive where expected
+#[tauri::command]
+pub async fn get_routing_table(state: State<'_, AppState>) -> Result<Vec<RoutingEntry>, String> {
+    Ok(state.event_bus.routing_table().await)
+}
+
+/// Attach a window to another open vault, so it can switch to it with
+/// `set_active_vault` without opening a window of its own
+#[tauri::command]
+pub async fn attach_vault(
     window_label: String,
-    method: String,
-    params: serde_json::Value,
+    vault_id: String,
     state: State<'_, AppState>,
-) -> Result<serde_json::Value, String> {
-    // println!("Sending command to sidecar '{}': {}", window_label, method);
+) -> Result<(), String> {
+    if state.event_bus.window_for_vault(&vault_id).await.is_none() {
+        return Err(format!("Failed to attach vault: vault {} is not open", vault_id));
+    }
+    state.event_bus
+        .attach_vault(&window_label, vault_id)
+        .await
+        .map_err(|e| format!("Failed to attach vault: {}", e))
+}
+
+/// Detach a window from a vault attached with `attach_vault`. Returns false
+/// if it was not attached.
+#[tauri::command]
+pub async fn detach_vault(
+    window_label: String,
+    vault_id: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    Ok(state.event_bus.detach_vault(&window_label, &vault_id).await)
+}
+
+/// Make one of a window's vaults (its own or an attached one) the one it
+/// receives vault events from and sends commands to by default
+#[tauri::command]
+pub async fn set_active_vault(
+    window_label: String,
+    vault_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.event_bus
+        .set_active_vault(&window_label, &vault_id)
+        .await
+        .map_err(|e| format!("Failed to set active vault: {}", e))
+}
+
+/// The id of the vault a window is working with
+#[tauri::command]
+pub async fn get_active_vault(
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<String, String> {
+    state.event_bus
+        .active_vault(&window_label)
+        .await
+        .ok_or_else(|| format!("Window not found: {}", window_label))
+}
+
+/// Path of the file routed events are recorded in this session. Requires
+/// `debug.event_log` in `tailor.toml`.
+#[tauri::command]
+pub async fn get_event_log_path(state: State<'_, AppState>) -> Result<String, String> {
+    state.event_bus
+        .event_log_path()
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| "Event logging is disabled; set debug.event_log in tailor.toml".to_string())
+}
+
+/// Wipe the shared pip cache used for vault dependency installs
+#[tauri::command]
+pub async fn clear_dependency_cache(
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.dependency_checker
+        .clear_cache()
+        .map_err(|e| format!("Failed to clear dependency cache: {}", e))
+}
+
+/// Delete a vault's installed plugin dependencies and install them again,
+/// e.g. after an interrupted install. Refused while the vault is open or
+/// opening, and the vault cannot be opened until it finishes; a pre-warmed
+/// sidecar for it is stopped first.
+#[tauri::command]
+pub async fn reset_dependencies(
+    app: AppHandle,
+    vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<InstallReport, String> {
+    let vault_path = state.window_manager.lock().await.normalize_vault_path(&vault_path);
+    let vault_id = WindowManager::vault_id(&vault_path);
+    let reset = async {
+        stop_for_dependency_change(&state, &vault_id, "resetting", ShutdownReason::DependencyReset).await?;
+        confirm_postinstall(Some(&app), &state, &vault_path, true).await?;
+
+        let report = state.dependency_checker
+            .reset(&vault_path)
+            .await
+            .map_err(|e| format!("Failed to reset dependencies: {}", e))?;
+        if report.installed {
+            record_install(&state, &vault_path);
+        }
+        Ok(report)
+    };
+    state.opening_vaults
+        .exclusive(&vault_id, "The vault's dependencies were being reset; open it again", reset)
+        .await
+        .unwrap_or_else(|| Err("Wait for the vault to finish opening before resetting its dependencies".to_string()))
+}
+
+/// Make sure a vault whose dependencies are about to change is closed, and
+/// stop its pre-warmed sidecar. Called with the vault's open guard held.
+async fn stop_for_dependency_change(
+    state: &AppState,
+    vault_id: &str,
+    action: &str,
+    reason: ShutdownReason,
+) -> Result<(), String> {
+    if let Some((window_label, _)) = state.window_manager.lock().await.find_window_for_vault(vault_id) {
+        return Err(format!(
+            "Close the vault before {} its dependencies (open in window '{}')",
+            action, window_label
+        ));
+    }
 
+    let _ = state.prewarming.wait(vault_id).await;
+    let prewarm_label = SidecarManager::prewarm_label(vault_id);
     state.sidecar_manager
-        .send_command(&window_label, &method, params)
+        .terminate_sidecar(&prewarm_label, reason)
         .await
-        .map_err(|e| format!("Sidecar error: {}", e))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to stop pre-warmed sidecar: {}", e))
+}
+
+/// Move a closed vault's plugin requirements from lib/ into a virtual
+/// environment. lib/ is only removed once everything it provided imports
+/// from the new environment; otherwise the vault is left as it was. Like
+/// `reset_dependencies`, the vault cannot be opened meanwhile.
+#[tauri::command]
+pub async fn migrate_vault_dependencies(
+    app: AppHandle,
+    vault_path: String,
+    state: State<'_, AppState>,
+) -> Result<MigrationReport, String> {
+    let vault_path = state.window_manager.lock().await.normalize_vault_path(&vault_path);
+    let vault_id = WindowManager::vault_id(&vault_path);
+    let migration = async {
+        stop_for_dependency_change(&state, &vault_id, "migrating", ShutdownReason::DependencyMigration).await?;
+        confirm_postinstall(Some(&app), &state, &vault_path, true).await?;
+
+        let report = state.dependency_checker
+            .migrate_to_venv(&vault_path)
+            .await
+            .map_err(|e| format!("Failed to migrate dependencies: {:#}", e))?;
+        if report.migrated {
+            record_install(&state, &vault_path);
+        }
+        Ok(report)
+    };
+    state.opening_vaults
+        .exclusive(&vault_id, "The vault's dependencies were being migrated; open it again", migration)
+        .await
+        .unwrap_or_else(|| Err("Wait for the vault to finish opening before migrating its dependencies".to_string()))
+}
+
+/// Set a vault window's zoom factor (1.0 = 100%), remembered for the vault
+#[tauri::command]
+pub async fn set_vault_zoom(
+    app: AppHandle,
+    window_label: String,
+    factor: f64,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.window_manager
+        .lock()
+        .await
+        .set_zoom(&app, &window_label, factor)
+        .map_err(|e| format!("Failed to set zoom: {}", e))
+}
+
+/// Displays available for placing vault windows, indexed for `open_vault`'s
+/// `monitor_index`
+#[tauri::command]
+pub async fn list_monitors(app: AppHandle) -> Result<Vec<MonitorInfo>, String> {
+    WindowManager::list_monitors(&app)
+        .map_err(|e| format!("Failed to list monitors: {}", e))
 }
 
 /// Close a vault window and terminate its sidecar
 #[tauri::command]
 pub async fn close_vault(
+    app: AppHandle,
     window_label: String,
     state: State<'_, AppState>,
 ) -> Result<(), String> {
     println!("Closing vault window: {}", window_label);
 
     // Step 1: Terminate sidecar
-    state.sidecar_manager
-        .terminate_sidecar(&window_label)
+    let reason = ShutdownReason::UserClosed;
+    let sidecar = state.sidecar_manager
+        .terminate_sidecar(&window_label, reason)
         .await
         .map_err(|e| format!("Failed to terminate sidecar: {}", e))?;
 
     // Step 2: Remove window from tracking
-    state.window_manager
-        .lock()
-        .await
-        .remove_window(&window_label);
+    let vault_id = {
+        let mut window_manager = state.window_manager.lock().await;
+        let vault_id = window_manager.get_vault_id(&window_label).cloned();
+        window_manager.remove_window(&window_label);
+        vault_id
+    };
+    state.event_bus.unregister_window(&window_label).await;
 
     println!("Vault closed successfully: {}", window_label);
 
+    // Let the frontend know it is safe to reopen the vault
+    let payload = serde_json::json!({
+        "window_label": window_label,
+        "vault_id": vault_id,
+        "sidecar": sidecar,
+        "reason": reason,
+    });
+    if let Err(e) = app.emit("vault-closed", payload) {
+        println!("Warning: Failed to emit vault-closed: {}", e);
+    }
+
     Ok(())
 }
 
+/// Close a vault window only if its sidecar agrees. The sidecar is asked
+/// whether it has unsaved work; if it does, nothing is closed and its answer
+/// is returned so the frontend can ask the user, who may then `close_vault`.
+/// A sidecar that does not answer in time does not prevent the close.
+#[tauri::command]
+pub async fn request_close_vault(
+    app: AppHandle,
+    window_label: String,
+    state: State<'_, AppState>,
+) -> Result<CloseCheck, String> {
+    let check = state.sidecar_manager.before_close(&window_label).await;
+    if !check.approved {
+        println!("Sidecar for window '{}' has unsaved work, not closing: {:?}", window_label, check.unsaved);
+        return Ok(check);
+    }
+    close_vault(app, window_label, state).await?;
+    Ok(check)
+}
+
 /// Get the current window's vault information
 #[tauri::command]
 pub async fn get_current_vault_info(
@@ -143,10 +2056,15 @@ pub async fn get_current_vault_info(
         .await
         .ok_or_else(|| "Sidecar not found for this window".to_string())?;
     
+    let vault_id = WindowManager::vault_id(&vault_path);
+
+    let pid = state.sidecar_manager.get_pid(&window_label).await;
     Ok(VaultInfo {
         window_label,
         vault_path,
+        vault_id,
         ws_port,
+        pid,
     })
 }
 
@@ -846,6 +2764,78 @@ pub async fn delete_api_key(key_name: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Store a secret for a vault in the OS keychain. It reaches the vault's
+/// sidecar as the environment variable `key` from its next (re)start.
+#[tauri::command]
+pub async fn set_vault_secret(
+    vault_id: String,
+    key: String,
+    value: String,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    let secrets = state.vault_secrets.clone();
+    tokio::task::spawn_blocking(move || secrets.set(&vault_id, &key, &value))
+        .await
+        .map_err(|e| format!("Failed to store secret: {}", e))?
+        .map_err(|e| format!("Failed to store secret: {}", e))
+}
+
+/// Remove a vault's secret. Returns false if it had none by that name.
+#[tauri::command]
+pub async fn delete_vault_secret(
+    vault_id: String,
+    key: String,
+    state: State<'_, AppState>,
+) -> Result<bool, String> {
+    let secrets = state.vault_secrets.clone();
+    tokio::task::spawn_blocking(move || secrets.delete(&vault_id, &key))
+        .await
+        .map_err(|e| format!("Failed to remove secret: {}", e))?
+        .map_err(|e| format!("Failed to remove secret: {}", e))
+}
+
+/// Names of a vault's secrets; values are never sent to the frontend
+#[tauri::command]
+pub async fn list_vault_secrets(vault_id: String, state: State<'_, AppState>) -> Result<Vec<String>, String> {
+    Ok(state.vault_secrets.keys(&vault_id))
+}
+
+/// What the user has allowed or denied a vault's sidecar to ask of the host
+#[tauri::command]
+pub async fn get_vault_permissions(vault_id: String, state: State<'_, AppState>) -> Result<VaultGrants, String> {
+    Ok(state.vault_permissions.grants(&vault_id))
+}
+
+/// Allow or deny a vault a host capability, or with `granted` unset, forget
+/// the decision so the user is asked on the vault's next request
+#[tauri::command]
+pub async fn set_vault_permission(
+    vault_id: String,
+    capability: HostCapability,
+    granted: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.vault_permissions
+        .set(&vault_id, capability, granted)
+        .map_err(|e| format!("Failed to save vault permission: {}", e))
+}
+
+/// Choose whether a vault's sidecar runs sandboxed and whether it may reach
+/// the network from inside it; unset fields restore the default of no
+/// sandbox, and no network once sandboxed.
+/// Takes effect when the sidecar next starts.
+#[tauri::command]
+pub async fn set_vault_sandbox(
+    vault_id: String,
+    sandboxed: Option<bool>,
+    allow_network: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), String> {
+    state.vault_permissions
+        .set_sandbox(&vault_id, sandboxed, allow_network)
+        .map_err(|e| format!("Failed to save vault sandbox: {}", e))
+}
+
 /// Get plugin template
 #[tauri::command]
 pub async fn get_plugin_template() -> Result<String, String> {
@@ -915,6 +2905,35 @@ mod tests {
         assert!(template.contains("class Plugin(PluginBase):"));
     }
 
+    #[test]
+    fn test_rpc_params() {
+        assert_eq!(rpc_params("echo", serde_json::Value::Null).unwrap(), json!({}));
+        assert_eq!(rpc_params("echo", json!([1, 2])).unwrap(), json!([1, 2]));
+        assert!(matches!(rpc_params("echo", json!("text")), Err(TailorError::InvalidRequest { .. })));
+        assert!(matches!(rpc_params(" ", json!({})), Err(TailorError::InvalidRequest { .. })));
+    }
+
+    #[test]
+    fn test_check_vault_dir() {
+        let dir = std::env::temp_dir().join(format!("tailor_validate_{}", uuid::Uuid::new_v4()));
+        let kinds = |path: &Path| check_vault_dir(path).into_iter().map(|i| i.kind).collect::<Vec<_>>();
+
+        assert_eq!(kinds(&dir), vec![VaultIssueKind::NotFound]);
+        fs::create_dir_all(&dir).unwrap();
+        // A directory without .vault.toml still opens, so it only gets a warning
+        assert!(kinds(&dir).is_empty());
+        assert_eq!(vault_dir_warnings(&dir)[0].kind, VaultIssueKind::MissingVaultConfig);
+        fs::write(dir.join(".vault.toml"), "name = \"Test\"").unwrap();
+        assert!(kinds(&dir).is_empty());
+        assert!(vault_dir_warnings(&dir).is_empty());
+        assert_eq!(kinds(&dir.join(".vault.toml")), vec![VaultIssueKind::NotADirectory]);
+        // A vault cannot grant itself anything
+        fs::write(dir.join(".vault.toml"), "name = \"Test\"\n[permissions]\nopen_vault = true\n").unwrap();
+        assert_eq!(vault_dir_warnings(&dir)[0].kind, VaultIssueKind::IgnoredPermissions);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[tokio::test]
     async fn test_get_settings_schema() {
         let schema = get_settings_schema().await.unwrap();
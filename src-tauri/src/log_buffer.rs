@@ -0,0 +1,264 @@
+use std::collections::VecDeque;
+use std::io::BufRead;
+use std::sync::{Arc, Mutex};
+use serde::Serialize;
+
+/// Default number of lines retained per sidecar
+pub const DEFAULT_LOG_LINES: usize = 500;
+
+/// Default total bytes retained per sidecar
+pub const DEFAULT_LOG_BYTES: usize = 1024 * 1024;
+
+/// Longest line kept; the rest of a longer line is discarded
+pub const MAX_LINE_BYTES: usize = 16 * 1024;
+
+const TRUNCATION_MARKER: &str = "… [truncated]";
+
+/// First line of a Python traceback
+const TRACEBACK_START: &str = "Traceback (most recent call last):";
+
+/// Lines introducing the next traceback of a chained exception
+const TRACEBACK_CHAIN: &[&str] = &[
+    "During handling of the above exception, another exception occurred:",
+    "The above exception was the direct cause of the following exception:",
+];
+
+/// Longest traceback kept in one block; longer ones are split
+const MAX_TRACEBACK_LINES: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LogLine {
+    pub stream: LogStream,
+    pub line: String,
+}
+
+/// Bounded buffer of the most recent output lines from a sidecar.
+/// Cloning shares the underlying buffer so reader threads can append to it.
+#[derive(Clone)]
+pub struct LogBuffer {
+    inner: Arc<Mutex<Lines>>,
+    capacity: usize,
+    max_bytes: usize,
+}
+
+#[derive(Default)]
+struct Lines {
+    lines: VecDeque<LogLine>,
+    bytes: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(DEFAULT_LOG_LINES, DEFAULT_LOG_BYTES)
+    }
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize, max_bytes: usize) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(Lines::default())),
+            capacity,
+            max_bytes,
+        }
+    }
+
+    /// Append a line, evicting the oldest once the line or byte budget is exceeded
+    pub fn push(&self, stream: LogStream, line: String) {
+        if self.capacity == 0 || line.len() > self.max_bytes {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        while inner.lines.len() >= self.capacity || inner.bytes + line.len() > self.max_bytes {
+            match inner.lines.pop_front() {
+                Some(evicted) => inner.bytes -= evicted.line.len(),
+                None => break,
+            }
+        }
+        inner.bytes += line.len();
+        inner.lines.push_back(LogLine { stream, line });
+    }
+
+    /// Buffered lines from one stream joined with newlines
+    pub fn text(&self, stream: LogStream) -> String {
+        self.inner.lock().unwrap()
+            .lines
+            .iter()
+            .filter(|l| l.stream == stream)
+            .map(|l| l.line.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The last `count` buffered lines from one stream joined with newlines
+    pub fn tail(&self, stream: LogStream, count: usize) -> String {
+        let inner = self.inner.lock().unwrap();
+        let mut lines: Vec<&str> = inner.lines
+            .iter()
+            .rev()
+            .filter(|l| l.stream == stream)
+            .take(count)
+            .map(|l| l.line.as_str())
+            .collect();
+        lines.reverse();
+        lines.join("\n")
+    }
+}
+
+/// Groups stderr lines into blocks: a Python traceback becomes one block
+/// ending at its exception line, any other line is a block of its own.
+#[derive(Default)]
+pub struct TracebackCoalescer {
+    block: Vec<String>,
+}
+
+impl TracebackCoalescer {
+    /// Feed one line, returning the block it completes, if any
+    pub fn push(&mut self, line: &str) -> Option<String> {
+        let starts_block = line.starts_with(TRACEBACK_START) || TRACEBACK_CHAIN.contains(&line);
+        if self.block.is_empty() {
+            if starts_block {
+                self.block.push(line.to_string());
+                return None;
+            }
+            return (!line.trim().is_empty()).then(|| line.to_string());
+        }
+
+        self.block.push(line.to_string());
+        // Frames and source lines are indented; the exception line is not
+        let continues = starts_block || line.is_empty() || line.starts_with([' ', '\t']);
+        if continues && self.block.len() < MAX_TRACEBACK_LINES {
+            return None;
+        }
+        self.finish()
+    }
+
+    /// Return whatever block is still open, e.g. at end of stream
+    pub fn finish(&mut self) -> Option<String> {
+        if self.block.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.block).join("\n"))
+    }
+}
+
+/// Read one line of output, keeping at most `max_len` bytes of it and
+/// lossily decoding invalid UTF-8. Returns `None` at end of stream.
+pub fn read_capped_line<R: BufRead>(reader: &mut R, max_len: usize) -> std::io::Result<Option<String>> {
+    let mut line = Vec::new();
+    let mut truncated = false;
+    let mut read_any = false;
+
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            break;
+        }
+        read_any = true;
+
+        let newline = available.iter().position(|&b| b == b'\n');
+        let chunk = &available[..newline.unwrap_or(available.len())];
+        let room = max_len.saturating_sub(line.len());
+        if chunk.len() > room {
+            truncated = true;
+        }
+        line.extend_from_slice(&chunk[..chunk.len().min(room)]);
+
+        let consumed = newline.map(|i| i + 1).unwrap_or(available.len());
+        reader.consume(consumed);
+        if newline.is_some() {
+            break;
+        }
+    }
+
+    if !read_any {
+        return Ok(None);
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+
+    let mut text = String::from_utf8_lossy(&line).into_owned();
+    if truncated {
+        text.push_str(TRUNCATION_MARKER);
+    }
+    Ok(Some(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_buffer_evicts_oldest() {
+        let buffer = LogBuffer::new(3, DEFAULT_LOG_BYTES);
+        buffer.push(LogStream::Stdout, "one".to_string());
+        buffer.push(LogStream::Stderr, "two".to_string());
+        buffer.push(LogStream::Stderr, "three".to_string());
+        buffer.push(LogStream::Stderr, "four".to_string());
+
+        assert_eq!(buffer.text(LogStream::Stdout), "");
+        assert_eq!(buffer.text(LogStream::Stderr), "two\nthree\nfour");
+        assert_eq!(buffer.tail(LogStream::Stderr, 2), "three\nfour");
+    }
+
+    #[test]
+    fn test_log_buffer_byte_budget() {
+        let buffer = LogBuffer::new(100, 10);
+        buffer.push(LogStream::Stderr, "aaaa".to_string());
+        buffer.push(LogStream::Stderr, "bbbb".to_string());
+        buffer.push(LogStream::Stderr, "cccc".to_string());
+        assert_eq!(buffer.text(LogStream::Stderr), "bbbb\ncccc");
+
+        // A single line larger than the whole budget is not kept
+        buffer.push(LogStream::Stderr, "x".repeat(11));
+        assert_eq!(buffer.text(LogStream::Stderr), "bbbb\ncccc");
+    }
+
+    #[test]
+    fn test_read_capped_line() {
+        let input: &[u8] = b"short\r\n0123456789abcdef\ninvalid \xff utf8\nlast";
+        let mut reader = std::io::BufReader::with_capacity(4, input);
+
+        assert_eq!(read_capped_line(&mut reader, 10).unwrap().unwrap(), "short");
+        assert_eq!(
+            read_capped_line(&mut reader, 10).unwrap().unwrap(),
+            format!("0123456789{}", TRUNCATION_MARKER)
+        );
+        assert_eq!(read_capped_line(&mut reader, 100).unwrap().unwrap(), "invalid \u{FFFD} utf8");
+        assert_eq!(read_capped_line(&mut reader, 100).unwrap().unwrap(), "last");
+        assert!(read_capped_line(&mut reader, 100).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_traceback_coalescer() {
+        let mut coalescer = TracebackCoalescer::default();
+        assert_eq!(coalescer.push("Loading plugins"), Some("Loading plugins".to_string()));
+        assert_eq!(coalescer.push(""), None);
+
+        let traceback = [
+            "Traceback (most recent call last):",
+            "  File \"main.py\", line 3, in <module>",
+            "    run()",
+            "ValueError: bad value",
+        ];
+        for line in &traceback[..3] {
+            assert_eq!(coalescer.push(line), None);
+        }
+        assert_eq!(coalescer.push(traceback[3]), Some(traceback.join("\n")));
+
+        assert_eq!(coalescer.push("Traceback (most recent call last):"), None);
+        assert_eq!(coalescer.finish(), Some("Traceback (most recent call last):".to_string()));
+        assert_eq!(coalescer.finish(), None);
+    }
+}
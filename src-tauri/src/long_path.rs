@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Result};
+
+/// Longest path most Windows APIs take without the `\\?\` prefix (MAX_PATH,
+/// less the 8.3 file name `CreateDirectory` reserves room for)
+const WINDOWS_MAX_PATH: usize = 248;
+
+/// Longest path Windows accepts at all, even with the prefix
+const WINDOWS_MAX_EXTENDED_PATH: usize = 32_767;
+
+/// Longest working directory a Windows process can be started in; the
+/// prefix does not apply to it
+const WINDOWS_MAX_WORKING_DIR: usize = 258;
+
+/// Longest single file or directory name on common file systems
+const MAX_COMPONENT_CHARS: usize = 255;
+
+/// `path` in a form that keeps working past MAX_PATH: on Windows, absolute
+/// paths near the limit get the `\\?\` extended-length prefix. Shorter paths,
+/// and every path on other platforms, are returned unchanged.
+pub fn extended(path: &Path) -> PathBuf {
+    #[cfg(windows)]
+    if let Some(extended) = path.to_str().and_then(extend_windows_path) {
+        return PathBuf::from(extended);
+    }
+    path.to_path_buf()
+}
+
+/// Add the extended-length prefix to a long absolute Windows path. Returns
+/// None for paths that do not need it, already have it, or cannot take it
+/// (relative paths, or ones with `.`/`..` the prefix would stop resolving).
+#[cfg_attr(not(windows), allow(dead_code))]
+fn extend_windows_path(path: &str) -> Option<String> {
+    if path.len() < WINDOWS_MAX_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    let path = path.replace('/', "\\");
+    if path.split('\\').any(|component| component == "." || component == "..") {
+        return None;
+    }
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{}", share));
+    }
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return Some(format!(r"\\?\{}", path));
+    }
+    None
+}
+
+/// Fail with a clear message if a path cannot be used on this platform even
+/// in extended form
+pub fn check(path: &Path) -> Result<()> {
+    if let Some(name) = path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .find(|name| name.chars().count() > MAX_COMPONENT_CHARS)
+    {
+        bail!("Path has a name longer than {} characters: {}", MAX_COMPONENT_CHARS, name);
+    }
+    let length = path.to_string_lossy().encode_utf16().count();
+    if cfg!(windows) && length > WINDOWS_MAX_EXTENDED_PATH {
+        bail!("Path is {} characters long, more than Windows allows ({}): {}",
+              length, WINDOWS_MAX_EXTENDED_PATH, path.display());
+    }
+    Ok(())
+}
+
+/// Fail with a clear message if a process cannot be started in `dir`
+pub fn check_working_dir(dir: &Path) -> Result<()> {
+    check(dir)?;
+    let length = dir.to_string_lossy().encode_utf16().count();
+    if cfg!(windows) && length > WINDOWS_MAX_WORKING_DIR {
+        bail!("{} is too long to run the sidecar in ({} characters, Windows allows {}). \
+               Set sidecar.working_dir in tailor.toml to a shorter directory.",
+              dir.display(), length, WINDOWS_MAX_WORKING_DIR);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extend_windows_path() {
+        let deep = format!(r"C:\Users\me\{}", r"Projects\Notes".repeat(20));
+        assert_eq!(extend_windows_path(&deep), Some(format!(r"\\?\{}", deep)));
+
+        let share = format!(r"\\server\share\{}", "a".repeat(250));
+        assert_eq!(extend_windows_path(&share), Some(format!(r"\\?\UNC\server\share\{}", "a".repeat(250))));
+
+        assert_eq!(extend_windows_path(r"C:\Users\me\Notes"), None);
+        assert_eq!(extend_windows_path(&format!(r"\\?\{}", deep)), None);
+        assert_eq!(extend_windows_path(&format!(r"C:\{}\..\x", "a".repeat(250))), None);
+        assert_eq!(extend_windows_path(&"relative\\".repeat(30)), None);
+    }
+
+    #[test]
+    fn test_check_rejects_overlong_names() {
+        assert!(check(Path::new("/vaults/notes/lib")).is_ok());
+        let long_name = Path::new("/vaults").join("n".repeat(MAX_COMPONENT_CHARS + 1));
+        assert!(check(&long_name).unwrap_err().to_string().contains("longer than 255"));
+    }
+}
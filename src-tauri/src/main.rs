@@ -1,42 +1,245 @@
 // Prevents additional console window on Windows in release builds
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod app_info;
+mod cancellation;
 mod window_manager;
 mod sidecar_manager;
+mod sidecar_transport;
 mod dependency_checker;
+mod env_cache;
 mod ipc_router;
 mod event_bus;
+mod event_log;
+mod event_schema;
+mod config;
+mod open_guard;
+mod error;
+mod log_buffer;
+mod long_path;
+mod memory_limit;
+mod metrics;
+mod port_owner;
+mod sandbox;
+mod host_requests;
+mod window_state;
+mod vault_archive;
+mod vault_manifest;
+mod vault_permissions;
+mod vault_secrets;
+mod diagnostics;
+mod dotenv;
 
 use std::sync::Arc;
-use tauri::Manager;
+use tauri::{Emitter, Manager};
 use tokio::sync::Mutex;
 
 use window_manager::WindowManager;
+use window_state::WindowStateStore;
 use sidecar_manager::SidecarManager;
 use event_bus::EventBus;
+use event_log::EventLog;
+use dependency_checker::{DependencyChecker, POSTINSTALL_TRUST_FILE};
+use env_cache::EnvCache;
+use config::AppConfig;
+use open_guard::OpenGuard;
+use ipc_router::VaultOpenResult;
+use error::TailorError;
+use metrics::Metrics;
+use vault_archive::VaultArchives;
+use vault_permissions::VaultPermissions;
+use vault_secrets::VaultSecrets;
+use cancellation::OperationRegistry;
+
+/// Label of the launcher window vaults are opened from (see tauri.conf.json)
+const LAUNCHER_WINDOW: &str = "main";
 
 #[derive(Default)]
 struct AppState {
     window_manager: Arc<Mutex<WindowManager>>,
     sidecar_manager: Arc<SidecarManager>,
-    #[allow(dead_code)]
+    dependency_checker: Arc<DependencyChecker>,
     event_bus: Arc<EventBus>,
+    opening_vaults: Arc<OpenGuard<VaultOpenResult, TailorError>>,
+    /// Pre-warm spawns in flight, by vault id, resolving to the sidecar's port
+    prewarming: Arc<OpenGuard<u16>>,
+    /// Vaults unpacked from zip archives
+    vault_archives: Arc<VaultArchives>,
+    /// Per-vault secrets kept in the OS keychain
+    vault_secrets: Arc<VaultSecrets>,
+    /// What the user allows each vault's sidecar to ask of the host
+    vault_permissions: Arc<VaultPermissions>,
+    /// Operations each window has in flight, for `cancel_all`
+    operations: Arc<OperationRegistry>,
+    metrics: Arc<Metrics>,
 }
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .setup(|app| {
+            // Group all vault windows under one taskbar entry on Windows
+            window_manager::set_app_user_model_id(&app.config().identifier);
+
+            // Load backend configuration
+            let config_path = app.path().app_config_dir()?.join("tailor.toml");
+            let config = AppConfig::load(&config_path);
+
+            let pip_cache_dir = match config.dependencies.cache_dir {
+                Some(dir) => dir,
+                None => app.path().app_cache_dir()?.join("pip-cache"),
+            };
+            let env_cache_dir = match config.dependencies.env_cache_dir {
+                Some(dir) => dir,
+                None => app.path().app_data_dir()?.join("env-cache"),
+            };
+            let env_cache = EnvCache::new(env_cache_dir, config.dependencies.env_cache_max_mb * 1024 * 1024);
+
             // Initialize application state
-            let window_manager = Arc::new(Mutex::new(WindowManager::new()));
-            let sidecar_manager = Arc::new(SidecarManager::new());
-            let event_bus = Arc::new(EventBus::new());
+            let metrics = Arc::new(Metrics::default());
+            let window_states = WindowStateStore::load(app.path().app_data_dir()?.join("window-state.json"));
+            let window_manager = WindowManager::with_window_states(window_states)
+                .with_base_dir(config.vaults.base_dir)
+                .with_open_timeout(std::time::Duration::from_secs(config.vaults.open_timeout_secs))
+                .with_max_concurrent_opens(config.vaults.max_concurrent_opens);
+            let window_manager = Arc::new(Mutex::new(window_manager));
+            let (stderr_tx, mut stderr_rx) = tokio::sync::mpsc::unbounded_channel();
+            let reap_interval = config.sidecar.reap_interval_secs;
+            let (host_request_tx, mut host_request_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (connection_tx, mut connection_rx) = tokio::sync::mpsc::unbounded_channel();
+            let (notification_tx, mut notification_rx) = tokio::sync::mpsc::unbounded_channel();
+            let vault_secrets = Arc::new(VaultSecrets::new(app.path().app_data_dir()?.join("vault-secrets.json")));
+            let sidecar_manager = Arc::new(
+                SidecarManager::new(metrics.clone(), config.sidecar)
+                    .with_stderr_sink(stderr_tx)
+                    .with_host_request_sink(host_request_tx)
+                    .with_connection_sink(connection_tx)
+                    .with_notification_sink(notification_tx)
+                    .with_secrets(vault_secrets.clone()),
+            );
+            let mut event_bus = EventBus::new(metrics.clone())
+                .with_schema_validation(config.debug.event_schema_validation);
+            if config.debug.event_log {
+                let event_log_dir = app.path().app_log_dir()?.join("events");
+                match EventLog::create(&event_log_dir, config.debug.event_log_max_mb * 1024 * 1024) {
+                    Ok(event_log) => event_bus = event_bus.with_event_log(event_log),
+                    Err(e) => eprintln!("Failed to start event log: {}", e),
+                }
+            }
+            let event_bus = Arc::new(event_bus);
+            let (postinstall_tx, mut postinstall_rx) = tokio::sync::mpsc::unbounded_channel();
+            let dependency_checker = Arc::new(
+                DependencyChecker::new(pip_cache_dir, env_cache)
+                    .with_trust_file(app.path().app_data_dir()?.join(POSTINSTALL_TRUST_FILE))
+                    .with_postinstall_timeout(std::time::Duration::from_secs(config.dependencies.postinstall_timeout_secs))
+                    .with_postinstall_sink(postinstall_tx),
+            );
 
             // Store state in app
             app.manage(AppState {
                 window_manager: window_manager.clone(),
                 sidecar_manager: sidecar_manager.clone(),
+                dependency_checker: dependency_checker.clone(),
                 event_bus: event_bus.clone(),
+                opening_vaults: Arc::new(OpenGuard::new()),
+                prewarming: Arc::new(OpenGuard::new()),
+                vault_archives: Arc::new(VaultArchives::new(app.path().app_data_dir()?.join("extracted-vaults"))),
+                vault_secrets,
+                vault_permissions: Arc::new(VaultPermissions::new(app.path().app_data_dir()?.join("vault-permissions.json"))),
+                operations: Arc::new(OperationRegistry::default()),
+                metrics,
+            });
+
+            // Surface sidecar stderr (when `sidecar.forward_stderr` is set) in its window
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while let Some(report) = stderr_rx.recv().await {
+                    ipc_router::forward_sidecar_stderr(&app_handle, report).await;
+                }
+            });
+
+            // Tell each window how reaching its sidecar is going
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while let Some(change) = connection_rx.recv().await {
+                    let _ = app_handle.emit_to(&change.window_label, "sidecar-connection", &change);
+                }
+            });
+
+            // Stream post-install script output to the vault's window, or to
+            // the launcher that is opening it when it has none yet
+            let app_handle = app.handle().clone();
+            let windows = window_manager.clone();
+            tauri::async_runtime::spawn(async move {
+                while let Some(output) = postinstall_rx.recv().await {
+                    let vault_window = windows.lock().await
+                        .find_window_for_vault(&WindowManager::vault_id(&output.vault_path))
+                        .map(|(label, _)| label);
+                    let target = vault_window.as_deref().unwrap_or(LAUNCHER_WINDOW);
+                    let _ = app_handle.emit_to(target, "postinstall-output", &output);
+                }
+            });
+
+            // Route notifications sidecars send on command connections
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while let Some(notification) = notification_rx.recv().await {
+                    ipc_router::forward_sidecar_notification(&app_handle, notification).await;
+                }
+            });
+
+            // Answer `host.*` requests sidecars make while handling commands.
+            // Each is handled on its own task: a dialog may wait on the user.
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                while let Some(call) = host_request_rx.recv().await {
+                    let app_handle = app_handle.clone();
+                    tauri::async_runtime::spawn(async move {
+                        host_requests::answer(&app_handle, call).await;
+                    });
+                }
+            });
+
+            // Notice requirements edited while their vault is open
+            let update_check_interval = config.dependencies.update_check_interval_secs;
+            if update_check_interval > 0 {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    let mut outdated = std::collections::HashSet::new();
+                    loop {
+                        tokio::time::sleep(std::time::Duration::from_secs(update_check_interval)).await;
+                        ipc_router::check_dependency_updates(&app_handle, &mut outdated).await;
+                    }
+                });
+            }
+
+            // Drop tracking entries of sidecars that died without anyone noticing
+            if reap_interval > 0 {
+                let app_handle = app.handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    loop {
+                        tokio::time::sleep(std::time::Duration::from_secs(reap_interval)).await;
+                        ipc_router::reap_dead_sidecars(&app_handle).await;
+                    }
+                });
+            }
+
+            // Tell windows when their sidecar dies from its memory limit
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                loop {
+                    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                    ipc_router::report_oom_kills(&app_handle).await;
+                }
+            });
+
+            // Ctrl-C / SIGTERM (e.g. during `cargo tauri dev`) must not orphan sidecars
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                wait_for_shutdown_signal().await;
+                println!("Shutdown signal received - performing cleanup");
+                app_handle.state::<AppState>().sidecar_manager.shutdown_all();
+                app_handle.exit(0);
             });
 
             println!("Tailor initialized successfully");
@@ -44,8 +247,39 @@ fn main() {
         })
         .invoke_handler(tauri::generate_handler![
             ipc_router::open_vault,
+            ipc_router::open_vaults,
+            ipc_router::open_vault_archive,
+            ipc_router::list_vault_extractions,
+            ipc_router::remove_vault_extraction,
+            ipc_router::prewarm_vault,
+            ipc_router::call_sidecar,
             ipc_router::send_to_sidecar,
+            ipc_router::cancel_all,
+            ipc_router::reload_window,
+            ipc_router::send_batch_to_sidecar,
+            ipc_router::stream_from_sidecar,
+            ipc_router::save_sidecar_output,
+            ipc_router::sidecar_capabilities,
+            ipc_router::list_vault_entries,
+            ipc_router::invalidate_vault_entries,
+            ipc_router::sidecar_status,
+            ipc_router::wait_for_sidecar_ready,
+            ipc_router::all_sidecar_status,
+            ipc_router::get_sidecar_logs,
+            ipc_router::get_sidecar_command,
+            ipc_router::reclaim_port,
+            ipc_router::clear_sidecar_error,
+            ipc_router::restart_sidecar,
+            ipc_router::restart_all_sidecars,
+            ipc_router::set_sidecar_log_level,
+            ipc_router::rotate_sidecar_log,
+            ipc_router::pause_sidecar,
+            ipc_router::resume_sidecar,
+            ipc_router::detach_sidecar,
+            ipc_router::attach_sidecar,
             ipc_router::close_vault,
+            ipc_router::request_close_vault,
+            ipc_router::switch_vault,
             ipc_router::get_current_vault_info,
             ipc_router::list_vaults,
             ipc_router::get_vault_info,
@@ -60,11 +294,66 @@ fn main() {
             ipc_router::get_api_keys,
             ipc_router::save_api_key,
             ipc_router::delete_api_key,
+            ipc_router::set_vault_secret,
+            ipc_router::delete_vault_secret,
+            ipc_router::list_vault_secrets,
+            ipc_router::get_vault_permissions,
+            ipc_router::set_vault_permission,
+            ipc_router::set_vault_sandbox,
             ipc_router::get_plugin_template,
             ipc_router::validate_plugin,
             ipc_router::get_effective_settings,
             ipc_router::get_settings_schema,
+            ipc_router::clear_dependency_cache,
+            ipc_router::dependencies_status,
+            ipc_router::preflight_check,
+            ipc_router::validate_vault,
+            ipc_router::install_dependencies,
+            ipc_router::list_installed_packages,
+            ipc_router::reset_dependencies,
+            ipc_router::migrate_vault_dependencies,
+            ipc_router::reset_settings,
+            ipc_router::set_vault_zoom,
+            ipc_router::list_monitors,
+            ipc_router::get_metrics,
+            ipc_router::get_event_log_path,
+            ipc_router::get_routing_table,
+            ipc_router::attach_vault,
+            ipc_router::detach_vault,
+            ipc_router::set_active_vault,
+            ipc_router::get_active_vault,
+            ipc_router::app_info,
+            ipc_router::export_diagnostics,
+            ipc_router::handle_sidecar_request,
+            ipc_router::pause_events,
+            ipc_router::resume_events,
+            ipc_router::ack_event,
+            ipc_router::reconnect,
         ])
+        .on_window_event(|window, event| {
+            // Remember size, maximized and fullscreen state of vault windows
+            if let tauri::WindowEvent::Resized(_) = event {
+                let app = window.app_handle().clone();
+                let label = window.label().to_string();
+                tauri::async_runtime::spawn(async move {
+                    let state = app.state::<AppState>();
+                    let generation = match state.window_manager.lock().await.capture_window_state(&app, &label) {
+                        Ok(Some(generation)) => generation,
+                        Ok(None) => return,
+                        Err(e) => {
+                            eprintln!("Failed to capture window state for '{}': {}", label, e);
+                            return;
+                        }
+                    };
+                    // Only the last of a burst of resizes writes the file
+                    tokio::time::sleep(window_state::SAVE_DEBOUNCE).await;
+                    let saved = state.window_manager.lock().await.save_window_states(generation);
+                    if let Err(e) = saved {
+                        eprintln!("Failed to save window state for '{}': {}", label, e);
+                    }
+                });
+            }
+        })
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|app, event| {
@@ -72,6 +361,34 @@ fn main() {
                 println!("Application exiting - performing cleanup");
                 let state = app.state::<AppState>();
                 state.sidecar_manager.shutdown_all();
+                // Write out window state still waiting on its debounce
+                let flushed = state.window_manager.try_lock().map(|window_manager| window_manager.flush_window_states());
+                if let Ok(Err(e)) = flushed {
+                    eprintln!("Failed to save window state: {}", e);
+                }
             }
         });
 }
+
+/// Resolve once the process is asked to stop via Ctrl-C or SIGTERM
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+                return;
+            }
+            Err(e) => eprintln!("Failed to install SIGTERM handler: {}", e),
+        }
+    }
+
+    if let Err(e) = tokio::signal::ctrl_c().await {
+        eprintln!("Failed to install Ctrl-C handler: {}", e);
+        std::future::pending::<()>().await;
+    }
+}
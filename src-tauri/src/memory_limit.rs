@@ -0,0 +1,130 @@
+use std::process::{Child, Command, ExitStatus};
+use anyhow::Result;
+
+/// Stderr markers of a Python process that ran out of memory
+const OUT_OF_MEMORY_MARKERS: &[&str] = &[
+    "MemoryError",
+    "Cannot allocate memory",
+    "out of memory",
+];
+
+/// STATUS_NO_MEMORY, the exit code of a Windows process that could not
+/// allocate and did not handle it
+#[cfg(windows)]
+const STATUS_NO_MEMORY: u32 = 0xC000_0017;
+
+/// Cap the address space of the process `command` spawns (Unix). Allocations
+/// past the cap fail, which Python reports as a `MemoryError`.
+#[cfg(unix)]
+pub fn limit_command(command: &mut Command, limit_bytes: u64) {
+    use std::os::unix::process::CommandExt;
+
+    let limit = libc::rlimit {
+        rlim_cur: limit_bytes as libc::rlim_t,
+        rlim_max: limit_bytes as libc::rlim_t,
+    };
+    // SAFETY: setrlimit is async-signal-safe and only affects the child
+    unsafe {
+        command.pre_exec(move || {
+            if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Nothing to do before spawning on Windows; see `limit_process`
+#[cfg(windows)]
+pub fn limit_command(_command: &mut Command, _limit_bytes: u64) {}
+
+/// Put a spawned process in a Job Object with a per-process memory limit
+/// (Windows). Allocations past the limit fail, as with `RLIMIT_AS` on Unix.
+#[cfg(windows)]
+pub fn limit_process(child: &Child, limit_bytes: u64) -> Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use anyhow::Context;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{
+        AssignProcessToJobObject, CreateJobObjectW, JobObjectExtendedLimitInformation,
+        SetInformationJobObject, JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JOB_OBJECT_LIMIT_PROCESS_MEMORY,
+    };
+
+    // SAFETY: the job handle is checked before use and closed exactly once;
+    // `info` outlives the call that reads it
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job.is_null() {
+            return Err(std::io::Error::last_os_error()).context("Failed to create job object");
+        }
+
+        let mut info: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_PROCESS_MEMORY;
+        info.ProcessMemoryLimit = usize::try_from(limit_bytes).unwrap_or(usize::MAX);
+        let applied = SetInformationJobObject(
+            job,
+            JobObjectExtendedLimitInformation,
+            &info as *const _ as *const std::ffi::c_void,
+            std::mem::size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        ) != 0 && AssignProcessToJobObject(job, child.as_raw_handle() as _) != 0;
+        let error = std::io::Error::last_os_error();
+
+        // The job stays alive as long as the sidecar is assigned to it
+        CloseHandle(job);
+        if !applied {
+            return Err(error).context("Failed to apply memory limit");
+        }
+    }
+    Ok(())
+}
+
+/// Nothing to do after spawning on Unix; see `limit_command`
+#[cfg(unix)]
+pub fn limit_process(_child: &Child, _limit_bytes: u64) -> Result<()> {
+    Ok(())
+}
+
+/// Whether a sidecar that exited on its own most likely hit its memory
+/// limit, judging by its exit status and the tail of its stderr
+pub fn exited_for_memory(status: ExitStatus, stderr: &str) -> bool {
+    if status.success() {
+        return false;
+    }
+    #[cfg(windows)]
+    if status.code().map(|code| code as u32) == Some(STATUS_NO_MEMORY) {
+        return true;
+    }
+    OUT_OF_MEMORY_MARKERS.iter().any(|marker| stderr.contains(marker))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exit_status(code: i32) -> ExitStatus {
+        #[cfg(target_os = "windows")]
+        let status = Command::new("cmd").args(["/C", &format!("exit {}", code)]).status().unwrap();
+        #[cfg(not(target_os = "windows"))]
+        let status = Command::new("sh").args(["-c", &format!("exit {}", code)]).status().unwrap();
+        status
+    }
+
+    #[test]
+    fn test_exited_for_memory() {
+        let traceback = "Traceback (most recent call last):\n  File \"leak.py\", line 3\nMemoryError";
+        assert!(exited_for_memory(exit_status(1), traceback));
+        assert!(!exited_for_memory(exit_status(1), "ImportError: No module named 'x'"));
+        assert!(!exited_for_memory(exit_status(0), traceback));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_limit_command_caps_address_space() {
+        let mut command = Command::new("sh");
+        command.args(["-c", "ulimit -v"]);
+        limit_command(&mut command, 512 * 1024 * 1024);
+        let output = command.output().unwrap();
+        // `ulimit -v` reports KiB
+        assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "524288");
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+
+/// Process-wide counters for diagnostics
+#[derive(Default)]
+pub struct Metrics {
+    pub vaults_opened: AtomicU64,
+    pub sidecars_spawned: AtomicU64,
+    pub sidecars_restarted: AtomicU64,
+    pub sidecars_crashed: AtomicU64,
+    pub commands_sent: AtomicU64,
+    pub events_routed: AtomicU64,
+    /// IPC emits carrying routed events; below `events_routed` thanks to batching
+    pub event_emits: AtomicU64,
+    /// Routed events dropped undelivered because a newer one superseded them
+    pub events_coalesced: AtomicU64,
+    pub dependency_installs: AtomicU64,
+    /// Time spent installing plugin requirements
+    pub dependency_install_ms: AtomicU64,
+}
+
+/// Point-in-time copy of all counters
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MetricsSnapshot {
+    pub vaults_opened: u64,
+    pub sidecars_spawned: u64,
+    pub sidecars_restarted: u64,
+    pub sidecars_crashed: u64,
+    pub commands_sent: u64,
+    pub events_routed: u64,
+    pub event_emits: u64,
+    pub events_coalesced: u64,
+    pub dependency_installs: u64,
+    pub dependency_install_ms: u64,
+}
+
+impl Metrics {
+    /// Increment a counter by one
+    pub fn incr(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add `amount` to a counter, e.g. a duration
+    pub fn add(counter: &AtomicU64, amount: u64) {
+        counter.fetch_add(amount, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            vaults_opened: self.vaults_opened.load(Ordering::Relaxed),
+            sidecars_spawned: self.sidecars_spawned.load(Ordering::Relaxed),
+            sidecars_restarted: self.sidecars_restarted.load(Ordering::Relaxed),
+            sidecars_crashed: self.sidecars_crashed.load(Ordering::Relaxed),
+            commands_sent: self.commands_sent.load(Ordering::Relaxed),
+            events_routed: self.events_routed.load(Ordering::Relaxed),
+            event_emits: self.event_emits.load(Ordering::Relaxed),
+            events_coalesced: self.events_coalesced.load(Ordering::Relaxed),
+            dependency_installs: self.dependency_installs.load(Ordering::Relaxed),
+            dependency_install_ms: self.dependency_install_ms.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_reflects_counters() {
+        let metrics = Metrics::default();
+        Metrics::incr(&metrics.vaults_opened);
+        Metrics::incr(&metrics.commands_sent);
+        Metrics::incr(&metrics.commands_sent);
+        Metrics::add(&metrics.dependency_install_ms, 1500);
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.vaults_opened, 1);
+        assert_eq!(snapshot.commands_sent, 2);
+        assert_eq!(snapshot.events_routed, 0);
+        assert_eq!(snapshot.dependency_install_ms, 1500);
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use tokio::sync::watch;
+
+type Outcome<T, E> = Option<Result<T, E>>;
+
+/// Deduplicates concurrent operations on the same key (e.g. opening a vault).
+/// The first caller runs the operation; callers arriving while it is in flight
+/// await its result instead of racing it. Failures are reported as `E`,
+/// which the guard also builds from its own messages.
+pub struct OpenGuard<T, E = String> {
+    in_flight: Mutex<HashMap<String, watch::Receiver<Outcome<T, E>>>>,
+}
+
+impl<T: Clone, E: Clone + From<String>> Default for OpenGuard<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone, E: Clone + From<String>> OpenGuard<T, E> {
+    pub fn new() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Run `operation` for `key` unless one is already in flight, in which
+    /// case wait for and return that operation's result.
+    pub async fn run<F>(&self, key: &str, operation: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        let (tx, rx) = watch::channel(None);
+        let existing = {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            match in_flight.get(key) {
+                Some(existing) => Some(existing.clone()),
+                None => {
+                    in_flight.insert(key.to_string(), rx);
+                    None
+                }
+            }
+        };
+
+        if let Some(mut rx) = existing {
+            println!("Operation already in flight for '{}', waiting for its result", key);
+            return match rx.wait_for(|outcome| outcome.is_some()).await {
+                Ok(outcome) => outcome.clone().unwrap(),
+                Err(_) => Err(format!("In-flight operation for '{}' was cancelled", key).into()),
+            };
+        }
+
+        // Clears the entry even if this future is dropped mid-operation
+        let _entry = InFlightEntry { guard: self, key };
+
+        let result = operation.await;
+        let _ = tx.send(Some(result.clone()));
+        result
+    }
+
+    /// Run an operation of another kind (e.g. resetting a vault's
+    /// dependencies) while holding `key`, so none of this guard's operations
+    /// run for it meanwhile; callers that arrive during it fail with `busy`.
+    /// Returns None without running it if an operation for `key` is already
+    /// in flight.
+    pub async fn exclusive<F: Future>(&self, key: &str, busy: &str, operation: F) -> Option<F::Output> {
+        let (tx, rx) = watch::channel(None);
+        {
+            let mut in_flight = self.in_flight.lock().unwrap();
+            if in_flight.contains_key(key) {
+                return None;
+            }
+            in_flight.insert(key.to_string(), rx);
+        }
+
+        let _entry = InFlightEntry { guard: self, key };
+        let result = operation.await;
+        let _ = tx.send(Some(Err(busy.to_string().into())));
+        Some(result)
+    }
+
+    /// Whether an operation is in flight for `key`
+    pub fn is_in_flight(&self, key: &str) -> bool {
+        self.in_flight.lock().unwrap().contains_key(key)
+    }
+
+    /// Wait for the operation in flight for `key`, if any, and return its result
+    pub async fn wait(&self, key: &str) -> Option<Result<T, E>> {
+        let mut rx = self.in_flight.lock().unwrap().get(key)?.clone();
+        let outcome = match rx.wait_for(|outcome| outcome.is_some()).await {
+            Ok(outcome) => outcome.clone(),
+            Err(_) => Some(Err(format!("In-flight operation for '{}' was cancelled", key).into())),
+        };
+        outcome
+    }
+}
+
+struct InFlightEntry<'a, T, E> {
+    guard: &'a OpenGuard<T, E>,
+    key: &'a str,
+}
+
+impl<T, E> Drop for InFlightEntry<'_, T, E> {
+    fn drop(&mut self) {
+        if let Ok(mut in_flight) = self.guard.in_flight.lock() {
+            in_flight.remove(self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_concurrent_runs_execute_once() {
+        let guard = Arc::new(OpenGuard::<u16>::new());
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..16 {
+            let guard = guard.clone();
+            let runs = runs.clone();
+            handles.push(tokio::spawn(async move {
+                guard.run("/tmp/vault", async {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                    Ok(9000)
+                }).await
+            }));
+        }
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap(), Ok(9000));
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+        assert!(guard.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_joins_in_flight_run() {
+        let guard = Arc::new(OpenGuard::<u16>::new());
+        assert_eq!(guard.wait("/tmp/vault").await, None);
+
+        let running = guard.clone();
+        let handle = tokio::spawn(async move {
+            running.run("/tmp/vault", async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Ok(9002)
+            }).await
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        assert_eq!(guard.wait("/tmp/vault").await, Some(Ok(9002)));
+        assert_eq!(handle.await.unwrap(), Ok(9002));
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_holds_key() {
+        let guard = Arc::new(OpenGuard::<u16>::new());
+
+        let holding = guard.clone();
+        let handle = tokio::spawn(async move {
+            holding.exclusive("/tmp/vault", "vault is busy", async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                7
+            }).await
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        // Runs arriving meanwhile wait, then are told it was busy
+        assert_eq!(guard.run("/tmp/vault", async { Ok(9003) }).await, Err("vault is busy".to_string()));
+        assert_eq!(handle.await.unwrap(), Some(7));
+
+        // Nothing else may hold the key while a run is in flight
+        let running = guard.clone();
+        let run = tokio::spawn(async move {
+            running.run("/tmp/vault", async {
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                Ok(9004)
+            }).await
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        assert_eq!(guard.exclusive("/tmp/vault", "vault is busy", async { 7 }).await, None);
+        assert_eq!(run.await.unwrap(), Ok(9004));
+        assert!(guard.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_run_releases_key() {
+        let guard = OpenGuard::<u16>::new();
+
+        let pending = guard.run("/tmp/vault", std::future::pending());
+        let _ = tokio::time::timeout(std::time::Duration::from_millis(10), pending).await;
+
+        assert!(guard.in_flight.lock().unwrap().is_empty());
+        assert_eq!(guard.run("/tmp/vault", async { Ok(9001) }).await, Ok(9001));
+    }
+}
@@ -0,0 +1,232 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+/// How long a terminated process has to exit before it is killed outright
+#[cfg(unix)]
+const TERMINATE_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// A process listening on a TCP port
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortOwner {
+    pub port: u16,
+    pub pid: u32,
+    /// Executable name, if it could be read
+    pub name: Option<String>,
+}
+
+/// The process listening on `port`, if any
+pub fn find(port: u16) -> Result<Option<PortOwner>> {
+    Ok(listening_pid(port)?.map(|pid| PortOwner { port, pid, name: process_name(pid) }))
+}
+
+/// Socket inodes listening on `port` in a `/proc/net/tcp` or `tcp6` table
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn listening_inodes(table: &str, port: u16) -> Vec<u64> {
+    // Columns: sl local_address rem_address st tx:rx tr:when retrnsmt uid timeout inode
+    const LISTEN: &str = "0A";
+    table.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<&str> = line.split_whitespace().collect();
+            let local_port = columns.get(1)?.rsplit(':').next()?;
+            if u16::from_str_radix(local_port, 16).ok()? != port || *columns.get(3)? != LISTEN {
+                return None;
+            }
+            columns.get(9)?.parse().ok()
+        })
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn listening_pid(port: u16) -> Result<Option<u32>> {
+    use std::fs;
+
+    let mut sockets = Vec::new();
+    for table in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        if let Ok(contents) = fs::read_to_string(table) {
+            sockets.extend(listening_inodes(&contents, port).into_iter().map(|inode| format!("socket:[{}]", inode)));
+        }
+    }
+    if sockets.is_empty() {
+        return Ok(None);
+    }
+
+    // Find the process holding one of the sockets; other users' processes
+    // cannot be inspected and are skipped
+    for entry in fs::read_dir("/proc").context("Failed to list processes")?.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|name| name.parse::<u32>().ok()) else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            if let Ok(target) = fs::read_link(fd.path()) {
+                if sockets.iter().any(|socket| target.as_os_str() == socket.as_str()) {
+                    return Ok(Some(pid));
+                }
+            }
+        }
+    }
+    bail!("Port {} is in use by a process this user cannot inspect", port)
+}
+
+#[cfg(target_os = "linux")]
+fn process_name(pid: u32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid)).ok().map(|name| name.trim().to_string())
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn listening_pid(port: u16) -> Result<Option<u32>> {
+    let output = std::process::Command::new("lsof")
+        .args(["-nP", "-t", "-sTCP:LISTEN"])
+        .arg(format!("-iTCP:{}", port))
+        .output()
+        .context("Failed to run lsof")?;
+    // lsof exits with 1 when nothing matches
+    Ok(String::from_utf8_lossy(&output.stdout).lines().find_map(|line| line.trim().parse().ok()))
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn process_name(pid: u32) -> Option<String> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "comm=", "-p", &pid.to_string()])
+        .output()
+        .ok()?;
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(windows)]
+fn listening_pid(port: u16) -> Result<Option<u32>> {
+    use windows_sys::Win32::NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, MIB_TCP6TABLE_OWNER_PID, MIB_TCPTABLE_OWNER_PID, TCP_TABLE_OWNER_PID_LISTENER,
+    };
+    use windows_sys::Win32::Networking::WinSock::{AF_INET, AF_INET6};
+
+    for family in [AF_INET, AF_INET6] {
+        // Ask for the size first, then fetch; retry if the table grew between calls
+        let mut size = 0u32;
+        let mut buffer: Vec<u8> = Vec::new();
+        loop {
+            // SAFETY: `buffer` holds `size` bytes for the call to fill
+            let result = unsafe {
+                GetExtendedTcpTable(buffer.as_mut_ptr().cast(), &mut size, 0, family as u32,
+                                    TCP_TABLE_OWNER_PID_LISTENER, 0)
+            };
+            match result {
+                0 => break,
+                // ERROR_INSUFFICIENT_BUFFER
+                122 => buffer = vec![0; size as usize],
+                code => bail!("Failed to read the TCP table (error {})", code),
+            }
+        }
+
+        // SAFETY: on success the buffer holds a table of `dwNumEntries` rows
+        // of the type matching `family`
+        unsafe {
+            if family == AF_INET {
+                let table = &*(buffer.as_ptr() as *const MIB_TCPTABLE_OWNER_PID);
+                let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+                if let Some(row) = rows.iter().find(|row| u16::from_be(row.dwLocalPort as u16) == port) {
+                    return Ok(Some(row.dwOwningPid));
+                }
+            } else {
+                let table = &*(buffer.as_ptr() as *const MIB_TCP6TABLE_OWNER_PID);
+                let rows = std::slice::from_raw_parts(table.table.as_ptr(), table.dwNumEntries as usize);
+                if let Some(row) = rows.iter().find(|row| u16::from_be(row.dwLocalPort as u16) == port) {
+                    return Ok(Some(row.dwOwningPid));
+                }
+            }
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(windows)]
+fn process_name(pid: u32) -> Option<String> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+
+    // SAFETY: the handle is checked before use and closed exactly once
+    unsafe {
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut path = [0u16; 1024];
+        let mut length = path.len() as u32;
+        let ok = QueryFullProcessImageNameW(process, 0, path.as_mut_ptr(), &mut length) != 0;
+        CloseHandle(process);
+        let path = String::from_utf16_lossy(&path[..length as usize]);
+        ok.then(|| path.rsplit('\\').next().unwrap_or(&path).to_string())
+    }
+}
+
+/// Stop a process: SIGTERM, then SIGKILL if it is still running after a
+/// grace period (Unix)
+#[cfg(unix)]
+pub fn terminate(pid: u32) -> Result<()> {
+    let pid = libc::pid_t::try_from(pid).context("Invalid process id")?;
+    // SAFETY: kill has no memory-safety preconditions
+    let alive = || unsafe { libc::kill(pid, 0) == 0 };
+    if unsafe { libc::kill(pid, libc::SIGTERM) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to stop process");
+    }
+    let deadline = std::time::Instant::now() + TERMINATE_GRACE;
+    while alive() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    if alive() && unsafe { libc::kill(pid, libc::SIGKILL) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to kill process");
+    }
+    Ok(())
+}
+
+/// Stop a process (Windows, where there is no graceful equivalent)
+#[cfg(windows)]
+pub fn terminate(pid: u32) -> Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    // SAFETY: the handle is checked before use and closed exactly once
+    unsafe {
+        let process = OpenProcess(PROCESS_TERMINATE, 0, pid);
+        if process.is_null() {
+            return Err(std::io::Error::last_os_error()).context("Failed to open process");
+        }
+        let terminated = TerminateProcess(process, 1) != 0;
+        let error = std::io::Error::last_os_error();
+        CloseHandle(process);
+        if !terminated {
+            return Err(error).context("Failed to kill process");
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listening_inodes() {
+        let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+            \x20  0: 0100007F:2328 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41234 1 0000000000000000 100 0 0 10 0\n\
+            \x20  1: 0100007F:2328 0100007F:B4F2 01 00000000:00000000 00:00000000 00000000  1000        0 41300 1 0000000000000000 20 4 30 10 -1\n\
+            \x20  2: 0100007F:2329 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 41250 1 0000000000000000 100 0 0 10 0\n";
+        // Only the listening socket on 9000 (0x2328), not the connection
+        assert_eq!(listening_inodes(table, 9000), [41234]);
+        assert!(listening_inodes(table, 9002).is_empty());
+    }
+
+    #[test]
+    fn test_find_listener() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let owner = find(port).unwrap().expect("our listener is found");
+        assert_eq!(owner.pid, std::process::id());
+    }
+}
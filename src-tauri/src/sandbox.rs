@@ -0,0 +1,296 @@
+use std::ffi::OsString;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::{bail, Context, Result};
+
+/// System directories a sandboxed interpreter needs to read on Linux
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const LINUX_SYSTEM_DIRS: &[&str] = &["/usr", "/bin", "/sbin", "/lib", "/lib64", "/lib32", "/etc"];
+
+/// Where user data lives on macOS; reads there are limited to allowed paths
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const MACOS_USER_DIRS: &[&str] = &["/Users", "/Volumes"];
+
+/// Temporary directories a sandboxed sidecar may write to on macOS
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+const MACOS_TEMP_DIRS: &[&str] = &["/private/tmp", "/private/var/folders"];
+
+/// Restrictions for a sidecar running plugins the user does not fully
+/// trust: it may write only to the vault (and the paths the host needs),
+/// though not to the vault's configuration or installed packages, and read
+/// little else outside system and Python directories. Best effort; it is
+/// not a security boundary against a determined attacker.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    /// Whether plugins may reach the network. The host's own connections to
+    /// the sidecar work either way.
+    pub allow_network: bool,
+}
+
+impl SandboxPolicy {
+    /// Whether the sidecar gets a network of its own, so the host must
+    /// forward its port to it (Linux, where loopback cannot be singled out)
+    pub fn isolates_network(&self) -> bool {
+        cfg!(target_os = "linux") && !self.allow_network
+    }
+}
+
+/// What a sandboxed sidecar may touch beyond system and Python directories
+#[derive(Debug, Default)]
+pub struct SandboxPaths {
+    /// Directories it may write to, e.g. the vault
+    pub writable: Vec<PathBuf>,
+    /// Directories it may only read, e.g. the bundled sidecar
+    pub readable: Vec<PathBuf>,
+    /// Files under `writable` it may neither change nor create, e.g. the
+    /// vault's manifest
+    pub protected_files: Vec<PathBuf>,
+    /// Directories under `writable` it may neither change nor create, e.g.
+    /// the vault's installed packages
+    pub protected_dirs: Vec<PathBuf>,
+}
+
+/// A command that runs `python` under the sandbox, confined to `paths`.
+/// Variables set on it pass through the launcher to the sidecar.
+pub fn command(python: &str, policy: &SandboxPolicy, paths: SandboxPaths) -> Result<Command> {
+    let canonical = |paths: Vec<PathBuf>| -> Vec<PathBuf> {
+        paths.into_iter().filter_map(|path| path.canonicalize().ok()).collect()
+    };
+    let protected = |paths: Vec<PathBuf>| -> Vec<PathBuf> {
+        paths.iter().filter_map(|path| resolve_parent(path)).collect()
+    };
+    let readable = paths.readable.into_iter().chain(python_roots(python)).collect();
+    let paths = SandboxPaths {
+        writable: canonical(paths.writable),
+        readable: canonical(readable),
+        protected_files: protected(paths.protected_files),
+        protected_dirs: protected(paths.protected_dirs),
+    };
+    platform_command(python, policy, &paths)
+}
+
+/// `path` under its canonical parent directory, whether or not it exists.
+/// The parent is created if need be, so the path has a fixed place to be
+/// protected at; the path itself is left unresolved, so a symlink there is
+/// protected rather than what it points to.
+fn resolve_parent(path: &Path) -> Option<PathBuf> {
+    let (parent, name) = (path.parent()?, path.file_name()?);
+    if let Err(e) = std::fs::create_dir_all(parent) {
+        eprintln!("Failed to create {}: {}", parent.display(), e);
+    }
+    Some(parent.canonicalize().ok()?.join(name))
+}
+
+#[cfg(target_os = "linux")]
+fn platform_command(python: &str, policy: &SandboxPolicy, paths: &SandboxPaths) -> Result<Command> {
+    let available = Command::new("bwrap").arg("--version").output().is_ok_and(|output| output.status.success());
+    if !available {
+        bail!("The vault is set to run sandboxed, which needs bubblewrap (bwrap); install it or let the vault run unsandboxed in its settings");
+    }
+    let mut command = Command::new("bwrap");
+    command.args(bwrap_args(policy, paths)).arg(python);
+    Ok(command)
+}
+
+#[cfg(target_os = "macos")]
+fn platform_command(python: &str, policy: &SandboxPolicy, paths: &SandboxPaths) -> Result<Command> {
+    let mut command = Command::new("sandbox-exec");
+    command.arg("-p").arg(seatbelt_profile(policy, paths)).arg(python);
+    Ok(command)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn platform_command(_python: &str, _policy: &SandboxPolicy, _paths: &SandboxPaths) -> Result<Command> {
+    bail!("The vault is set to run sandboxed, which is not supported on this platform; let the vault run unsandboxed in its settings")
+}
+
+/// bubblewrap arguments: a fresh mount namespace with system directories
+/// and `readable` read-only, `writable` read-write except for the protected
+/// paths, and everything else (home directories included) absent
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn bwrap_args(policy: &SandboxPolicy, paths: &SandboxPaths) -> Vec<OsString> {
+    let mut args: Vec<OsString> = vec!["--die-with-parent".into(), "--unshare-all".into()];
+    if policy.allow_network {
+        args.push("--share-net".into());
+    }
+    for dir in LINUX_SYSTEM_DIRS {
+        args.extend([OsString::from("--ro-bind-try"), dir.into(), dir.into()]);
+    }
+    args.extend(["--proc", "/proc", "--dev", "/dev", "--tmpfs", "/tmp"].map(OsString::from));
+    // Later binds win, so a writable vault inside a readable directory stays
+    // writable, and protected paths inside it do not
+    for (flag, dirs) in [("--ro-bind", &paths.readable), ("--bind", &paths.writable)] {
+        for dir in dirs {
+            args.extend([OsString::from(flag), dir.into(), dir.into()]);
+        }
+    }
+    // A mount point cannot be renamed, so binding the directories holding
+    // protected paths onto themselves keeps them from being moved aside
+    // and recreated without the protection
+    let holders: std::collections::BTreeSet<&Path> = paths.protected_files.iter()
+        .chain(&paths.protected_dirs)
+        .filter_map(|path| path.parent())
+        .filter(|parent| paths.writable.iter().any(|dir| parent.starts_with(dir) && parent != dir))
+        .collect();
+    for holder in holders {
+        args.extend([OsString::from("--bind"), holder.into(), holder.into()]);
+    }
+    // Missing paths are covered too, so they cannot be created; this leaves
+    // an empty file or directory behind in their place
+    for file in &paths.protected_files {
+        let source = if file.exists() { file.as_os_str() } else { "/dev/null".as_ref() };
+        args.extend([OsString::from("--ro-bind"), source.into(), file.into()]);
+    }
+    for dir in &paths.protected_dirs {
+        if dir.exists() {
+            args.extend([OsString::from("--ro-bind"), dir.into(), dir.into()]);
+        } else {
+            args.extend([OsString::from("--tmpfs"), dir.into(), OsString::from("--remount-ro"), dir.into()]);
+        }
+    }
+    args
+}
+
+/// Seatbelt profile for `sandbox-exec`: writes only to `writable` (but not
+/// the protected paths) and the temporary directories, no reads of user data
+/// outside the allowed paths, and, unless allowed, no network beyond localhost
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn seatbelt_profile(policy: &SandboxPolicy, paths: &SandboxPaths) -> String {
+    fn subpaths<'a>(paths: impl IntoIterator<Item = &'a Path>) -> String {
+        paths.into_iter().map(|path| format!(" (subpath {})", seatbelt_string(path))).collect()
+    }
+    let temp_dirs = MACOS_TEMP_DIRS.iter().map(Path::new);
+    let user_dirs = MACOS_USER_DIRS.iter().map(Path::new);
+
+    // Later rules take precedence over earlier ones
+    let mut profile = String::from("(version 1)\n(allow default)\n(deny file-write*)\n");
+    profile += &format!("(allow file-write* (literal \"/dev/null\"){})\n",
+                        subpaths(paths.writable.iter().map(PathBuf::as_path).chain(temp_dirs)));
+    if !paths.protected_files.is_empty() || !paths.protected_dirs.is_empty() {
+        let literals: String = paths.protected_files.iter().map(|path| format!(" (literal {})", seatbelt_string(path))).collect();
+        profile += &format!("(deny file-write*{}{})\n", literals, subpaths(paths.protected_dirs.iter().map(PathBuf::as_path)));
+    }
+    profile += &format!("(deny file-read*{})\n", subpaths(user_dirs));
+    profile += &format!("(allow file-read*{})\n",
+                        subpaths(paths.readable.iter().chain(&paths.writable).map(PathBuf::as_path)));
+    if !policy.allow_network {
+        profile += "(deny network*)\n\
+                    (allow network* (local ip \"localhost:*\"))\n\
+                    (allow network* (remote ip \"localhost:*\"))\n\
+                    (allow network* (remote unix-socket))\n";
+    }
+    profile
+}
+
+/// A path as a quoted Seatbelt string
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn seatbelt_string(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", path)
+}
+
+/// Directories the interpreter loads code from (its prefixes and the user
+/// site directory), which stay readable inside the sandbox
+fn python_roots(python: &str) -> Vec<PathBuf> {
+    let script = "import site, sys; print(sys.prefix); print(sys.base_prefix); print(site.getusersitepackages())";
+    match Command::new(python).args(["-c", script]).output() {
+        Ok(output) if output.status.success() => String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| PathBuf::from(line.trim()))
+            .collect(),
+        _ => {
+            eprintln!("Failed to ask {} for its install directories", python);
+            Vec::new()
+        }
+    }
+}
+
+/// Forwards a TCP port to a sidecar's Unix socket while held, for sidecars
+/// the sandbox gives a network of their own
+pub struct PortForward(tokio::task::JoinHandle<()>);
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Accept connections on `addr` and pass each through to `socket`
+#[cfg(unix)]
+pub async fn forward_port(addr: SocketAddr, socket: PathBuf) -> Result<PortForward> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to listen on {} for the sandboxed sidecar", addr))?;
+    let task = tokio::spawn(async move {
+        while let Ok((mut client, _)) = listener.accept().await {
+            let socket = socket.clone();
+            tokio::spawn(async move {
+                match tokio::net::UnixStream::connect(&socket).await {
+                    Ok(mut sidecar) => {
+                        let _ = tokio::io::copy_bidirectional(&mut client, &mut sidecar).await;
+                    }
+                    Err(e) => eprintln!("Failed to reach sandboxed sidecar at {}: {}", socket.display(), e),
+                }
+            });
+        }
+    });
+    Ok(PortForward(task))
+}
+
+#[cfg(not(unix))]
+pub async fn forward_port(_addr: SocketAddr, _socket: PathBuf) -> Result<PortForward> {
+    bail!("Forwarding to a sidecar needs Unix sockets")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bwrap_args() {
+        let vault = PathBuf::from("/home/me/vault");
+        let app = PathBuf::from("/opt/tailor");
+        let paths = SandboxPaths {
+            protected_files: vec![vault.join("tailor.json"), vault.join("plugins/requirements.txt")],
+            protected_dirs: vec![vault.join("lib")],
+            writable: vec![vault],
+            readable: vec![app],
+        };
+        let args = bwrap_args(&SandboxPolicy::default(), &paths);
+        let args: Vec<&str> = args.iter().map(|arg| arg.to_str().unwrap()).collect();
+        let joined = args.join(" ");
+        assert!(!args.contains(&"--share-net"));
+        assert!(joined.contains("--ro-bind /opt/tailor /opt/tailor"));
+        // The vault is bound read-write after the read-only directories, and
+        // the protected paths read-only over that, even where they are missing
+        assert!(joined.ends_with("--bind /home/me/vault /home/me/vault \
+                                  --bind /home/me/vault/plugins /home/me/vault/plugins \
+                                  --ro-bind /dev/null /home/me/vault/tailor.json \
+                                  --ro-bind /dev/null /home/me/vault/plugins/requirements.txt \
+                                  --tmpfs /home/me/vault/lib --remount-ro /home/me/vault/lib"), "{}", joined);
+
+        let args = bwrap_args(&SandboxPolicy { allow_network: true }, &SandboxPaths::default());
+        assert!(args.contains(&OsString::from("--share-net")));
+    }
+
+    #[test]
+    fn test_seatbelt_profile() {
+        let vault = PathBuf::from("/Users/me/My \"Notes\"");
+        let paths = SandboxPaths {
+            protected_files: vec![vault.join("tailor.json")],
+            protected_dirs: vec![vault.join("lib")],
+            writable: vec![vault],
+            readable: vec![],
+        };
+        let profile = seatbelt_profile(&SandboxPolicy::default(), &paths);
+        assert!(profile.contains(r#"(subpath "/Users/me/My \"Notes\"")"#));
+        assert!(profile.contains(r#"(deny file-write* (literal "/Users/me/My \"Notes\"/tailor.json") (subpath "/Users/me/My \"Notes\"/lib"))"#));
+        assert!(profile.contains("(deny network*)"));
+        let reads = profile.find("(deny file-read*").unwrap();
+        assert!(profile[reads..].contains("(allow file-read* (subpath \"/Users/me"));
+
+        let profile = seatbelt_profile(&SandboxPolicy { allow_network: true }, &SandboxPaths::default());
+        assert!(!profile.contains("network"));
+    }
+}
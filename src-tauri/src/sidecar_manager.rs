@@ -1,50 +1,602 @@
-use std::collections::HashMap;
-use std::process::{Child, Command, Stdio};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::sync::Arc;
 use tokio::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::oneshot;
 use anyhow::{Result, Context, anyhow};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
-use futures::{SinkExt, StreamExt};
-use url::Url;
+use serde::Serialize;
 
+use crate::app_info::AppInfo;
+use crate::config::{OutputCapture, SidecarConfig};
+use crate::dependency_checker::DependencyChecker;
+use crate::diagnostics::REDACTED;
+use crate::dotenv::{self, DOTENV_FILE};
+use crate::error::TailorError;
+use crate::memory_limit;
+use crate::long_path;
+use crate::log_buffer::{LogBuffer, LogStream, TracebackCoalescer, DEFAULT_LOG_BYTES, MAX_LINE_BYTES, read_capped_line};
+use crate::metrics::Metrics;
+use crate::open_guard::OpenGuard;
+use crate::port_owner::{self, PortOwner};
+use crate::sandbox::{self, PortForward, SandboxPaths, SandboxPolicy};
+use crate::sidecar_transport::{Frame, SidecarTransport, WebSocketTransport};
+use crate::vault_manifest::MANIFEST_FILE;
+use crate::vault_secrets::VaultSecrets;
+use crate::window_manager::WindowManager;
+
+/// How a freshly spawned sidecar's startup ended
+#[derive(Debug, PartialEq)]
+enum Startup {
+    /// The WebSocket server accepted a connection
+    Ready,
+    /// The process exited before becoming ready
+    Exited(std::process::ExitStatus),
+    /// The spawn timeout elapsed first
+    TimedOut,
+    /// The process's state could not be read
+    Unknown(String),
+}
+
+/// A spawned sidecar not yet handed to `processes`. Killed if dropped
+/// before then, so an abandoned spawn leaves no orphan behind.
+struct UntrackedChild(Option<Child>);
+
+impl Drop for UntrackedChild {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.0.take() {
+            if matches!(child.try_wait(), Ok(None)) {
+                eprintln!("Sidecar spawn abandoned, killing PID {}", child.id());
+                let _ = child.kill();
+            }
+            let _ = child.wait();
+        }
+    }
+}
+
+/// A sidecar's Unix domain socket, removed once the sidecar is no longer
+/// tracked
+pub struct SocketFile(PathBuf);
+
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Longest Unix socket path accepted everywhere (`sun_path` is 104 bytes on
+/// macOS, including the terminator)
+#[cfg(unix)]
+const MAX_SOCKET_PATH: usize = 100;
+
+/// Vault-relative path of an optional vault-specific sidecar entrypoint
+const VAULT_SIDECAR_SCRIPT: &str = "plugins/server.py";
+
+/// Lines of stderr kept in the last error when a sidecar crashes
+const CRASH_STDERR_LINES: usize = 20;
+
+/// Notification method carrying one partial result of a streaming request
+const STREAM_CHUNK_METHOD: &str = "stream.chunk";
+
+/// First byte of a binary frame carrying raw bytes of a streaming request:
+/// then the request id's length (u16, big-endian), the id and the data.
+/// Compressed responses start with a zlib header instead.
+const BINARY_CHUNK_TAG: u8 = 0;
+
+/// Receives the request id and data of each tagged binary chunk
+type DataHandler<'a> = dyn FnMut(&str, &[u8]) -> Result<()> + Send + 'a;
+
+/// Handshake sent once a sidecar is ready; its result advertises capabilities
+const HANDSHAKE_METHOD: &str = "system.initialize";
+
+/// Sidecar command listing the files and directories of a vault
+const LIST_ENTRIES_METHOD: &str = "vault.list_entries";
+
+/// Sidecar event that invalidates cached vault listings
+const FILE_CHANGED_EVENT: &str = "file-changed";
+
+/// Log levels the sidecar accepts for `--log-level` and `system.set_log_level`
+pub const LOG_LEVELS: &[&str] = &["DEBUG", "INFO", "WARNING", "ERROR", "CRITICAL"];
+
+/// Prefix of JSON-RPC methods a sidecar calls on the host
+pub const HOST_METHOD_PREFIX: &str = "host.";
+
+/// Method that changes a running sidecar's log level
+const SET_LOG_LEVEL_METHOD: &str = "system.set_log_level";
+
+/// Most times the reaper restarts a crashed sidecar within
+/// `AUTO_RESTART_WINDOW` (see `auto_restart`)
+const MAX_AUTO_RESTARTS: usize = 3;
+
+/// How far back automatic restarts count toward `MAX_AUTO_RESTARTS`
+const AUTO_RESTART_WINDOW: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Uptime after which a crash is taken as a fresh failure rather than part
+/// of a crash loop, so earlier automatic restarts no longer count
+const STABLE_UPTIME: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// Method that makes a sidecar start a fresh log file
+const ROTATE_LOG_METHOD: &str = "system.rotate_log";
+
+/// Method asking a sidecar whether its vault can close without losing work
+const BEFORE_CLOSE_METHOD: &str = "system.before_close";
+
+/// Control messages asking a sidecar to stop and restart its background work
+const PAUSE_METHOD: &str = "$/pause";
+const RESUME_METHOD: &str = "$/resume";
+
+/// Ports sidecars are given, wrapping around at the end
+const FIRST_PORT: u16 = 9000;
+const LAST_PORT: u16 = 19000;
+
+/// Close codes a sidecar shutting down on purpose sends: normal closure and
+/// going away
+const CLEAN_CLOSE_CODES: [u16; 2] = [1000, 1001];
+
+/// How long a new sidecar has to answer the handshake
+const HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
 
 pub struct SidecarProcess {
-    pub child: Child,
-    #[allow(dead_code)]
+    /// None for a sidecar started outside Tailor, which is never killed or
+    /// restarted
+    pub child: Option<Child>,
+    /// OS process id, for finding the sidecar in Activity Monitor / Task
+    /// Manager; 0 if an external sidecar's could not be found
+    pub pid: u32,
+    pub vault_path: String,
+    pub ws_port: u16,
+    /// How commands reach the sidecar (a WebSocket to `ws_port` in production)
+    pub transport: Arc<dyn SidecarTransport>,
+    /// Vault-local entrypoint in use, or None for the bundled sidecar
+    pub script: Option<PathBuf>,
+    pub logs: LogBuffer,
+    /// Where its output goes; `logs` stays empty unless captured
+    pub output_capture: OutputCapture,
+    pub last_error: Option<SidecarError>,
+    pub started_at: std::time::Instant,
+    /// Times the sidecar has been restarted in place
+    pub restarts: u32,
+    /// When the reaper restarted it after recent crashes; manual restarts
+    /// are not counted
+    pub auto_restarts: Vec<std::time::Instant>,
+    /// What the sidecar advertised in its handshake; None if it did not answer
+    pub capabilities: Option<serde_json::Value>,
+    /// `vault.list_entries` results by request params, until a file changes
+    pub entries_cache: HashMap<String, serde_json::Value>,
+    /// Bumped on every invalidation so a listing fetched across one is not cached
+    pub entries_generation: u64,
+    /// Memory cap applied at spawn, in bytes
+    pub memory_limit: Option<u64>,
+    /// Log level the sidecar currently runs at, if one was chosen
+    pub log_level: Option<String>,
+    /// Whether an exit caused by the memory cap has been reported
+    pub oom_reported: bool,
+    /// Whether the sidecar has been asked to hold its background work
+    pub paused: bool,
+    /// Where the host is in reaching the sidecar
+    pub connection: ConnectionState,
+    /// Unix socket commands are sent over, if not TCP
+    pub socket: Option<SocketFile>,
+    /// How the sidecar last closed a connection with a close frame, if it
+    /// has not answered a command since
+    pub last_close: Option<ConnectionClose>,
+    /// Sandbox the sidecar runs in, if any
+    pub sandbox: Option<SandboxPolicy>,
+    /// Passes the frontend's connections through to a sidecar the sandbox
+    /// cut off from the host's network
+    pub port_forward: Option<PortForward>,
+}
+
+impl SidecarProcess {
+    /// Whether the sidecar was started outside Tailor and attached to
+    pub fn is_external(&self) -> bool {
+        self.child.is_none()
+    }
+
+    /// Its exit status once the process has exited. An external sidecar's
+    /// process cannot be waited on and is taken to be running.
+    fn try_wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        match &mut self.child {
+            Some(child) => child.try_wait(),
+            None => Ok(None),
+        }
+    }
+
+    /// Whether the reaper may restart the sidecar after it died at `now`,
+    /// recording the restart if so: at most `MAX_AUTO_RESTARTS` within
+    /// `AUTO_RESTART_WINDOW`, counted afresh once it stayed up for
+    /// `STABLE_UPTIME`
+    fn take_auto_restart(&mut self, now: std::time::Instant) -> bool {
+        if now.duration_since(self.started_at) >= STABLE_UPTIME {
+            self.auto_restarts.clear();
+        }
+        self.auto_restarts.retain(|restarted| now.duration_since(*restarted) < AUTO_RESTART_WINDOW);
+        if self.auto_restarts.len() >= MAX_AUTO_RESTARTS {
+            return false;
+        }
+        self.auto_restarts.push(now);
+        true
+    }
+}
+
+/// Where the host is in reaching a sidecar, from launch to exit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    /// The process is starting and not accepting connections yet
+    Spawning,
+    /// Its port is open and the handshake is under way
+    Connecting,
+    /// The last connection to it succeeded
+    Ready,
+    /// A connection failed, or the sidecar is being restarted
+    Reconnecting,
+    /// The process has exited or was stopped
+    Dead,
+}
+
+/// A sidecar's connection state changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionChange {
+    pub window_label: String,
+    pub ws_port: u16,
+    pub state: ConnectionState,
+}
+
+/// A sidecar closed a connection with a close frame rather than dropping it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionClose {
+    pub code: u16,
+    pub reason: String,
+    /// Whether it was shutting down on purpose (1000 or 1001) rather than failing
+    pub clean: bool,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// The most recent failure seen for a sidecar
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SidecarError {
+    pub message: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl SidecarError {
+    fn now(message: impl Into<String>) -> Self {
+        Self { message: message.into(), timestamp: chrono::Utc::now() }
+    }
+}
+
+/// How a sidecar ended when its vault was closed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Termination {
+    /// It had already exited on its own
+    Exited,
+    /// It was still running and had to be killed
+    Killed,
+    /// No sidecar was tracked for the window
+    NotRunning,
+    /// It was started outside Tailor and was left running
+    Released,
+}
+
+/// Why a sidecar stopped, for logs, events and restart decisions
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShutdownReason {
+    /// The user closed the vault
+    UserClosed,
+    /// The window switched to another vault
+    VaultSwitched,
+    /// The app is exiting
+    AppExit,
+    /// Replaced by a fresh process
+    Restart,
+    /// Replaced by a sidecar started outside Tailor
+    Attached,
+    /// Opening the vault failed partway and was rolled back
+    OpenFailed,
+    /// A pre-warmed sidecar was never adopted
+    PrewarmExpired,
+    /// Stopped so the vault's dependencies could be reinstalled
+    DependencyReset,
+    /// Stopped so the vault's dependencies could move to a virtual environment
+    DependencyMigration,
+    /// The sidecar exited on its own
+    Crashed,
+    /// The sidecar cleanly closed its connection and then exited
+    ClosedBySidecar,
+    /// The sidecar exited after exceeding its memory limit
+    OutOfMemory,
+    /// A sidecar started outside Tailor stopped accepting connections
+    Disconnected,
+}
+
+impl ShutdownReason {
+    /// Whether a sidecar that stopped for this reason should be started
+    /// again automatically. Deliberate shutdowns never are, and neither is
+    /// one that ran out of memory, since it would only do so again.
+    pub fn allows_auto_restart(self) -> bool {
+        matches!(self, ShutdownReason::Crashed)
+    }
+}
+
+impl std::fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            ShutdownReason::UserClosed => "closed by user",
+            ShutdownReason::VaultSwitched => "vault switched",
+            ShutdownReason::AppExit => "app exiting",
+            ShutdownReason::Restart => "restart",
+            ShutdownReason::Attached => "external sidecar attached",
+            ShutdownReason::OpenFailed => "open failed",
+            ShutdownReason::PrewarmExpired => "pre-warm expired",
+            ShutdownReason::DependencyReset => "dependency reset",
+            ShutdownReason::DependencyMigration => "dependency migration",
+            ShutdownReason::Crashed => "crashed",
+            ShutdownReason::ClosedBySidecar => "closed by sidecar",
+            ShutdownReason::OutOfMemory => "out of memory",
+            ShutdownReason::Disconnected => "disconnected",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Snapshot of a sidecar for status displays
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarStatus {
+    pub window_label: String,
+    pub pid: u32,
+    pub ws_port: u16,
+    pub running: bool,
+    pub script: Option<PathBuf>,
+    pub last_error: Option<SidecarError>,
+    pub uptime_secs: u64,
+    pub restart_count: u32,
+    pub paused: bool,
+    pub connection: ConnectionState,
+    pub last_close: Option<ConnectionClose>,
+    /// Started outside Tailor and attached with `attach_sidecar`
+    pub external: bool,
+}
+
+/// Per-vault settings for spawning a sidecar, usually from its manifest
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpawnOptions {
+    /// Script to run instead of the bundled sidecar
+    pub entrypoint: Option<PathBuf>,
+    /// Memory cap in bytes
+    pub memory_limit: Option<u64>,
+    /// Passed as `--log-level`; the sidecar's own default if unset
+    pub log_level: Option<String>,
+    /// Run the sidecar confined to its vault
+    pub sandbox: Option<SandboxPolicy>,
+}
+
+/// Outcome of `reclaim_port`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PortReclaim {
+    pub port: u16,
+    /// What was listening on the port; None if it was already free
+    pub owner: Option<PortOwner>,
+    /// Whether the owner was stopped and the port is free again
+    pub reclaimed: bool,
+}
+
+/// A sidecar's answer to whether its vault may close now
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CloseCheck {
+    pub approved: bool,
+    /// Work that closing would lose, as described by the sidecar's plugins
+    pub unsaved: Vec<String>,
+    /// The sidecar did not answer within `before_close_timeout_ms`, so the
+    /// close was approved without it
+    pub timed_out: bool,
+}
+
+/// A sidecar that exited after hitting its memory limit
+#[derive(Debug, Clone, Serialize)]
+pub struct OomKill {
+    pub window_label: String,
+    pub vault_path: String,
+    pub limit_bytes: u64,
+    pub exit_status: String,
+}
+
+/// A sidecar that exited on its own, and was either dropped from tracking
+/// or restarted
+#[derive(Debug, Clone, Serialize)]
+pub struct ReapedSidecar {
+    pub window_label: String,
     pub vault_path: String,
     pub ws_port: u16,
+    pub exit_status: String,
+    pub reason: ShutdownReason,
+    /// Last lines the sidecar wrote to stderr
+    pub stderr: String,
+    /// Port of the replacement, if it was restarted
+    pub restarted_port: Option<u16>,
+}
+
+/// Buffered output of a sidecar, for diagnostics
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarLogs {
+    pub window_label: String,
+    pub vault_path: String,
+    /// Anything but `Capture` means the output was never recorded, so empty
+    /// logs say nothing about what the sidecar printed
+    pub output_capture: OutputCapture,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// How a sidecar was launched, so it can be run by hand to reproduce a
+/// startup failure. Secret values are redacted.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SidecarCommand {
+    pub program: String,
+    pub args: Vec<String>,
+    /// Variables set on top of the inherited environment
+    pub env: BTreeMap<String, String>,
+    pub cwd: Option<String>,
+}
+
+impl SidecarCommand {
+    /// Capture a command before it is spawned, masking the values of the
+    /// variables named in `secrets`
+    fn capture(command: &Command, secrets: &HashSet<String>) -> Self {
+        let text = |value: &std::ffi::OsStr| value.to_string_lossy().into_owned();
+        Self {
+            program: text(command.get_program()),
+            args: command.get_args().map(text).collect(),
+            env: command.get_envs()
+                .filter_map(|(name, value)| {
+                    let name = text(name);
+                    let value = if secrets.contains(&name) { REDACTED.to_string() } else { text(value?) };
+                    Some((name, value))
+                })
+                .collect(),
+            cwd: command.get_current_dir().map(|dir| dir.display().to_string()),
+        }
+    }
 }
 
 pub struct SidecarManager {
     processes: Arc<Mutex<HashMap<String, SidecarProcess>>>,
     next_port: Arc<Mutex<u16>>,
+    /// Ports handed out to sidecars that are still starting up
+    reserved_ports: Arc<Mutex<HashSet<u16>>>,
+    metrics: Arc<Metrics>,
+    config: SidecarConfig,
+    /// Receives stderr blocks when `forward_stderr` is enabled
+    stderr_sink: Option<UnboundedSender<StderrReport>>,
+    /// Restarts in progress by window label, resolving to the new port;
+    /// commands for a restarting sidecar wait on these
+    restarting: Arc<OpenGuard<u16>>,
+    /// Receives `host.*` requests sidecars send while answering a command
+    host_request_sink: Option<UnboundedSender<HostCall>>,
+    /// Keychain secrets injected into each vault's sidecar environment
+    secrets: Option<Arc<VaultSecrets>>,
+    /// Receives every connection state change
+    connection_sink: Option<UnboundedSender<ConnectionChange>>,
+    /// Receives notifications a sidecar sends while answering a command
+    notification_sink: Option<UnboundedSender<SidecarNotification>>,
+    /// Private directory holding sidecar sockets, created on first use; None
+    /// if it could not be
+    socket_dir: std::sync::OnceLock<Option<PathBuf>>,
+    /// How each window's sidecar was last launched, kept when it fails to
+    /// start so the failure can be reproduced
+    invocations: std::sync::Mutex<HashMap<String, SidecarCommand>>,
+}
+
+/// A JSON-RPC notification (e.g. a `trigger_event`) a sidecar sent on a
+/// command's connection rather than to the frontend
+#[derive(Debug, Clone, PartialEq)]
+pub struct SidecarNotification {
+    pub window_label: String,
+    pub message: serde_json::Value,
+}
+
+/// A JSON-RPC request a sidecar made of the host. The JSON-RPC response goes
+/// back on `reply` and is sent to the sidecar on the connection it came from.
+pub struct HostCall {
+    pub window_label: String,
+    pub request: serde_json::Value,
+    pub reply: oneshot::Sender<serde_json::Value>,
+}
+
+/// A line or whole traceback a sidecar wrote to stderr. Identified by port
+/// rather than label, since a pre-warmed sidecar changes label when adopted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StderrReport {
+    pub ws_port: u16,
+    pub text: String,
 }
 
 impl Default for SidecarManager {
     fn default() -> Self {
-        Self::new()
+        Self::new(Arc::default(), SidecarConfig::default())
     }
 }
 
 impl SidecarManager {
-    pub fn new() -> Self {
+    pub fn new(metrics: Arc<Metrics>, config: SidecarConfig) -> Self {
         Self {
             processes: Arc::new(Mutex::new(HashMap::new())),
-            next_port: Arc::new(Mutex::new(9000)),
+            next_port: Arc::new(Mutex::new(FIRST_PORT)),
+            reserved_ports: Arc::new(Mutex::new(HashSet::new())),
+            metrics,
+            config,
+            stderr_sink: None,
+            restarting: Arc::new(OpenGuard::new()),
+            host_request_sink: None,
+            secrets: None,
+            connection_sink: None,
+            notification_sink: None,
+            socket_dir: std::sync::OnceLock::new(),
+            invocations: std::sync::Mutex::new(HashMap::new()),
         }
     }
 
-    /// Spawn a Python sidecar process for a vault
+    /// Pass `host.*` requests from sidecars to `sink`. Without one they are
+    /// answered with a method-not-found error.
+    pub fn with_host_request_sink(mut self, sink: UnboundedSender<HostCall>) -> Self {
+        self.host_request_sink = Some(sink);
+        self
+    }
+
+    /// Pass each vault's secrets to its sidecar as environment variables
+    pub fn with_secrets(mut self, secrets: Arc<VaultSecrets>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    /// Send sidecar stderr, coalesced into blocks, to `sink` if the config
+    /// enables `forward_stderr`
+    pub fn with_stderr_sink(mut self, sink: UnboundedSender<StderrReport>) -> Self {
+        self.stderr_sink = Some(sink);
+        self
+    }
+
+    /// Pass notifications interleaved with command responses to `sink`
+    pub fn with_notification_sink(mut self, sink: UnboundedSender<SidecarNotification>) -> Self {
+        self.notification_sink = Some(sink);
+        self
+    }
+
+    /// Report connection state changes on `sink`
+    pub fn with_connection_sink(mut self, sink: UnboundedSender<ConnectionChange>) -> Self {
+        self.connection_sink = Some(sink);
+        self
+    }
+
+    /// Spawn a Python sidecar process for a vault with the given entrypoint,
+    /// memory limit and log level
     pub async fn spawn_sidecar(
         &self,
         window_label: String,
         vault_path: String,
+        options: SpawnOptions,
     ) -> Result<u16> {
-        // Allocate port
+        // Allocate port; it stays reserved until the process is tracked or fails
         let ws_port = self.allocate_port().await;
+        let result = self.spawn_on_port(window_label, vault_path, options, ws_port).await;
+        self.reserved_ports.lock().await.remove(&ws_port);
+        result
+    }
 
+    async fn spawn_on_port(
+        &self,
+        window_label: String,
+        vault_path: String,
+        options: SpawnOptions,
+        ws_port: u16,
+    ) -> Result<u16> {
+        let SpawnOptions { entrypoint, memory_limit, log_level, sandbox } = options;
         // Get Python executable path
-        let python_exe = self.get_python_executable()?;
+        let python_exe = Self::get_python_executable()?;
         
         // Get project root (parent of src-tauri) to set as CWD
         let project_root = std::env::current_dir()?
@@ -57,216 +609,1656 @@ impl SidecarManager {
         println!("Python executable: {}", python_exe);
         println!("Project root: {}", project_root.display());
 
-        // Spawn Python process with unbuffered output
-        let mut child = Command::new(&python_exe)
-            .arg("-u")  // Unbuffered output
-            .arg("-m")
-            .arg("sidecar")
+        // Run in a predictable directory rather than wherever Tauri was
+        // started; the project root goes on PYTHONPATH so `-m sidecar` resolves
+        let working_dir = self.config.working_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(&vault_path));
+        println!("Working directory: {}", working_dir.display());
+        long_path::check_working_dir(&working_dir)?;
+
+        // Commands go over a Unix socket if configured, or if the sandbox
+        // leaves no other way in; the frontend keeps TCP
+        let isolated = sandbox.as_ref().is_some_and(SandboxPolicy::isolates_network);
+        let socket_path = self.socket_path(ws_port, isolated);
+        if isolated && socket_path.is_none() {
+            anyhow::bail!("The sandbox cuts the sidecar off from the network, and no Unix socket could be set up to reach it");
+        }
+
+        // The vault's .env ranks below secrets and the host's own variables,
+        // so it only fills in names neither sets; its values are redacted
+        // like secrets
+        let mut dotenv = dotenv::load(Path::new(&vault_path));
+        let secrets = match self.secrets.clone() {
+            Some(secrets) => {
+                let vault_id = WindowManager::vault_id(&vault_path);
+                // The keychain blocks; values are only ever passed to the child
+                tokio::task::spawn_blocking(move || secrets.load(&vault_id)).await?
+            }
+            None => HashMap::new(),
+        };
+        dotenv.retain(|name, _| !secrets.contains_key(name));
+        if !dotenv.is_empty() {
+            println!("Environment from {}: {}", DOTENV_FILE, dotenv.keys().cloned().collect::<Vec<_>>().join(", "));
+        }
+        if !secrets.is_empty() {
+            let mut names: Vec<&String> = secrets.keys().collect();
+            names.sort();
+            println!("Secrets: {}", names.iter().map(|name| name.as_str()).collect::<Vec<_>>().join(", "));
+        }
+        let secret_names: HashSet<String> = dotenv.keys().chain(secrets.keys()).cloned().collect();
+
+        // Prefer a vault-specific entrypoint over the bundled sidecar
+        let script = entrypoint.or_else(|| Self::vault_sidecar_script(&vault_path));
+        let mut command = match &sandbox {
+            Some(policy) => {
+                println!("Sandbox: network {}", if policy.allow_network { "allowed" } else { "blocked" });
+                let mut writable = vec![PathBuf::from(&vault_path)];
+                writable.extend(socket_path.as_deref().and_then(Path::parent).map(Path::to_path_buf));
+                // Plugins may write to the vault, but not to what configures its
+                // sidecar or what the host installs and runs outside the sandbox
+                let paths = SandboxPaths {
+                    writable,
+                    readable: vec![project_root.clone(), working_dir.clone()],
+                    protected_files: [MANIFEST_FILE, DOTENV_FILE, ".vault.toml"]
+                        .map(|file| Path::new(&vault_path).join(file))
+                        .into_iter()
+                        .chain(DependencyChecker::install_inputs(&vault_path))
+                        .collect(),
+                    protected_dirs: DependencyChecker::install_dirs(&vault_path),
+                };
+                sandbox::command(&python_exe, policy, paths)?
+            }
+            None => Command::new(&python_exe),
+        };
+        // Set on the launcher's environment, which it passes through, so the
+        // values never show in its arguments; loader variables that would
+        // affect the launcher itself are already filtered out
+        command.envs(&dotenv).envs(&secrets);
+        command.arg("-u");  // Unbuffered output
+        match &script {
+            Some(script) => {
+                println!("Sidecar script: {} (vault override)", script.display());
+                command.arg(script);
+            }
+            None => {
+                println!("Sidecar script: bundled sidecar module");
+                command.arg("-m").arg("sidecar");
+            }
+        }
+
+        if let Some(limit) = memory_limit {
+            println!("Memory limit: {} MiB", limit / (1024 * 1024));
+            memory_limit::limit_command(&mut command, limit);
+        }
+        if let Some(level) = &log_level {
+            command.arg("--log-level").arg(level);
+        }
+        if let Some(path) = &socket_path {
+            println!("Command socket: {}", path.display());
+            command.arg("--ws-uds").arg(path);
+        }
+
+        command
             .arg("--vault")
-            .arg(&vault_path)
+            .arg(long_path::extended(Path::new(&vault_path)))
+            .arg("--ws-host")
+            .arg(self.config.ws_host.to_string())
             .arg("--ws-port")
             .arg(ws_port.to_string())
-            .current_dir(&project_root)
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to spawn Python sidecar")?;
+            .arg("--ping-interval")
+            .arg(self.config.ping_interval_secs.to_string())
+            .current_dir(&working_dir)
+            .env("PYTHONPATH", Self::python_path(&project_root)?)
+            .stdout(Self::output_stdio(self.config.output_capture))
+            .stderr(Self::output_stdio(self.config.output_capture));
+        self.invocations.lock().unwrap_or_else(|e| e.into_inner())
+            .insert(window_label.clone(), SidecarCommand::capture(&command, &secret_names));
+
+        // Spawn Python process with unbuffered output
+        let child = command.spawn().context("Failed to spawn Python sidecar")?;
+
+        // Kill the process if this spawn is abandoned (e.g. an open timing out)
+        let mut guard = UntrackedChild(Some(child));
+        let child = guard.0.as_mut().expect("child is present until tracked");
+
+        if let Some(limit) = memory_limit {
+            memory_limit::limit_process(child, limit)?;
+        }
 
         let pid = child.id();
         println!("Sidecar spawned with PID: {}", pid);
+        Metrics::incr(&self.metrics.sidecars_spawned);
+        self.announce_connection(&window_label, ws_port, ConnectionState::Spawning);
+
+        let logs = LogBuffer::new(self.config.log_buffer_lines, DEFAULT_LOG_BYTES);
 
         // Capture stdout for debugging
         if let Some(stdout) = child.stdout.take() {
-            use std::io::BufRead;
-            std::thread::spawn(move || {
-                let reader = std::io::BufReader::new(stdout);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        println!("[Sidecar] {}", line);
-                    }
-                }
-            });
+            let logs = logs.clone();
+            std::thread::spawn(move || Self::capture_output(stdout, LogStream::Stdout, logs, None));
         }
 
         // Capture stderr for debugging
-        if let Some(stderr) = child.stderr.take() {
-            use std::io::BufRead;
-            std::thread::spawn(move || {
-                let reader = std::io::BufReader::new(stderr);
-                for line in reader.lines() {
-                    if let Ok(line) = line {
-                        eprintln!("[Sidecar Error] {}", line);
-                    }
+        let forward = self.stderr_sink.clone()
+            .filter(|_| self.config.forward_stderr)
+            .map(|sink| (ws_port, sink));
+        let stderr_reader = child.stderr.take().map(|stderr| {
+            let logs = logs.clone();
+            std::thread::spawn(move || Self::capture_output(stderr, LogStream::Stderr, logs, forward))
+        });
+
+        // Catch sidecars that die on startup (e.g. import errors) or hang in it
+        let spawn_timeout = std::time::Duration::from_millis(self.config.spawn_timeout_ms);
+        let startup = Self::watch_startup(child, self.connect_addr(ws_port), socket_path.as_deref(), spawn_timeout).await;
+        match &startup {
+            Startup::Ready => {}
+            Startup::Exited(status) => {
+                eprintln!("Sidecar for window '{}' exited during startup ({})", window_label, status);
+            }
+            Startup::TimedOut => {
+                eprintln!("Sidecar for window '{}' not ready after {}ms, killing it",
+                          window_label, self.config.spawn_timeout_ms);
+            }
+            Startup::Unknown(e) => {
+                eprintln!("Failed to poll sidecar for window '{}' during startup, killing it: {}", window_label, e);
+            }
+        }
+        if matches!(startup, Startup::TimedOut | Startup::Unknown(_)) {
+            if let Err(e) = child.kill() {
+                eprintln!("Failed to kill sidecar process: {}", e);
+            }
+            let _ = child.wait();
+        }
+
+        if startup != Startup::Ready {
+            Metrics::incr(&self.metrics.sidecars_crashed);
+            self.announce_connection(&window_label, ws_port, ConnectionState::Dead);
+
+            // Give the reader a moment to drain the remaining stderr
+            if let Some(reader) = stderr_reader {
+                let deadline = std::time::Instant::now() + std::time::Duration::from_millis(500);
+                while !reader.is_finished() && std::time::Instant::now() < deadline {
+                    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
                 }
-            });
+            }
+
+            let stderr = match self.config.output_capture {
+                OutputCapture::Capture => logs.text(LogStream::Stderr),
+                capture => format!("(sidecar output is not captured: sidecar.output_capture = \"{}\")",
+                                   capture.as_str()),
+            };
+            return Err(TailorError::SidecarSpawnFailed { stderr }.into());
         }
 
+        let port_forward = match socket_path.clone().filter(|_| isolated) {
+            Some(path) => Some(sandbox::forward_port(SocketAddr::new(self.config.ws_host, ws_port), path).await?),
+            None => None,
+        };
+
         // Store process
         let process = SidecarProcess {
-            child,
+            child: guard.0.take(),
+            pid,
             vault_path: vault_path.clone(),
             ws_port,
+            transport: Arc::new(self.transport_for(ws_port, socket_path.as_deref())),
+            script,
+            logs,
+            output_capture: self.config.output_capture,
+            last_error: None,
+            started_at: std::time::Instant::now(),
+            restarts: 0,
+            auto_restarts: Vec::new(),
+            capabilities: None,
+            entries_cache: HashMap::new(),
+            entries_generation: 0,
+            memory_limit,
+            log_level,
+            oom_reported: false,
+            paused: false,
+            connection: ConnectionState::Connecting,
+            socket: socket_path.map(SocketFile),
+            last_close: None,
+            sandbox,
+            port_forward,
         };
 
         self.processes.lock().await.insert(window_label.clone(), process);
+        self.announce_connection(&window_label, ws_port, ConnectionState::Connecting);
+        // Connecting for the handshake moves it to Ready
+        self.handshake(&window_label).await;
 
         Ok(ws_port)
     }
 
-    /// Terminate a sidecar process
-    pub async fn terminate_sidecar(&self, window_label: &str) -> Result<()> {
-        let mut processes = self.processes.lock().await;
-        
-        if let Some(mut process) = processes.remove(window_label) {
-            println!("Terminating sidecar for window '{}'", window_label);
-            
-            // Try graceful shutdown first
-            if let Err(e) = process.child.kill() {
-                eprintln!("Failed to kill sidecar process: {}", e);
-            }
-            
-            // Wait for process to exit
-            if let Err(e) = process.child.wait() {
-                eprintln!("Failed to wait for sidecar exit: {}", e);
-            }
-            
-            println!("Sidecar terminated for window '{}'", window_label);
-        }
-
-        Ok(())
-    }
+    /// Tell a freshly started sidecar the host's version and ask for its
+    /// capabilities. Sidecars that do not implement the handshake (e.g. older
+    /// vault scripts) keep working, just without advertised capabilities.
+    /// Returns whether the sidecar answered at all.
+    async fn handshake(&self, window_label: &str) -> bool {
+        let params = serde_json::json!({ "host": AppInfo::current() });
+        let request = self.build_request(HANDSHAKE_METHOD, params);
+        let request_id = request["id"].clone();
+        let exchange = self.exchange_inner(window_label, HANDSHAKE_METHOD, &request, None, |response| {
+            Self::is_response_to(response, &request_id)
+        });
 
-    /// Terminate ALL sidecar processes (used for app shutdown)
-    pub fn shutdown_all(&self) {
-        println!("Shutting down all sidecars...");
-        // Use blocking lock for shutdown
-        if let Ok(mut processes) = self.processes.try_lock() {
-             for (label, mut process) in processes.drain() {
-                println!("Killing sidecar for window '{}' (PID: {})", label, process.child.id());
-                if let Err(e) = process.child.kill() {
-                    eprintln!("Failed to kill sidecar {}: {}", label, e);
-                } else {
-                     let _ = process.child.wait(); // Best effort wait
+        let capabilities = match tokio::time::timeout(HANDSHAKE_TIMEOUT, exchange).await {
+            Ok(Ok(response)) => match response.pointer("/result/capabilities") {
+                Some(capabilities) => {
+                    if let Some(version) = response.pointer("/result/version").and_then(|v| v.as_str()) {
+                        println!("Sidecar for window '{}' is version {} (host {})",
+                                 window_label, version, env!("CARGO_PKG_VERSION"));
+                    }
+                    capabilities.clone()
                 }
-             }
-        } else {
-            // Fallback: If we can't lock (unlikely in shutdown), we might leak. 
-            // Better to force lock if possible, but try_lock avoids deadlock potential in panic paths.
-            eprintln!("Failed to acquire lock for shutdown cleanup!");
-        }
-    }
-    
-    /// Get WebSocket port for a sidecar
-    pub async fn get_ws_port(&self, window_label: &str) -> Option<u16> {
-        self.processes.lock().await
-            .get(window_label)
-            .map(|p| p.ws_port)
-    }
-
-    /// Allocate next available port by actually checking port availability
-    async fn allocate_port(&self) -> u16 {
-        let mut port = self.next_port.lock().await;
-        
-        // Try to find an available port starting from current port
-        loop {
-            if Self::is_port_available(*port) {
-                let allocated = *port;
-                *port += 1;
-                return allocated;
+                None => {
+                    println!("Sidecar for window '{}' advertised no capabilities", window_label);
+                    return true;
+                }
+            },
+            Ok(Err(e)) => {
+                eprintln!("Handshake with sidecar for window '{}' failed: {}", window_label, e);
+                return false;
             }
-            *port += 1;
-            
-            // Wrap around if we exceed reasonable ports
-            if *port > 19000 {
-                *port = 9000;
+            Err(_) => {
+                eprintln!("Sidecar for window '{}' did not answer the handshake within {}s",
+                          window_label, HANDSHAKE_TIMEOUT.as_secs());
+                return false;
             }
+        };
+
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.capabilities = Some(capabilities);
         }
-    }
-    
-    /// Check if a port is available
-    fn is_port_available(port: u16) -> bool {
-        use std::net::TcpListener;
-        TcpListener::bind(("127.0.0.1", port)).is_ok()
+        true
     }
 
-    /// Get Python executable path
-    fn get_python_executable(&self) -> Result<String> {
-        // Try to find Python in PATH
-        #[cfg(target_os = "windows")]
-        let python_candidates = vec!["python.exe", "python3.exe"];
-        
-        #[cfg(not(target_os = "windows"))]
-        let python_candidates = vec!["python3", "python"];
+    /// Label a vault's pre-warmed sidecar is tracked under until a window adopts it
+    pub fn prewarm_label(vault_id: &str) -> String {
+        format!("prewarm_{}", vault_id)
+    }
 
-        for candidate in python_candidates {
-            if let Ok(output) = Command::new(candidate)
-                .arg("--version")
-                .output()
-            {
-                if output.status.success() {
-                    return Ok(candidate.to_string());
-                }
+    /// Move a running sidecar to another label (a pre-warmed sidecar to the
+    /// window that opened its vault). Returns its port, or None if there is
+    /// no live sidecar under `from`.
+    pub async fn adopt(&self, from: &str, to: &str) -> Option<u16> {
+        let mut processes = self.processes.lock().await;
+        let mut process = processes.remove(from)?;
+        if !matches!(process.try_wait(), Ok(None)) {
+            eprintln!("Sidecar '{}' is no longer running, not adopting it", from);
+            if let Some(child) = process.child.as_mut() {
+                let _ = child.wait();
             }
+            return None;
         }
 
-        anyhow::bail!("Python not found in PATH")
+        let ws_port = process.ws_port;
+        let connection = process.connection;
+        processes.insert(to.to_string(), process);
+        let mut invocations = self.invocations.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(invocation) = invocations.remove(from) {
+            invocations.insert(to.to_string(), invocation);
+        }
+        drop(invocations);
+        println!("Sidecar '{}' adopted by window '{}'", from, to);
+        // The adopting window has heard nothing about it so far
+        self.announce_connection(to, ws_port, connection);
+        Some(ws_port)
     }
 
-    /// Send a command to the sidecar via WebSocket
-    pub async fn send_command(
+    /// Start a sidecar for `vault_path` and swap it in for the window's
+    /// current one, which is only stopped once the new one is up. If the
+    /// spawn fails the window keeps its current sidecar. Returns the new port.
+    pub async fn replace_sidecar(
         &self,
         window_label: &str,
-        method: &str,
-        params: serde_json::Value,
-    ) -> Result<serde_json::Value> {
-        // 1. Get port
-        let port = self.get_ws_port(window_label)
+        vault_path: String,
+        options: SpawnOptions,
+        reason: ShutdownReason,
+    ) -> Result<u16> {
+        let staging = format!("replace_{}", window_label);
+        self.spawn_sidecar(staging.clone(), vault_path, options).await?;
+        if let Err(e) = self.terminate_sidecar(window_label, reason).await {
+            let _ = self.terminate_sidecar(&staging, ShutdownReason::OpenFailed).await;
+            return Err(e);
+        }
+        self.adopt(&staging, window_label)
             .await
-            .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))?;
+            .ok_or_else(|| anyhow!("The new sidecar exited before it could replace the old one"))
+    }
 
-        // 2. Connect
-        let url = Url::parse(&format!("ws://127.0.0.1:{}", port))
-            .context("Invalid WebSocket URL")?;
+    /// Use a sidecar started outside Tailor (e.g. under a debugger) and
+    /// listening on `ws_port` as the window's sidecar, in place of the
+    /// current one, which is only stopped once the new one has answered the
+    /// handshake. It is never killed or restarted; terminating it only stops
+    /// tracking it.
+    pub async fn attach_sidecar(&self, window_label: String, vault_path: String, ws_port: u16) -> Result<u16> {
+        if let Some(label) = self.label_for_port(ws_port).await {
+            anyhow::bail!("Port {} belongs to the sidecar of window '{}'", ws_port, label);
+        }
+        if !Self::accepts_connections(self.connect_addr(ws_port), None).await {
+            anyhow::bail!("No sidecar is listening on port {}", ws_port);
+        }
+        // Best effort: only for status displays
+        let pid = tokio::task::spawn_blocking(move || port_owner::find(ws_port))
+            .await?
+            .ok()
+            .flatten()
+            .map_or(0, |owner| owner.pid);
+        println!("Attaching window '{}' to external sidecar on port {} (PID: {})", window_label, ws_port, pid);
 
-        let (mut ws_stream, _) = connect_async(url.to_string())
+        let process = SidecarProcess {
+            child: None,
+            pid,
+            vault_path,
+            ws_port,
+            transport: Arc::new(self.transport_for(ws_port, None)),
+            script: None,
+            logs: LogBuffer::new(self.config.log_buffer_lines, DEFAULT_LOG_BYTES),
+            // Its output goes wherever it was started from
+            output_capture: OutputCapture::Inherit,
+            last_error: None,
+            started_at: std::time::Instant::now(),
+            restarts: 0,
+            auto_restarts: Vec::new(),
+            capabilities: None,
+            entries_cache: HashMap::new(),
+            entries_generation: 0,
+            memory_limit: None,
+            log_level: None,
+            oom_reported: false,
+            paused: false,
+            connection: ConnectionState::Connecting,
+            socket: None,
+            last_close: None,
+            sandbox: None,
+            port_forward: None,
+        };
+        // Reach it under a staging label first, so a wrong port leaves the
+        // window's current sidecar running
+        let staging = format!("attach_{}", window_label);
+        self.processes.lock().await.insert(staging.clone(), process);
+        if !self.handshake(&staging).await {
+            self.processes.lock().await.remove(&staging);
+            anyhow::bail!("The process on port {} did not answer as a sidecar", ws_port);
+        }
+        if let Err(e) = self.terminate_sidecar(&window_label, ShutdownReason::Attached).await {
+            self.processes.lock().await.remove(&staging);
+            return Err(e);
+        }
+        self.adopt(&staging, &window_label)
             .await
-            .context("Failed to connect to sidecar WebSocket")?;
+            .ok_or_else(|| anyhow!("The attached sidecar went away before the window could use it"))
+    }
 
+    /// Terminate a sidecar process, reporting how it ended
+    pub async fn terminate_sidecar(&self, window_label: &str, reason: ShutdownReason) -> Result<Termination> {
+        let mut processes = self.processes.lock().await;
+        
+        let Some(mut process) = processes.remove(window_label) else {
+            return Ok(Termination::NotRunning);
+        };
+        self.invocations.lock().unwrap_or_else(|e| e.into_inner()).remove(window_label);
+        println!("Terminating sidecar for window '{}' ({})", window_label, reason);
 
-        // 3. Construct JSON-RPC Request
-        let request_id = uuid::Uuid::new_v4().to_string();
-        let request = serde_json::json!({
-            "jsonrpc": "2.0",
-            "method": method,
-            "params": params,
-            "id": request_id
-        });
+        // Tailor did not start an external sidecar, so it is not Tailor's to stop
+        let Some(child) = process.child.as_mut() else {
+            println!("Released external sidecar for window '{}' on port {}", window_label, process.ws_port);
+            if process.connection != ConnectionState::Dead {
+                self.announce_connection(window_label, process.ws_port, ConnectionState::Dead);
+            }
+            return Ok(Termination::Released);
+        };
 
-        // 4. Send Request
-        let request_text = serde_json::to_string(&request)?;
-        ws_stream.send(Message::Text(request_text)).await
-            .context("Failed to send WebSocket message")?;
-
-        // 5. Await Response
-        // We expect a single response for the request
-        while let Some(msg) = ws_stream.next().await {
-            let msg = msg.context("WebSocket stream error")?;
-            match msg {
-                Message::Text(text) => {
-                    let response: serde_json::Value = serde_json::from_str(&text)
-                        .context("Failed to parse sidecar response")?;
-                    
-                    if response.get("id").and_then(|id| id.as_str()) == Some(&request_id) {
-                         return Ok(response);
-                    }
+        // A sidecar that already stopped on its own needs no kill
+        let termination = match child.try_wait() {
+            Ok(Some(status)) => {
+                println!("Sidecar for window '{}' had already exited ({})", window_label, status);
+                Termination::Exited
+            }
+            _ => {
+                if let Err(e) = child.kill() {
+                    eprintln!("Failed to kill sidecar process: {}", e);
                 }
-                Message::Close(_) => break,
-                _ => {}
+                Termination::Killed
             }
-        }
+        };
+
+        // Wait for process to exit
+        if let Err(e) = child.wait() {
+            eprintln!("Failed to wait for sidecar exit: {}", e);
+        }
+        if process.connection != ConnectionState::Dead {
+            self.announce_connection(window_label, process.ws_port, ConnectionState::Dead);
+        }
+        
+        println!("Sidecar terminated for window '{}'", window_label);
+        Ok(termination)
+    }
+
+    /// Replace a window's sidecar with a fresh process for the same vault,
+    /// script and memory limit. Commands sent meanwhile are held until the
+    /// new sidecar has answered its handshake. Returns the new port.
+    pub async fn restart_sidecar(&self, window_label: &str) -> Result<u16> {
+        self.restarting
+            .run(window_label, async {
+                self.restart_inner(window_label).await.map_err(|e| format!("{:#}", e))
+            })
+            .await
+            .map_err(|e| anyhow!("Failed to restart sidecar: {}", e))
+    }
+
+    async fn restart_inner(&self, window_label: &str) -> Result<u16> {
+        let (vault_path, options, restarts, auto_restarts) = {
+            let processes = self.processes.lock().await;
+            let process = processes.get(window_label)
+                .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))?;
+            if process.is_external() {
+                anyhow::bail!("The sidecar for window '{}' was started outside Tailor; restart it there", window_label);
+            }
+            let options = SpawnOptions {
+                entrypoint: process.script.clone(),
+                memory_limit: process.memory_limit,
+                log_level: process.log_level.clone(),
+                sandbox: process.sandbox.clone(),
+            };
+            (process.vault_path.clone(), options, process.restarts, process.auto_restarts.clone())
+        };
+
+        println!("Restarting sidecar for window '{}'", window_label);
+        self.set_connection(window_label, ConnectionState::Reconnecting).await;
+        self.terminate_sidecar(window_label, ShutdownReason::Restart).await?;
+        let ws_port = self.spawn_sidecar(window_label.to_string(), vault_path, options).await?;
+
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.restarts = restarts + 1;
+            process.auto_restarts = auto_restarts;
+        }
+        Metrics::incr(&self.metrics.sidecars_restarted);
+        Ok(ws_port)
+    }
+
+    /// Change a running sidecar's log level. The level is kept for restarts.
+    pub async fn set_log_level(&self, window_label: &str, level: &str) -> Result<()> {
+        let level = normalize_log_level(level)
+            .ok_or_else(|| anyhow!("Unknown log level '{}' (expected one of {})", level, LOG_LEVELS.join(", ")))?;
+
+        let response = self.send_command(
+            window_label,
+            SET_LOG_LEVEL_METHOD,
+            serde_json::json!({ "level": level }),
+        ).await?;
+        Self::check_status(&response)?;
+
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.log_level = Some(level.to_string());
+        }
+        println!("Sidecar for window '{}' now logs at {}", window_label, level);
+        Ok(())
+    }
+
+    /// Have a sidecar flush its log file, move it aside and start a new one.
+    /// Returns the path of the new log file.
+    pub async fn rotate_log(&self, window_label: &str) -> Result<String> {
+        let response = self.send_command(window_label, ROTATE_LOG_METHOD, serde_json::json!({})).await?;
+        Self::check_status(&response)?;
+
+        let log_file = response.pointer("/result/log_file")
+            .and_then(|path| path.as_str())
+            .ok_or_else(|| anyhow!("Sidecar did not report its new log file"))?;
+        let rotated_to = response.pointer("/result/rotated_to").and_then(|path| path.as_str());
+        println!("Rotated sidecar log for window '{}' (previous log: {})",
+                 window_label, rotated_to.unwrap_or("none"));
+        Ok(log_file.to_string())
+    }
+
+    /// Ask a sidecar to stop (or restart) its background work while keeping
+    /// its state, e.g. to save battery. The sidecar decides what to hold; the
+    /// host only records the request. A restarted sidecar starts unpaused.
+    pub async fn set_paused(&self, window_label: &str, paused: bool) -> Result<()> {
+        let method = if paused { PAUSE_METHOD } else { RESUME_METHOD };
+        let response = self.send_command(window_label, method, serde_json::json!({})).await?;
+        Self::check_status(&response)?;
+
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.paused = paused;
+        }
+        println!("Sidecar for window '{}' {}", window_label, if paused { "paused" } else { "resumed" });
+        Ok(())
+    }
+
+    /// Ask a sidecar whether its vault can close. Sidecars that cannot
+    /// answer (unreachable, too slow, or without the method) do not hold the
+    /// close up.
+    pub async fn before_close(&self, window_label: &str) -> CloseCheck {
+        let timeout = std::time::Duration::from_millis(self.config.before_close_timeout_ms);
+        let exchange = self.send_command(window_label, BEFORE_CLOSE_METHOD, serde_json::json!({}));
+        let response = match tokio::time::timeout(timeout, exchange).await {
+            Ok(Ok(response)) => response,
+            Ok(Err(e)) => {
+                eprintln!("Sidecar for window '{}' could not confirm close: {}", window_label, e);
+                return CloseCheck { approved: true, unsaved: Vec::new(), timed_out: false };
+            }
+            Err(_) => {
+                eprintln!("Sidecar for window '{}' did not confirm close within {}ms, closing anyway",
+                          window_label, timeout.as_millis());
+                return CloseCheck { approved: true, unsaved: Vec::new(), timed_out: true };
+            }
+        };
+        if let Err(e) = Self::check_status(&response) {
+            eprintln!("Sidecar for window '{}' could not confirm close: {}", window_label, e);
+            return CloseCheck { approved: true, unsaved: Vec::new(), timed_out: false };
+        }
+
+        let unsaved: Vec<String> = response.pointer("/result/unsaved")
+            .and_then(|unsaved| serde_json::from_value(unsaved.clone()).ok())
+            .unwrap_or_default();
+        let approved = response.pointer("/result/approve")
+            .and_then(|approve| approve.as_bool())
+            .unwrap_or(unsaved.is_empty());
+        CloseCheck { approved, unsaved, timed_out: false }
+    }
+
+    /// Fail on a JSON-RPC error or a `{"status": "error"}` result from one of
+    /// the sidecar's system commands
+    fn check_status(response: &serde_json::Value) -> Result<()> {
+        if let Some(error) = Self::rpc_error_message(response) {
+            anyhow::bail!("{}", error);
+        }
+        if response.pointer("/result/status").and_then(|s| s.as_str()) == Some("error") {
+            let error = response.pointer("/result/error").and_then(|e| e.as_str()).unwrap_or("unknown error");
+            anyhow::bail!("{}", error);
+        }
+        Ok(())
+    }
+
+    /// Hold a command while its sidecar restarts, for up to `restart_wait_ms`
+    async fn await_restart(&self, window_label: &str) -> Result<()> {
+        let wait_ms = self.config.restart_wait_ms;
+        match tokio::time::timeout(std::time::Duration::from_millis(wait_ms), self.restarting.wait(window_label)).await {
+            Ok(Some(Err(e))) => Err(anyhow!(e)),
+            Ok(_) => Ok(()),
+            Err(_) => Err(TailorError::Timeout {
+                message: format!("sidecar for '{}' was still restarting after {}ms", window_label, wait_ms),
+            }.into()),
+        }
+    }
+
+    /// Stop managing a sidecar without killing it, so a debugger can be
+    /// attached to the still-running process. Returns its PID.
+    pub async fn detach_sidecar(&self, window_label: &str) -> Result<u32> {
+        let mut process = self.processes.lock().await
+            .remove(window_label)
+            .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))?;
+
+        // Dropping a `Child` neither kills nor waits on the process, but the
+        // socket it still listens on, and any forward to it, must be kept
+        std::mem::forget(process.socket.take());
+        std::mem::forget(process.port_forward.take());
+        let pid = process.pid;
+        println!("Detached sidecar for window '{}' (PID: {}); it is no longer managed", window_label, pid);
+        Ok(pid)
+    }
+
+    /// Terminate ALL sidecar processes (used for app shutdown)
+    pub fn shutdown_all(&self) {
+        println!("Shutting down all sidecars ({})...", ShutdownReason::AppExit);
+        // Use blocking lock for shutdown
+        if let Ok(mut processes) = self.processes.try_lock() {
+             for (label, process) in processes.drain() {
+                // External sidecars are left running
+                let Some(mut child) = process.child else { continue };
+                println!("Killing sidecar for window '{}' (PID: {})", label, child.id());
+                if let Err(e) = child.kill() {
+                    eprintln!("Failed to kill sidecar {}: {}", label, e);
+                } else {
+                     let _ = child.wait(); // Best effort wait
+                }
+             }
+        } else {
+            // Fallback: If we can't lock (unlikely in shutdown), we might leak. 
+            // Better to force lock if possible, but try_lock avoids deadlock potential in panic paths.
+            eprintln!("Failed to acquire lock for shutdown cleanup!");
+        }
+        // Empty now unless a detached sidecar still has its socket there
+        if let Some(Some(dir)) = self.socket_dir.get() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+    
+    /// OS process id of a window's sidecar
+    pub async fn get_pid(&self, window_label: &str) -> Option<u32> {
+        self.processes.lock().await
+            .get(window_label)
+            .map(|p| p.pid)
+    }
+
+    /// Get WebSocket port for a sidecar
+    pub async fn get_ws_port(&self, window_label: &str) -> Option<u16> {
+        self.processes.lock().await
+            .get(window_label)
+            .map(|p| p.ws_port)
+    }
+
+    /// Capabilities a sidecar advertised in its handshake. `Ok(None)` means
+    /// the sidecar is running but did not advertise any.
+    pub async fn capabilities(&self, window_label: &str) -> Result<Option<serde_json::Value>> {
+        self.processes.lock().await
+            .get(window_label)
+            .map(|p| p.capabilities.clone())
+            .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))
+    }
+
+    /// With `validate_methods` set, reject a method the sidecar did not list
+    /// in `capabilities.methods`. Anything is allowed when the sidecar
+    /// advertised no method list.
+    pub async fn check_method_supported(&self, window_label: &str, method: &str) -> std::result::Result<(), TailorError> {
+        if !self.config.validate_methods {
+            return Ok(());
+        }
+        self.await_restart(window_label).await.map_err(TailorError::from_transport_error)?;
+
+        let processes = self.processes.lock().await;
+        let methods = processes.get(window_label)
+            .and_then(|p| p.capabilities.as_ref())
+            .and_then(|capabilities| capabilities.get("methods"))
+            .and_then(|methods| methods.as_array());
+
+        match methods {
+            Some(methods) if !methods.iter().any(|m| m.as_str() == Some(method)) => {
+                Err(TailorError::MethodNotSupported { method: method.to_string() })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Label of the tracked sidecar listening on `ws_port`
+    pub async fn label_for_port(&self, ws_port: u16) -> Option<String> {
+        self.processes.lock().await
+            .iter()
+            .find(|(_, p)| p.ws_port == ws_port)
+            .map(|(label, _)| label.clone())
+    }
+
+    /// Check that a port handed back by the frontend belongs to the window's
+    /// tracked sidecar, so a stale port is never connected to
+    pub async fn validate_port(&self, window_label: &str, ws_port: u16) -> std::result::Result<(), TailorError> {
+        match self.get_ws_port(window_label).await {
+            Some(port) if port == ws_port => Ok(()),
+            _ => Err(TailorError::SidecarNotFound {
+                window_label: window_label.to_string(),
+                ws_port,
+            }),
+        }
+    }
+
+    /// Current state of a sidecar, recording a crash as its last error
+    pub async fn status(&self, window_label: &str) -> Option<SidecarStatus> {
+        let mut processes = self.processes.lock().await;
+        let process = processes.get_mut(window_label)?;
+        Some(self.snapshot(window_label, process))
+    }
+
+    /// Current state of every tracked sidecar, taken under a single lock
+    pub async fn all_status(&self) -> Vec<SidecarStatus> {
+        let mut processes = self.processes.lock().await;
+        let mut statuses: Vec<SidecarStatus> = processes
+            .iter_mut()
+            .map(|(label, process)| self.snapshot(label, process))
+            .collect();
+        statuses.sort_by(|a, b| a.window_label.cmp(&b.window_label));
+        statuses
+    }
+
+    /// Memory-limited sidecars that have exited after hitting their limit
+    /// since the last call. Each exit is reported once.
+    pub async fn oom_kills(&self) -> Vec<OomKill> {
+        let mut processes = self.processes.lock().await;
+        let mut kills = Vec::new();
+        for (window_label, process) in processes.iter_mut() {
+            let Some(limit_bytes) = process.memory_limit else { continue };
+            if process.oom_reported {
+                continue;
+            }
+            let Ok(Some(status)) = process.try_wait() else { continue };
+            process.oom_reported = true;
+            let stderr = process.logs.tail(LogStream::Stderr, CRASH_STDERR_LINES);
+            if memory_limit::exited_for_memory(status, &stderr) {
+                kills.push(OomKill {
+                    window_label: window_label.clone(),
+                    vault_path: process.vault_path.clone(),
+                    limit_bytes,
+                    exit_status: status.to_string(),
+                });
+            }
+        }
+        kills
+    }
+
+    /// Deal with sidecars that have exited on their own: restart crashed
+    /// ones if `auto_restart` is set and they have restarts left, otherwise
+    /// stop tracking them and free their ports. Attached sidecars, which
+    /// have no process to wait on, count as exited once their port stops
+    /// accepting connections. Sidecars being restarted, and memory-limited
+    /// ones whose exit `oom_kills` has not seen yet, are left for later.
+    pub async fn reap_dead(&self) -> Vec<ReapedSidecar> {
+        let attached: Vec<(String, u16)> = self.processes.lock().await
+            .iter()
+            .filter(|(_, process)| process.is_external())
+            .map(|(window_label, process)| (window_label.clone(), process.ws_port))
+            .collect();
+        let mut unreachable = HashSet::new();
+        for (window_label, ws_port) in attached {
+            if !Self::accepts_connections(self.connect_addr(ws_port), None).await {
+                unreachable.insert((window_label, ws_port));
+            }
+        }
+
+        let mut dead = Vec::new();
+        let mut to_restart = Vec::new();
+        {
+            let mut processes = self.processes.lock().await;
+            for (window_label, process) in processes.iter_mut() {
+                if self.restarting.is_in_flight(window_label)
+                    || (process.memory_limit.is_some() && !process.oom_reported)
+                {
+                    continue;
+                }
+                let stderr = process.logs.tail(LogStream::Stderr, CRASH_STDERR_LINES);
+                let (status, reason) = match process.try_wait() {
+                    Ok(Some(status)) => {
+                        let reason = if process.memory_limit.is_some() && memory_limit::exited_for_memory(status, &stderr) {
+                            ShutdownReason::OutOfMemory
+                        } else if process.last_close.as_ref().is_some_and(|close| close.clean) {
+                            // It said it was going away; restarting it would undo that
+                            ShutdownReason::ClosedBySidecar
+                        } else {
+                            ShutdownReason::Crashed
+                        };
+                        (status.to_string(), reason)
+                    }
+                    _ if unreachable.contains(&(window_label.clone(), process.ws_port)) => {
+                        (format!("port {} no longer accepts connections", process.ws_port), ShutdownReason::Disconnected)
+                    }
+                    _ => continue,
+                };
+                let restart = self.config.auto_restart
+                    && reason.allows_auto_restart()
+                    && process.take_auto_restart(std::time::Instant::now());
+                if restart {
+                    to_restart.push(window_label.clone());
+                }
+                dead.push(ReapedSidecar {
+                    window_label: window_label.clone(),
+                    vault_path: process.vault_path.clone(),
+                    ws_port: process.ws_port,
+                    exit_status: status,
+                    reason,
+                    stderr,
+                    restarted_port: None,
+                });
+            }
+
+            let mut reserved = self.reserved_ports.lock().await;
+            for sidecar in dead.iter().filter(|s| !to_restart.contains(&s.window_label)) {
+                if processes.remove(&sidecar.window_label).is_some_and(|p| p.connection != ConnectionState::Dead) {
+                    self.announce_connection(&sidecar.window_label, sidecar.ws_port, ConnectionState::Dead);
+                }
+                reserved.remove(&sidecar.ws_port);
+                println!("Reaped sidecar for window '{}' on port {} ({}, {})",
+                         sidecar.window_label, sidecar.ws_port, sidecar.reason, sidecar.exit_status);
+            }
+        }
+
+        for sidecar in dead.iter_mut().filter(|s| to_restart.contains(&s.window_label)) {
+            println!("Sidecar for window '{}' {} ({}), restarting it",
+                     sidecar.window_label, sidecar.reason, sidecar.exit_status);
+            match self.restart_sidecar(&sidecar.window_label).await {
+                Ok(ws_port) => sidecar.restarted_port = Some(ws_port),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
+        dead
+    }
+
+    fn snapshot(&self, window_label: &str, process: &mut SidecarProcess) -> SidecarStatus {
+        let running = match process.try_wait() {
+            Ok(Some(status)) => {
+                if process.last_error.is_none() {
+                    let stderr = process.logs.tail(LogStream::Stderr, CRASH_STDERR_LINES);
+                    process.last_error = Some(SidecarError::now(if stderr.is_empty() {
+                        format!("Sidecar exited ({})", status)
+                    } else {
+                        format!("Sidecar exited ({}):\n{}", status, stderr)
+                    }));
+                }
+                if process.connection != ConnectionState::Dead {
+                    process.connection = ConnectionState::Dead;
+                    self.announce_connection(window_label, process.ws_port, ConnectionState::Dead);
+                }
+                false
+            }
+            Ok(None) => true,
+            Err(e) => {
+                eprintln!("Failed to poll sidecar status: {}", e);
+                false
+            }
+        };
+
+        SidecarStatus {
+            window_label: window_label.to_string(),
+            pid: process.pid,
+            ws_port: process.ws_port,
+            running,
+            script: process.script.clone(),
+            last_error: process.last_error.clone(),
+            uptime_secs: process.started_at.elapsed().as_secs(),
+            restart_count: process.restarts,
+            paused: process.paused,
+            connection: process.connection,
+            last_close: process.last_close.clone(),
+            external: process.is_external(),
+        }
+    }
+
+    /// Recent output of every tracked sidecar
+    pub async fn logs(&self) -> Vec<SidecarLogs> {
+        self.processes.lock().await
+            .iter()
+            .map(|(label, process)| Self::buffered_logs(label, process))
+            .collect()
+    }
+
+    /// How a window's sidecar was last launched, including one that failed
+    /// to start
+    pub fn command_for(&self, window_label: &str) -> Option<SidecarCommand> {
+        self.invocations.lock().unwrap_or_else(|e| e.into_inner()).get(window_label).cloned()
+    }
+
+    /// Recent output of one sidecar
+    pub async fn logs_for(&self, window_label: &str) -> Option<SidecarLogs> {
+        let processes = self.processes.lock().await;
+        Some(Self::buffered_logs(window_label, processes.get(window_label)?))
+    }
+
+    fn buffered_logs(window_label: &str, process: &SidecarProcess) -> SidecarLogs {
+        SidecarLogs {
+            window_label: window_label.to_string(),
+            vault_path: process.vault_path.clone(),
+            output_capture: process.output_capture,
+            stdout: process.logs.text(LogStream::Stdout),
+            stderr: process.logs.text(LogStream::Stderr),
+        }
+    }
+
+    /// Where a new sidecar's stdout or stderr should go
+    fn output_stdio(capture: OutputCapture) -> Stdio {
+        match capture {
+            OutputCapture::Capture => Stdio::piped(),
+            OutputCapture::Inherit => Stdio::inherit(),
+            OutputCapture::Null => Stdio::null(),
+        }
+    }
+
+    /// Forget the last error recorded for a sidecar
+    pub async fn clear_error(&self, window_label: &str) -> Result<()> {
+        self.processes.lock().await
+            .get_mut(window_label)
+            .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))?
+            .last_error = None;
+        Ok(())
+    }
+
+    /// Record a failure against a sidecar
+    async fn record_error(&self, window_label: &str, message: String) {
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.last_error = Some(SidecarError::now(message));
+        }
+    }
+
+    /// Move a tracked sidecar to `state`, announcing it if that is a change
+    async fn set_connection(&self, window_label: &str, state: ConnectionState) {
+        let mut processes = self.processes.lock().await;
+        let Some(process) = processes.get_mut(window_label) else { return };
+        if process.connection != state {
+            process.connection = state;
+            self.announce_connection(window_label, process.ws_port, state);
+        }
+    }
+
+    /// A connection to a sidecar failed: it is dead if its process has
+    /// exited, otherwise the next command will try again
+    async fn connection_failed(&self, window_label: &str) {
+        let exited = match self.processes.lock().await.get_mut(window_label) {
+            Some(process) => !matches!(process.try_wait(), Ok(None)),
+            None => return,
+        };
+        let state = if exited { ConnectionState::Dead } else { ConnectionState::Reconnecting };
+        self.set_connection(window_label, state).await;
+    }
+
+    /// The sidecar closed a connection with a close frame. A clean close
+    /// means it is shutting down on purpose, so it is not retried; any other
+    /// code is treated like a dropped connection.
+    async fn connection_closed(&self, window_label: &str, code: u16, reason: &str) -> anyhow::Error {
+        let clean = CLEAN_CLOSE_CODES.contains(&code);
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.last_close = Some(ConnectionClose {
+                code,
+                reason: reason.to_string(),
+                clean,
+                timestamp: chrono::Utc::now(),
+            });
+        }
+        if clean {
+            println!("Sidecar for window '{}' closed the connection ({}: {})", window_label, code, reason);
+        } else {
+            eprintln!("Sidecar for window '{}' closed the connection abnormally ({}: {})", window_label, code, reason);
+            self.connection_failed(window_label).await;
+        }
+        anyhow!("Sidecar closed the connection ({}{})", code,
+                if reason.is_empty() { String::new() } else { format!(": {}", reason) })
+    }
+
+    fn announce_connection(&self, window_label: &str, ws_port: u16, state: ConnectionState) {
+        println!("Sidecar for window '{}' on port {}: {:?}", window_label, ws_port, state);
+        if let Some(sink) = &self.connection_sink {
+            let _ = sink.send(ConnectionChange { window_label: window_label.to_string(), ws_port, state });
+        }
+    }
+
+    /// Echo a sidecar output stream to the console and record it in the log buffer
+    fn capture_output<R: std::io::Read>(
+        source: R,
+        stream: LogStream,
+        logs: LogBuffer,
+        forward: Option<(u16, UnboundedSender<StderrReport>)>,
+    ) {
+        let mut reader = std::io::BufReader::new(source);
+        let mut coalescer = TracebackCoalescer::default();
+        let report = |text: String| {
+            if let Some((ws_port, sink)) = &forward {
+                // The receiver only goes away when the app is shutting down
+                let _ = sink.send(StderrReport { ws_port: *ws_port, text });
+            }
+        };
+
+        loop {
+            match read_capped_line(&mut reader, MAX_LINE_BYTES) {
+                Ok(Some(line)) => {
+                    match stream {
+                        LogStream::Stdout => println!("[Sidecar] {}", line),
+                        LogStream::Stderr => eprintln!("[Sidecar Error] {}", line),
+                    }
+                    if forward.is_some() {
+                        if let Some(block) = coalescer.push(&line) {
+                            report(block);
+                        }
+                    }
+                    logs.push(stream, line);
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    eprintln!("Failed to read sidecar output: {}", e);
+                    break;
+                }
+            }
+        }
+        if let Some(block) = coalescer.finish() {
+            report(block);
+        }
+    }
+
+    /// Watch a newly spawned child until its WebSocket accepts connections
+    /// (on `socket` if it has one, else `addr`), it exits, or `timeout` elapses
+    async fn watch_startup(
+        child: &mut Child,
+        addr: SocketAddr,
+        socket: Option<&Path>,
+        timeout: std::time::Duration,
+    ) -> Startup {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        while tokio::time::Instant::now() < deadline {
+            match child.try_wait() {
+                Ok(Some(status)) => return Startup::Exited(status),
+                Ok(None) => {}
+                Err(e) => return Startup::Unknown(e.to_string()),
+            }
+
+            if Self::accepts_connections(addr, socket).await {
+                return Startup::Ready;
+            }
+
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+
+        match child.try_wait() {
+            Ok(Some(status)) => Startup::Exited(status),
+            _ => Startup::TimedOut,
+        }
+    }
+
+    /// Path of the Unix socket a sidecar on `ws_port` should listen on for
+    /// commands, if `unix_socket` is set (or `required`) and a socket can be
+    /// used here
+    #[cfg(unix)]
+    fn socket_path(&self, ws_port: u16, required: bool) -> Option<PathBuf> {
+        use std::os::unix::fs::DirBuilderExt;
+
+        if !self.config.unix_socket && !required {
+            return None;
+        }
+        let dir = self.socket_dir.get_or_init(|| {
+            // Short, since socket paths are limited; 0700 so only this user can connect
+            let dir = std::env::temp_dir().join(format!("tailor-{}", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+            match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+                Ok(()) => Some(dir),
+                Err(e) => {
+                    eprintln!("Failed to create sidecar socket directory, using TCP: {}", e);
+                    None
+                }
+            }
+        });
+        let path = dir.as_ref()?.join(format!("{}.sock", ws_port));
+        if path.as_os_str().len() > MAX_SOCKET_PATH {
+            eprintln!("Socket path {} is too long, using TCP", path.display());
+            return None;
+        }
+        Some(path)
+    }
+
+    /// Unix sockets are not used on this platform
+    #[cfg(not(unix))]
+    fn socket_path(&self, _ws_port: u16, _required: bool) -> Option<PathBuf> {
+        None
+    }
+
+    /// How commands reach a sidecar: its socket if it has one, else its port
+    fn transport_for(&self, ws_port: u16, socket_path: Option<&Path>) -> WebSocketTransport {
+        #[cfg(unix)]
+        if let Some(path) = socket_path {
+            return WebSocketTransport::unix(path.to_path_buf()).with_max_message_size(self.config.max_response_bytes);
+        }
+        #[cfg(not(unix))]
+        let _ = socket_path;
+        WebSocketTransport::new(self.connect_addr(ws_port)).with_max_message_size(self.config.max_response_bytes)
+    }
+
+    /// Readiness probe: whether a sidecar's WebSocket socket, or else its
+    /// port, accepts connections. The sidecar opens its socket first.
+    async fn accepts_connections(addr: SocketAddr, socket: Option<&Path>) -> bool {
+        #[cfg(unix)]
+        if let Some(path) = socket {
+            return tokio::net::UnixStream::connect(path).await.is_ok();
+        }
+        #[cfg(not(unix))]
+        let _ = socket;
+        tokio::net::TcpStream::connect(addr).await.is_ok()
+    }
+
+    /// Address to reach a sidecar on. A sidecar listening on every interface
+    /// is reached over the matching loopback address.
+    pub fn connect_addr(&self, ws_port: u16) -> SocketAddr {
+        let host = match self.config.ws_host {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            host => host,
+        };
+        SocketAddr::new(host, ws_port)
+    }
+
+    /// Wait until the window's sidecar is tracked and accepting connections.
+    /// Returns at once if it already is; a window whose sidecar is still
+    /// being spawned is waited on too.
+    pub async fn wait_until_ready(
+        &self,
+        window_label: &str,
+        timeout: std::time::Duration,
+    ) -> std::result::Result<(), TailorError> {
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        loop {
+            let tracked = match self.processes.lock().await.get_mut(window_label) {
+                Some(process) => {
+                    if let Ok(Some(status)) = process.try_wait() {
+                        return Err(TailorError::SidecarUnreachable {
+                            message: format!("Sidecar exited ({})", status),
+                        });
+                    }
+                    Some(process.ws_port)
+                }
+                None => None,
+            };
+            if let Some(ws_port) = tracked {
+                if Self::accepts_connections(self.connect_addr(ws_port), None).await {
+                    return Ok(());
+                }
+            }
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(TailorError::Timeout {
+                    message: format!("sidecar for window '{}' not ready after {}ms",
+                                     window_label, timeout.as_millis()),
+                });
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+    }
+
+    /// Allocate the next port that is neither held by a tracked or starting
+    /// sidecar nor bound by anything else on the machine
+    async fn allocate_port(&self) -> u16 {
+        let mut port = self.next_port.lock().await;
+        let mut in_use: HashSet<u16> = self.processes.lock().await
+            .values()
+            .map(|p| p.ws_port)
+            .collect();
+        let mut reserved = self.reserved_ports.lock().await;
+        in_use.extend(reserved.iter().copied());
+
+        // Try to find an available port starting from current port
+        loop {
+            let candidate = *port;
+            *port += 1;
+
+            // Wrap around if we exceed reasonable ports
+            if *port > LAST_PORT {
+                *port = FIRST_PORT;
+            }
+
+            if !in_use.contains(&candidate) && Self::is_port_available(self.config.ws_host, candidate) {
+                reserved.insert(candidate);
+                return candidate;
+            }
+        }
+    }
+    
+    /// Find whatever is listening on a sidecar port that is not one of ours,
+    /// e.g. a sidecar orphaned by a crash, and with `expected_pid` stop it so
+    /// the port can be allocated again. Without it only the owner is
+    /// reported; the PID from that report confirms the stop, which is refused
+    /// if the port has changed hands since. Ports of tracked sidecars, and
+    /// this process, are refused.
+    pub async fn reclaim_port(&self, port: u16, expected_pid: Option<u32>) -> Result<PortReclaim> {
+        if !(FIRST_PORT..=LAST_PORT).contains(&port) {
+            anyhow::bail!("Port {} is outside the sidecar range {}-{}", port, FIRST_PORT, LAST_PORT);
+        }
+        if let Some((label, _)) = self.processes.lock().await.iter().find(|(_, p)| p.ws_port == port) {
+            anyhow::bail!("Port {} belongs to the sidecar for window '{}'; restart or close it instead", port, label);
+        }
+        if self.reserved_ports.lock().await.contains(&port) {
+            anyhow::bail!("Port {} is being given to a sidecar that is starting", port);
+        }
+
+        let owner = tokio::task::spawn_blocking(move || port_owner::find(port)).await??;
+        let Some(owner) = owner else {
+            return Ok(PortReclaim { port, owner: None, reclaimed: false });
+        };
+        if expected_pid.is_some_and(|pid| pid != owner.pid) {
+            anyhow::bail!("Port {} is now held by PID {}, not PID {}; check the new owner before stopping it",
+                          port, owner.pid, expected_pid.unwrap_or_default());
+        }
+        if owner.pid == std::process::id() {
+            anyhow::bail!("Port {} is held by Tailor itself", port);
+        }
+        if expected_pid.is_none() {
+            return Ok(PortReclaim { port, owner: Some(owner), reclaimed: false });
+        }
+
+        println!("Stopping {} (PID {}) to reclaim port {}",
+                 owner.name.as_deref().unwrap_or("unknown process"), owner.pid, port);
+        let pid = owner.pid;
+        tokio::task::spawn_blocking(move || port_owner::terminate(pid)).await??;
+
+        // The socket can outlive the process briefly
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(2);
+        while !Self::is_port_available(self.config.ws_host, port) && std::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        }
+        let reclaimed = Self::is_port_available(self.config.ws_host, port);
+        Ok(PortReclaim { port, owner: Some(owner), reclaimed })
+    }
+
+    /// Check if a port is free on the interface sidecars listen on
+    fn is_port_available(host: IpAddr, port: u16) -> bool {
+        use std::net::TcpListener;
+        TcpListener::bind((host, port)).is_ok()
+    }
+
+    /// The vault's own sidecar entrypoint, if it ships one
+    fn vault_sidecar_script(vault_path: &str) -> Option<PathBuf> {
+        let script = Path::new(vault_path).join(VAULT_SIDECAR_SCRIPT);
+        script.is_file().then_some(script)
+    }
+
+    /// PYTHONPATH with `root` ahead of any entries already set
+    fn python_path(root: &Path) -> Result<std::ffi::OsString> {
+        let existing = std::env::var_os("PYTHONPATH").unwrap_or_default();
+        let paths = std::iter::once(root.to_path_buf())
+            .chain(std::env::split_paths(&existing));
+        std::env::join_paths(paths).context("Invalid PYTHONPATH")
+    }
+
+    /// Get Python executable path
+    pub fn get_python_executable() -> Result<String> {
+        // Try to find Python in PATH
+        #[cfg(target_os = "windows")]
+        let python_candidates = vec!["python.exe", "python3.exe"];
+        
+        #[cfg(not(target_os = "windows"))]
+        let python_candidates = vec!["python3", "python"];
+
+        for candidate in python_candidates {
+            if let Ok(output) = Command::new(candidate)
+                .arg("--version")
+                .output()
+            {
+                if output.status.success() {
+                    return Ok(candidate.to_string());
+                }
+            }
+        }
+
+        anyhow::bail!("Python not found in PATH")
+    }
+
+    /// Send a command to the sidecar via WebSocket
+    pub async fn send_command(
+        &self,
+        window_label: &str,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value> {
+        let request = self.build_request(method, params);
+        let request_id = request["id"].clone();
+
+        let response = self.exchange(window_label, method, &request, None, |response| {
+            Self::is_response_to(response, &request_id)
+        }).await?;
+
+        Ok(response)
+    }
+
+    /// List vault entries through the sidecar, answering repeated requests
+    /// from a cache that `file-changed` events clear (the sidecar sends one
+    /// on each tick that finds the vault changed). Returns the JSON-RPC
+    /// response; error responses are not cached.
+    pub async fn list_entries(&self, window_label: &str, params: serde_json::Value) -> Result<serde_json::Value> {
+        let key = params.to_string();
+        let generation = {
+            let processes = self.processes.lock().await;
+            let process = processes.get(window_label)
+                .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))?;
+            if let Some(cached) = process.entries_cache.get(&key) {
+                return Ok(cached.clone());
+            }
+            process.entries_generation
+        };
+
+        let response = self.send_command(window_label, LIST_ENTRIES_METHOD, params).await?;
+        if response.get("error").is_none() {
+            if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+                if process.entries_generation == generation {
+                    process.entries_cache.insert(key, response.clone());
+                }
+            }
+        }
+        Ok(response)
+    }
+
+    /// Forget cached vault listings for a window's sidecar
+    pub async fn invalidate_entries(&self, window_label: &str) {
+        if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+            process.entries_cache.clear();
+            process.entries_generation += 1;
+        }
+    }
+
+    /// Whether a sidecar message is a `file-changed` event notification
+    fn is_file_changed_event(message: &serde_json::Value) -> bool {
+        message.get("method").and_then(|m| m.as_str()) == Some("trigger_event")
+            && message.pointer("/params/event_type").and_then(|t| t.as_str()) == Some(FILE_CHANGED_EVENT)
+    }
+
+    /// Send a command whose handler streams its results. Each `stream.chunk`
+    /// notification tagged with the request id is passed to `on_chunk`; the
+    /// final response is returned once the sidecar sends it.
+    pub async fn send_streaming(
+        &self,
+        window_label: &str,
+        method: &str,
+        params: serde_json::Value,
+        mut on_chunk: impl FnMut(serde_json::Value) + Send,
+    ) -> Result<serde_json::Value> {
+        let mut request = self.build_request(method, params);
+        // Extension field: the sidecar sends partial results before the response
+        request["stream"] = true.into();
+        let request_id = request["id"].clone();
+
+        self.exchange(window_label, method, &request, None, |message| {
+            if message.get("method").and_then(|m| m.as_str()) == Some(STREAM_CHUNK_METHOD)
+                && message["params"]["id"] == request_id
+            {
+                on_chunk(message["params"]["chunk"].clone());
+                return false;
+            }
+            Self::is_response_to(message, &request_id)
+        }).await
+    }
+
+    /// Send a command whose handler streams raw bytes, e.g. an export. Each
+    /// binary chunk tagged with the request id is passed to `on_data`, which
+    /// may fail the command; the final response is returned once the sidecar
+    /// sends it.
+    pub async fn send_binary_stream(
+        &self,
+        window_label: &str,
+        method: &str,
+        params: serde_json::Value,
+        mut on_data: impl FnMut(&[u8]) -> Result<()> + Send,
+    ) -> Result<serde_json::Value> {
+        let mut request = self.build_request(method, params);
+        // Extension fields: bytes chunks arrive as tagged binary frames
+        request["stream"] = true.into();
+        request["binary"] = true.into();
+        let request_id = request["id"].clone();
+
+        let mut on_chunk = |id: &str, data: &[u8]| {
+            if request_id.as_str() == Some(id) {
+                on_data(data)?;
+            }
+            Ok(())
+        };
+        self.exchange(window_label, method, &request, Some(&mut on_chunk), |message| {
+            Self::is_response_to(message, &request_id)
+        }).await
+    }
+
+    /// Split a tagged binary chunk frame into its request id and data
+    fn parse_binary_chunk(frame: &[u8]) -> Option<(&str, &[u8])> {
+        let (&BINARY_CHUNK_TAG, rest) = frame.split_first()? else {
+            return None;
+        };
+        let id_len = u16::from_be_bytes([*rest.first()?, *rest.get(1)?]) as usize;
+        let rest = &rest[2..];
+        if rest.len() < id_len {
+            return None;
+        }
+        let (id, data) = rest.split_at(id_len);
+        Some((std::str::from_utf8(id).ok()?, data))
+    }
+
+    /// Send several commands as one JSON-RPC batch frame. Each command is an
+    /// object with `method` and optional `params`; responses are returned in
+    /// the same order as the commands.
+    pub async fn send_batch(
+        &self,
+        window_label: &str,
+        commands: Vec<serde_json::Value>,
+    ) -> Result<Vec<serde_json::Value>> {
+        if commands.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut requests = Vec::with_capacity(commands.len());
+        for command in commands {
+            let method = command.get("method")
+                .and_then(|m| m.as_str())
+                .ok_or_else(|| anyhow!("Batch command is missing a method"))?;
+            let params = command.get("params").cloned().unwrap_or_else(|| serde_json::json!({}));
+            requests.push(self.build_request(method, params));
+        }
+        let ids: Vec<serde_json::Value> = requests.iter().map(|r| r["id"].clone()).collect();
+
+        let response = self.exchange(
+            window_label,
+            "batch",
+            &serde_json::Value::Array(requests),
+            None,
+            |response| response.is_array(),
+        ).await?;
+
+        // Responses in a batch may arrive in any order
+        let mut by_id: HashMap<String, serde_json::Value> = HashMap::new();
+        if let serde_json::Value::Array(responses) = response {
+            for response in responses {
+                if let Some(id) = response.get("id").and_then(|id| id.as_str()) {
+                    by_id.insert(id.to_string(), response);
+                }
+            }
+        }
+
+        ids.iter()
+            .map(|id| {
+                let id = id.as_str().unwrap_or_default();
+                by_id.remove(id).ok_or_else(|| anyhow!("No response for batch request {}", id))
+            })
+            .collect()
+    }
+
+    /// Build a JSON-RPC request with a fresh id
+    fn build_request(&self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let mut request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": uuid::Uuid::new_v4().to_string()
+        });
+        if self.config.compression {
+            // Extension field: responses above this size come back zlib-compressed.
+            // tungstenite 0.24 cannot negotiate permessage-deflate, so the
+            // sidecar compresses whole frames instead.
+            request["compress"] = self.config.compression_threshold.into();
+        }
+        request
+    }
+
+    /// Send a request on a fresh connection and wait for the first message
+    /// accepted by `is_response`, bounded by the configured command timeout.
+    /// `is_response` sees every message in order, so it may also consume
+    /// intermediate ones. Tagged binary chunks go to `on_data`, and are
+    /// ignored without it. `host.*` requests the sidecar makes meanwhile are
+    /// answered on the same connection and count toward the timeout.
+    async fn exchange(
+        &self,
+        window_label: &str,
+        description: &str,
+        request: &serde_json::Value,
+        on_data: Option<&mut DataHandler<'_>>,
+        is_response: impl FnMut(&serde_json::Value) -> bool,
+    ) -> Result<serde_json::Value> {
+        self.await_restart(window_label).await?;
+        let result = self.exchange_inner(window_label, description, request, on_data, is_response).await;
+
+        match &result {
+            Err(e) => self.record_error(window_label, format!("{}: {}", description, e)).await,
+            Ok(response) => {
+                // It answered, so any earlier close was not it going away
+                if let Some(process) = self.processes.lock().await.get_mut(window_label) {
+                    process.last_close = None;
+                }
+                let responses = response.as_array().map_or(std::slice::from_ref(response), Vec::as_slice);
+                if let Some(error) = responses.iter().rev().find_map(Self::rpc_error_message) {
+                    self.record_error(window_label, error).await;
+                }
+            }
+        }
+        result
+    }
+
+    /// The message of a JSON-RPC error response
+    fn rpc_error_message(response: &serde_json::Value) -> Option<String> {
+        let error = response.get("error")?;
+        Some(error.get("message")
+            .and_then(|m| m.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| error.to_string()))
+    }
+
+    /// Run an exchange under `command_timeout_ms`, which stops counting
+    /// while a host request waits on the user (e.g. in a save dialog)
+    async fn exchange_inner(
+        &self,
+        window_label: &str,
+        description: &str,
+        request: &serde_json::Value,
+        mut on_data: Option<&mut DataHandler<'_>>,
+        mut is_response: impl FnMut(&serde_json::Value) -> bool,
+    ) -> Result<serde_json::Value> {
+        let timeout = self.config.command_timeout_ms.map(std::time::Duration::from_millis);
+        let mut deadline = timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+        let timed_out = || anyhow!("Sidecar did not respond to '{}' within {}ms",
+                                   description, self.config.command_timeout_ms.unwrap_or_default());
+
+        // 1. Get transport
+        let transport = self.processes.lock().await
+            .get(window_label)
+            .map(|p| p.transport.clone())
+            .ok_or_else(|| anyhow!("Sidecar not found for window: {}", window_label))?;
+
+        // 2. Refuse oversized requests before touching the connection
+        let request_text = serde_json::to_string(request)?;
+        if request_text.len() > self.config.max_request_bytes {
+            return Err(TailorError::PayloadTooLarge {
+                size: request_text.len(),
+                limit: self.config.max_request_bytes,
+            }.into());
+        }
+
+        // 3. Connect and send request
+        let connected = before_deadline(deadline, transport.connect()).await.ok_or_else(timed_out)?;
+        let mut connection = match connected {
+            Ok(connection) => {
+                self.set_connection(window_label, ConnectionState::Ready).await;
+                connection
+            }
+            Err(e) => {
+                self.connection_failed(window_label).await;
+                return Err(e);
+            }
+        };
+        before_deadline(deadline, connection.send(request_text)).await.ok_or_else(timed_out)??;
+        for _ in 0..request.as_array().map_or(1, Vec::len) {
+            Metrics::incr(&self.metrics.commands_sent);
+        }
+
+        // 4. Await Response
+        while let Some(frame) = before_deadline(deadline, connection.recv()).await.ok_or_else(timed_out)? {
+            let text = match frame? {
+                Frame::Text(text) => text,
+                Frame::Binary(data) if data.first() == Some(&BINARY_CHUNK_TAG) => {
+                    let (id, chunk) = Self::parse_binary_chunk(&data)
+                        .ok_or_else(|| anyhow!("Malformed binary chunk from sidecar"))?;
+                    match on_data.as_mut() {
+                        Some(on_data) => on_data(id, chunk)?,
+                        None => eprintln!("Ignoring {} byte binary chunk for '{}'", chunk.len(), description),
+                    }
+                    continue;
+                }
+                Frame::Binary(compressed) => Self::decompress(&compressed, self.config.max_response_bytes)?,
+                Frame::Close { code, reason } => {
+                    return Err(self.connection_closed(window_label, code, &reason).await);
+                }
+            };
+
+            let response: serde_json::Value = serde_json::from_str(&text)
+                .context("Failed to parse sidecar response")?;
+
+            // Events the sidecar happens to send on this connection
+            if Self::is_file_changed_event(&response) {
+                self.invalidate_entries(window_label).await;
+            }
+
+            // The sidecar needs something from the host to finish the command
+            if Self::is_host_request(&response) {
+                let asked = tokio::time::Instant::now();
+                let reply = self.answer_host_request(window_label, response).await;
+                deadline = deadline.map(|deadline| deadline + asked.elapsed());
+                connection.send(serde_json::to_string(&reply)?).await?;
+                continue;
+            }
+
+            // Other notifications, which may carry the request's id, are
+            // forwarded; stream chunks are left to `is_response`
+            if Self::is_notification(&response)
+                && response["method"].as_str() != Some(STREAM_CHUNK_METHOD)
+            {
+                match &self.notification_sink {
+                    Some(sink) => {
+                        let _ = sink.send(SidecarNotification { window_label: window_label.to_string(), message: response });
+                    }
+                    None => println!("Dropping sidecar notification '{}' sent during '{}'", response["method"], description),
+                }
+                continue;
+            }
+
+            if is_response(&response) {
+                 return Ok(response);
+            }
+        }
 
         Err(anyhow!("Connection closed without valid response"))
     }
+
+    /// Whether a message is the JSON-RPC response to request `id`: it has a
+    /// `result` or `error` and, unlike a notification, no `method`
+    fn is_response_to(message: &serde_json::Value, id: &serde_json::Value) -> bool {
+        message.get("method").is_none()
+            && (message.get("result").is_some() || message.get("error").is_some())
+            && message.get("id") == Some(id)
+    }
+
+    /// Whether a sidecar message is a request or notification rather than a response
+    fn is_notification(message: &serde_json::Value) -> bool {
+        message.get("method").is_some_and(|m| m.is_string())
+    }
+
+    /// Whether a sidecar message is a `host.*` request awaiting a response
+    fn is_host_request(message: &serde_json::Value) -> bool {
+        message.get("method")
+            .and_then(|m| m.as_str())
+            .is_some_and(|m| m.starts_with(HOST_METHOD_PREFIX))
+            && message.get("id").is_some_and(|id| !id.is_null())
+    }
+
+    /// Hand a `host.*` request to the host request sink and wait for its
+    /// JSON-RPC response
+    async fn answer_host_request(&self, window_label: &str, request: serde_json::Value) -> serde_json::Value {
+        let id = request["id"].clone();
+        let method = request["method"].as_str().unwrap_or_default().to_string();
+        println!("Sidecar for '{}' requested '{}' of the host", window_label, method);
+
+        let error = |code: i64, message: String| serde_json::json!({
+            "jsonrpc": "2.0", "error": { "code": code, "message": message }, "id": id,
+        });
+        let Some(sink) = &self.host_request_sink else {
+            return error(-32601, format!("Host does not accept requests: {}", method));
+        };
+        let (reply, response) = oneshot::channel();
+        let call = HostCall { window_label: window_label.to_string(), request, reply };
+        if sink.send(call).is_err() {
+            return error(-32603, "Host is shutting down".to_string());
+        }
+        response.await.unwrap_or_else(|_| error(-32603, format!("Host dropped request: {}", method)))
+    }
+
+    /// Inflate a zlib-compressed response frame, giving up once it inflates
+    /// past `limit` bytes
+    fn decompress(data: &[u8], limit: usize) -> Result<String> {
+        use std::io::Read;
+        let mut text = String::new();
+        flate2::read::ZlibDecoder::new(data)
+            .take(limit as u64 + 1)
+            .read_to_string(&mut text)
+            .context("Failed to decompress sidecar response")?;
+        if text.len() > limit {
+            return Err(TailorError::PayloadTooLarge { size: text.len(), limit }.into());
+        }
+        Ok(text)
+    }
 }
 
 impl Drop for SidecarManager {
@@ -278,9 +2270,23 @@ impl Drop for SidecarManager {
     }
 }
 
+/// The canonical spelling of a sidecar log level, matched case-insensitively
+pub fn normalize_log_level(level: &str) -> Option<&'static str> {
+    LOG_LEVELS.iter().copied().find(|known| known.eq_ignore_ascii_case(level.trim()))
+}
+
+/// Run `future` until `deadline`, if there is one. None if it ran out.
+async fn before_deadline<T>(deadline: Option<tokio::time::Instant>, future: impl std::future::Future<Output = T>) -> Option<T> {
+    match deadline {
+        Some(deadline) => tokio::time::timeout_at(deadline, future).await.ok(),
+        None => Some(future.await),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sidecar_transport::MemoryTransport;
 
     #[test]
     fn test_is_port_available() {
@@ -289,19 +2295,869 @@ mod tests {
         let port = listener.local_addr().unwrap().port();
         
         // Port should be unavailable because we bound to it
-        assert!(!SidecarManager::is_port_available(port));
+        assert!(!SidecarManager::is_port_available(Ipv4Addr::LOCALHOST.into(), port));
         
         // Drop the listener to free the port
         drop(listener);
         
         // Now it should be available
-        assert!(SidecarManager::is_port_available(port));
+        assert!(SidecarManager::is_port_available(Ipv4Addr::LOCALHOST.into(), port));
+    }
+
+    #[test]
+    fn test_connect_addr_uses_loopback_for_unspecified_host() {
+        let manager = |ws_host: IpAddr| SidecarManager::new(Arc::default(), SidecarConfig { ws_host, ..SidecarConfig::default() });
+
+        assert_eq!(SidecarManager::default().connect_addr(9000).to_string(), "127.0.0.1:9000");
+        assert_eq!(manager(Ipv4Addr::UNSPECIFIED.into()).connect_addr(9000).to_string(), "127.0.0.1:9000");
+        assert_eq!(manager(Ipv6Addr::UNSPECIFIED.into()).connect_addr(9000).to_string(), "[::1]:9000");
+        assert_eq!(manager("::1".parse().unwrap()).connect_addr(9000).to_string(), "[::1]:9000");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_socket_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let manager = SidecarManager::default();
+        assert!(manager.socket_path(9000, false).is_none());
+        // A sandboxed sidecar without a network needs one either way
+        let required = manager.socket_path(9000, true).unwrap();
+        std::fs::remove_dir(required.parent().unwrap()).unwrap();
+
+        let manager = SidecarManager::new(Arc::default(), SidecarConfig { unix_socket: true, ..SidecarConfig::default() });
+        let path = manager.socket_path(9000, false).unwrap();
+        assert_eq!(path.file_name().unwrap(), "9000.sock");
+        let dir = path.parent().unwrap();
+        assert_eq!(std::fs::metadata(dir).unwrap().permissions().mode() & 0o777, 0o700);
+        // Every sidecar's socket shares the directory
+        assert_eq!(manager.socket_path(9001, false).unwrap().parent(), Some(dir));
+        std::fs::remove_dir(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watch_startup_reports_early_exit() {
+        #[cfg(target_os = "windows")]
+        let mut child = Command::new("cmd").args(["/C", "exit 3"]).spawn().unwrap();
+        #[cfg(not(target_os = "windows"))]
+        let mut child = Command::new("sh").args(["-c", "exit 3"]).spawn().unwrap();
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let startup = SidecarManager::watch_startup(&mut child, addr, None, std::time::Duration::from_secs(5)).await;
+        match startup {
+            Startup::Exited(status) => assert_eq!(status.code(), Some(3)),
+            other => panic!("expected early exit, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_startup_times_out() {
+        #[cfg(target_os = "windows")]
+        let mut child = Command::new("cmd").args(["/C", "ping -n 6 127.0.0.1 >nul"]).spawn().unwrap();
+        #[cfg(not(target_os = "windows"))]
+        let mut child = Command::new("sh").args(["-c", "sleep 5"]).spawn().unwrap();
+
+        let listener = std::net::TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let startup = SidecarManager::watch_startup(&mut child, addr, None, std::time::Duration::from_millis(300)).await;
+        assert_eq!(startup, Startup::TimedOut);
+        child.kill().unwrap();
+        child.wait().unwrap();
     }
 
     #[tokio::test]
     async fn test_manager_default_state() {
-        let manager = SidecarManager::new();
+        let manager = SidecarManager::default();
         assert_eq!(*manager.next_port.lock().await, 9000);
         assert!(manager.processes.lock().await.is_empty());
     }
+
+    #[test]
+    fn test_decompress_response() {
+        use std::io::Write;
+        let payload = r#"{"jsonrpc":"2.0","result":{"ok":true},"id":"1"}"#;
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(payload.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(SidecarManager::decompress(&compressed, 1024).unwrap(), payload);
+        assert!(SidecarManager::decompress(b"not zlib", 1024).is_err());
+
+        let err = SidecarManager::decompress(&compressed, 10).unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(TailorError::PayloadTooLarge { limit: 10, .. })));
+    }
+
+    #[test]
+    fn test_python_path_starts_with_root() {
+        let root = Path::new("/opt/tailor");
+        let path = SidecarManager::python_path(root).unwrap();
+        assert_eq!(std::env::split_paths(&path).next(), Some(root.to_path_buf()));
+    }
+
+    #[test]
+    fn test_vault_sidecar_script() {
+        let vault = std::env::temp_dir().join(format!("tailor_script_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(vault.join("plugins")).unwrap();
+        let vault_path = vault.to_string_lossy().to_string();
+        assert_eq!(SidecarManager::vault_sidecar_script(&vault_path), None);
+
+        std::fs::write(vault.join("plugins").join("server.py"), "").unwrap();
+        assert_eq!(
+            SidecarManager::vault_sidecar_script(&vault_path),
+            Some(vault.join("plugins").join("server.py"))
+        );
+
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[test]
+    fn test_rpc_error_message() {
+        let failed = serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": "1"});
+        let ok = serde_json::json!({"jsonrpc": "2.0", "result": {}, "id": "2"});
+        assert_eq!(SidecarManager::rpc_error_message(&failed).as_deref(), Some("Method not found"));
+        assert_eq!(SidecarManager::rpc_error_message(&ok), None);
+    }
+
+    /// Track a sidecar for `label` whose commands are answered in memory
+    async fn track_memory_sidecar(manager: &SidecarManager, label: &str, transport: MemoryTransport) {
+        #[cfg(target_os = "windows")]
+        let child = Command::new("cmd").args(["/C", "ping -n 30 127.0.0.1 >nul"]).spawn().unwrap();
+        #[cfg(not(target_os = "windows"))]
+        let child = Command::new("sh").args(["-c", "sleep 30"]).spawn().unwrap();
+
+        manager.processes.lock().await.insert(label.to_string(), SidecarProcess {
+            pid: child.id(),
+            child: Some(child),
+            vault_path: "/tmp/vault".to_string(),
+            ws_port: 0,
+            transport: Arc::new(transport),
+            script: None,
+            logs: LogBuffer::default(),
+            output_capture: OutputCapture::Capture,
+            last_error: None,
+            started_at: std::time::Instant::now(),
+            restarts: 0,
+            auto_restarts: Vec::new(),
+            capabilities: None,
+            entries_cache: HashMap::new(),
+            entries_generation: 0,
+            memory_limit: None,
+            log_level: None,
+            oom_reported: false,
+            paused: false,
+            connection: ConnectionState::Connecting,
+            socket: None,
+            last_close: None,
+            sandbox: None,
+            port_forward: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_binary_chunk() {
+        let mut frame = vec![BINARY_CHUNK_TAG, 0, 2];
+        frame.extend_from_slice(b"r1PK\x03\x04");
+        assert_eq!(SidecarManager::parse_binary_chunk(&frame), Some(("r1", &b"PK\x03\x04"[..])));
+        // Id longer than the frame, and a compressed response
+        assert_eq!(SidecarManager::parse_binary_chunk(&[BINARY_CHUNK_TAG, 0, 9, b'r']), None);
+        assert_eq!(SidecarManager::parse_binary_chunk(&[0x78, 0x9c, 1]), None);
+    }
+
+    #[test]
+    fn test_capture_command_redacts_secrets() {
+        let mut command = Command::new("python3");
+        command.args(["-u", "-m", "sidecar", "--ws-port", "9000"])
+            .current_dir("/vaults/notes")
+            .env("PYTHONPATH", "/app")
+            .env("OPENAI_API_KEY", "sk-secret");
+        let secrets = HashSet::from(["OPENAI_API_KEY".to_string()]);
+        let captured = SidecarCommand::capture(&command, &secrets);
+        assert_eq!(captured.program, "python3");
+        assert_eq!(captured.args, ["-u", "-m", "sidecar", "--ws-port", "9000"]);
+        assert_eq!(captured.env["PYTHONPATH"], "/app");
+        assert_eq!(captured.env["OPENAI_API_KEY"], REDACTED);
+        assert_eq!(captured.cwd.as_deref(), Some("/vaults/notes"));
+    }
+
+    #[tokio::test]
+    async fn test_send_command_over_memory_transport() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| match request["method"].as_str() {
+            Some("echo") => serde_json::json!({"jsonrpc": "2.0", "result": request["params"], "id": request["id"]}),
+            _ => serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": request["id"]}),
+        })).await;
+
+        let response = manager.send_command("w1", "echo", serde_json::json!({"n": 1})).await.unwrap();
+        assert_eq!(response["result"]["n"], 1);
+
+        manager.send_command("w1", "missing", serde_json::json!({})).await.unwrap();
+        let status = manager.status("w1").await.unwrap();
+        assert_eq!(status.last_error.unwrap().message, "Method not found");
+        assert_eq!(manager.metrics.snapshot().commands_sent, 2);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_commands_wait_for_restart() {
+        let manager = Arc::new(SidecarManager::default());
+        let restart = {
+            let manager = manager.clone();
+            tokio::spawn(async move {
+                manager.restarting.run("w1", async {
+                    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+                    track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+                        serde_json::json!({"jsonrpc": "2.0", "result": "restarted", "id": request["id"]})
+                    })).await;
+                    Ok(9001)
+                }).await
+            })
+        };
+
+        // Sent while the sidecar is not tracked, so it only succeeds by waiting
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let response = manager.send_command("w1", "echo", serde_json::json!({})).await.unwrap();
+        assert_eq!(response["result"], "restarted");
+        assert_eq!(restart.await.unwrap(), Ok(9001));
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_log_level() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            assert_eq!(request["method"], SET_LOG_LEVEL_METHOD);
+            serde_json::json!({"jsonrpc": "2.0", "result": {"status": "success", "level": request["params"]["level"]}, "id": request["id"]})
+        })).await;
+
+        assert!(manager.set_log_level("w1", "verbose").await.is_err());
+        manager.set_log_level("w1", "debug").await.unwrap();
+        assert_eq!(manager.processes.lock().await["w1"].log_level.as_deref(), Some("DEBUG"));
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rotate_log() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            assert_eq!(request["method"], ROTATE_LOG_METHOD);
+            serde_json::json!({"jsonrpc": "2.0", "result": {
+                "status": "success", "log_file": "/v/.tailor/logs/sidecar.log", "rotated_to": "/v/.tailor/logs/sidecar.1.log",
+            }, "id": request["id"]})
+        })).await;
+        track_memory_sidecar(&manager, "w2", MemoryTransport::new(|request| {
+            serde_json::json!({"jsonrpc": "2.0", "result": {"status": "error", "error": "File logging is disabled"}, "id": request["id"]})
+        })).await;
+
+        assert_eq!(manager.rotate_log("w1").await.unwrap(), "/v/.tailor/logs/sidecar.log");
+        let error = manager.rotate_log("w2").await.unwrap_err();
+        assert!(error.to_string().contains("File logging is disabled"));
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+        manager.terminate_sidecar("w2", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reclaim_port_refusals() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            serde_json::json!({"jsonrpc": "2.0", "result": {}, "id": request["id"]})
+        })).await;
+        manager.processes.lock().await.get_mut("w1").unwrap().ws_port = 9100;
+
+        assert!(manager.reclaim_port(80, Some(1)).await.unwrap_err().to_string().contains("outside"));
+        assert!(manager.reclaim_port(9100, Some(1)).await.unwrap_err().to_string().contains("'w1'"));
+
+        // A port held by this process is never killed, even when confirmed
+        let held = (9101..=LAST_PORT)
+            .find_map(|port| std::net::TcpListener::bind(("127.0.0.1", port)).ok())
+            .unwrap();
+        let port = held.local_addr().unwrap().port();
+        let pid = std::process::id();
+        // Stopping is refused once the port is held by another process than the one reported
+        let err = manager.reclaim_port(port, Some(pid + 1)).await.unwrap_err().to_string();
+        assert!(err.contains(&format!("now held by PID {}", pid)), "{}", err);
+        assert!(manager.reclaim_port(port, Some(pid)).await.unwrap_err().to_string().contains("Tailor itself"));
+        drop(held);
+        assert_eq!(manager.reclaim_port(port, None).await.unwrap().owner, None);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_paused() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            assert!(request["method"] == PAUSE_METHOD || request["method"] == RESUME_METHOD);
+            serde_json::json!({"jsonrpc": "2.0", "result": {"status": "success"}, "id": request["id"]})
+        })).await;
+        track_memory_sidecar(&manager, "w2", MemoryTransport::new(|request| {
+            serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": request["id"]})
+        })).await;
+
+        manager.set_paused("w1", true).await.unwrap();
+        assert!(manager.status("w1").await.unwrap().paused);
+        manager.set_paused("w1", false).await.unwrap();
+        assert!(!manager.status("w1").await.unwrap().paused);
+        // A sidecar that does not take the signal is not marked paused
+        assert!(manager.set_paused("w2", true).await.is_err());
+        assert!(!manager.status("w2").await.unwrap().paused);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+        manager.terminate_sidecar("w2", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_before_close() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            assert_eq!(request["method"], BEFORE_CLOSE_METHOD);
+            serde_json::json!({"jsonrpc": "2.0", "result": {
+                "status": "success", "approve": false, "unsaved": ["Draft in chat 'Ideas'"],
+            }, "id": request["id"]})
+        })).await;
+        // Sidecars without the method do not block closing
+        track_memory_sidecar(&manager, "w2", MemoryTransport::new(|request| {
+            serde_json::json!({"jsonrpc": "2.0", "error": {"code": -32601, "message": "Method not found"}, "id": request["id"]})
+        })).await;
+
+        let check = manager.before_close("w1").await;
+        assert!(!check.approved);
+        assert_eq!(check.unsaved, ["Draft in chat 'Ideas'"]);
+        assert!(manager.before_close("w2").await.approved);
+        assert!(manager.before_close("missing").await.approved);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+        manager.terminate_sidecar("w2", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_notifications_interleaved_with_response() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let manager = SidecarManager::default().with_notification_sink(tx);
+        track_memory_sidecar(&manager, "w1", MemoryTransport::with_messages(|request| vec![
+            // Progress sharing the request's id must not resolve the command
+            serde_json::json!({"jsonrpc": "2.0", "method": "progress", "params": {"percentage": 50}, "id": request["id"]}),
+            serde_json::json!({"jsonrpc": "2.0", "method": "trigger_event", "params": {"event_type": "NOTIFY"}, "id": "evt_1"}),
+            serde_json::json!({"jsonrpc": "2.0", "result": {"done": true}, "id": request["id"]}),
+        ])).await;
+
+        let response = manager.send_command("w1", "index", serde_json::json!({})).await.unwrap();
+        assert_eq!(response["result"]["done"], true);
+        let forwarded: Vec<String> = std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|n| n.message["method"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(forwarded, ["progress", "trigger_event"]);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_batch_restores_order() {
+        let manager = SidecarManager::default();
+        // Answer the batch in reverse order
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            let responses: Vec<serde_json::Value> = request.as_array().unwrap()
+                .iter()
+                .rev()
+                .map(|r| serde_json::json!({"jsonrpc": "2.0", "result": r["method"], "id": r["id"]}))
+                .collect();
+            serde_json::Value::Array(responses)
+        })).await;
+
+        let commands = vec![
+            serde_json::json!({"method": "first"}),
+            serde_json::json!({"method": "second", "params": {}}),
+        ];
+        let responses = manager.send_batch("w1", commands).await.unwrap();
+        assert_eq!(responses[0]["result"], "first");
+        assert_eq!(responses[1]["result"], "second");
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_host_request_answered_mid_command() {
+        let (sink, mut calls) = tokio::sync::mpsc::unbounded_channel();
+        let manager = SidecarManager::default().with_host_request_sink(sink);
+        let command_id = Arc::new(std::sync::Mutex::new(serde_json::Value::Null));
+        track_memory_sidecar(&manager, "w1", MemoryTransport::with_messages(move |message| {
+            if message["method"] == "notes.export" {
+                *command_id.lock().unwrap() = message["id"].clone();
+                return vec![serde_json::json!({"jsonrpc": "2.0", "method": "host.save_dialog", "params": {}, "id": "h1"})];
+            }
+            // The host's response to the dialog request completes the command
+            assert_eq!(message["id"], "h1");
+            vec![serde_json::json!({"jsonrpc": "2.0", "result": {"saved_to": message["result"]}, "id": *command_id.lock().unwrap()})]
+        })).await;
+
+        tokio::spawn(async move {
+            let call: HostCall = calls.recv().await.unwrap();
+            assert_eq!(call.window_label, "w1");
+            assert_eq!(call.request["method"], "host.save_dialog");
+            let _ = call.reply.send(serde_json::json!({"jsonrpc": "2.0", "result": "/tmp/out.md", "id": call.request["id"]}));
+        });
+
+        let response = manager.send_command("w1", "notes.export", serde_json::json!({})).await.unwrap();
+        assert_eq!(response["result"]["saved_to"], "/tmp/out.md");
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_host_request_wait_pauses_timeout() {
+        let (sink, mut calls) = tokio::sync::mpsc::unbounded_channel();
+        let config = SidecarConfig { command_timeout_ms: Some(200), ..Default::default() };
+        let manager = SidecarManager::new(Arc::new(Metrics::default()), config).with_host_request_sink(sink);
+        let command_id = Arc::new(std::sync::Mutex::new(serde_json::Value::Null));
+        track_memory_sidecar(&manager, "w1", MemoryTransport::with_messages(move |message| {
+            if message["method"] == "notes.export" {
+                *command_id.lock().unwrap() = message["id"].clone();
+                return vec![serde_json::json!({"jsonrpc": "2.0", "method": "host.save_dialog", "params": {}, "id": "h1"})];
+            }
+            vec![serde_json::json!({"jsonrpc": "2.0", "result": message["result"], "id": *command_id.lock().unwrap()})]
+        })).await;
+
+        // The user takes longer over the dialog than the command may take
+        tokio::spawn(async move {
+            let call: HostCall = calls.recv().await.unwrap();
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            let _ = call.reply.send(serde_json::json!({"jsonrpc": "2.0", "result": "/tmp/out.md", "id": call.request["id"]}));
+        });
+
+        let response = manager.send_command("w1", "notes.export", serde_json::json!({})).await.unwrap();
+        assert_eq!(response["result"], "/tmp/out.md");
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_streaming_forwards_chunks() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::with_messages(|request| {
+            assert_eq!(request["stream"], true);
+            let id = request["id"].clone();
+            vec![
+                serde_json::json!({"jsonrpc": "2.0", "method": STREAM_CHUNK_METHOD, "params": {"id": "other", "chunk": 0}}),
+                serde_json::json!({"jsonrpc": "2.0", "method": STREAM_CHUNK_METHOD, "params": {"id": id, "chunk": 1}}),
+                serde_json::json!({"jsonrpc": "2.0", "method": STREAM_CHUNK_METHOD, "params": {"id": id, "chunk": 2}}),
+                serde_json::json!({"jsonrpc": "2.0", "result": {"chunks": 2}, "id": id}),
+            ]
+        })).await;
+
+        let mut chunks = Vec::new();
+        let response = manager
+            .send_streaming("w1", "notes.export", serde_json::json!({}), |chunk| chunks.push(chunk))
+            .await
+            .unwrap();
+        assert_eq!(chunks, vec![serde_json::json!(1), serde_json::json!(2)]);
+        assert_eq!(response["result"]["chunks"], 2);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_detach_leaves_process_running() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+
+        let pid = manager.detach_sidecar("w1").await.unwrap();
+        assert!(manager.get_ws_port("w1").await.is_none());
+        assert!(manager.detach_sidecar("w1").await.is_err());
+
+        // The process is still alive; clean it up
+        #[cfg(not(target_os = "windows"))]
+        assert!(Command::new("kill").arg(pid.to_string()).status().unwrap().success());
+        #[cfg(target_os = "windows")]
+        assert!(Command::new("taskkill").args(["/F", "/PID", &pid.to_string()]).status().unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn test_validate_port() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+
+        assert!(manager.validate_port("w1", 0).await.is_ok());
+        assert_eq!(
+            manager.validate_port("w1", 9002).await,
+            Err(TailorError::SidecarNotFound { window_label: "w1".to_string(), ws_port: 9002 })
+        );
+        assert!(manager.validate_port("w2", 0).await.is_err());
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_all_status() {
+        let manager = SidecarManager::default();
+        assert!(manager.all_status().await.is_empty());
+
+        track_memory_sidecar(&manager, "w2", MemoryTransport::new(|request| request)).await;
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+
+        let statuses = manager.all_status().await;
+        let labels: Vec<&str> = statuses.iter().map(|s| s.window_label.as_str()).collect();
+        assert_eq!(labels, vec!["w1", "w2"]);
+        assert!(statuses.iter().all(|s| s.running && s.restart_count == 0));
+        assert_eq!(manager.get_pid("w1").await, Some(statuses[0].pid));
+        assert_eq!(manager.get_pid("w3").await, None);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+        manager.terminate_sidecar("w2", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reap_dead_drops_exited_sidecars() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+        track_memory_sidecar(&manager, "w2", MemoryTransport::new(|request| request)).await;
+        assert!(manager.reap_dead().await.is_empty());
+
+        {
+            let mut processes = manager.processes.lock().await;
+            let child = processes.get_mut("w1").unwrap().child.as_mut().unwrap();
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+        let reaped = manager.reap_dead().await;
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].window_label, "w1");
+        assert_eq!(reaped[0].reason, ShutdownReason::Crashed);
+        assert!(reaped[0].restarted_port.is_none());
+        assert!(manager.status("w1").await.is_none());
+        assert!(manager.status("w2").await.is_some());
+
+        manager.terminate_sidecar("w2", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connection_state_changes() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let manager = SidecarManager::default().with_connection_sink(tx);
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| {
+            serde_json::json!({"jsonrpc": "2.0", "result": {}, "id": request["id"]})
+        })).await;
+        assert_eq!(manager.status("w1").await.unwrap().connection, ConnectionState::Connecting);
+
+        manager.send_command("w1", "ping", serde_json::json!({})).await.unwrap();
+        manager.send_command("w1", "ping", serde_json::json!({})).await.unwrap();
+        assert_eq!(rx.try_recv().unwrap().state, ConnectionState::Ready);
+        assert!(rx.try_recv().is_err(), "only changes are announced");
+
+        // Nothing listens on a freed port, but the process is still alive
+        let free_port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        manager.processes.lock().await.get_mut("w1").unwrap().transport =
+            Arc::new(WebSocketTransport::new(manager.connect_addr(free_port)));
+        assert!(manager.send_command("w1", "ping", serde_json::json!({})).await.is_err());
+        assert_eq!(rx.try_recv().unwrap().state, ConnectionState::Reconnecting);
+
+        {
+            let mut processes = manager.processes.lock().await;
+            let child = processes.get_mut("w1").unwrap().child.as_mut().unwrap();
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+        assert_eq!(manager.status("w1").await.unwrap().connection, ConnectionState::Dead);
+        assert_eq!(rx.try_recv().unwrap().state, ConnectionState::Dead);
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_close_frames() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let config = SidecarConfig { auto_restart: true, ..SidecarConfig::default() };
+        let manager = SidecarManager::new(Arc::default(), config).with_connection_sink(tx);
+        track_memory_sidecar(&manager, "w1", MemoryTransport::with_frames(|_| vec![
+            Frame::Close { code: 1000, reason: "shutting down".to_string() },
+        ])).await;
+        track_memory_sidecar(&manager, "w2", MemoryTransport::with_frames(|_| vec![
+            Frame::Close { code: 1011, reason: String::new() },
+        ])).await;
+
+        let error = manager.send_command("w1", "ping", serde_json::json!({})).await.unwrap_err();
+        assert_eq!(error.to_string(), "Sidecar closed the connection (1000: shutting down)");
+        let close = manager.status("w1").await.unwrap().last_close.unwrap();
+        assert!(close.clean);
+        // Connecting succeeded, and a clean close is not retried
+        assert_eq!(rx.try_recv().unwrap().state, ConnectionState::Ready);
+        assert!(rx.try_recv().is_err());
+
+        assert!(manager.send_command("w2", "ping", serde_json::json!({})).await.is_err());
+        assert!(!manager.status("w2").await.unwrap().last_close.unwrap().clean);
+        assert_eq!(rx.try_recv().unwrap().state, ConnectionState::Ready);
+        assert_eq!(rx.try_recv().unwrap().state, ConnectionState::Reconnecting);
+
+        // Once it exits, the sidecar that said it was going away is not restarted
+        {
+            let mut processes = manager.processes.lock().await;
+            let child = processes.get_mut("w1").unwrap().child.as_mut().unwrap();
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+        let reaped = manager.reap_dead().await;
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].reason, ShutdownReason::ClosedBySidecar);
+        assert_eq!(reaped[0].restarted_port, None);
+        manager.terminate_sidecar("w2", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auto_restart_budget() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+        let mut processes = manager.processes.lock().await;
+        let process = processes.get_mut("w1").unwrap();
+
+        // Manual restarts do not use up automatic ones
+        process.restarts = 10;
+        let now = std::time::Instant::now();
+        for _ in 0..MAX_AUTO_RESTARTS {
+            assert!(process.take_auto_restart(now));
+        }
+        assert!(!process.take_auto_restart(now));
+
+        // Restarts older than the window no longer count
+        let later = now + AUTO_RESTART_WINDOW;
+        process.started_at = later;
+        assert!(process.take_auto_restart(later));
+
+        // Neither do any once it stayed up long enough
+        process.auto_restarts = vec![later; MAX_AUTO_RESTARTS];
+        assert!(process.take_auto_restart(later + STABLE_UPTIME));
+        assert_eq!(process.auto_restarts.len(), 1);
+
+        let _ = process.child.as_mut().unwrap().kill();
+    }
+
+    #[tokio::test]
+    async fn test_clean_close_forgotten_once_answered() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::with_frames(move |request| {
+            if counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                vec![Frame::Close { code: 1000, reason: String::new() }]
+            } else {
+                vec![Frame::Text(serde_json::json!({"jsonrpc": "2.0", "result": {}, "id": request["id"]}).to_string())]
+            }
+        })).await;
+
+        assert!(manager.send_command("w1", "ping", serde_json::json!({})).await.is_err());
+        assert!(manager.status("w1").await.unwrap().last_close.is_some());
+        manager.send_command("w1", "ping", serde_json::json!({})).await.unwrap();
+        assert!(manager.status("w1").await.unwrap().last_close.is_none());
+
+        // A later exit is a crash, not the sidecar going away
+        {
+            let mut processes = manager.processes.lock().await;
+            let child = processes.get_mut("w1").unwrap().child.as_mut().unwrap();
+            child.kill().unwrap();
+            child.wait().unwrap();
+        }
+        let reaped = manager.reap_dead().await;
+        assert_eq!(reaped[0].reason, ShutdownReason::Crashed);
+    }
+
+    #[tokio::test]
+    async fn test_attach_external_sidecar() {
+        use futures::{SinkExt, StreamExt};
+
+        // Stands in for a sidecar started by hand: answers every request
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let Ok(mut stream) = tokio_tungstenite::accept_async(socket).await else { return };
+                    while let Some(Ok(message)) = stream.next().await {
+                        let Ok(request) = serde_json::from_str::<serde_json::Value>(message.to_text().unwrap_or_default()) else { continue };
+                        let response = serde_json::json!({"jsonrpc": "2.0", "result": {"ok": true}, "id": request["id"]});
+                        let _ = stream.send(response.to_string().into()).await;
+                    }
+                });
+            }
+        });
+
+        let manager = SidecarManager::default();
+        manager.attach_sidecar("w1".to_string(), "/tmp/vault".to_string(), ws_port).await.unwrap();
+        let status = manager.status("w1").await.unwrap();
+        assert!(status.external && status.running);
+        assert_eq!(status.pid, std::process::id());
+        let response = manager.send_command("w1", "ping", serde_json::json!({})).await.unwrap();
+        assert_eq!(response["result"]["ok"], true);
+
+        // Something on the port that is not a sidecar leaves the current one in place
+        let stranger = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stranger_port = stranger.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = stranger.accept().await {
+                drop(socket);
+            }
+        });
+        assert!(manager.attach_sidecar("w1".to_string(), "/tmp/vault".to_string(), stranger_port).await.is_err());
+        assert_eq!(manager.status("w1").await.unwrap().ws_port, ws_port);
+        assert!(manager.send_command("w1", "ping", serde_json::json!({})).await.is_ok());
+
+        // It is Tailor's to use, not to restart or stop
+        assert!(manager.restart_sidecar("w1").await.is_err());
+        assert!(manager.attach_sidecar("w2".to_string(), "/tmp/vault".to_string(), ws_port).await.is_err());
+        assert_eq!(manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap(), Termination::Released);
+        assert!(SidecarManager::accepts_connections(manager.connect_addr(ws_port), None).await);
+
+        // Once it stops listening it is reaped like a sidecar that exited
+        manager.attach_sidecar("w1".to_string(), "/tmp/vault".to_string(), ws_port).await.unwrap();
+        assert!(manager.reap_dead().await.is_empty());
+        server.abort();
+        let _ = server.await;
+        let reaped = manager.reap_dead().await;
+        assert_eq!(reaped.len(), 1);
+        assert_eq!(reaped[0].reason, ShutdownReason::Disconnected);
+        assert!(manager.status("w1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_request_rejected() {
+        let config = SidecarConfig { max_request_bytes: 300, ..SidecarConfig::default() };
+        let manager = SidecarManager::new(Arc::default(), config);
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| serde_json::json!({
+            "jsonrpc": "2.0", "result": "ok", "id": request["id"],
+        }))).await;
+
+        assert!(manager.send_command("w1", "echo", serde_json::json!({})).await.is_ok());
+        let err = manager.send_command("w1", "echo", serde_json::json!({ "text": "x".repeat(300) }))
+            .await
+            .unwrap_err();
+        assert!(matches!(err.downcast_ref(), Some(TailorError::PayloadTooLarge { limit: 300, .. })));
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_list_entries_cached_until_file_changed() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(move |request| {
+            let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            serde_json::json!({"jsonrpc": "2.0", "result": { "entries": [], "call": n }, "id": request["id"]})
+        })).await;
+
+        let params = serde_json::json!({ "path": "" });
+        let first = manager.list_entries("w1", params.clone()).await.unwrap();
+        let second = manager.list_entries("w1", params.clone()).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // Different params are a different cache entry
+        manager.list_entries("w1", serde_json::json!({ "path": "notes" })).await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        assert!(SidecarManager::is_file_changed_event(&serde_json::json!({
+            "method": "trigger_event", "params": { "event_type": "file-changed", "data": {} }
+        })));
+        manager.invalidate_entries("w1").await;
+        let third = manager.list_entries("w1", params).await.unwrap();
+        assert_eq!(third["result"]["call"], 2);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_handshake_capabilities() {
+        let config = SidecarConfig { validate_methods: true, ..SidecarConfig::default() };
+        let manager = SidecarManager::new(Arc::default(), config);
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": { "capabilities": { "methods": ["system.initialize", "echo"] } },
+            "id": request["id"],
+        }))).await;
+
+        // Nothing advertised yet: every method is allowed
+        assert_eq!(manager.capabilities("w1").await.unwrap(), None);
+        assert!(manager.check_method_supported("w1", "missing").await.is_ok());
+
+        manager.handshake("w1").await;
+        let capabilities = manager.capabilities("w1").await.unwrap().unwrap();
+        assert_eq!(capabilities["methods"][1], "echo");
+        assert!(manager.check_method_supported("w1", "echo").await.is_ok());
+        assert_eq!(
+            manager.check_method_supported("w1", "missing").await,
+            Err(TailorError::MethodNotSupported { method: "missing".to_string() })
+        );
+        assert!(manager.capabilities("w2").await.is_err());
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_ready() {
+        let manager = SidecarManager::default();
+        let timeout = std::time::Duration::from_millis(200);
+
+        let err = manager.wait_until_ready("w1", timeout).await.unwrap_err();
+        assert!(matches!(err, TailorError::Timeout { .. }));
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+        manager.processes.lock().await.get_mut("w1").unwrap().ws_port = listener.local_addr().unwrap().port();
+        assert!(manager.wait_until_ready("w1", timeout).await.is_ok());
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_adopt_moves_sidecar() {
+        let manager = SidecarManager::default();
+        let label = SidecarManager::prewarm_label("abc");
+        track_memory_sidecar(&manager, &label, MemoryTransport::new(|request| request)).await;
+
+        assert_eq!(manager.adopt(&label, "w1").await, Some(0));
+        assert!(manager.get_ws_port(&label).await.is_none());
+        assert_eq!(manager.get_ws_port("w1").await, Some(0));
+        assert_eq!(manager.adopt(&label, "w2").await, None);
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replace_sidecar_keeps_old_one_on_failure() {
+        let manager = SidecarManager::default();
+        track_memory_sidecar(&manager, "w1", MemoryTransport::new(|request| request)).await;
+        let pid = manager.get_pid("w1").await;
+
+        // The new vault's sidecar exits straight away
+        let vault = std::env::temp_dir().join(format!("tailor_replace_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&vault).unwrap();
+        let script = vault.join("failing_sidecar.py");
+        std::fs::write(&script, "import sys\nsys.exit(3)\n").unwrap();
+        let options = SpawnOptions { entrypoint: Some(script), ..Default::default() };
+
+        let result = manager.replace_sidecar("w1", vault.to_string_lossy().to_string(), options, ShutdownReason::VaultSwitched).await;
+        assert!(result.is_err());
+        assert_eq!(manager.get_pid("w1").await, pid);
+        assert!(manager.get_ws_port("replace_w1").await.is_none());
+
+        manager.terminate_sidecar("w1", ShutdownReason::UserClosed).await.unwrap();
+        std::fs::remove_dir_all(&vault).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_allocate_port_skips_reserved() {
+        let manager = SidecarManager::default();
+        let first = manager.allocate_port().await;
+
+        // Rewind as if the counter had wrapped; the reserved port is skipped
+        *manager.next_port.lock().await = first;
+        let second = manager.allocate_port().await;
+        assert_ne!(first, second);
+        assert!(manager.reserved_ports.lock().await.contains(&first));
+    }
 }
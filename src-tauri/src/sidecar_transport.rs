@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use futures::{SinkExt, StreamExt};
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::{connect_async_with_config, WebSocketStream};
+use tokio_tungstenite::tungstenite::error::{CapacityError, Error as WsError};
+use tokio_tungstenite::tungstenite::protocol::{Message, WebSocketConfig};
+use url::Url;
+
+use crate::error::TailorError;
+
+/// A payload received from the sidecar
+#[derive(Debug, Clone, PartialEq)]
+pub enum Frame {
+    Text(String),
+    /// zlib-compressed JSON
+    Binary(Vec<u8>),
+    /// The sidecar closed the connection with a close frame, as opposed to
+    /// dropping it
+    Close { code: u16, reason: String },
+}
+
+/// Close code a close frame without a status stands for (RFC 6455)
+const NO_STATUS_CODE: u16 = 1005;
+
+/// How `SidecarManager` reaches a sidecar. Each command opens its own
+/// connection, sends one request and reads frames until it sees its response.
+pub trait SidecarTransport: Send + Sync {
+    fn connect(&self) -> BoxFuture<'_, Result<Box<dyn SidecarConnection>>>;
+}
+
+/// One open connection to a sidecar
+pub trait SidecarConnection: Send {
+    fn send(&mut self, text: String) -> BoxFuture<'_, Result<()>>;
+
+    /// Next frame from the sidecar, or `None` once the connection closes
+    fn recv(&mut self) -> BoxFuture<'_, Option<Result<Frame>>>;
+}
+
+/// The real transport: a WebSocket to the sidecar's local port, or to its
+/// Unix domain socket where the platform has them
+pub struct WebSocketTransport {
+    endpoint: Endpoint,
+    /// Largest message accepted from the sidecar; unset uses tungstenite's default
+    max_message_size: Option<usize>,
+}
+
+/// Where a sidecar accepts WebSocket connections
+enum Endpoint {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl WebSocketTransport {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { endpoint: Endpoint::Tcp(addr), max_message_size: None }
+    }
+
+    /// Connect over the Unix domain socket at `path` instead of TCP
+    #[cfg(unix)]
+    pub fn unix(path: PathBuf) -> Self {
+        Self { endpoint: Endpoint::Unix(path), max_message_size: None }
+    }
+
+    /// Fail a connection, rather than buffer it, once an incoming message
+    /// grows past `bytes`
+    pub fn with_max_message_size(mut self, bytes: usize) -> Self {
+        self.max_message_size = Some(bytes);
+        self
+    }
+}
+
+impl SidecarTransport for WebSocketTransport {
+    fn connect(&self) -> BoxFuture<'_, Result<Box<dyn SidecarConnection>>> {
+        Box::pin(async move {
+            let mut config = WebSocketConfig::default();
+            if let Some(max) = self.max_message_size {
+                config.max_message_size = Some(max);
+                config.max_frame_size = Some(max);
+            }
+
+            match &self.endpoint {
+                Endpoint::Tcp(addr) => {
+                    let url = Url::parse(&format!("ws://{}", addr))
+                        .context("Invalid WebSocket URL")?;
+                    let (stream, _) = connect_async_with_config(url.to_string(), Some(config), false)
+                        .await
+                        .context("Failed to connect to sidecar WebSocket")?;
+                    Ok(Box::new(WebSocketConnection { stream }) as Box<dyn SidecarConnection>)
+                }
+                #[cfg(unix)]
+                Endpoint::Unix(path) => {
+                    let socket = tokio::net::UnixStream::connect(path)
+                        .await
+                        .with_context(|| format!("Failed to connect to sidecar socket {}", path.display()))?;
+                    // The handshake needs a URL; its host is not used
+                    let (stream, _) = tokio_tungstenite::client_async_with_config("ws://localhost/", socket, Some(config))
+                        .await
+                        .context("Failed to open WebSocket over sidecar socket")?;
+                    Ok(Box::new(WebSocketConnection { stream }) as Box<dyn SidecarConnection>)
+                }
+            }
+        })
+    }
+}
+
+struct WebSocketConnection<S> {
+    stream: WebSocketStream<S>,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> SidecarConnection for WebSocketConnection<S> {
+    fn send(&mut self, text: String) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            self.stream.send(Message::Text(text)).await
+                .context("Failed to send WebSocket message")
+        })
+    }
+
+    fn recv(&mut self) -> BoxFuture<'_, Option<Result<Frame>>> {
+        Box::pin(async move {
+            while let Some(msg) = self.stream.next().await {
+                // The connection is dropped with the error, discarding the partial message
+                if let Err(WsError::Capacity(CapacityError::MessageTooLong { size, max_size })) = &msg {
+                    eprintln!("Sidecar sent a {} byte message, over the {} byte limit; closing connection",
+                              size, max_size);
+                    return Some(Err(TailorError::PayloadTooLarge { size: *size, limit: *max_size }.into()));
+                }
+                match msg.context("WebSocket stream error") {
+                    Ok(Message::Text(text)) => return Some(Ok(Frame::Text(text))),
+                    Ok(Message::Binary(data)) => return Some(Ok(Frame::Binary(data))),
+                    Ok(Message::Close(frame)) => {
+                        let (code, reason) = frame
+                            .map_or((NO_STATUS_CODE, String::new()), |frame| (frame.code.into(), frame.reason.into_owned()));
+                        return Some(Ok(Frame::Close { code, reason }));
+                    }
+                    Ok(_) => continue,
+                    Err(e) => return Some(Err(e)),
+                }
+            }
+            None
+        })
+    }
+}
+
+/// In-memory transport for tests: answers each request with `handler`
+#[cfg(test)]
+pub struct MemoryTransport {
+    handler: std::sync::Arc<dyn Fn(serde_json::Value) -> Vec<Frame> + Send + Sync>,
+}
+
+#[cfg(test)]
+impl MemoryTransport {
+    pub fn new(handler: impl Fn(serde_json::Value) -> serde_json::Value + Send + Sync + 'static) -> Self {
+        Self::with_messages(move |request| vec![handler(request)])
+    }
+
+    /// A transport whose handler answers each request with several messages
+    pub fn with_messages(handler: impl Fn(serde_json::Value) -> Vec<serde_json::Value> + Send + Sync + 'static) -> Self {
+        Self::with_frames(move |request| {
+            handler(request).into_iter().map(|message| Frame::Text(message.to_string())).collect()
+        })
+    }
+
+    /// A transport whose handler answers each request with raw frames
+    pub fn with_frames(handler: impl Fn(serde_json::Value) -> Vec<Frame> + Send + Sync + 'static) -> Self {
+        Self { handler: std::sync::Arc::new(handler) }
+    }
+}
+
+#[cfg(test)]
+impl SidecarTransport for MemoryTransport {
+    fn connect(&self) -> BoxFuture<'_, Result<Box<dyn SidecarConnection>>> {
+        let connection = MemoryConnection {
+            handler: self.handler.clone(),
+            pending: std::collections::VecDeque::new(),
+        };
+        Box::pin(async move { Ok(Box::new(connection) as Box<dyn SidecarConnection>) })
+    }
+}
+
+#[cfg(test)]
+struct MemoryConnection {
+    handler: std::sync::Arc<dyn Fn(serde_json::Value) -> Vec<Frame> + Send + Sync>,
+    pending: std::collections::VecDeque<Frame>,
+}
+
+#[cfg(test)]
+impl SidecarConnection for MemoryConnection {
+    fn send(&mut self, text: String) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let request = serde_json::from_str(&text)?;
+            self.pending.extend((self.handler)(request));
+            Ok(())
+        })
+    }
+
+    fn recv(&mut self) -> BoxFuture<'_, Option<Result<Frame>>> {
+        Box::pin(async move { self.pending.pop_front().map(Ok) })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_unix_socket_transport() {
+        let path = std::env::temp_dir().join(format!("tailor_{}.sock", &uuid::Uuid::new_v4().simple().to_string()[..8]));
+        let listener = tokio::net::UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = tokio_tungstenite::accept_async(socket).await.unwrap();
+            while let Some(Ok(message)) = stream.next().await {
+                if message.is_text() {
+                    stream.send(message).await.unwrap();
+                }
+            }
+        });
+
+        let mut connection = WebSocketTransport::unix(path.clone()).connect().await.unwrap();
+        connection.send("ping".to_string()).await.unwrap();
+        assert_eq!(connection.recv().await.unwrap().unwrap(), Frame::Text("ping".to_string()));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
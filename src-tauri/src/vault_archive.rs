@@ -0,0 +1,241 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Record of extractions, kept in the managed directory
+const REGISTRY_FILE: &str = "extractions.json";
+
+/// A vault unpacked from a zip archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Extraction {
+    pub archive_path: String,
+    /// Directory the archive was unpacked into; removed on cleanup
+    pub extracted_to: String,
+    /// The vault inside it (the archive's single top-level folder, if it has one)
+    pub vault_path: String,
+    pub extracted_at: String,
+}
+
+/// Unpacks vaults distributed as zip files and remembers where, so the
+/// extracted copies can be listed and cleaned up. Managed extractions live
+/// under `dir`, keyed by the archive's name and content hash, so reopening
+/// the same archive reuses its earlier extraction.
+pub struct VaultArchives {
+    dir: PathBuf,
+    // Serializes registry read-modify-writes
+    registry_lock: Mutex<()>,
+}
+
+impl Default for VaultArchives {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir().join("tailor-extracted-vaults"))
+    }
+}
+
+impl VaultArchives {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir, registry_lock: Mutex::new(()) }
+    }
+
+    /// Extract a vault archive into the managed directory, or into a new
+    /// folder named after the archive inside `destination`
+    pub fn extract(&self, zip_path: &Path, destination: Option<&Path>) -> Result<Extraction> {
+        if !is_archive(zip_path) {
+            bail!("Not a zip archive: {}", zip_path.display());
+        }
+        let stem = zip_path.file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "vault".to_string());
+
+        let target = match destination {
+            Some(destination) => destination.join(&stem),
+            None => self.dir.join(format!("{}-{}", stem, &archive_hash(zip_path)?[..12])),
+        };
+        if let Some(existing) = self.list().into_iter().find(|e| Path::new(&e.extracted_to) == target) {
+            if Path::new(&existing.vault_path).is_dir() {
+                println!("Reusing extraction of {} at {}", zip_path.display(), existing.extracted_to);
+                return Ok(existing);
+            }
+        }
+        if target.exists() {
+            bail!("Destination already exists: {}", target.display());
+        }
+
+        if let Err(e) = extract_zip(zip_path, &target) {
+            let _ = fs::remove_dir_all(&target);
+            return Err(e);
+        }
+        let vault_path = match find_vault_root(&target) {
+            Ok(vault_path) => vault_path,
+            Err(e) => {
+                let _ = fs::remove_dir_all(&target);
+                return Err(e);
+            }
+        };
+
+        let extraction = Extraction {
+            archive_path: zip_path.to_string_lossy().to_string(),
+            extracted_to: target.to_string_lossy().to_string(),
+            vault_path: vault_path.to_string_lossy().to_string(),
+            extracted_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.update(|extractions| {
+            extractions.retain(|e| e.extracted_to != extraction.extracted_to);
+            extractions.push(extraction.clone());
+        })?;
+        println!("Extracted vault archive {} to {}", zip_path.display(), extraction.extracted_to);
+        Ok(extraction)
+    }
+
+    /// Every recorded extraction
+    pub fn list(&self) -> Vec<Extraction> {
+        fs::read_to_string(self.dir.join(REGISTRY_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Delete the extraction containing `vault_path` and forget it.
+    /// Returns false if no extraction contains that vault.
+    pub fn remove(&self, vault_path: &str) -> Result<bool> {
+        let Some(extraction) = self.list().into_iter().find(|e| e.vault_path == vault_path) else {
+            return Ok(false);
+        };
+        if Path::new(&extraction.extracted_to).exists() {
+            fs::remove_dir_all(&extraction.extracted_to)
+                .with_context(|| format!("Failed to remove {}", extraction.extracted_to))?;
+        }
+        self.update(|extractions| extractions.retain(|e| e.extracted_to != extraction.extracted_to))?;
+        println!("Removed extracted vault {}", extraction.extracted_to);
+        Ok(true)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut Vec<Extraction>)) -> Result<()> {
+        let _guard = self.registry_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut extractions = self.list();
+        change(&mut extractions);
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Failed to create {}", self.dir.display()))?;
+        fs::write(self.dir.join(REGISTRY_FILE), serde_json::to_string_pretty(&extractions)?)
+            .context("Failed to save extraction registry")
+    }
+}
+
+/// Whether a path names an existing `.zip` file
+pub fn is_archive(path: &Path) -> bool {
+    path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Hex SHA-256 of a file's contents
+fn archive_hash(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Unpack a zip into `target`, refusing entries whose paths would land
+/// outside it (zip-slip)
+fn extract_zip(zip_path: &Path, target: &Path) -> Result<()> {
+    let file = fs::File::open(zip_path)
+        .with_context(|| format!("Failed to open {}", zip_path.display()))?;
+    let mut archive = zip::ZipArchive::new(file).context("Invalid zip archive")?;
+    fs::create_dir_all(target)
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let Some(relative) = entry.enclosed_name() else {
+            bail!("Archive entry escapes the extraction directory: {}", entry.name());
+        };
+        let path = target.join(relative);
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut out = fs::File::create(&path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        io::copy(&mut entry, &mut out)
+            .with_context(|| format!("Failed to extract {}", entry.name()))?;
+    }
+    Ok(())
+}
+
+/// The vault directory inside an extraction: the extraction itself, or its
+/// only top-level folder when the archive wraps the vault in one
+fn find_vault_root(target: &Path) -> Result<PathBuf> {
+    if target.join(".vault.toml").is_file() {
+        return Ok(target.to_path_buf());
+    }
+    let entries: Vec<PathBuf> = fs::read_dir(target)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    if let [only] = entries.as_slice() {
+        if only.join(".vault.toml").is_file() {
+            return Ok(only.clone());
+        }
+    }
+    bail!("Archive does not contain a vault (missing .vault.toml)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, contents) in entries {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_extract_and_remove() {
+        let dir = std::env::temp_dir().join(format!("tailor_archive_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let zip_path = dir.join("Notes.zip");
+        write_zip(&zip_path, &[("Notes/.vault.toml", "name = \"Notes\"\n"), ("Notes/plugins/a.py", "")]);
+
+        let archives = VaultArchives::new(dir.join("managed"));
+        let extraction = archives.extract(&zip_path, None).unwrap();
+        assert!(extraction.vault_path.ends_with("Notes"));
+        assert!(Path::new(&extraction.vault_path).join("plugins/a.py").is_file());
+
+        // The same archive reuses its extraction
+        assert_eq!(archives.extract(&zip_path, None).unwrap(), extraction);
+        assert_eq!(archives.list().len(), 1);
+
+        assert!(archives.remove(&extraction.vault_path).unwrap());
+        assert!(!Path::new(&extraction.extracted_to).exists());
+        assert!(archives.list().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_rejects_zip_slip() {
+        let dir = std::env::temp_dir().join(format!("tailor_archive_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let zip_path = dir.join("evil.zip");
+        write_zip(&zip_path, &[(".vault.toml", ""), ("../escaped.txt", "gotcha")]);
+
+        let archives = VaultArchives::new(dir.join("managed"));
+        assert!(archives.extract(&zip_path, Some(&dir.join("out"))).is_err());
+        assert!(!dir.join("escaped.txt").exists());
+        // A failed extraction leaves nothing behind
+        assert!(!dir.join("out").join("evil").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
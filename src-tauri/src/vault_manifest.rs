@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::event_bus::EventPolicy;
+use crate::event_schema::EventSchemas;
+use crate::sandbox::SandboxPolicy;
+use crate::sidecar_manager::{normalize_log_level, SpawnOptions, LOG_LEVELS};
+
+/// Vault-relative path of the optional vault manifest
+pub const MANIFEST_FILE: &str = "tailor.json";
+
+/// Declarative vault settings read from `tailor.json`. Every field is
+/// optional; unknown fields are ignored so older hosts accept newer manifests.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VaultManifest {
+    pub sidecar: SidecarManifest,
+    pub window: WindowManifest,
+    /// Overrides the `[events]` table of `.vault.toml`
+    pub events: Option<EventPolicy>,
+    /// JSON schemas for the data of this vault's event types, checked when
+    /// `debug.event_schema_validation` is on
+    pub event_schemas: HashMap<String, serde_json::Value>,
+    pub dependencies: DependenciesManifest,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DependenciesManifest {
+    /// Reinstall plugin requirements and restart the sidecar as soon as the
+    /// background check sees them change, instead of asking. Off by default,
+    /// since installs then run unattended.
+    pub auto_reinstall_on_change: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SidecarManifest {
+    /// Vault-relative Python script to run instead of the bundled sidecar
+    pub entrypoint: Option<String>,
+    /// Minimum Python version, e.g. "3.11"
+    pub python_version: Option<String>,
+    /// Memory cap for the sidecar in MiB; unlimited when unset
+    pub memory_limit_mb: Option<u64>,
+    /// Sidecar log verbosity, e.g. "DEBUG"
+    pub log_level: Option<String>,
+    /// Vault-relative Python script run after plugin requirements are
+    /// installed, instead of `plugins/postinstall.py`
+    pub postinstall: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct WindowManifest {
+    /// Logical size used until the window has a saved size of its own
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    /// Frontend route shown when the window opens, e.g. "settings"
+    pub route: Option<String>,
+}
+
+impl VaultManifest {
+    /// Read and validate a vault's manifest, using defaults if it has none
+    pub fn load(vault_path: &str) -> Result<Self> {
+        let path = Path::new(vault_path).join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        // A sandbox leaves an empty file in place of a missing manifest
+        if contents.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let manifest: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid {}", MANIFEST_FILE))?;
+        manifest.validate(vault_path)
+            .with_context(|| format!("Invalid {}", MANIFEST_FILE))?;
+        Ok(manifest)
+    }
+
+    fn validate(&self, vault_path: &str) -> Result<()> {
+        for (name, script) in [("entrypoint", &self.sidecar.entrypoint), ("postinstall", &self.sidecar.postinstall)] {
+            let Some(script) = script else {
+                continue;
+            };
+            let relative = Path::new(script);
+            if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+                bail!("sidecar.{} must be a path inside the vault: {}", name, script);
+            }
+            if !Path::new(vault_path).join(relative).is_file() {
+                bail!("sidecar.{} not found: {}", name, script);
+            }
+        }
+        if let Some(version) = &self.sidecar.python_version {
+            if parse_python_version(version).is_none() {
+                bail!("sidecar.python_version must look like \"3.11\": {}", version);
+            }
+        }
+        if let Some(level) = &self.sidecar.log_level {
+            if normalize_log_level(level).is_none() {
+                bail!("sidecar.log_level must be one of {}: {}", LOG_LEVELS.join(", "), level);
+            }
+        }
+        EventSchemas::builtin().with_schemas(&self.event_schemas)?;
+        if self.sidecar.memory_limit_mb == Some(0) {
+            bail!("sidecar.memory_limit_mb must be greater than 0");
+        }
+        for (name, size) in [("width", self.window.width), ("height", self.window.height)] {
+            if size.is_some_and(|size| !(size.is_finite() && size > 0.0)) {
+                bail!("window.{} must be a positive number", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// The sidecar's memory cap in bytes, if the manifest sets one
+    pub fn memory_limit_bytes(&self) -> Option<u64> {
+        self.sidecar.memory_limit_mb.map(|mb| mb.saturating_mul(1024 * 1024))
+    }
+
+    /// How to spawn this vault's sidecar. The sandbox is the user's choice,
+    /// kept by the host, never the vault's.
+    pub fn spawn_options(&self, vault_path: &str, sandbox: Option<SandboxPolicy>) -> SpawnOptions {
+        SpawnOptions {
+            entrypoint: self.entrypoint(vault_path),
+            memory_limit: self.memory_limit_bytes(),
+            log_level: self.sidecar.log_level.as_deref().and_then(normalize_log_level).map(str::to_string),
+            sandbox,
+        }
+    }
+
+    /// Absolute path of the declared post-install script
+    pub fn postinstall(&self, vault_path: &str) -> Option<PathBuf> {
+        self.sidecar.postinstall
+            .as_ref()
+            .map(|script| Path::new(vault_path).join(script))
+    }
+
+    /// Absolute path of the declared sidecar entrypoint
+    pub fn entrypoint(&self, vault_path: &str) -> Option<PathBuf> {
+        self.sidecar.entrypoint
+            .as_ref()
+            .map(|entrypoint| Path::new(vault_path).join(entrypoint))
+    }
+}
+
+/// Parse the major and minor parts of a version such as "3.11" or "3.11.4"
+pub fn parse_python_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    if parts.any(|patch| patch.parse::<u32>().is_err()) {
+        return None;
+    }
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = std::env::temp_dir().join(format!("tailor_manifest_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("plugins")).unwrap();
+        let vault_path = dir.to_string_lossy().to_string();
+
+        // No manifest falls back to defaults
+        let manifest = VaultManifest::load(&vault_path).unwrap();
+        assert!(manifest.entrypoint(&vault_path).is_none());
+        assert!(manifest.memory_limit_bytes().is_none());
+        assert!(manifest.events.is_none());
+        assert!(!manifest.dependencies.auto_reinstall_on_change);
+
+        fs::write(dir.join(MANIFEST_FILE), r#"{
+            "sidecar": { "entrypoint": "plugins/main.py", "python_version": "3.11", "memory_limit_mb": 512, "log_level": "debug",
+                         "sandbox": { "enabled": false } },
+            "window": { "width": 900, "route": "settings" },
+            "dependencies": { "auto_reinstall_on_change": true },
+            "future_field": true
+        }"#).unwrap();
+        assert!(VaultManifest::load(&vault_path).is_err());
+
+        fs::write(dir.join("plugins").join("main.py"), "").unwrap();
+        let manifest = VaultManifest::load(&vault_path).unwrap();
+        assert_eq!(manifest.entrypoint(&vault_path), Some(dir.join("plugins/main.py")));
+        assert_eq!(manifest.window.width, Some(900.0));
+        assert_eq!(manifest.window.route.as_deref(), Some("settings"));
+        assert!(manifest.dependencies.auto_reinstall_on_change);
+        assert_eq!(manifest.memory_limit_bytes(), Some(512 * 1024 * 1024));
+        let sandbox = Some(SandboxPolicy { allow_network: false });
+        let options = manifest.spawn_options(&vault_path, sandbox.clone());
+        assert_eq!(options.log_level.as_deref(), Some("DEBUG"));
+        // The vault cannot turn its own sandbox off
+        assert_eq!(options.sandbox, sandbox);
+
+        fs::write(dir.join(MANIFEST_FILE), r#"{ "sidecar": { "entrypoint": "../escape.py" } }"#).unwrap();
+        assert!(VaultManifest::load(&vault_path).is_err());
+
+        fs::write(dir.join(MANIFEST_FILE), r#"{ "sidecar": { "log_level": "LOUD" } }"#).unwrap();
+        assert!(VaultManifest::load(&vault_path).is_err());
+
+        fs::write(dir.join(MANIFEST_FILE), r#"{ "event_schemas": { "indexer.done": { "type": 7 } } }"#).unwrap();
+        assert!(VaultManifest::load(&vault_path).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_python_version() {
+        assert_eq!(parse_python_version("3.11"), Some((3, 11)));
+        assert_eq!(parse_python_version("3.10.4"), Some((3, 10)));
+        assert_eq!(parse_python_version("3"), None);
+        assert_eq!(parse_python_version("3.x"), None);
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::sandbox::SandboxPolicy;
+
+/// Something a vault's sidecar may ask the host to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostCapability {
+    /// Open another vault in a new window
+    OpenVault,
+    /// Show file dialogs over the vault's window
+    Dialogs,
+    /// Read host settings with `get_setting`
+    ReadSettings,
+}
+
+impl HostCapability {
+    /// What granting the capability allows, for asking the user
+    pub fn describe(self) -> &'static str {
+        match self {
+            HostCapability::OpenVault => "open other vaults",
+            HostCapability::Dialogs => "show file dialogs",
+            HostCapability::ReadSettings => "read Tailor's settings",
+        }
+    }
+}
+
+/// The user's decisions for one vault; None where they have not been asked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VaultGrants {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_vault: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialogs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_settings: Option<bool>,
+    /// Whether the sidecar runs sandboxed; no unless the user turned it on
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sandboxed: Option<bool>,
+    /// Whether plugins may reach the network from inside the sandbox; no
+    /// unless the user said otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_network: Option<bool>,
+}
+
+impl VaultGrants {
+    fn slot(&mut self, capability: HostCapability) -> &mut Option<bool> {
+        match capability {
+            HostCapability::OpenVault => &mut self.open_vault,
+            HostCapability::Dialogs => &mut self.dialogs,
+            HostCapability::ReadSettings => &mut self.read_settings,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The sandbox the vault's sidecar runs in; None unless the user
+    /// turned it on
+    pub fn sandbox(&self) -> Option<SandboxPolicy> {
+        self.sandboxed.unwrap_or(false).then(|| SandboxPolicy {
+            allow_network: self.allow_network.unwrap_or(false),
+        })
+    }
+}
+
+/// What each vault may do, decided by the user and kept in the app's data
+/// directory, out of reach of the vaults themselves. Nothing is granted
+/// until the user allows it. A sidecar is sandboxed only if the user
+/// chose that, and then has no network unless they allow it too.
+pub struct VaultPermissions {
+    path: PathBuf,
+    // Serializes read-modify-writes
+    lock: Mutex<()>,
+}
+
+impl Default for VaultPermissions {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir().join("tailor-vault-permissions.json"))
+    }
+}
+
+impl VaultPermissions {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, lock: Mutex::new(()) }
+    }
+
+    /// The user's decisions for a vault
+    pub fn grants(&self, vault_id: &str) -> VaultGrants {
+        self.read().remove(vault_id).unwrap_or_default()
+    }
+
+    /// Whether the user allowed a vault a capability; None if not yet asked
+    pub fn decision(&self, vault_id: &str, capability: HostCapability) -> Option<bool> {
+        *self.grants(vault_id).slot(capability)
+    }
+
+    /// Record the user's decision, or forget it with None so they are asked again
+    pub fn set(&self, vault_id: &str, capability: HostCapability, granted: Option<bool>) -> Result<()> {
+        self.update(|all| {
+            let grants = all.entry(vault_id.to_string()).or_default();
+            *grants.slot(capability) = granted;
+            if grants.is_empty() {
+                all.remove(vault_id);
+            }
+        })
+    }
+
+    /// Set whether a vault's sidecar is sandboxed and may reach the
+    /// network; None restores the default. Applies from its next start.
+    pub fn set_sandbox(&self, vault_id: &str, sandboxed: Option<bool>, allow_network: Option<bool>) -> Result<()> {
+        self.update(|all| {
+            let grants = all.entry(vault_id.to_string()).or_default();
+            grants.sandboxed = sandboxed;
+            grants.allow_network = allow_network;
+            if grants.is_empty() {
+                all.remove(vault_id);
+            }
+        })
+    }
+
+    fn read(&self) -> BTreeMap<String, VaultGrants> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut BTreeMap<String, VaultGrants>)) -> Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut all = self.read();
+        change(&mut all);
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&all)?)
+            .context("Failed to save vault permissions")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decisions_recorded_per_vault() {
+        let path = std::env::temp_dir().join(format!("tailor_permissions_{}.json", uuid::Uuid::new_v4()));
+        let permissions = VaultPermissions::new(path.clone());
+        assert_eq!(permissions.decision("vault_a", HostCapability::OpenVault), None);
+
+        permissions.set("vault_a", HostCapability::OpenVault, Some(true)).unwrap();
+        permissions.set("vault_a", HostCapability::Dialogs, Some(false)).unwrap();
+        assert_eq!(permissions.decision("vault_a", HostCapability::OpenVault), Some(true));
+        assert_eq!(permissions.decision("vault_a", HostCapability::Dialogs), Some(false));
+        assert_eq!(permissions.decision("vault_a", HostCapability::ReadSettings), None);
+        assert_eq!(permissions.decision("vault_b", HostCapability::OpenVault), None);
+
+        // Unsandboxed until the user opts in, then without a network unless allowed
+        assert_eq!(permissions.grants("vault_a").sandbox(), None);
+        permissions.set_sandbox("vault_a", Some(true), None).unwrap();
+        assert_eq!(permissions.grants("vault_a").sandbox(), Some(SandboxPolicy { allow_network: false }));
+        permissions.set_sandbox("vault_a", Some(true), Some(true)).unwrap();
+        assert_eq!(permissions.grants("vault_a").sandbox(), Some(SandboxPolicy { allow_network: true }));
+        permissions.set_sandbox("vault_a", None, Some(true)).unwrap();
+        assert_eq!(permissions.grants("vault_a").sandbox(), None);
+        assert_eq!(permissions.grants("vault_b").sandbox(), None);
+        permissions.set_sandbox("vault_a", None, None).unwrap();
+
+        // Forgetting every decision drops the vault's entry
+        permissions.set("vault_a", HostCapability::OpenVault, None).unwrap();
+        permissions.set("vault_a", HostCapability::Dialogs, None).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap().trim(), "{}");
+
+        fs::remove_file(&path).unwrap();
+    }
+}
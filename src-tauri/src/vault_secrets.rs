@@ -0,0 +1,194 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use anyhow::{bail, Context, Result};
+
+/// Keychain service every vault secret is stored under
+const KEYCHAIN_SERVICE: &str = "tailor-vault-secrets";
+
+/// Per-vault secrets kept in the OS keychain and handed to the vault's
+/// sidecar as environment variables when it is spawned. Values never touch
+/// disk; only the names of each vault's secrets are recorded in `index_path`,
+/// since the keychain cannot be enumerated.
+pub struct VaultSecrets {
+    index_path: PathBuf,
+    // Serializes index read-modify-writes
+    index_lock: Mutex<()>,
+}
+
+impl Default for VaultSecrets {
+    fn default() -> Self {
+        Self::new(std::env::temp_dir().join("tailor-vault-secrets.json"))
+    }
+}
+
+impl VaultSecrets {
+    pub fn new(index_path: PathBuf) -> Self {
+        Self { index_path, index_lock: Mutex::new(()) }
+    }
+
+    /// Store a secret for a vault, replacing any earlier value. `key` becomes
+    /// the environment variable name, so it must be a valid one.
+    pub fn set(&self, vault_id: &str, key: &str, value: &str) -> Result<()> {
+        validate_key(key)?;
+        entry(vault_id, key)?
+            .set_password(value)
+            .with_context(|| format!("Failed to store secret '{}' in the keychain", key))?;
+        self.update(|index| {
+            let keys = index.entry(vault_id.to_string()).or_default();
+            if !keys.iter().any(|k| k == key) {
+                keys.push(key.to_string());
+                keys.sort();
+            }
+        })?;
+        println!("Stored secret '{}' for vault {}", key, vault_id);
+        Ok(())
+    }
+
+    /// Remove a vault's secret. Returns false if it had none by that name.
+    pub fn delete(&self, vault_id: &str, key: &str) -> Result<bool> {
+        if !self.keys(vault_id).iter().any(|k| k == key) {
+            return Ok(false);
+        }
+        match entry(vault_id, key)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(e) => bail!("Failed to remove secret '{}' from the keychain: {}", key, e),
+        }
+        self.update(|index| {
+            if let Some(keys) = index.get_mut(vault_id) {
+                keys.retain(|k| k != key);
+                if keys.is_empty() {
+                    index.remove(vault_id);
+                }
+            }
+        })?;
+        println!("Removed secret '{}' for vault {}", key, vault_id);
+        Ok(true)
+    }
+
+    /// Names of a vault's secrets
+    pub fn keys(&self, vault_id: &str) -> Vec<String> {
+        self.read_index().remove(vault_id).unwrap_or_default()
+    }
+
+    /// A vault's secrets by name, read from the keychain. Secrets that can
+    /// no longer be read, or have reserved names, are left out. Blocks on
+    /// the keychain.
+    pub fn load(&self, vault_id: &str) -> HashMap<String, String> {
+        self.keys(vault_id)
+            .into_iter()
+            .filter_map(|key| {
+                if is_reserved_env_name(&key) {
+                    eprintln!("Ignoring secret '{}' for vault {}: reserved name", key, vault_id);
+                    return None;
+                }
+                match entry(vault_id, &key).and_then(|entry| Ok(entry.get_password()?)) {
+                    Ok(value) => Some((key, value)),
+                    Err(e) => {
+                        eprintln!("Failed to read secret '{}' for vault {}: {}", key, vault_id, e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Every stored secret value, for redacting them from output
+    pub fn all_values(&self) -> Vec<String> {
+        self.read_index()
+            .into_keys()
+            .flat_map(|vault_id| self.load(&vault_id).into_values())
+            .collect()
+    }
+
+    fn read_index(&self) -> BTreeMap<String, Vec<String>> {
+        fs::read_to_string(&self.index_path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut BTreeMap<String, Vec<String>>)) -> Result<()> {
+        let _guard = self.index_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut index = self.read_index();
+        change(&mut index);
+        if let Some(parent) = self.index_path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&self.index_path, serde_json::to_string_pretty(&index)?)
+            .context("Failed to save secret index")
+    }
+}
+
+fn entry(vault_id: &str, key: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("{}/{}", vault_id, key))
+        .context("Failed to open the keychain")
+}
+
+/// Variables that steer the dynamic loader, the Python interpreter or how
+/// programs are found, which a vault may not set for its sidecar
+const RESERVED_ENV_NAMES: &[&str] = &["PATH", "HOME", "SHELL", "TMPDIR", "VIRTUAL_ENV"];
+const RESERVED_ENV_PREFIXES: &[&str] = &["LD_", "DYLD_", "PYTHON"];
+
+/// Whether a vault may not set the environment variable `name`
+pub fn is_reserved_env_name(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    RESERVED_ENV_NAMES.contains(&name.as_str())
+        || RESERVED_ENV_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+/// Secret names are used as environment variable names: ASCII letters,
+/// digits and underscores, not starting with a digit, and not reserved
+fn validate_key(key: &str) -> Result<()> {
+    let valid = key.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("Invalid secret name '{}': use letters, digits and underscores, e.g. OPENAI_API_KEY", key);
+    }
+    if is_reserved_env_name(key) {
+        bail!("Invalid secret name '{}': it would change how the sidecar runs", key);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_key() {
+        assert!(validate_key("OPENAI_API_KEY").is_ok());
+        assert!(validate_key("_token2").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key("2FA").is_err());
+        assert!(validate_key("API-KEY").is_err());
+
+        for reserved in ["PATH", "HOME", "LD_PRELOAD", "ld_library_path", "DYLD_INSERT_LIBRARIES", "PYTHONHOME", "PYTHONPATH"] {
+            assert!(validate_key(reserved).is_err(), "{} was accepted", reserved);
+        }
+        assert!(validate_key("PATH_TO_MODEL").is_ok());
+    }
+
+    #[test]
+    fn test_index_tracks_names() {
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let path = std::env::temp_dir().join(format!("tailor_secrets_{}.json", uuid::Uuid::new_v4()));
+        let secrets = VaultSecrets::new(path.clone());
+
+        secrets.set("vault_a", "SERVICE_TOKEN", "s3cret").unwrap();
+        secrets.set("vault_a", "API_KEY", "k3y").unwrap();
+        secrets.set("vault_a", "API_KEY", "k3y-2").unwrap();
+        assert_eq!(secrets.keys("vault_a"), ["API_KEY", "SERVICE_TOKEN"]);
+        assert!(secrets.keys("vault_b").is_empty());
+        // Only names are written to disk
+        assert!(!fs::read_to_string(&path).unwrap().contains("s3cret"));
+
+        assert!(secrets.delete("vault_a", "API_KEY").unwrap());
+        assert!(!secrets.delete("vault_a", "API_KEY").unwrap());
+        assert_eq!(secrets.keys("vault_a"), ["SERVICE_TOKEN"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,9 +1,81 @@
 use std::collections::HashMap;
-use tauri::{AppHandle, WebviewWindowBuilder};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, WebviewWindowBuilder};
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::vault_manifest::WindowManifest;
+use crate::window_state::{WindowState, WindowStateStore};
+
+/// How long opening a vault may take unless configured otherwise
+const DEFAULT_OPEN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Vaults a batch open works on at once unless configured
+const DEFAULT_MAX_CONCURRENT_OPENS: usize = 2;
+
+/// Range of accepted webview zoom factors
+const MIN_ZOOM: f64 = 0.25;
+const MAX_ZOOM: f64 = 5.0;
+
+/// A display, as reported to the frontend for choosing where vaults open
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonitorInfo {
+    pub index: usize,
+    pub name: Option<String>,
+    /// Top-left corner on the virtual desktop, in physical pixels
+    pub x: i32,
+    pub y: i32,
+    /// Size in physical pixels
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f64,
+}
+
+/// Logical top-left position of a window on the virtual desktop
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowPosition {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// How an open vault's directory overlaps another vault's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Nesting {
+    /// The other vault is inside the open one
+    Inside,
+    /// The other vault contains the open one
+    Contains,
+}
+
+/// An open vault whose directory overlaps another vault's
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NestedVault {
+    pub window_label: String,
+    pub vault_path: String,
+    pub nesting: Nesting,
+}
+
+/// Where to place a new vault window; by default the OS decides
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WindowPlacement {
+    /// Centre the window on this monitor (an index from `list_monitors`)
+    pub monitor_index: Option<usize>,
+    /// Explicit position; takes precedence over `monitor_index`
+    pub position: Option<WindowPosition>,
+}
 
 pub struct WindowManager {
     windows: HashMap<String, String>, // window_label -> vault_path
+    vault_ids: HashMap<String, String>, // window_label -> vault_id
+    window_states: WindowStateStore,
+    /// Where relative vault paths are resolved; the home directory if unset
+    base_dir: Option<PathBuf>,
+    /// Longest a vault open may take before it is abandoned and rolled back
+    open_timeout: std::time::Duration,
+    /// Vaults a batch open works on at once
+    max_concurrent_opens: usize,
 }
 
 impl Default for WindowManager {
@@ -12,36 +84,161 @@ impl Default for WindowManager {
     }
 }
 
+/// Give every window in the process the same app user model ID so Windows
+/// groups them under one taskbar entry. A no-op on other platforms.
+pub fn set_app_user_model_id(app_id: &str) {
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::UI::Shell::SetCurrentProcessExplicitAppUserModelID;
+
+        let wide: Vec<u16> = app_id.encode_utf16().chain(std::iter::once(0)).collect();
+        // SAFETY: `wide` is a NUL-terminated UTF-16 string that outlives the call
+        let result = unsafe { SetCurrentProcessExplicitAppUserModelID(wide.as_ptr()) };
+        if result < 0 {
+            eprintln!("Failed to set app user model ID: HRESULT {:#x}", result);
+        }
+    }
+
+    #[cfg(not(target_os = "windows"))]
+    let _ = app_id;
+}
+
 impl WindowManager {
     pub fn new() -> Self {
+        Self::with_window_states(WindowStateStore::default())
+    }
+
+    /// Create a manager that restores and records window geometry in `window_states`
+    pub fn with_window_states(window_states: WindowStateStore) -> Self {
         Self {
             windows: HashMap::new(),
+            vault_ids: HashMap::new(),
+            window_states,
+            base_dir: None,
+            open_timeout: DEFAULT_OPEN_TIMEOUT,
+            max_concurrent_opens: DEFAULT_MAX_CONCURRENT_OPENS,
+        }
+    }
+
+    /// Abandon vault opens that take longer than `open_timeout`
+    pub fn with_open_timeout(mut self, open_timeout: std::time::Duration) -> Self {
+        self.open_timeout = open_timeout;
+        self
+    }
+
+    pub fn open_timeout(&self) -> std::time::Duration {
+        self.open_timeout
+    }
+
+    /// Open at most `max_concurrent_opens` vaults at once in a batch open
+    pub fn with_max_concurrent_opens(mut self, max_concurrent_opens: usize) -> Self {
+        self.max_concurrent_opens = max_concurrent_opens.max(1);
+        self
+    }
+
+    pub fn max_concurrent_opens(&self) -> usize {
+        self.max_concurrent_opens
+    }
+
+    /// Resolve relative vault paths against `base_dir` instead of the home directory
+    pub fn with_base_dir(mut self, base_dir: Option<PathBuf>) -> Self {
+        self.base_dir = base_dir;
+        self
+    }
+
+    /// Expand a leading `~` and resolve a relative path against the base
+    /// directory, so paths from the CLI or file associations open predictably
+    pub fn normalize_vault_path(&self, vault_path: &str) -> String {
+        let home = dirs::home_dir();
+        Self::resolve_path(vault_path, home.as_deref(), self.base_dir.as_deref())
+            .to_string_lossy()
+            .to_string()
+    }
+
+    fn resolve_path(vault_path: &str, home: Option<&Path>, base_dir: Option<&Path>) -> PathBuf {
+        let expanded = match (vault_path.strip_prefix('~'), home) {
+            (Some(""), Some(home)) => home.to_path_buf(),
+            (Some(rest), Some(home)) if rest.starts_with(['/', '\\']) => home.join(&rest[1..]),
+            _ => PathBuf::from(vault_path),
+        };
+        if expanded.is_absolute() {
+            return expanded;
+        }
+        match base_dir.or(home) {
+            Some(base) => base.join(expanded),
+            None => expanded,
+        }
+    }
+
+    /// Draw labels from `generate` until one is neither tracked here nor
+    /// `taken` by an existing window
+    fn unique_label(&self, mut generate: impl FnMut() -> String, taken: impl Fn(&str) -> bool) -> String {
+        loop {
+            let label = generate();
+            if !self.windows.contains_key(&label) && !taken(&label) {
+                return label;
+            }
+            eprintln!("Window label '{}' is already in use, generating another", label);
         }
     }
 
-    /// Create a new vault window
+    /// Create a new vault window, falling back to the manifest's size and
+    /// route where the window has no saved state
     pub fn create_vault_window(
         &mut self,
         app: &AppHandle,
         vault_path: String,
+        vault_id: String,
+        manifest: &WindowManifest,
+        placement: WindowPlacement,
     ) -> Result<String> {
         // Generate unique window label
-        let window_label = format!("vault_{}", uuid::Uuid::new_v4());
+        let window_label = self.unique_label(
+            || format!("vault_{}", uuid::Uuid::new_v4()),
+            |label| app.get_webview_window(label).is_some(),
+        );
 
-        // Create the window
-        let _window = WebviewWindowBuilder::new(
+        // Create the window at its saved size
+        let saved = self.window_states.get(&Self::vault_key(&vault_path))
+            .unwrap_or_else(|| {
+                let default = WindowState::default();
+                WindowState {
+                    width: manifest.width.unwrap_or(default.width),
+                    height: manifest.height.unwrap_or(default.height),
+                    ..default
+                }
+            });
+        let page = match &manifest.route {
+            Some(route) => format!("vault.html#{}", route),
+            None => "vault.html".to_string(),
+        };
+        let mut builder = WebviewWindowBuilder::new(
             app,
             &window_label,
-            tauri::WebviewUrl::App("vault.html".into()),
+            tauri::WebviewUrl::App(page.into()),
         )
         .title(format!("Tailor - {}", Self::extract_vault_name(&vault_path)))
-        .inner_size(1200.0, 800.0)
+        .inner_size(saved.width, saved.height)
         .resizable(true)
-        .decorations(false)
-        .build()?;
+        .decorations(false);
+        if let Some(position) = Self::resolve_placement(app, placement, saved.width, saved.height)? {
+            builder = builder.position(position.x, position.y);
+        }
+        let window = builder.build()?;
+
+        // Maximize after building so un-maximizing returns to the saved size
+        if saved.fullscreen {
+            window.set_fullscreen(true)?;
+        } else if saved.maximized {
+            window.maximize()?;
+        }
+        if saved.zoom != 1.0 {
+            window.set_zoom(saved.zoom)?;
+        }
 
         // Store window reference
         self.windows.insert(window_label.clone(), vault_path.clone());
+        self.vault_ids.insert(window_label.clone(), vault_id);
 
         println!("Created window '{}' for vault: {}", window_label, vault_path);
 
@@ -53,9 +250,175 @@ impl WindowManager {
         self.windows.get(window_label)
     }
 
+    /// Get the id of the vault open in a window
+    pub fn get_vault_id(&self, window_label: &str) -> Option<&String> {
+        self.vault_ids.get(window_label)
+    }
+
+    /// Find the window (and its vault path) that has a vault open, by vault id
+    pub fn find_window_for_vault(&self, vault_id: &str) -> Option<(String, String)> {
+        let (label, _) = self.vault_ids.iter().find(|(_, id)| id.as_str() == vault_id)?;
+        let path = self.windows.get(label)?;
+        Some((label.clone(), path.clone()))
+    }
+
+    /// Open vaults whose directory is inside `vault_path` or contains it. The
+    /// window `except` (e.g. one switching vaults) is left out.
+    pub fn nested_vaults(&self, vault_path: &str, except: Option<&str>) -> Vec<NestedVault> {
+        let key = Self::vault_key(vault_path);
+        let mut nested: Vec<NestedVault> = self.windows
+            .iter()
+            .filter(|(label, _)| Some(label.as_str()) != except)
+            .filter_map(|(label, open_path)| {
+                let nesting = Self::nesting(Path::new(&key), Path::new(&Self::vault_key(open_path)))?;
+                Some(NestedVault { window_label: label.clone(), vault_path: open_path.clone(), nesting })
+            })
+            .collect();
+        nested.sort_by(|a, b| a.window_label.cmp(&b.window_label));
+        nested
+    }
+
+    /// How `other` relates to the vault at `open`, compared by whole path
+    /// components, so `/notes2` is not inside `/notes`
+    fn nesting(other: &Path, open: &Path) -> Option<Nesting> {
+        if other == open {
+            None
+        } else if other.starts_with(open) {
+            Some(Nesting::Inside)
+        } else if open.starts_with(other) {
+            Some(Nesting::Contains)
+        } else {
+            None
+        }
+    }
+
+    /// Stable id of a vault: a hash of its canonical path
+    pub fn vault_id(vault_path: &str) -> String {
+        let digest = Sha256::digest(Self::vault_key(vault_path).as_bytes());
+        digest.iter().take(8).map(|b| format!("{:02x}", b)).collect()
+    }
+
+    /// Canonical key identifying a vault regardless of how its path was spelled
+    pub fn vault_key(vault_path: &str) -> String {
+        std::fs::canonicalize(vault_path)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| vault_path.to_string())
+    }
+
+    /// Point an existing window at a different vault and retitle it
+    pub fn switch_vault(
+        &mut self,
+        app: &AppHandle,
+        window_label: &str,
+        vault_path: String,
+        vault_id: String,
+    ) -> Result<()> {
+        let current = self.windows.get_mut(window_label)
+            .ok_or_else(|| anyhow::anyhow!("Window not found: {}", window_label))?;
+        *current = vault_path.clone();
+        self.vault_ids.insert(window_label.to_string(), vault_id);
+
+        if let Some(window) = app.get_webview_window(window_label) {
+            window.set_title(&format!("Tailor - {}", Self::extract_vault_name(&vault_path)))?;
+            let zoom = self.window_states.get(&Self::vault_key(&vault_path)).unwrap_or_default().zoom;
+            window.set_zoom(zoom)?;
+        }
+
+        println!("Switched window '{}' to vault: {}", window_label, vault_path);
+        Ok(())
+    }
+
+    /// Record the geometry of a vault window, returning the change's
+    /// generation for `save_window_states`. None if the window is not a vault window.
+    pub fn capture_window_state(&self, app: &AppHandle, window_label: &str) -> Result<Option<u64>> {
+        let (Some(vault_path), Some(window)) = (
+            self.windows.get(window_label),
+            app.get_webview_window(window_label),
+        ) else {
+            return Ok(None);
+        };
+        self.window_states.capture(&Self::vault_key(vault_path), &window).map(Some)
+    }
+
+    /// Persist window states unless they changed after `generation`
+    pub fn save_window_states(&self, generation: u64) -> Result<()> {
+        self.window_states.save_if_current(generation).map(|_| ())
+    }
+
+    /// Persist window states now, whatever saves are pending
+    pub fn flush_window_states(&self) -> Result<()> {
+        self.window_states.save()
+    }
+
+    /// Zoom a vault window's webview and remember the factor for its vault
+    pub fn set_zoom(&self, app: &AppHandle, window_label: &str, zoom: f64) -> Result<()> {
+        if !(MIN_ZOOM..=MAX_ZOOM).contains(&zoom) {
+            anyhow::bail!("Zoom must be between {} and {}", MIN_ZOOM, MAX_ZOOM);
+        }
+        let vault_path = self.windows.get(window_label)
+            .ok_or_else(|| anyhow::anyhow!("Window not found: {}", window_label))?;
+        let window = app.get_webview_window(window_label)
+            .ok_or_else(|| anyhow::anyhow!("Window not found: {}", window_label))?;
+
+        window.set_zoom(zoom)?;
+        self.window_states.set_zoom(&Self::vault_key(vault_path), zoom);
+        self.window_states.save()
+    }
+
+    /// The displays available for placing windows, in the order
+    /// `WindowPlacement::monitor_index` refers to
+    pub fn list_monitors(app: &AppHandle) -> Result<Vec<MonitorInfo>> {
+        Ok(app.available_monitors()?
+            .iter()
+            .enumerate()
+            .map(|(index, monitor)| MonitorInfo {
+                index,
+                name: monitor.name().cloned(),
+                x: monitor.position().x,
+                y: monitor.position().y,
+                width: monitor.size().width,
+                height: monitor.size().height,
+                scale_factor: monitor.scale_factor(),
+            })
+            .collect())
+    }
+
+    /// Logical position for a new window of the given logical size, if the
+    /// placement asks for one
+    fn resolve_placement(
+        app: &AppHandle,
+        placement: WindowPlacement,
+        width: f64,
+        height: f64,
+    ) -> Result<Option<WindowPosition>> {
+        if placement.position.is_some() {
+            return Ok(placement.position);
+        }
+        let Some(index) = placement.monitor_index else {
+            return Ok(None);
+        };
+        let monitors = Self::list_monitors(app)?;
+        let monitor = monitors.get(index)
+            .ok_or_else(|| anyhow::anyhow!("No monitor at index {} ({} available)", index, monitors.len()))?;
+        Ok(Some(Self::centered_on(monitor, width, height)))
+    }
+
+    /// Position that centres a window of the given logical size on a
+    /// monitor, keeping its top-left corner on that monitor
+    fn centered_on(monitor: &MonitorInfo, width: f64, height: f64) -> WindowPosition {
+        let scale = monitor.scale_factor;
+        let (left, top) = (monitor.x as f64 / scale, monitor.y as f64 / scale);
+        let (monitor_width, monitor_height) = (monitor.width as f64 / scale, monitor.height as f64 / scale);
+        WindowPosition {
+            x: left + ((monitor_width - width) / 2.0).max(0.0),
+            y: top + ((monitor_height - height) / 2.0).max(0.0),
+        }
+    }
+
     /// Remove window from tracking
     pub fn remove_window(&mut self, window_label: &str) {
         self.windows.remove(window_label);
+        self.vault_ids.remove(window_label);
         println!("Removed window: {}", window_label);
     }
 
@@ -94,6 +457,88 @@ mod tests {
         assert_eq!(manager.get_vault_path("test_window"), None);
     }
 
+    #[test]
+    fn test_unique_label_retries_on_collision() {
+        let mut manager = WindowManager::new();
+        manager.windows.insert("vault_a".to_string(), "/tmp/a".to_string());
+
+        let mut candidates = vec!["vault_c", "vault_b", "vault_a"];
+        let label = manager.unique_label(|| candidates.pop().unwrap().to_string(), |label| label == "vault_b");
+        assert_eq!(label, "vault_c");
+        assert!(candidates.is_empty());
+    }
+
+    #[test]
+    fn test_centered_on_monitor() {
+        let monitor = MonitorInfo {
+            index: 1,
+            name: Some("External".to_string()),
+            x: 3840,
+            y: 0,
+            width: 3840,
+            height: 2160,
+            scale_factor: 2.0,
+        };
+        assert_eq!(WindowManager::centered_on(&monitor, 1200.0, 800.0), WindowPosition { x: 2280.0, y: 140.0 });
+        // A window larger than the monitor starts at its corner
+        assert_eq!(WindowManager::centered_on(&monitor, 4000.0, 3000.0), WindowPosition { x: 1920.0, y: 0.0 });
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_resolve_path() {
+        let home = Path::new("/home/user");
+        let resolve = |path| WindowManager::resolve_path(path, Some(home), None);
+        assert_eq!(resolve("~"), PathBuf::from("/home/user"));
+        assert_eq!(resolve("~/Documents/vault"), PathBuf::from("/home/user/Documents/vault"));
+        assert_eq!(resolve("vaults/notes"), PathBuf::from("/home/user/vaults/notes"));
+        assert_eq!(resolve("/srv/vault"), PathBuf::from("/srv/vault"));
+        assert_eq!(resolve("~other/vault"), PathBuf::from("/home/user/~other/vault"));
+
+        let base = Path::new("/data");
+        assert_eq!(WindowManager::resolve_path("notes", Some(home), Some(base)), PathBuf::from("/data/notes"));
+    }
+
+    #[test]
+    fn test_nested_vaults() {
+        let mut manager = WindowManager::new();
+        manager.windows.insert("projects".to_string(), "/projects".to_string());
+        manager.windows.insert("notes".to_string(), "/projects/notes".to_string());
+        manager.windows.insert("other".to_string(), "/projects-old".to_string());
+
+        let nested = manager.nested_vaults("/projects/notes/daily", None);
+        assert_eq!(nested.iter().map(|v| (v.window_label.as_str(), v.nesting)).collect::<Vec<_>>(),
+                   [("notes", Nesting::Inside), ("projects", Nesting::Inside)]);
+        let nested = manager.nested_vaults("/projects", Some("projects"));
+        assert_eq!(nested, [NestedVault {
+            window_label: "notes".to_string(),
+            vault_path: "/projects/notes".to_string(),
+            nesting: Nesting::Contains,
+        }]);
+        assert!(manager.nested_vaults("/srv/vault", None).is_empty());
+
+        // Nested vaults still get their own ids
+        assert_ne!(WindowManager::vault_id("/projects"), WindowManager::vault_id("/projects/notes"));
+    }
+
+    #[test]
+    fn test_find_window_for_vault() {
+        let mut manager = WindowManager::new();
+        let vault_dir = std::env::temp_dir().join(format!("tailor_vault_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&vault_dir).unwrap();
+
+        let vault_path = vault_dir.to_string_lossy().to_string();
+        manager.windows.insert("test_window".to_string(), vault_path.clone());
+        manager.vault_ids.insert("test_window".to_string(), WindowManager::vault_id(&vault_path));
+
+        // A differently-spelled but equal path resolves to the same window
+        let alias = vault_dir.join(".").to_string_lossy().to_string();
+        let found = manager.find_window_for_vault(&WindowManager::vault_id(&alias));
+        assert_eq!(found, Some(("test_window".to_string(), vault_path)));
+
+        std::fs::remove_dir_all(&vault_dir).unwrap();
+    }
+
     #[test]
     fn test_extract_vault_name() {
         assert_eq!(WindowManager::extract_vault_name("/path/to/my_vault"), "my_vault");
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tauri::WebviewWindow;
+
+/// Saved geometry of a vault window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowState {
+    /// Logical inner size while neither maximized nor fullscreen
+    pub width: f64,
+    pub height: f64,
+    pub maximized: bool,
+    pub fullscreen: bool,
+    /// Webview zoom factor, 1.0 being 100%
+    pub zoom: f64,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        Self {
+            width: 1200.0,
+            height: 800.0,
+            maximized: false,
+            fullscreen: false,
+            zoom: 1.0,
+        }
+    }
+}
+
+/// How long window state must stay unchanged before it is written out, so
+/// a drag-resize saves once rather than on every event
+pub const SAVE_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Window states keyed by vault key, persisted as JSON
+#[derive(Default)]
+pub struct WindowStateStore {
+    path: Option<PathBuf>,
+    states: Mutex<HashMap<String, WindowState>>,
+    /// Bumped by every change, so a debounced save can tell it is stale
+    generation: AtomicU64,
+    /// Held while writing so concurrent saves cannot interleave on the temp file
+    write_lock: Mutex<()>,
+}
+
+impl WindowStateStore {
+    /// Load saved states, starting empty if the file is missing or invalid
+    pub fn load(path: PathBuf) -> Self {
+        let states = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            states: Mutex::new(states),
+            ..Self::default()
+        }
+    }
+
+    /// The saved state for a vault, if it has one
+    pub fn get(&self, vault_key: &str) -> Option<WindowState> {
+        self.states.lock().unwrap().get(vault_key).cloned()
+    }
+
+    /// Record a window's current state and return the change's generation
+    /// (see `save_if_current`). The size is only taken from a normal window,
+    /// so restoring maximized keeps the size to return to.
+    pub fn capture(&self, vault_key: &str, window: &WebviewWindow) -> Result<u64> {
+        if window.is_minimized()? {
+            return Ok(self.generation.load(Ordering::SeqCst));
+        }
+        let maximized = window.is_maximized()?;
+        let fullscreen = window.is_fullscreen()?;
+
+        let mut states = self.states.lock().unwrap();
+        let state = states.entry(vault_key.to_string()).or_default();
+        state.maximized = maximized;
+        state.fullscreen = fullscreen;
+        if !maximized && !fullscreen {
+            let size = window.inner_size()?.to_logical::<f64>(window.scale_factor()?);
+            state.width = size.width;
+            state.height = size.height;
+        }
+        Ok(self.changed())
+    }
+
+    /// Record the zoom factor chosen for a vault's window
+    pub fn set_zoom(&self, vault_key: &str, zoom: f64) -> u64 {
+        self.states.lock().unwrap()
+            .entry(vault_key.to_string())
+            .or_default()
+            .zoom = zoom;
+        self.changed()
+    }
+
+    fn changed(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    /// Save unless something changed after `generation`, in which case the
+    /// save scheduled for that later change will write it. Returns whether
+    /// this call saved.
+    pub fn save_if_current(&self, generation: u64) -> Result<bool> {
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    /// Write all states to disk. The file is replaced atomically, so a crash
+    /// or a concurrent save never leaves it half-written.
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let _writing = self.write_lock.lock().unwrap();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create window state directory")?;
+        }
+        let json = serde_json::to_string_pretty(&*self.states.lock().unwrap())?;
+
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        fs::write(&temp, json).context("Failed to write window state")?;
+        fs::rename(&temp, path).context("Failed to replace window state")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let path = std::env::temp_dir().join(format!("tailor_window_state_{}.json", uuid::Uuid::new_v4()));
+        let store = WindowStateStore::load(path.clone());
+        assert_eq!(store.get("/vaults/a"), None);
+
+        store.states.lock().unwrap().insert("/vaults/a".to_string(), WindowState {
+            width: 900.0,
+            height: 700.0,
+            maximized: true,
+            fullscreen: false,
+            zoom: 1.0,
+        });
+        store.set_zoom("/vaults/a", 1.25);
+        store.save().unwrap();
+
+        let reloaded = WindowStateStore::load(path.clone());
+        let state = reloaded.get("/vaults/a").unwrap();
+        assert!(state.maximized);
+        assert_eq!(state.width, 900.0);
+        assert_eq!(state.zoom, 1.25);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_debounced_save_skips_stale_generation() {
+        let path = std::env::temp_dir().join(format!("tailor_window_state_{}.json", uuid::Uuid::new_v4()));
+        let store = WindowStateStore::load(path.clone());
+
+        let first = store.set_zoom("/vaults/a", 1.5);
+        let second = store.set_zoom("/vaults/a", 2.0);
+        assert!(!store.save_if_current(first).unwrap());
+        assert!(!path.exists());
+
+        assert!(store.save_if_current(second).unwrap());
+        assert_eq!(WindowStateStore::load(path.clone()).get("/vaults/a").unwrap().zoom, 2.0);
+        // Nothing is left behind from the atomic replace
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        assert!(!PathBuf::from(temp).exists());
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -277,7 +277,7 @@ function renderEmptyState(vaultsGrid) {
             }
         } catch (error) {
             console.error('Error opening vault:', error);
-            alert(`Failed to open vault: ${error}`);
+            alert(`Failed to open vault: ${error.message ?? error}`);
         }
     });
 }
@@ -337,7 +337,7 @@ function setupEventListeners(container) {
             }
         } catch (error) {
             console.error('Error opening vault:', error);
-            alert(`Failed to open vault: ${error}`);
+            alert(`Failed to open vault: ${error.message ?? error}`);
         }
     });
 
@@ -462,10 +462,13 @@ async function openPluginsModal(container) {
 
 async function openVault(vaultPath) {
     try {
-        await vaultApi.openVaultByPath(vaultPath);
+        const result = await vaultApi.openVaultByPath(vaultPath);
+        for (const warning of result?.warnings ?? []) {
+            console.warn(`[Dashboard] ${vaultPath}: ${warning}`);
+        }
     } catch (error) {
         console.error('Error opening vault:', error);
-        alert(`Failed to open vault: ${error}`);
+        alert(`Failed to open vault: ${error.message ?? error}`);
     }
 }
 
@@ -493,6 +496,6 @@ async function createNewVault(container) {
         }
     } catch (error) {
         console.error('Error creating vault:', error);
-        alert(`Failed to create vault: ${error}`);
+        alert(`Failed to create vault: ${error.message ?? error}`);
     }
 }
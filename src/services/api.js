@@ -25,10 +25,63 @@ export const vaultApi = {
     },
 
     /**
-     * Open a vault by path
+     * Open a vault by path, optionally centred on a monitor from
+     * listMonitors() or at an explicit logical { x, y } position.
+     * Resolves to { info, dependency_report, warnings }; rejects with
+     * { kind, code, message, data }, e.g. kind 'timeout'.
      */
-    async openVaultByPath(vaultPath) {
-        return await invoke('open_vault', { vaultPath });
+    async openVaultByPath(vaultPath, { monitorIndex, position } = {}) {
+        return await invoke('open_vault', { vaultPath, monitorIndex, position });
+    },
+
+    /**
+     * Open several vaults, e.g. to restore a session, a few at a time.
+     * Progress arrives as 'vault-open-progress' events.
+     * Resolves to [{ vault_path, result, error }] in the order given.
+     */
+    async openVaults(vaultPaths) {
+        return await invoke('open_vaults', { vaultPaths });
+    },
+
+    /**
+     * Open a vault shared as a .zip. It is extracted under the app data
+     * directory unless a destination is given, or pickDestination asks the
+     * user for one.
+     */
+    async openVaultArchive(zipPath, { destination, pickDestination } = {}) {
+        return await invoke('open_vault_archive', { zipPath, destination, pickDestination });
+    },
+
+    /**
+     * Vaults extracted from archives.
+     * Resolves to [{ archive_path, extracted_to, vault_path, extracted_at }].
+     */
+    async listVaultExtractions() {
+        return await invoke('list_vault_extractions', {});
+    },
+
+    /**
+     * Delete a closed vault's extracted copy
+     */
+    async removeVaultExtraction(vaultPath) {
+        return await invoke('remove_vault_extraction', { vaultPath });
+    },
+
+    /**
+     * Check whether a directory could be opened as a vault, without opening it.
+     * Resolves to { valid, vault_path, issues, warnings }, where issues
+     * and warnings are [{ kind, message }]; only issues make it invalid.
+     */
+    async validateVault(vaultPath) {
+        return await invoke('validate_vault', { vaultPath });
+    },
+
+    /**
+     * Displays available for placing vault windows.
+     * Resolves to [{ index, name, x, y, width, height, scale_factor }].
+     */
+    async listMonitors() {
+        return await invoke('list_monitors', {});
     },
 
     /**
@@ -69,6 +122,81 @@ export const vaultApi = {
         return await invoke('close_vault', { windowLabel });
     },
 
+    /**
+     * Abort everything in flight for this window (sidecar commands, streams,
+     * dependency installs); each fails with a 'cancelled' error.
+     * Resolves to { cancelled, commands, streams, dependency_installs }.
+     */
+    async cancelAll() {
+        return await invoke('cancel_all');
+    },
+
+    /**
+     * Acknowledge a host-routed event sent with requires_ack, so it is not
+     * re-emitted. Resolves to whether it was still awaiting acknowledgement.
+     */
+    async ackEvent(windowLabel, eventId) {
+        return await invoke('ack_event', { windowLabel, eventId });
+    },
+
+    /**
+     * Close a vault window unless its sidecar reports unsaved work.
+     * Resolves to { approved, unsaved, timed_out }; when not approved the
+     * window stays open and closeVault() forces the close.
+     */
+    async requestCloseVault(windowLabel) {
+        return await invoke('request_close_vault', { windowLabel });
+    },
+
+    /**
+     * Attach a window to another open vault (by vault id) so it can switch
+     * to it with setActiveVault()
+     */
+    async attachVault(windowLabel, vaultId) {
+        return await invoke('attach_vault', { windowLabel, vaultId });
+    },
+
+    /**
+     * Detach a window from a vault; resolves to false if it was not attached
+     */
+    async detachVault(windowLabel, vaultId) {
+        return await invoke('detach_vault', { windowLabel, vaultId });
+    },
+
+    /**
+     * Choose which of a window's vaults it receives vault events from and
+     * sends sidecar commands to
+     */
+    async setActiveVault(windowLabel, vaultId) {
+        return await invoke('set_active_vault', { windowLabel, vaultId });
+    },
+
+    /**
+     * Id of the vault a window is working with
+     */
+    async getActiveVault(windowLabel) {
+        return await invoke('get_active_vault', { windowLabel });
+    },
+
+    /**
+     * Export a file the sidecar produces: the user picks where to save it,
+     * then `method` streams the bytes there. dialog takes { title, file_name,
+     * filters: [{ name, extensions }] }. Progress arrives as
+     * 'sidecar-output-progress' events.
+     * Resolves to { path, bytes }, or null if the user cancelled.
+     */
+    async saveSidecarOutput(windowLabel, method, params = {}, dialog = null) {
+        return await invoke('save_sidecar_output', { windowLabel, method, params, dialog });
+    },
+
+    /**
+     * Packages installed for a vault's plugins.
+     * Resolves to [{ name, version, location }].
+     */
+    async listInstalledPackages(vaultPath) {
+        return await invoke('list_installed_packages', { vaultPath });
+    },
+
     /**
      * Update plugin configuration in .vault.toml
      */
@@ -128,6 +256,15 @@ export const settingsApi = {
         return await invoke('save_global_settings', { settings });
     },
 
+    /**
+     * Restart every open vault's sidecar, e.g. so a changed global setting
+     * takes effect. Progress arrives as 'sidecars-restart-progress' events.
+     * Resolves to [{ window_label, ws_port, error }].
+     */
+    async restartAllSidecars() {
+        return await invoke('restart_all_sidecars', {});
+    },
+
     /**
      * Get vault settings
      */
@@ -170,6 +307,56 @@ export const settingsApi = {
         return await invoke('save_api_key', { keyName, keyValue });
     },
 
+    /**
+     * Store a vault secret in the OS keychain. The vault's sidecar sees it
+     * as the environment variable `key` after its next restart.
+     */
+    async setVaultSecret(vaultId, key, value) {
+        return await invoke('set_vault_secret', { vaultId, key, value });
+    },
+
+    /**
+     * Remove a vault secret
+     */
+    async deleteVaultSecret(vaultId, key) {
+        return await invoke('delete_vault_secret', { vaultId, key });
+    },
+
+    /**
+     * Names of a vault's secrets (values are never returned)
+     */
+    async listVaultSecrets(vaultId) {
+        return await invoke('list_vault_secrets', { vaultId });
+    },
+
+    /**
+     * What a vault's sidecar has been allowed (true) or denied (false) to
+     * ask of the host, and its sandbox settings; anything left at its
+     * default is left out.
+     * Resolves to { open_vault, dialogs, read_settings, sandboxed, allow_network }.
+     */
+    async getVaultPermissions(vaultId) {
+        return await invoke('get_vault_permissions', { vaultId });
+    },
+
+    /**
+     * Allow or deny a vault 'open_vault', 'dialogs' or 'read_settings'.
+     * Pass granted = null to ask the user again on its next request.
+     */
+    async setVaultPermission(vaultId, capability, granted) {
+        return await invoke('set_vault_permission', { vaultId, capability, granted });
+    },
+
+    /**
+     * Choose whether a vault's sidecar runs sandboxed and may reach the
+     * network from inside it. Pass null for either to restore the default:
+     * no sandbox, and no network once sandboxed. Applies when the sidecar
+     * next starts.
+     */
+    async setVaultSandbox(vaultId, { sandboxed = null, allowNetwork = null } = {}) {
+        return await invoke('set_vault_sandbox', { vaultId, sandboxed, allowNetwork });
+    },
+
     /**
      * Delete API key
      */
@@ -195,5 +382,81 @@ export const developerApi = {
     async validatePlugin(vaultPath, pluginPath) {
         return await invoke('validate_plugin', { vaultPath, pluginPath });
     },
+
+    /**
+     * Recent output of a window's sidecar.
+     * Resolves to { window_label, vault_path, output_capture, stdout, stderr };
+     * output_capture other than 'capture' means nothing was recorded.
+     */
+    async getSidecarLogs(windowLabel) {
+        return await invoke('get_sidecar_logs', { windowLabel });
+    },
+
+    /**
+     * How a window's sidecar was launched, to run it by hand when it will
+     * not start. Secret values are redacted.
+     * Resolves to { program, args, env, cwd }.
+     */
+    async getSidecarCommand(windowLabel) {
+        return await invoke('get_sidecar_command', { windowLabel });
+    },
+
+    /**
+     * Use a sidecar you started yourself (e.g. under a debugger) on wsPort
+     * in place of the window's own. Tailor never kills or restarts it.
+     * Resolves to { window_label, vault_path, vault_id, ws_port, pid }.
+     */
+    async attachSidecar(windowLabel, wsPort) {
+        return await invoke('attach_sidecar', { windowLabel, wsPort });
+    },
+
+    /**
+     * Reload a vault window's page (e.g. after editing plugin assets)
+     * without restarting its sidecar. Events arriving meanwhile are
+     * replayed once the page reconnects.
+     */
+    async reloadWindow(windowLabel) {
+        return await invoke('reload_window', { windowLabel });
+    },
+
+    /**
+     * Find what holds a sidecar port that Tailor is not using, e.g. an
+     * orphaned sidecar. Call without expectedPid to see the owner, then
+     * with expectedPid: owner.pid to stop it; that fails if another process
+     * has taken the port in between.
+     * Resolves to { port, owner: { port, pid, name } | null, reclaimed }.
+     */
+    async reclaimPort(port, { expectedPid = null } = {}) {
+        return await invoke('reclaim_port', { port, expectedPid });
+    },
+
+    /**
+     * The event bus's view of every window, for debugging event routing.
+     * Resolves to [{ window_label, vault_id, subscriptions, policy, paused, ... }].
+     */
+    async getRoutingTable() {
+        return await invoke('get_routing_table', {});
+    },
 };
 
+
+/**
+ * Environment API
+ */
+export const environmentApi = {
+    /**
+     * Backend version and build details.
+     * Resolves to { version, build_hash, tauri_version, os, arch }.
+     */
+    async appInfo() {
+        return await invoke('app_info', {});
+    },
+
+    /**
+     * Check that Python and pip are available, e.g. on first launch.
+     * Resolves to { python_ok, pip_ok, python_version, issues }.
+     */
+    async preflightCheck() {
+        return await invoke('preflight_check', {});
+    },
+};
@@ -11,6 +11,8 @@ const pending = new Map();
 let reconnectAttempts = 0;
 const maxReconnectAttempts = 10;
 let currentPort = null;
+// Interface the sidecar listens on, as reported by Tauri (sidecar.ws_host)
+let currentHost = '127.0.0.1';
 
 // Import log from globals (set by layout module)
 const getLog = () => window.log || console.log;
@@ -47,9 +49,9 @@ export function connect(explicitPort, loadPluginsFn, handleEventFn) {
     }
     currentPort = port;
 
-    log(`Connecting to ws://127.0.0.1:${port}... (attempt ${reconnectAttempts + 1}/${maxReconnectAttempts})`);
+    log(`Connecting to ws://${currentHost}:${port}... (attempt ${reconnectAttempts + 1}/${maxReconnectAttempts})`);
 
-    ws = new WebSocket(`ws://127.0.0.1:${port}`);
+    ws = new WebSocket(`ws://${currentHost}:${port}`);
 
     ws.onopen = () => {
         reconnectAttempts = 0;
@@ -70,6 +72,8 @@ export function connect(explicitPort, loadPluginsFn, handleEventFn) {
             const data = JSON.parse(e.data);
             if (data.method === 'trigger_event') {
                 if (handleEventFn) handleEventFn(data.params);
+            } else if (data.method && data.method.startsWith('host.') && data.id !== undefined) {
+                relayHostRequest(data);
             } else if (data.id && pending.has(data.id)) {
                 pending.get(data.id)(data);
                 pending.delete(data.id);
@@ -80,6 +84,25 @@ export function connect(explicitPort, loadPluginsFn, handleEventFn) {
     };
 }
 
+/**
+ * Relay a host request from the sidecar to the Tauri backend and send back the reply
+ * @param {object} data - JSON-RPC request from the sidecar
+ */
+async function relayHostRequest(data) {
+    let reply;
+    try {
+        const { invoke } = await import('@tauri-apps/api/core');
+        const result = await invoke('handle_sidecar_request', { method: data.method, params: data.params || {} });
+        reply = { jsonrpc: '2.0', id: data.id, result };
+    } catch (err) {
+        reply = { jsonrpc: '2.0', id: data.id, error: { code: -32000, message: String(err) } };
+    }
+
+    if (ws && ws.readyState === WebSocket.OPEN) {
+        ws.send(JSON.stringify(reply));
+    }
+}
+
 /**
  * Schedule reconnection with exponential backoff
  */
@@ -100,6 +123,16 @@ function scheduleReconnect(loadPluginsFn, handleEventFn) {
     }, delay);
 }
 
+/**
+ * Point the connection at a restarted sidecar's new port. The old socket
+ * closes with the old process, and reconnection then uses this port.
+ * @param {number} port - Port reported by the `sidecar-restarted` event
+ */
+export function setSidecarPort(port) {
+    currentPort = port;
+    reconnectAttempts = 0;
+}
+
 /**
  * Make a JSON-RPC request
  * @param {string} method - RPC method name
@@ -132,6 +165,7 @@ window.request = request;
 export async function autoConnect(loadPluginsFn, handleEventFn) {
     const log = getLog();
     let autoPort = null;
+    let sidecarReady = false;
 
     // 1. Check URL Params
     const params = new URLSearchParams(window.location.search);
@@ -144,10 +178,16 @@ export async function autoConnect(loadPluginsFn, handleEventFn) {
     if (!autoPort) {
         try {
             const { invoke } = await import('@tauri-apps/api/core');
-            const vaultInfo = await invoke('get_current_vault_info');
+            const { getCurrentWebviewWindow } = await import('@tauri-apps/api/webviewWindow');
+            // Re-registers this window and replays events held while it reloaded
+            const windowLabel = getCurrentWebviewWindow().label;
+            const vaultInfo = await invoke('reconnect', { windowLabel });
             if (vaultInfo && vaultInfo.ws_port) {
                 autoPort = vaultInfo.ws_port;
-                log(`Found port via Tauri: ${autoPort}`);
+                if (vaultInfo.ws_host) currentHost = vaultInfo.ws_host;
+                log(`Found port via Tauri: ${autoPort} (${vaultInfo.missed_event_count} missed events replayed)`);
+                await invoke('wait_for_sidecar_ready', { windowLabel, timeoutMs: 10000 });
+                sidecarReady = true;
             }
         } catch (e) {
             // Not in Tauri or API failed
@@ -155,7 +195,8 @@ export async function autoConnect(loadPluginsFn, handleEventFn) {
     }
 
     if (autoPort) {
-        setTimeout(() => connect(autoPort, loadPluginsFn, handleEventFn), 300);
+        // No need to give the sidecar time to start once Tauri reports it ready
+        setTimeout(() => connect(autoPort, loadPluginsFn, handleEventFn), sidecarReady ? 0 : 300);
     } else {
         log('No auto-connect port found. Connection will use default (9002).');
         setTimeout(() => connect('9002', loadPluginsFn, handleEventFn), 300);
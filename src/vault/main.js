@@ -7,10 +7,12 @@
 import { SidebarManager, PanelManager, ToolbarManager, ModalManager, ToolboxManager } from './managers/index.js';
 import { registerAction, unregisterAction, refreshComposerToolbar, showToast } from './chat/index.js';
 import { initLayout, initResize, log } from './layout.js';
-import { autoConnect, request } from './connection.js';
+import { autoConnect, request, setSidecarPort } from './connection.js';
 import { loadPlugins, handleEvent } from './plugins.js';
 import { initSettings, loadAndApplySettings } from './settings.js';
 import { initPluginStore } from './plugin-store.js';
+import { vaultApi } from '../services/api.js';
+import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 
 
 /**
@@ -104,5 +106,146 @@ export function initVault() {
     // Auto-connect to WebSocket and load plugins
     autoConnect(loadPlugins, handleEvent);
 
+    // Start over when this window is switched to another vault
+    getCurrentWebviewWindow().listen('vault-switched', () => {
+        console.log('[Vault] Vault switched, reloading');
+        window.location.reload();
+    });
+
+    // Requirements were edited while the vault was open; reinstall only if the user agrees
+    getCurrentWebviewWindow().listen('dependencies-outdated', async ({ payload }) => {
+        if (!confirm('Plugin requirements have changed. Install the updated dependencies now?')) return;
+        try {
+            const { invoke } = await import('@tauri-apps/api/core');
+            await invoke('install_dependencies', { windowLabel: payload.window_label });
+            showToast('Dependencies installed. Reload the vault to use them.');
+        } catch (e) {
+            showToast(`Dependency install failed: ${e}`, 'error');
+        }
+    });
+
+    // Host-side view of the sidecar: spawning, connecting, ready, reconnecting or dead
+    getCurrentWebviewWindow().listen('sidecar-connection', ({ payload }) => {
+        const failed = payload.state === 'reconnecting' || payload.state === 'dead';
+        log(`Sidecar ${payload.state} (port ${payload.ws_port})`, failed ? 'error' : 'info');
+    });
+
+    // This vault and another open one are nested; both sidecars see the shared files
+    getCurrentWebviewWindow().listen('nested-vault', ({ payload }) => {
+        const paths = [payload.vault_path, ...payload.nested.map(v => v.vault_path)].join(', ');
+        log(`Nested vaults are open (${paths}); close one to avoid handling files twice`.replace(/[&<>]/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;' }[c])), 'error');
+    });
+
+    // Output of a vault's post-install script while its dependencies install
+    getCurrentWebviewWindow().listen('postinstall-output', ({ payload }) => {
+        const text = `[postinstall] ${payload.line}`;
+        log(text.replace(/[&<>]/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;' }[c])), payload.stream === 'stderr' ? 'error' : 'info');
+    });
+
+    // tailor.json sets dependencies.auto_reinstall_on_change: requirements are
+    // reinstalled and the sidecar restarted without asking
+    getCurrentWebviewWindow().listen('dependencies-reinstalling', () => {
+        showToast('Plugin requirements changed; reinstalling dependencies...');
+    });
+    getCurrentWebviewWindow().listen('dependencies-reinstalled', ({ payload }) => {
+        if (payload.error) {
+            showToast(`Dependency reinstall failed: ${payload.error}`, 'error');
+        } else {
+            showToast('Dependencies reinstalled and sidecar restarted.');
+        }
+    });
+
+    // The sidecar was restarted on a new port; reconnect there
+    getCurrentWebviewWindow().listen('sidecar-restarted', ({ payload }) => {
+        log(`Sidecar restarted on port ${payload.ws_port}`, 'info');
+        setSidecarPort(payload.ws_port);
+        if (payload.reason === 'crashed') {
+            showToast('The sidecar crashed and was restarted.', 'error');
+        }
+    });
+
+    // The sidecar exceeded the memory limit set in tailor.json
+    getCurrentWebviewWindow().listen('sidecar-killed-oom', ({ payload }) => {
+        const limitMb = Math.round(payload.limit_bytes / (1024 * 1024));
+        log(`Sidecar stopped: it exceeded its ${limitMb} MiB memory limit (${payload.exit_status})`, 'error');
+        showToast(`A plugin used more than ${limitMb} MiB of memory and the sidecar was stopped. Reload the vault to restart it.`, 'error');
+    });
+
+    // The sidecar exited on its own and the host stopped tracking it
+    getCurrentWebviewWindow().listen('sidecar-reaped', ({ payload }) => {
+        if (payload.reason === 'closed_by_sidecar') {
+            log(`Sidecar shut down (${payload.exit_status})`, 'info');
+            showToast('The sidecar shut down. Reload the vault to restart it.');
+            return;
+        }
+        if (payload.reason === 'disconnected') {
+            log(`Attached sidecar went away (${payload.exit_status})`, 'error');
+            showToast('The attached sidecar stopped responding. Reload the vault or attach it again.', 'error');
+            return;
+        }
+        log(`Sidecar exited (${payload.exit_status})${payload.stderr ? `:\n${payload.stderr}` : ''}`, 'error');
+        showToast('The sidecar stopped unexpectedly. Reload the vault to restart it.', 'error');
+    });
+
+    // A sidecar event was dropped for not matching its schema (debug.event_schema_validation = "strict")
+    getCurrentWebviewWindow().listen('protocol-violation', ({ payload }) => {
+        const text = `Dropped '${payload.event_type}' event: ${payload.violations.join('; ')}`;
+        log(text.replace(/[&<>]/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;' }[c])), 'error');
+    });
+
+    // Events routed by the host carry a sequence number per sidecar, counted
+    // for this window alone; warn about gaps, and handle each sequence number
+    // once since events awaiting an ack are re-emitted
+    const lastSeq = new Map();
+    const handled = new Set();
+    getCurrentWebviewWindow().listen('event-sequence-reset', ({ payload }) => {
+        lastSeq.delete(payload.source_window);
+        for (const key of handled) {
+            if (key.startsWith(`${payload.source_window}:`)) handled.delete(key);
+        }
+    });
+
+    const ack = (payload) => {
+        if (!payload?.requires_ack || payload.id == null) return;
+        vaultApi.ackEvent(getCurrentWebviewWindow().label, payload.id)
+            .catch(e => console.warn(`[Vault] Failed to acknowledge event ${payload.id}:`, e));
+    };
+
+    // Events superseded by a newer one with the same coalesce_key are never
+    // delivered; the newer one lists their sequence numbers
+    const supersededIn = (events) => new Set(events.flatMap(
+        e => (e?.superseded ?? []).map(seq => `${e.source_window}:${seq}`)));
+
+    // Events the host routes from sidecars, e.g. stderr (sidecar.forward_stderr)
+    const onSidecarEvent = (payload, superseded = supersededIn([payload])) => {
+        // Acked on receipt; a re-emission is acked again in case the first ack was lost
+        ack(payload);
+        if (payload?.seq != null) {
+            const key = `${payload.source_window}:${payload.seq}`;
+            if (handled.has(key)) return;
+            handled.add(key);
+            if (handled.size > 1000) handled.delete(handled.values().next().value);
+
+            let expected = (lastSeq.get(payload.source_window) ?? 0) + 1;
+            while (superseded.has(`${payload.source_window}:${expected}`)) expected++;
+            if (payload.seq !== expected) {
+                console.warn(`[Vault] Event ${payload.seq} from '${payload.source_window}' arrived, expected ${expected}`);
+            }
+            lastSeq.set(payload.source_window, Math.max(payload.seq, lastSeq.get(payload.source_window) ?? 0));
+        }
+        if (payload?.event_type !== 'sidecar-error') return;
+        const message = String(payload.data?.message ?? '');
+        const escaped = message.replace(/[&<>]/g, c => ({ '&': '&amp;', '<': '&lt;', '>': '&gt;' }[c]));
+        log(`<pre>${escaped}</pre>`, 'error');
+        // A traceback's last line names the exception
+        showToast(message.trim().split('\n').pop(), 'error');
+    };
+    getCurrentWebviewWindow().listen('sidecar-event', ({ payload }) => onSidecarEvent(payload));
+    // Most events arrive batched, one emit per frame
+    getCurrentWebviewWindow().listen('sidecar-events', ({ payload }) => {
+        const superseded = supersededIn(payload);
+        payload.forEach(event => onSidecarEvent(event, superseded));
+    });
+
     console.log('[Vault] Initialization complete');
 }
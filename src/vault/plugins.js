@@ -90,6 +90,16 @@ export function handleEvent(evt) {
         }
     }
 
+    // Vault files changed: drop the host's cached listings
+    if (eventType === 'file-changed') {
+        import('@tauri-apps/api/core')
+            .then(async ({ invoke }) => {
+                const { getCurrentWebviewWindow } = await import('@tauri-apps/api/webviewWindow');
+                await invoke('invalidate_vault_entries', { windowLabel: getCurrentWebviewWindow().label });
+            })
+            .catch(() => { /* Not in Tauri */ });
+    }
+
     // Handle UI Commands from Backend
     if (eventType === 'UI_COMMAND') {
         console.log('[handleEvent] UI_COMMAND received:', evt.data);